@@ -23,6 +23,7 @@
 pub mod vcard;
 
 use crate::datetime::{self, UnixTimestamp};
+use crate::email::{Address, Envelope};
 use std::collections::HashMap;
 use uuid::Uuid;
 
@@ -62,6 +63,10 @@ pub struct AddressBook {
     created: UnixTimestamp,
     last_edited: UnixTimestamp,
     pub cards: HashMap<CardId, Card>,
+    /// Addresses harvested from envelopes, keyed by lowercase address spec. They are offered as
+    /// completions along with the cards.
+    #[serde(default)]
+    seen_addresses: HashMap<String, Address>,
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -75,6 +80,9 @@ pub struct Card {
     //address
     birthday: Option<UnixTimestamp>,
     email: String,
+    /// Addresses besides `email`, e.g. from a vCard with more than one `EMAIL` property.
+    #[serde(default)]
+    additional_emails: Vec<String>,
     url: String,
     key: String,
 
@@ -93,6 +101,7 @@ impl AddressBook {
             created: datetime::now(),
             last_edited: datetime::now(),
             cards: HashMap::default(),
+            seen_addresses: HashMap::default(),
         }
     }
 
@@ -124,12 +133,80 @@ impl AddressBook {
     pub fn card_exists(&self, card_id: CardId) -> bool {
         self.cards.contains_key(&card_id)
     }
+    /// Returns cards and seen addresses whose name or address contains `term`, ignoring case.
     pub fn search(&self, term: &str) -> Vec<String> {
-        self.cards
+        let term = term.to_lowercase();
+        let mut ret = self
+            .cards
             .values()
-            .filter(|c| c.email.contains(term))
-            .map(|c| format!("{} <{}>", &c.name, &c.email))
-            .collect()
+            .flat_map(|c| {
+                let name_matches = c.name.to_lowercase().contains(&term);
+                let term = term.clone();
+                c.emails()
+                    .filter(move |e| name_matches || e.to_lowercase().contains(&term))
+                    .map(move |e| format!("{} <{}>", &c.name, e))
+            })
+            .collect::<Vec<String>>();
+        let mut seen = self
+            .seen_addresses
+            .iter()
+            .filter(|(email, addr)| {
+                !self
+                    .cards
+                    .values()
+                    .any(|c| c.emails().any(|e| e.eq_ignore_ascii_case(email)))
+                    && (email.contains(&term)
+                        || addr
+                            .get_display_name()
+                            .map(|n| n.to_lowercase().contains(&term))
+                            .unwrap_or(false))
+            })
+            .map(|(_, addr)| match addr.get_display_name() {
+                /* Quote display names that would otherwise not parse back, e.g. `Doe, John` */
+                Some(name) if name.contains(|c| "()<>[]:;@\\,.\"".contains(c)) => format!(
+                    "\"{}\" <{}>",
+                    name.replace('\\', "\\\\").replace('"', "\\\""),
+                    addr.get_email()
+                ),
+                Some(name) => format!("{} <{}>", name, addr.get_email()),
+                None => addr.get_email(),
+            })
+            .collect::<Vec<String>>();
+        seen.sort();
+        ret.extend(seen);
+        ret
+    }
+
+    pub fn seen_addresses(&self) -> &HashMap<String, Address> {
+        &self.seen_addresses
+    }
+
+    /// Remembers `address` for autocompletion. A known address is only replaced if the new one has
+    /// a display name and the old one does not.
+    pub fn add_seen_address(&mut self, address: &Address) {
+        let email = address.get_email().to_lowercase();
+        if email.is_empty() {
+            return;
+        }
+        match self.seen_addresses.get(&email) {
+            Some(old)
+                if old.get_display_name().is_some() || address.get_display_name().is_none() => {}
+            _ => {
+                self.seen_addresses.insert(email, address.clone());
+            }
+        }
+    }
+
+    /// Harvests the `From`, `To` and `Cc` addresses of `envelope`.
+    pub fn harvest(&mut self, envelope: &Envelope) {
+        for addr in envelope
+            .from()
+            .iter()
+            .chain(envelope.to().iter())
+            .chain(envelope.cc().iter())
+        {
+            self.add_seen_address(addr);
+        }
     }
 }
 
@@ -153,6 +230,7 @@ impl Card {
             //address
             birthday: None,
             email: String::new(),
+            additional_emails: Vec::new(),
             url: String::new(),
             key: String::new(),
 
@@ -185,6 +263,15 @@ impl Card {
     pub fn email(&self) -> &str {
         self.email.as_str()
     }
+    pub fn additional_emails(&self) -> &[String] {
+        self.additional_emails.as_slice()
+    }
+    /// Returns the primary address followed by the additional ones.
+    pub fn emails(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.email.as_str())
+            .chain(self.additional_emails.iter().map(String::as_str))
+            .filter(|e| !e.is_empty())
+    }
     pub fn url(&self) -> &str {
         self.url.as_str()
     }
@@ -216,6 +303,9 @@ impl Card {
     pub fn set_email(&mut self, new: String) {
         self.email = new;
     }
+    pub fn set_additional_emails(&mut self, new: Vec<String>) {
+        self.additional_emails = new;
+    }
     pub fn set_url(&mut self, new: String) {
         self.url = new;
     }
@@ -282,3 +372,37 @@ impl Default for Card {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_addressbook_harvest() {
+        let mut book = AddressBook::new("test".to_string());
+        let mut card = Card::new();
+        card.set_name("Alice Doe".to_string());
+        card.set_email("alice@example.com".to_string());
+        book.add_card(card);
+        for raw in &[
+            &b"From: Bob Roberts <bob@example.com>\nTo: alice@example.com\nSubject: a\n\nbody\n"[..],
+            &b"From: bob@example.com\nTo: Carol <carol@example.org>\nCc: \"Dave, D.\" <dave@example.net>, bo@example.net\nSubject: b\n\nbody\n"[..],
+        ] {
+            book.harvest(&Envelope::from_bytes(raw, None).unwrap());
+        }
+        assert_eq!(book.seen_addresses().len(), 5);
+        /* Display name is kept when a bare address is seen later */
+        assert_eq!(book.search("bob"), vec!["Bob Roberts <bob@example.com>"]);
+        /* Cards come first and are not repeated */
+        assert_eq!(
+            book.search("ALI"),
+            vec!["Alice Doe <alice@example.com>".to_string()]
+        );
+        assert_eq!(
+            book.search("bo"),
+            vec!["Bob Roberts <bob@example.com>", "bo@example.net"]
+        );
+        assert_eq!(book.search("dave"), vec!["\"Dave, D.\" <dave@example.net>"]);
+        assert!(book.search("zed").is_empty());
+    }
+}
@@ -21,10 +21,13 @@
 
 /// Convert VCard strings to meli Cards (contacts).
 use super::*;
+use crate::email::attachment_types::Charset;
+use crate::email::parser::encodings::{decode_charset, quoted_printable_bytes};
 use crate::error::{MeliError, Result};
 use crate::parsec::{match_literal_anycase, one_or_more, peek, prefix, take_until, Parser};
 use std::collections::HashMap;
 use std::convert::TryInto;
+use std::path::Path;
 
 /* Supported vcard versions */
 pub trait VCardVersion: core::fmt::Debug {}
@@ -50,7 +53,7 @@ static FOOTER: &str = "END:VCARD\r\n";
 
 #[derive(Debug)]
 pub struct VCard<T: VCardVersion>(
-    HashMap<String, ContentLine>,
+    HashMap<String, Vec<ContentLine>>,
     std::marker::PhantomData<*const T>,
 );
 
@@ -70,6 +73,110 @@ pub struct ContentLine {
     value: String,
 }
 
+impl ContentLine {
+    fn is_quoted_printable(&self) -> bool {
+        self.params.iter().any(|p| {
+            p.eq_ignore_ascii_case("ENCODING=QUOTED-PRINTABLE")
+                || p.eq_ignore_ascii_case("QUOTED-PRINTABLE")
+        })
+    }
+
+    fn decode_quoted_printable(&mut self) {
+        let charset = self
+            .params
+            .iter()
+            .find(|p| {
+                p.len() > "CHARSET=".len() && p[.."CHARSET=".len()].eq_ignore_ascii_case("CHARSET=")
+            })
+            .map(|p| Charset::from(&p.as_bytes()["CHARSET=".len()..]))
+            .unwrap_or_default();
+        if let Ok((_, bytes)) = quoted_printable_bytes(self.value.as_bytes()) {
+            self.value = decode_charset(&bytes, charset)
+                .unwrap_or_else(|_| String::from_utf8_lossy(&bytes).into_owned());
+        }
+    }
+}
+
+/// Join folded lines (RFC 6350 3.2) and quoted-printable soft line breaks (vCard 2.1) into
+/// logical content lines.
+fn unfold_lines(input: &str) -> Vec<String> {
+    let mut ret: Vec<String> = Vec::new();
+    for l in input.lines() {
+        match ret.last_mut() {
+            Some(prev) if l.starts_with(' ') || l.starts_with('\t') => {
+                prev.push_str(&l[1..]);
+            }
+            Some(prev)
+                if prev.ends_with('=')
+                    && prev
+                        .split(':')
+                        .next()
+                        .map(|p| p.to_ascii_uppercase().contains("QUOTED-PRINTABLE"))
+                        .unwrap_or(false) =>
+            {
+                prev.pop();
+                prev.push_str(l);
+            }
+            _ if l.is_empty() => {}
+            _ => ret.push(l.to_string()),
+        }
+    }
+    ret
+}
+
+/// Unescape a TEXT value: `\\`, `\,`, `\;` and `\n`.
+fn unescape_text(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            ret.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => ret.push('\n'),
+            Some(c) => ret.push(c),
+            None => ret.push('\\'),
+        }
+    }
+    ret
+}
+
+fn escape_text(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' | ',' | ';' => {
+                ret.push('\\');
+                ret.push(c);
+            }
+            '\n' => ret.push_str("\\n"),
+            '\r' => {}
+            c => ret.push(c),
+        }
+    }
+    ret
+}
+
+/// Append `line` to `out`, folding it at 75 octets as required by RFC 6350 3.2.
+fn push_folded(out: &mut String, line: &str) {
+    let mut start = 0;
+    let mut limit = 75;
+    while line.len() - start > limit {
+        let mut end = start + limit;
+        while !line.is_char_boundary(end) {
+            end -= 1;
+        }
+        out.push_str(&line[start..end]);
+        out.push_str("\r\n ");
+        start = end;
+        /* Continuation lines start with a space */
+        limit = 74;
+    }
+    out.push_str(&line[start..]);
+    out.push_str("\r\n");
+}
+
 impl CardDeserializer {
     pub fn from_str(mut input: &str) -> Result<VCard<impl VCardVersion>> {
         input = if !input.starts_with(HEADER) || !input.ends_with(FOOTER) {
@@ -78,7 +185,7 @@ impl CardDeserializer {
             &input[HEADER.len()..input.len() - FOOTER.len()]
         };
 
-        let mut ret = HashMap::default();
+        let mut ret: HashMap<String, Vec<ContentLine>> = HashMap::default();
 
         enum Stage {
             Group,
@@ -88,7 +195,8 @@ impl CardDeserializer {
         }
         let mut stage: Stage;
 
-        for l in input.lines() {
+        for l in unfold_lines(input) {
+            let l = l.as_str();
             let mut el = ContentLine::default();
             let mut value_start = 0;
             let mut has_colon = false;
@@ -144,12 +252,21 @@ impl CardDeserializer {
                 )));
             }
             el.value = l[value_start..].replace("\\:", ":");
-            ret.insert(name, el);
+            if el.is_quoted_printable() {
+                el.decode_quoted_printable();
+            }
+            ret.entry(name.to_ascii_uppercase()).or_default().push(el);
         }
         Ok(VCard(ret, std::marker::PhantomData::<*const VCardVersion4>))
     }
 }
 
+impl<V: VCardVersion> VCard<V> {
+    fn remove_first(&mut self, name: &str) -> Option<ContentLine> {
+        self.0.remove(name).and_then(|v| v.into_iter().next())
+    }
+}
+
 impl<V: VCardVersion> TryInto<Card> for VCard<V> {
     type Error = crate::error::MeliError;
 
@@ -158,26 +275,40 @@ impl<V: VCardVersion> TryInto<Card> for VCard<V> {
         card.set_id(CardId::Hash({
             use std::hash::Hasher;
             let mut hasher = std::collections::hash_map::DefaultHasher::new();
-            if let Some(val) = self.0.get("FN") {
-                hasher.write(val.value.as_bytes());
-            }
-            if let Some(val) = self.0.get("N") {
-                hasher.write(val.value.as_bytes());
-            }
-            if let Some(val) = self.0.get("EMAIL") {
-                hasher.write(val.value.as_bytes());
+            for k in &["FN", "N", "EMAIL"] {
+                for val in self.0.get(*k).into_iter().flatten() {
+                    hasher.write(val.value.as_bytes());
+                }
             }
             hasher.finish()
         }));
-        if let Some(val) = self.0.remove("FN") {
-            card.set_name(val.value);
+        if let Some(val) = self.remove_first("FN") {
+            card.set_name(unescape_text(&val.value));
+        } else if let Some(val) = self.0.get("N").and_then(|v| v.first()) {
+            /* N:Family;Given;Additional;Prefix;Suffix */
+            let mut parts = val
+                .value
+                .split(';')
+                .map(unescape_text)
+                .collect::<Vec<String>>();
+            parts.resize(5, String::new());
+            let name = [&parts[3], &parts[1], &parts[2], &parts[0], &parts[4]]
+                .iter()
+                .filter(|p| !p.is_empty())
+                .map(|p| p.as_str())
+                .collect::<Vec<&str>>()
+                .join(" ");
+            if name.is_empty() {
+                return Err(MeliError::new("FN entry missing in VCard."));
+            }
+            card.set_name(name);
         } else {
             return Err(MeliError::new("FN entry missing in VCard."));
         }
-        if let Some(val) = self.0.remove("NICKNAME") {
-            card.set_additionalname(val.value);
+        if let Some(val) = self.remove_first("NICKNAME") {
+            card.set_additionalname(unescape_text(&val.value));
         }
-        if let Some(val) = self.0.remove("BDAY") {
+        if let Some(val) = self.remove_first("BDAY") {
             /* 4.3.4.  DATE-AND-OR-TIME
 
             Either a DATE-TIME, a DATE, or a TIME value.  To allow unambiguous
@@ -204,20 +335,29 @@ impl<V: VCardVersion> TryInto<Card> for VCard<V> {
             card.birthday = crate::datetime::timestamp_from_string(val.value.as_str(), "%Y%m%d")
                 .unwrap_or_default();
         }
-        if let Some(val) = self.0.remove("EMAIL") {
-            card.set_email(val.value);
+        if let Some(vals) = self.0.remove("EMAIL") {
+            let mut emails = vals
+                .into_iter()
+                .map(|val| unescape_text(val.value.trim()))
+                .filter(|e| !e.is_empty());
+            if let Some(email) = emails.next() {
+                card.set_email(email);
+            }
+            card.set_additional_emails(emails.collect());
         }
-        if let Some(val) = self.0.remove("URL") {
+        if let Some(val) = self.remove_first("URL") {
             card.set_url(val.value);
         }
-        if let Some(val) = self.0.remove("KEY") {
+        if let Some(val) = self.remove_first("KEY") {
             card.set_key(val.value);
         }
         for (k, v) in self.0.into_iter() {
-            if k.eq_ignore_ascii_case("VERSION") || k.eq_ignore_ascii_case("N") {
+            if k == "VERSION" || k == "N" {
                 continue;
             }
-            card.set_extra_property(&k, v.value);
+            if let Some(v) = v.into_iter().last() {
+                card.set_extra_property(&k, v.value);
+            }
         }
 
         Ok(card)
@@ -254,35 +394,48 @@ fn test_load_cards() {
     */
 }
 
+/// Parse every card in `contents`, which may use either CRLF or LF line endings.
+fn parse_cards(contents: &str) -> Vec<Result<Card>> {
+    let mut normalized = String::with_capacity(contents.len() + 64);
+    for l in contents.lines().filter(|l| !l.trim().is_empty()) {
+        normalized.push_str(l);
+        normalized.push_str("\r\n");
+    }
+    let cards = match parse_card().parse(normalized.as_str()) {
+        Ok((_, c)) => c,
+        Err(_) => return Vec::new(),
+    };
+    cards
+        .into_iter()
+        .map(|s| CardDeserializer::from_str(s).and_then(TryInto::try_into))
+        .collect()
+}
+
+/// Load cards from `p`, which is either a `.vcf` file or a directory of them.
 pub fn load_cards(p: &std::path::Path) -> Result<Vec<Card>> {
-    let vcf_dir = std::fs::read_dir(p);
     let mut ret: Vec<Result<_>> = Vec::new();
     let mut is_any_valid = false;
-    if vcf_dir.is_ok() {
-        let mut contents = String::with_capacity(256);
-        for f in vcf_dir? {
-            if f.is_err() {
-                continue;
-            }
-            let f = f?.path();
-            if f.is_file() {
-                use std::io::Read;
-                contents.clear();
-                std::fs::File::open(&f)?.read_to_string(&mut contents)?;
-                if let Ok((_, c)) = parse_card().parse(contents.as_str()) {
-                    for s in c {
-                        ret.push(
-                            CardDeserializer::from_str(s)
-                                .and_then(TryInto::try_into)
-                                .and_then(|mut card| {
-                                    Card::set_external_resource(&mut card, true);
-                                    is_any_valid = true;
-                                    Ok(card)
-                                }),
-                        );
-                    }
-                }
-            }
+    let files: Vec<std::path::PathBuf> = if p.is_file() {
+        vec![p.to_path_buf()]
+    } else if let Ok(vcf_dir) = std::fs::read_dir(p) {
+        vcf_dir
+            .filter_map(|f| f.ok().map(|f| f.path()))
+            .filter(|f| f.is_file())
+            .collect()
+    } else {
+        Vec::new()
+    };
+    for f in files {
+        let contents = match std::fs::read(&f) {
+            Ok(c) => String::from_utf8_lossy(&c).into_owned(),
+            Err(_) => continue,
+        };
+        for card in parse_cards(&contents) {
+            ret.push(card.map(|mut card| {
+                Card::set_external_resource(&mut card, true);
+                is_any_valid = true;
+                card
+            }));
         }
     }
     for c in &ret {
@@ -298,8 +451,150 @@ pub fn load_cards(p: &std::path::Path) -> Result<Vec<Card>> {
     }
 }
 
+/// Import the cards of a `.vcf` file. Unlike [`load_cards`], the cards are not marked as external
+/// resources and a malformed card or a file without any cards is an error.
+pub fn import_vcard(path: &Path) -> Result<Vec<Card>> {
+    let contents = std::fs::read(path)?;
+    let cards = parse_cards(&String::from_utf8_lossy(&contents));
+    if cards.is_empty() {
+        return Err(MeliError::new(format!(
+            "{} does not contain any vCards",
+            path.display()
+        )));
+    }
+    cards.into_iter().collect::<Result<Vec<Card>>>()
+}
+
+/// Serialize `card` as a vCard 3.0 entry with its `FN`, `N`, `NICKNAME`, `EMAIL` and `URL`
+/// properties.
+pub fn card_to_vcard(card: &Card) -> String {
+    let mut ret = String::with_capacity(128);
+    ret.push_str(HEADER);
+    ret.push_str("VERSION:3.0\r\n");
+    push_folded(&mut ret, &format!("FN:{}", escape_text(card.name())));
+    /* N:Family;Given;Additional;Prefix;Suffix, guessing that the last word is the family name */
+    let (given, family) = match card.name().trim().rsplitn(2, ' ').collect::<Vec<&str>>()[..] {
+        [family, given] => (given.trim(), family),
+        [family] => ("", family),
+        _ => ("", ""),
+    };
+    push_folded(
+        &mut ret,
+        &format!(
+            "N:{};{};;{};{}",
+            escape_text(family),
+            escape_text(given),
+            escape_text(card.name_prefix()),
+            escape_text(card.name_suffix())
+        ),
+    );
+    if !card.additionalname().is_empty() {
+        push_folded(
+            &mut ret,
+            &format!("NICKNAME:{}", escape_text(card.additionalname())),
+        );
+    }
+    for email in card.emails() {
+        push_folded(
+            &mut ret,
+            &format!("EMAIL;TYPE=INTERNET:{}", escape_text(email)),
+        );
+    }
+    if !card.url().is_empty() {
+        push_folded(&mut ret, &format!("URL:{}", card.url()));
+    }
+    ret.push_str(FOOTER);
+    ret
+}
+
+/// Write `cards` to `path` as a vCard 3.0 file.
+pub fn export_vcard(cards: &[Card], path: &Path) -> Result<()> {
+    let contents = cards.iter().map(card_to_vcard).collect::<String>();
+    std::fs::write(path, contents)?;
+    Ok(())
+}
+
 #[test]
 fn test_card() {
     let j = "BEGIN:VCARD\r\nVERSION:4.0\r\nN:Gump;Forrest;;Mr.;\r\nFN:Forrest Gump\r\nORG:Bubba Gump Shrimp Co.\r\nTITLE:Shrimp Man\r\nPHOTO;MEDIATYPE=image/gif:http://www.example.com/dir_photos/my_photo.gif\r\nTEL;TYPE=work,voice;VALUE=uri:tel:+1-111-555-1212\r\nTEL;TYPE=home,voice;VALUE=uri:tel:+1-404-555-1212\r\nADR;TYPE=WORK;PREF=1;LABEL=\"100 Waters Edge\\nBaytown\\, LA 30314\\nUnited States of America\":;;100 Waters Edge;Baytown;LA;30314;United States of America\r\nADR;TYPE=HOME;LABEL=\"42 Plantation St.\\nBaytown\\, LA 30314\\nUnited States of America\":;;42 Plantation St.;Baytown;LA;30314;United States of America\r\nEMAIL:forrestgump@example.com\r\nREV:20080424T195243Z\r\nx-qq:21588891\r\nEND:VCARD\r\n";
     println!("results = {:#?}", CardDeserializer::from_str(j).unwrap());
 }
+
+#[test]
+fn test_vcard_round_trip() {
+    let mut a = Card::new();
+    a.set_name("Forrest Gump".to_string());
+    a.set_email("forrestgump@example.com".to_string());
+    let mut b = Card::new();
+    b.set_name("Doe, John; \"Jr\" \\ the second".to_string());
+    b.set_email("john@example.com".to_string());
+    b.set_additional_emails(vec!["jdoe@example.org".to_string()]);
+    b.set_additionalname("Johnny".to_string());
+    let mut c = Card::new();
+    c.set_name("Ελένη Παπαδοπούλου with a name long enough to need folding".to_string());
+    c.set_email("eleni@example.com".to_string());
+
+    let dir = crate::TempDir::new("vcard-export");
+    let path = dir.join("export.vcf");
+    export_vcard(&[a.clone(), b.clone(), c.clone()], &path).unwrap();
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert!(contents.lines().all(|l| l.len() <= 75));
+    let imported = import_vcard(&path).unwrap();
+    assert_eq!(imported.len(), 3);
+    for (orig, new) in [a, b, c].iter().zip(imported.iter()) {
+        assert_eq!(orig.name(), new.name());
+        assert_eq!(orig.email(), new.email());
+        assert_eq!(orig.additional_emails(), new.additional_emails());
+        assert_eq!(orig.additionalname(), new.additionalname());
+        assert!(!new.external_resource());
+    }
+    /* Exporting the imported cards and importing them again yields the same set */
+    export_vcard(&imported, &path).unwrap();
+    let reimported = import_vcard(&path).unwrap();
+    assert_eq!(imported.len(), reimported.len());
+    for (a, b) in imported.iter().zip(reimported.iter()) {
+        assert_eq!(a.name(), b.name());
+        assert_eq!(
+            a.emails().collect::<Vec<_>>(),
+            b.emails().collect::<Vec<_>>()
+        );
+    }
+}
+
+#[test]
+fn test_vcard_multiple_emails() {
+    /* vCard 2.1 style entry with LF line endings, a quoted-printable name split by a soft line
+     * break and no FN */
+    let j = "BEGIN:VCARD\nVERSION:2.1\nN;CHARSET=UTF-8;ENCODING=QUOTED-PRINTABLE:M=C3=BCller;J=\n=C3=BCrgen;;;\nEMAIL;HOME:juergen@example.com\nEMAIL;WORK:j.mueller@example.org\nEND:VCARD\n";
+    let dir = crate::TempDir::new("vcard-import");
+    let path = dir.join("import.vcf");
+    std::fs::write(&path, j).unwrap();
+    let cards = import_vcard(&path).unwrap();
+    assert_eq!(cards.len(), 1);
+    assert_eq!(cards[0].name(), "Jürgen Müller");
+    assert_eq!(cards[0].email(), "juergen@example.com");
+    assert_eq!(
+        cards[0].additional_emails(),
+        &["j.mueller@example.org".to_string()]
+    );
+    let mut book = AddressBook::new("test".to_string());
+    for c in cards {
+        book.add_card(c);
+    }
+    assert_eq!(
+        book.search("jürgen"),
+        vec![
+            "Jürgen Müller <juergen@example.com>",
+            "Jürgen Müller <j.mueller@example.org>"
+        ]
+    );
+    assert_eq!(
+        book.search("example.org"),
+        vec!["Jürgen Müller <j.mueller@example.org>"]
+    );
+    /* A single file can also be given as the vcard folder */
+    assert_eq!(load_cards(&path).unwrap().len(), 1);
+
+    std::fs::write(&path, "Not a vCard\n").unwrap();
+    assert!(import_vcard(&path).is_err());
+}
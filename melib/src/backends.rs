@@ -45,6 +45,8 @@ pub mod jmap;
 pub mod maildir;
 #[cfg(feature = "mbox_backend")]
 pub mod mbox;
+#[cfg(feature = "test-utils")]
+pub mod memory;
 #[cfg(feature = "imap_backend")]
 pub use self::imap::ImapType;
 #[cfg(feature = "imap_backend")]
@@ -125,7 +127,8 @@ impl Backends {
                     create_fn: Box::new(|| Box::new(|f, i, ev| MaildirType::new(f, i, ev))),
                     validate_conf_fn: Box::new(MaildirType::validate_config),
                 },
-            );
+            )
+            .unwrap();
         }
         #[cfg(feature = "mbox_backend")]
         {
@@ -135,7 +138,8 @@ impl Backends {
                     create_fn: Box::new(|| Box::new(|f, i, ev| MboxType::new(f, i, ev))),
                     validate_conf_fn: Box::new(MboxType::validate_config),
                 },
-            );
+            )
+            .unwrap();
         }
         #[cfg(feature = "imap_backend")]
         {
@@ -145,14 +149,16 @@ impl Backends {
                     create_fn: Box::new(|| Box::new(|f, i, ev| imap::ImapType::new(f, i, ev))),
                     validate_conf_fn: Box::new(imap::ImapType::validate_config),
                 },
-            );
+            )
+            .unwrap();
             b.register(
                 "nntp".to_string(),
                 Backend {
                     create_fn: Box::new(|| Box::new(|f, i, ev| nntp::NntpType::new(f, i, ev))),
                     validate_conf_fn: Box::new(nntp::NntpType::validate_config),
                 },
-            );
+            )
+            .unwrap();
         }
         #[cfg(feature = "notmuch_backend")]
         {
@@ -163,7 +169,8 @@ impl Backends {
                         create_fn: Box::new(|| Box::new(|f, i, ev| NotmuchDb::new(f, i, ev))),
                         validate_conf_fn: Box::new(NotmuchDb::validate_config),
                     },
-                );
+                )
+                .unwrap();
             }
         }
         #[cfg(feature = "jmap_backend")]
@@ -174,26 +181,39 @@ impl Backends {
                     create_fn: Box::new(|| Box::new(|f, i, ev| jmap::JmapType::new(f, i, ev))),
                     validate_conf_fn: Box::new(jmap::JmapType::validate_config),
                 },
-            );
+            )
+            .unwrap();
         }
         b
     }
 
-    pub fn get(&self, key: &str) -> BackendCreator {
-        if !self.map.contains_key(key) {
-            if key == "notmuch" {
-                eprint!("{}", NOTMUCH_ERROR_MSG);
-            }
-            panic!("{} is not a valid mail backend", key);
+    pub fn get(&self, key: &str) -> Result<BackendCreator> {
+        if let Some(backend) = self.map.get(key) {
+            return Ok((backend.create_fn)());
         }
-        (self.map[key].create_fn)()
+        let mut available = self.map.keys().map(String::as_str).collect::<Vec<&str>>();
+        available.sort_unstable();
+        Err(MeliError::new(format!(
+            "{}{} is not a valid mail backend. Available backends are: {}",
+            if key == "notmuch" {
+                NOTMUCH_ERROR_MSG
+            } else {
+                ""
+            },
+            key,
+            available.join(", ")
+        )))
     }
 
-    pub fn register(&mut self, key: String, backend: Backend) {
+    pub fn register(&mut self, key: String, backend: Backend) -> Result<()> {
         if self.map.contains_key(&key) {
-            panic!("{} is an already registered backend", key);
+            return Err(MeliError::new(format!(
+                "{} is an already registered backend",
+                key
+            )));
         }
         self.map.insert(key, backend);
+        Ok(())
     }
 
     pub fn validate_config(&self, key: &str, s: &AccountSettings) -> Result<()> {
@@ -223,6 +243,13 @@ pub enum BackendEvent {
         level: crate::LoggingLevel,
     },
     Refresh(RefreshEvent),
+    /// Progress of a long running operation, e.g. `done` out of `total` bytes of a message were
+    /// downloaded.
+    Progress {
+        description: String,
+        done: usize,
+        total: usize,
+    },
     //Job(Box<Future<Output = Result<()>> + Send + 'static>)
 }
 
@@ -285,6 +312,8 @@ pub struct MailBackendCapabilities {
     pub supports_search: bool,
     pub supports_tags: bool,
     pub supports_submission: bool,
+    /// Whether `MailBackend::fetch_range` is implemented.
+    pub supports_fetch_range: bool,
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -307,6 +336,17 @@ pub trait MailBackend: ::std::fmt::Debug + Send + Sync {
         mailbox_hash: MailboxHash,
     ) -> Result<Pin<Box<dyn Stream<Item = Result<Vec<Envelope>>> + Send + 'static>>>;
 
+    /// Fetches only the envelopes in the window `from..=to` of `mailbox_hash`, counted from its
+    /// newest message (`0`), instead of the whole mailbox.
+    fn fetch_range(
+        &mut self,
+        _mailbox_hash: MailboxHash,
+        _from: usize,
+        _to: usize,
+    ) -> ResultFuture<Vec<Envelope>> {
+        Err(MeliError::new("Unimplemented."))
+    }
+
     fn refresh(&mut self, mailbox_hash: MailboxHash) -> ResultFuture<()>;
     fn watch(&self) -> ResultFuture<()>;
     fn mailboxes(&self) -> ResultFuture<HashMap<MailboxHash, Mailbox>>;
@@ -431,6 +471,18 @@ pub trait MailBackend: ::std::fmt::Debug + Send + Sync {
 pub trait BackendOp: ::std::fmt::Debug + ::std::marker::Send {
     fn as_bytes(&mut self) -> ResultFuture<Vec<u8>>;
     fn fetch_flags(&self) -> ResultFuture<Flag>;
+
+    /// The MIME structure of the message, if the backend can report it without fetching the
+    /// whole message.
+    fn body_structure(&mut self) -> ResultFuture<BodyStructure> {
+        Err(MeliError::new("Unimplemented."))
+    }
+
+    /// Fetches the still encoded body of a single part, numbered as in `BodyStructure`, without
+    /// fetching the whole message.
+    fn fetch_part(&mut self, _part: &str) -> ResultFuture<Vec<u8>> {
+        Err(MeliError::new("Unimplemented."))
+    }
 }
 
 /// Wrapper for BackendOps that are to be set read-only.
@@ -455,6 +507,96 @@ impl BackendOp for ReadOnlyOp {
     fn fetch_flags(&self) -> ResultFuture<Flag> {
         self.op.fetch_flags()
     }
+    fn body_structure(&mut self) -> ResultFuture<BodyStructure> {
+        self.op.body_structure()
+    }
+    fn fetch_part(&mut self, part: &str) -> ResultFuture<Vec<u8>> {
+        self.op.fetch_part(part)
+    }
+}
+
+/// A node of a message's MIME tree, as reported by a backend without fetching the message (e.g.
+/// from IMAP's `BODYSTRUCTURE`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct BodyStructure {
+    /// Part specifier to use with `BODY[<part_number>]`, e.g. `"1"` or `"2.1"`. Empty for the
+    /// top-level multipart container.
+    pub part_number: String,
+    /// Lowercase `type/subtype`, e.g. `"text/plain"`.
+    pub mime_type: String,
+    pub charset: Option<String>,
+    pub encoding: Option<String>,
+    /// The part's `Content-ID`, which `cid:` URLs refer to.
+    pub id: Option<String>,
+    /// Size of the encoded body in octets. Not reported for multipart containers.
+    pub size: Option<usize>,
+    /// Lowercase disposition type, e.g. `"attachment"` or `"inline"`.
+    pub disposition: Option<String>,
+    pub filename: Option<String>,
+    pub parts: Vec<BodyStructure>,
+}
+
+impl BodyStructure {
+    pub fn is_multipart(&self) -> bool {
+        self.mime_type.starts_with("multipart/")
+    }
+
+    /// Leaf parts that are not meant to be displayed inline as the message text.
+    pub fn attachments(&self) -> Vec<&BodyStructure> {
+        let mut ret = Vec::new();
+        self.collect_attachments(None, &mut ret);
+        ret
+    }
+
+    fn collect_attachments<'s>(&'s self, parent: Option<&str>, ret: &mut Vec<&'s BodyStructure>) {
+        if self.is_multipart() {
+            for p in &self.parts {
+                p.collect_attachments(Some(&self.mime_type), ret);
+            }
+            return;
+        }
+        let is_attachment = match self.disposition.as_deref() {
+            Some("attachment") => true,
+            Some("inline") => false,
+            /* The signature of a multipart/signed message (RFC 1847) */
+            _ if parent == Some("multipart/signed") && self.is_signature() => false,
+            /* Parts that the HTML body refers to with cid: URLs (RFC 2387) */
+            _ if parent == Some("multipart/related") && self.id.is_some() => false,
+            _ => self.filename.is_some() || !self.mime_type.starts_with("text/"),
+        };
+        if is_attachment {
+            ret.push(self);
+        }
+    }
+
+    fn is_signature(&self) -> bool {
+        matches!(
+            self.mime_type.as_str(),
+            "application/pgp-signature"
+                | "application/pkcs7-signature"
+                | "application/x-pkcs7-signature"
+        )
+    }
+
+    pub fn has_non_inline_parts(&self) -> bool {
+        !self.attachments().is_empty()
+    }
+
+    /// The first `text/plain` part that is not an attachment, i.e. the message's text.
+    pub fn text_part(&self) -> Option<&BodyStructure> {
+        let attachments = self.attachments();
+        let mut stack = vec![self];
+        while let Some(part) = stack.pop() {
+            if part.is_multipart() {
+                stack.extend(part.parts.iter().rev());
+            } else if part.mime_type == "text/plain"
+                && !attachments.iter().any(|a| std::ptr::eq(*a, part))
+            {
+                return Some(part);
+            }
+        }
+        None
+    }
 }
 
 #[derive(Debug, Copy, Hash, Eq, Clone, Serialize, Deserialize, PartialEq)]
@@ -530,6 +672,11 @@ pub trait BackendMailbox: Debug {
     fn special_usage(&self) -> SpecialUsageMailbox;
     fn permissions(&self) -> MailboxPermissions;
     fn count(&self) -> Result<(usize, usize)>;
+    /// Number of recent messages, i.e. delivered since the mailbox was last opened, if the
+    /// backend knows it.
+    fn recent(&self) -> Option<usize> {
+        None
+    }
 }
 
 pub type AccountHash = u64;
@@ -680,3 +827,16 @@ fn test_lazy_count_set() {
     }
     assert!(!new.insert_existing(10));
 }
+
+#[test]
+fn test_backends_get_unknown() {
+    let backends = Backends::new();
+    let err = match backends.get("imaps") {
+        Ok(_) => panic!("imaps should not be a registered backend"),
+        Err(err) => err.to_string(),
+    };
+    assert!(err.contains("imaps is not a valid mail backend"), "{}", err);
+    for key in backends.map.keys() {
+        assert!(err.contains(key.as_str()), "{}", err);
+    }
+}
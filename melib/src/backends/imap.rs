@@ -55,6 +55,7 @@ use std::convert::TryInto;
 use std::hash::Hasher;
 use std::pin::Pin;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex, RwLock};
 use std::time::{Duration, SystemTime};
 
@@ -69,6 +70,7 @@ pub static SUPPORTED_CAPABILITIES: &[&str] = &[
     "CONDSTORE",
     "ENABLE",
     "IDLE",
+    "AUTH=XOAUTH2",
     "IMAP4REV1",
     "LIST-EXTENDED",
     "LIST-STATUS",
@@ -84,6 +86,8 @@ pub static SUPPORTED_CAPABILITIES: &[&str] = &[
 pub struct EnvelopeCache {
     bytes: Option<Vec<u8>>,
     flags: Option<Flag>,
+    /// Body sections fetched with `ImapOp::fetch_part`, keyed by part number.
+    parts: HashMap<String, Vec<u8>>,
 }
 
 #[derive(Debug, Clone)]
@@ -95,6 +99,9 @@ pub struct ImapServerConf {
     pub use_starttls: bool,
     pub use_tls: bool,
     pub danger_accept_invalid_certs: bool,
+    /// Authenticate with `AUTHENTICATE XOAUTH2`, using `server_password` as the OAuth2 access
+    /// token.
+    pub use_oauth2: bool,
     pub protocol: ImapProtocol,
     pub timeout: Option<Duration>,
 }
@@ -165,8 +172,13 @@ pub struct UIDStore {
     highestmodseqs: Arc<Mutex<HashMap<MailboxHash, std::result::Result<ModSequence, ()>>>>,
     mailboxes: Arc<FutureMutex<HashMap<MailboxHash, ImapMailbox>>>,
     is_online: Arc<Mutex<(SystemTime, Result<()>)>>,
+    /// Consecutive failed connection attempts, for the reconnection backoff.
+    reconnect_failures: Arc<Mutex<usize>>,
     event_consumer: BackendEventConsumer,
     timeout: Option<Duration>,
+    /// Seconds between polls when IDLE is not available, `0` for manual refresh only. It is read
+    /// before every wait so that it can be changed without reconnecting.
+    poll_interval: Arc<AtomicU64>,
 }
 
 impl UIDStore {
@@ -196,10 +208,21 @@ impl UIDStore {
                 SystemTime::now(),
                 Err(MeliError::new("Account is uninitialised.")),
             ))),
+            reconnect_failures: Default::default(),
             event_consumer,
             timeout,
+            poll_interval: Arc::new(AtomicU64::new(IMAP_DEFAULT_POLL_INTERVAL)),
         }
     }
+
+    /// Returns the UIDs of the envelopes in `env_hashes`, skipping those not in `hash_index`.
+    fn uids(&self, env_hashes: &EnvelopeHashBatch) -> SmallVec<[UID; 64]> {
+        let hash_index_lck = self.hash_index.lock().unwrap();
+        env_hashes
+            .iter()
+            .filter_map(|env_hash| hash_index_lck.get(&env_hash).cloned().map(|(uid, _)| uid))
+            .collect()
+    }
 }
 
 #[derive(Debug)]
@@ -292,6 +315,7 @@ impl MailBackend for ImapType {
             extensions: Some(extensions),
             supports_tags: true,
             supports_submission: false,
+            supports_fetch_range: true,
         }
     }
 
@@ -355,6 +379,50 @@ impl MailBackend for ImapType {
         }))
     }
 
+    fn fetch_range(
+        &mut self,
+        mailbox_hash: MailboxHash,
+        from: usize,
+        to: usize,
+    ) -> ResultFuture<Vec<Envelope>> {
+        let connection = self.connection.clone();
+        let uid_store = self.uid_store.clone();
+        Ok(Box::pin(async move {
+            if from > to {
+                return Ok(vec![]);
+            }
+            let uidnext = {
+                let mut conn = connection.lock().await;
+                conn.connect().await?;
+                let mut response = Vec::with_capacity(8 * 1024);
+                conn.examine_mailbox(mailbox_hash, &mut response, true)
+                    .await?
+                    .map(|select_response| select_response.uidnext)
+                    .unwrap_or(0)
+            };
+            /* Like the full fetch, count UIDs down from the newest one */
+            let max_uid = uidnext.saturating_sub(1).saturating_sub(from);
+            if max_uid == 0 {
+                return Ok(vec![]);
+            }
+            let mut state = FetchState {
+                stage: FetchStage::FreshFetch {
+                    max_uid,
+                    min_uid: uidnext.saturating_sub(1).saturating_sub(to),
+                },
+                connection,
+                mailbox_hash,
+                uid_store,
+                cache_handle: None,
+            };
+            let mut ret = vec![];
+            while state.stage != FetchStage::Finished {
+                ret.extend(fetch_hlpr(&mut state).await?);
+            }
+            Ok(ret)
+        }))
+    }
+
     fn refresh(&mut self, mailbox_hash: MailboxHash) -> ResultFuture<()> {
         let main_conn = self.connection.clone();
         let uid_store = self.uid_store.clone();
@@ -365,7 +433,20 @@ impl MailBackend for ImapType {
                 .map(std::clone::Clone::clone)
                 .unwrap();
             let mut conn = timeout(uid_store.timeout, main_conn.lock()).await?;
-            watch::examine_updates(inbox, &mut conn, &uid_store).await?;
+            match conn.stream.as_ref()?.current_mailbox {
+                MailboxSelection::Select(h) | MailboxSelection::Examine(h) if h == mailbox_hash => {
+                    /* The mailbox is already selected: a NOOP is enough for the server to send
+                     * any pending EXISTS/EXPUNGE/FETCH untagged responses, which are turned into
+                     * refresh events by `process_untagged` while reading the response. */
+                    let mut response = Vec::with_capacity(8 * 1024);
+                    conn.send_command(b"NOOP").await?;
+                    conn.read_response(&mut response, RequiredResponses::NO_REQUIRED)
+                        .await?;
+                }
+                _ => {
+                    watch::examine_updates(inbox, &mut conn, &uid_store).await?;
+                }
+            }
             Ok(())
         }))
     }
@@ -458,7 +539,7 @@ impl MailBackend for ImapType {
         };
         Ok(Box::pin(async move {
             debug!(has_idle);
-            while let Err(err) = if has_idle {
+            'watch: while let Err(err) = if has_idle {
                 idle(ImapWatchKit {
                     conn: ImapConnection::new_connection(&server_conf, uid_store.clone()),
                     main_conn: main_conn.clone(),
@@ -473,23 +554,54 @@ impl MailBackend for ImapType {
                 })
                 .await
             } {
-                let mut main_conn_lck = timeout(uid_store.timeout, main_conn.lock()).await?;
                 if err.kind.is_network() {
                     uid_store.is_online.lock().unwrap().1 = Err(err.clone());
                 }
                 debug!("failure: {}", err.to_string());
-                match timeout(uid_store.timeout, main_conn_lck.connect())
-                    .await
-                    .and_then(|res| res)
-                {
-                    Err(err2) => {
-                        debug!("reconnect attempt failed: {}", err2.to_string());
-                    }
-                    Ok(()) => {
-                        debug!("reconnect attempt succesful");
-                        continue;
+                /* Keep retrying while the connection is down; the next round of the loop
+                 * re-SELECTs and re-IDLEs on a new connection. */
+                let mut main_conn_lck = loop {
+                    let mut main_conn_lck = timeout(uid_store.timeout, main_conn.lock()).await?;
+                    let result = match timeout(uid_store.timeout, main_conn_lck.connect()).await {
+                        Ok(result) => result,
+                        Err(err) => {
+                            /* connect() was cancelled before it could count the failure */
+                            *uid_store.reconnect_failures.lock().unwrap() += 1;
+                            Err(err)
+                        }
+                    };
+                    match result {
+                        Err(err2) if err2.kind.is_network() || err2.kind.is_timeout() => {
+                            debug!("reconnect attempt failed: {}", err2.to_string());
+                            drop(main_conn_lck);
+                            let wait = reconnect_backoff(std::cmp::max(
+                                1,
+                                *uid_store.reconnect_failures.lock().unwrap(),
+                            ));
+                            (uid_store.event_consumer)(
+                                uid_store.account_hash,
+                                BackendEvent::Notice {
+                                    description: None,
+                                    content: format!(
+                                        "{}: connection lost, retrying in {}s",
+                                        uid_store.account_name,
+                                        wait.as_secs()
+                                    ),
+                                    level: crate::LoggingLevel::INFO,
+                                },
+                            );
+                            smol::Timer::after(wait).await;
+                        }
+                        Err(err2) => {
+                            debug!("reconnect attempt failed: {}", err2.to_string());
+                            break main_conn_lck;
+                        }
+                        Ok(()) => {
+                            debug!("reconnect attempt succesful");
+                            continue 'watch;
+                        }
                     }
-                }
+                };
                 let account_hash = uid_store.account_hash;
                 main_conn_lck.add_refresh_event(RefreshEvent {
                     account_hash,
@@ -527,63 +639,11 @@ impl MailBackend for ImapType {
         mailbox_hash: MailboxHash,
         flags: Option<Flag>,
     ) -> ResultFuture<()> {
-        let uid_store = self.uid_store.clone();
-        let connection = self.connection.clone();
+        let fut = self.save_with_date(bytes, mailbox_hash, flags, None)?;
         Ok(Box::pin(async move {
-            let mut response = Vec::with_capacity(8 * 1024);
-            let mut conn = connection.lock().await;
-            conn.select_mailbox(mailbox_hash, &mut response, true)
-                .await?;
-            let path = {
-                let mailboxes = uid_store.mailboxes.lock().await;
-
-                let mailbox = mailboxes.get(&mailbox_hash).ok_or_else(|| {
-                    MeliError::new(format!("Mailbox with hash {} not found.", mailbox_hash))
-                })?;
-                if !mailbox.permissions.lock().unwrap().create_messages {
-                    return Err(MeliError::new(format!(
-                        "You are not allowed to create messages in mailbox {}",
-                        mailbox.path()
-                    )));
-                }
-
-                mailbox.imap_path().to_string()
-            };
-            let flags = flags.unwrap_or_else(Flag::empty);
-            let has_literal_plus: bool = uid_store
-                .capabilities
-                .lock()
-                .unwrap()
-                .iter()
-                .any(|cap| cap.eq_ignore_ascii_case(b"LITERAL+"));
-            if has_literal_plus {
-                conn.send_command(
-                    format!(
-                        "APPEND \"{}\" ({}) {{{}+}}",
-                        &path,
-                        flags_to_imap_list!(flags),
-                        bytes.len()
-                    )
-                    .as_bytes(),
-                )
-                .await?;
-            } else {
-                conn.send_command(
-                    format!(
-                        "APPEND \"{}\" ({}) {{{}}}",
-                        &path,
-                        flags_to_imap_list!(flags),
-                        bytes.len()
-                    )
-                    .as_bytes(),
-                )
-                .await?;
-                // wait for "+ Ready for literal data" reply
-                conn.wait_for_continuation_request().await?;
-            }
-            conn.send_literal(&bytes).await?;
-            conn.read_response(&mut response, RequiredResponses::empty())
-                .await?;
+            /* The UID, if any, is already recorded in `hash_index`, so that the message can be
+             * found when the server notifies us of it. */
+            let _uid: Option<UID> = fut.await?;
             Ok(())
         }))
     }
@@ -605,15 +665,7 @@ impl MailBackend for ImapType {
                 .iter()
                 .any(|cap| cap.eq_ignore_ascii_case(b"MOVE"));
         Ok(Box::pin(async move {
-            let uids: SmallVec<[UID; 64]> = {
-                let hash_index_lck = uid_store.hash_index.lock().unwrap();
-                env_hashes
-                    .iter()
-                    .filter_map(|env_hash| {
-                        hash_index_lck.get(&env_hash).cloned().map(|(uid, _)| uid)
-                    })
-                    .collect()
-            };
+            let uids = uid_store.uids(&env_hashes);
 
             if uids.is_empty() {
                 return Ok(());
@@ -630,35 +682,17 @@ impl MailBackend for ImapType {
             conn.select_mailbox(source_mailbox_hash, &mut response, false)
                 .await?;
             if has_move {
-                let command = {
-                    let mut cmd = format!("UID MOVE {}", uids[0]);
-                    for uid in uids.iter().skip(1) {
-                        cmd = format!("{},{}", cmd, uid);
-                    }
-                    format!("{} \"{}\"", cmd, dest_path)
-                };
+                let command = uid_copy_command(&uids, &dest_path, true);
                 conn.send_command(command.as_bytes()).await?;
                 conn.read_response(&mut response, RequiredResponses::empty())
                     .await?;
             } else {
-                let command = {
-                    let mut cmd = format!("UID COPY {}", uids[0]);
-                    for uid in uids.iter().skip(1) {
-                        cmd = format!("{},{}", cmd, uid);
-                    }
-                    format!("{} \"{}\"", cmd, dest_path)
-                };
+                let command = uid_copy_command(&uids, &dest_path, false);
                 conn.send_command(command.as_bytes()).await?;
                 conn.read_response(&mut response, RequiredResponses::empty())
                     .await?;
                 if move_ {
-                    let command = {
-                        let mut cmd = format!("UID STORE {}", uids[0]);
-                        for uid in uids.iter().skip(1) {
-                            cmd = format!("{},{}", cmd, uid);
-                        }
-                        format!("{} +FLAGS (\\Deleted)", cmd)
-                    };
+                    let command = format!("UID STORE {} +FLAGS (\\Deleted)", uid_set(&uids));
                     conn.send_command(command.as_bytes()).await?;
                     conn.read_response(&mut response, RequiredResponses::empty())
                         .await?;
@@ -677,15 +711,7 @@ impl MailBackend for ImapType {
         let connection = self.connection.clone();
         let uid_store = self.uid_store.clone();
         Ok(Box::pin(async move {
-            let uids: SmallVec<[UID; 64]> = {
-                let hash_index_lck = uid_store.hash_index.lock().unwrap();
-                env_hashes
-                    .iter()
-                    .filter_map(|env_hash| {
-                        hash_index_lck.get(&env_hash).cloned().map(|(uid, _)| uid)
-                    })
-                    .collect()
-            };
+            let uids = uid_store.uids(&env_hashes);
 
             if uids.is_empty() {
                 return Ok(());
@@ -695,111 +721,45 @@ impl MailBackend for ImapType {
             let mut conn = connection.lock().await;
             conn.select_mailbox(mailbox_hash, &mut response, false)
                 .await?;
-            if flags.iter().any(|(_, b)| *b) {
-                /* Set flags/tags to true */
-                let command = {
-                    let mut tag_lck = uid_store.tag_index.write().unwrap();
-                    let mut cmd = format!("UID STORE {}", uids[0]);
-                    for uid in uids.iter().skip(1) {
-                        cmd = format!("{},{}", cmd, uid);
-                    }
-                    cmd = format!("{} +FLAGS (", cmd);
-                    for (f, v) in flags.iter() {
-                        if !*v {
-                            continue;
-                        }
-                        match f {
-                            Ok(flag) if *flag == Flag::REPLIED => {
-                                cmd.push_str("\\Answered ");
-                            }
-                            Ok(flag) if *flag == Flag::FLAGGED => {
-                                cmd.push_str("\\Flagged ");
-                            }
-                            Ok(flag) if *flag == Flag::TRASHED => {
-                                cmd.push_str("\\Deleted ");
-                            }
-                            Ok(flag) if *flag == Flag::SEEN => {
-                                cmd.push_str("\\Seen ");
-                            }
-                            Ok(flag) if *flag == Flag::DRAFT => {
-                                cmd.push_str("\\Draft ");
-                            }
-                            Ok(_) => {
-                                crate::log(format!("Application error: more than one flag bit set in set_flags: {:?}", flags), crate::ERROR);
-                                return Err(MeliError::new(format!("Application error: more than one flag bit set in set_flags: {:?}", flags)).set_kind(crate::ErrorKind::Bug));
-                            }
-                            Err(tag) => {
-                                let hash = tag_hash!(tag);
-                                if !tag_lck.contains_key(&hash) {
-                                    tag_lck.insert(hash, tag.to_string());
-                                }
-                                cmd.push_str(tag);
-                                cmd.push(' ');
-                            }
+            {
+                /* Keywords that are not already known to the server can only be stored if
+                 * PERMANENTFLAGS includes \* */
+                let mailboxes = uid_store.mailboxes.lock().await;
+                let select = mailboxes[&mailbox_hash].select.read().unwrap();
+                if let Some(select) = select.as_ref() {
+                    if let Some(tag) = flags.iter().find_map(|(f, v)| match f {
+                        Err(tag)
+                            if *v
+                                && !select.can_create_flags
+                                && !select.permanentflags.1.contains(tag) =>
+                        {
+                            Some(tag)
                         }
+                        _ => None,
+                    }) {
+                        return Err(MeliError::new(format!(
+                            "Mailbox {} does not allow creating new keywords such as `{}`.",
+                            mailboxes[&mailbox_hash].path(),
+                            tag
+                        )));
                     }
-                    // pop last space
-                    cmd.pop();
-                    cmd.push(')');
-                    cmd
-                };
-                conn.send_command(command.as_bytes()).await?;
-                conn.read_response(&mut response, RequiredResponses::empty())
-                    .await?;
+                }
             }
-            if flags.iter().any(|(_, b)| !*b) {
-                /* Set flags/tags to false */
-                let command = {
-                    let mut cmd = format!("UID STORE {}", uids[0]);
-                    for uid in uids.iter().skip(1) {
-                        cmd = format!("{},{}", cmd, uid);
-                    }
-                    cmd = format!("{} -FLAGS (", cmd);
-                    for (f, v) in flags.iter() {
-                        if *v {
-                            continue;
-                        }
-                        match f {
-                            Ok(flag) if *flag == Flag::REPLIED => {
-                                cmd.push_str("\\Answered ");
-                            }
-                            Ok(flag) if *flag == Flag::FLAGGED => {
-                                cmd.push_str("\\Flagged ");
-                            }
-                            Ok(flag) if *flag == Flag::TRASHED => {
-                                cmd.push_str("\\Deleted ");
-                            }
-                            Ok(flag) if *flag == Flag::SEEN => {
-                                cmd.push_str("\\Seen ");
-                            }
-                            Ok(flag) if *flag == Flag::DRAFT => {
-                                cmd.push_str("\\Draft ");
-                            }
-                            Ok(_) => {
-                                crate::log(
-                                    format!(
-                        "Application error: more than one flag bit set in set_flags: {:?}", flags
-                    ),
-                                    crate::ERROR,
-                                );
-                                return Err(MeliError::new(format!(
-                        "Application error: more than one flag bit set in set_flags: {:?}", flags
-                    )));
-                            }
-                            Err(tag) => {
-                                cmd.push_str(tag);
-                                cmd.push(' ');
-                            }
-                        }
+            {
+                let mut tag_lck = uid_store.tag_index.write().unwrap();
+                for (f, v) in flags.iter() {
+                    if let (Err(tag), true) = (f, v) {
+                        let hash = tag_hash!(tag);
+                        tag_lck.entry(hash).or_insert_with(|| tag.to_string());
                     }
-                    // pop last space
-                    cmd.pop();
-                    cmd.push(')');
-                    cmd
-                };
-                conn.send_command(command.as_bytes()).await?;
-                conn.read_response(&mut response, RequiredResponses::empty())
-                    .await?;
+                }
+            }
+            for value in &[true, false] {
+                if let Some(command) = uid_store_command(&uids, &flags, *value)? {
+                    conn.send_command(command.as_bytes()).await?;
+                    conn.read_response(&mut response, RequiredResponses::empty())
+                        .await?;
+                }
             }
             Ok(())
         }))
@@ -810,19 +770,30 @@ impl MailBackend for ImapType {
         env_hashes: EnvelopeHashBatch,
         mailbox_hash: MailboxHash,
     ) -> ResultFuture<()> {
-        let flag_future = self.set_flags(
-            env_hashes,
-            mailbox_hash,
-            smallvec::smallvec![(Ok(Flag::TRASHED), true)],
-        )?;
         let connection = self.connection.clone();
+        let uid_store = self.uid_store.clone();
+        let has_uidplus: bool = uid_store
+            .capabilities
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|cap| cap.eq_ignore_ascii_case(b"UIDPLUS"));
         Ok(Box::pin(async move {
-            flag_future.await?;
+            let uids = uid_store.uids(&env_hashes);
+            if uids.is_empty() {
+                return Ok(());
+            }
             let mut response = Vec::with_capacity(8 * 1024);
             let mut conn = connection.lock().await;
-            conn.send_command("EXPUNGE".as_bytes()).await?;
-            conn.read_response(&mut response, RequiredResponses::empty())
+            conn.select_mailbox(mailbox_hash, &mut response, false)
                 .await?;
+            /* The untagged EXPUNGE responses are turned into Remove refresh events by
+             * `process_untagged` while reading the response. */
+            for cmd in delete_commands(&uids, has_uidplus) {
+                conn.send_command(cmd.as_bytes()).await?;
+                conn.read_response(&mut response, RequiredResponses::empty())
+                    .await?;
+            }
             debug!("EXPUNGE response: {}", &String::from_utf8_lossy(&response));
             Ok(())
         }))
@@ -974,19 +945,13 @@ impl MailBackend for ImapType {
                 if mailboxes[&mailbox_hash].is_subscribed() == new_val {
                     return Ok(());
                 }
-                command = format!("SUBSCRIBE \"{}\"", mailboxes[&mailbox_hash].imap_path());
+                command = subscription_command(mailboxes[&mailbox_hash].imap_path(), new_val);
             }
 
             let mut response = Vec::with_capacity(8 * 1024);
             {
                 let mut conn_lck = connection.lock().await;
-                if new_val {
-                    conn_lck.send_command(command.as_bytes()).await?;
-                } else {
-                    conn_lck
-                        .send_command(format!("UN{}", command).as_bytes())
-                        .await?;
-                }
+                conn_lck.send_command(command.as_bytes()).await?;
                 conn_lck
                     .read_response(&mut response, RequiredResponses::empty())
                     .await?;
@@ -1083,94 +1048,14 @@ impl MailBackend for ImapType {
             ));
         }
         let mailbox_hash = mailbox_hash.unwrap();
-        fn rec(q: &crate::search::Query, s: &mut String) {
-            use crate::search::{escape_double_quote, Query::*};
-            match q {
-                Subject(t) => {
-                    s.push_str(" SUBJECT \"");
-                    s.extend(escape_double_quote(t).chars());
-                    s.push_str("\"");
-                }
-                From(t) => {
-                    s.push_str(" FROM \"");
-                    s.extend(escape_double_quote(t).chars());
-                    s.push_str("\"");
-                }
-                To(t) => {
-                    s.push_str(" TO \"");
-                    s.extend(escape_double_quote(t).chars());
-                    s.push_str("\"");
-                }
-                Cc(t) => {
-                    s.push_str(" CC \"");
-                    s.extend(escape_double_quote(t).chars());
-                    s.push_str("\"");
-                }
-                Bcc(t) => {
-                    s.push_str(" BCC \"");
-                    s.extend(escape_double_quote(t).chars());
-                    s.push_str("\"");
-                }
-                AllText(t) => {
-                    s.push_str(" TEXT \"");
-                    s.extend(escape_double_quote(t).chars());
-                    s.push_str("\"");
-                }
-                Flags(v) => {
-                    for f in v {
-                        match f.as_str() {
-                            "draft" => {
-                                s.push_str(" DRAFT ");
-                            }
-                            "deleted" => {
-                                s.push_str(" DELETED ");
-                            }
-                            "flagged" => {
-                                s.push_str(" FLAGGED ");
-                            }
-                            "recent" => {
-                                s.push_str(" RECENT ");
-                            }
-                            "seen" | "read" => {
-                                s.push_str(" SEEN ");
-                            }
-                            "unseen" | "unread" => {
-                                s.push_str(" UNSEEN ");
-                            }
-                            "answered" => {
-                                s.push_str(" ANSWERED ");
-                            }
-                            "unanswered" => {
-                                s.push_str(" UNANSWERED ");
-                            }
-                            keyword => {
-                                s.push_str(" KEYWORD ");
-                                s.push_str(keyword);
-                                s.push_str(" ");
-                            }
-                        }
-                    }
-                }
-                And(q1, q2) => {
-                    rec(q1, s);
-                    s.push_str(" ");
-                    rec(q2, s);
-                }
-                Or(q1, q2) => {
-                    s.push_str(" OR ");
-                    rec(q1, s);
-                    s.push_str(" ");
-                    rec(q2, s);
-                }
-                Not(q) => {
-                    s.push_str(" NOT ");
-                    rec(q, s);
-                }
-                _ => {}
-            }
-        }
-        let mut query_str = String::new();
-        rec(&query, &mut query_str);
+        let has_esearch: bool = self
+            .uid_store
+            .capabilities
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|cap| cap.eq_ignore_ascii_case(b"ESEARCH"));
+        let command = uid_search_command(&query, has_esearch);
         let connection = self.connection.clone();
         let uid_store = self.uid_store.clone();
 
@@ -1179,18 +1064,26 @@ impl MailBackend for ImapType {
             let mut conn = connection.lock().await;
             conn.examine_mailbox(mailbox_hash, &mut response, false)
                 .await?;
-            conn.send_command(format!("UID SEARCH CHARSET UTF-8 {}", query_str).as_bytes())
-                .await?;
+            conn.send_command(command.as_bytes()).await?;
             conn.read_response(&mut response, RequiredResponses::SEARCH)
                 .await?;
             debug!(
-                "searching for {} returned: {}",
-                query_str,
+                "{} returned: {}",
+                command,
                 String::from_utf8_lossy(&response)
             );
 
             for l in response.split_rn() {
-                if l.starts_with(b"* SEARCH") {
+                if l.starts_with(b"* ESEARCH") {
+                    let (_, esearch) = protocol_parser::esearch_results(l)?;
+                    let uid_index = uid_store.uid_index.lock()?;
+                    return Ok(esearch
+                        .all
+                        .into_iter()
+                        .filter_map(|uid| uid_index.get(&(mailbox_hash, uid)))
+                        .copied()
+                        .collect());
+                } else if l.starts_with(b"* SEARCH") {
                     use std::iter::FromIterator;
                     let uid_index = uid_store.uid_index.lock()?;
                     return Ok(SmallVec::from_iter(
@@ -1244,6 +1137,7 @@ impl ImapType {
         let use_starttls = use_tls && get_conf_val!(s["use_starttls"], !(server_port == 993))?;
         let danger_accept_invalid_certs: bool =
             get_conf_val!(s["danger_accept_invalid_certs"], false)?;
+        let use_oauth2: bool = get_conf_val!(s["use_oauth2"], false)?;
         #[cfg(feature = "sqlite3")]
         let keep_offline_cache = get_conf_val!(s["offline_cache"], true)?;
         #[cfg(not(feature = "sqlite3"))]
@@ -1269,6 +1163,7 @@ impl ImapType {
             use_tls,
             use_starttls,
             danger_accept_invalid_certs,
+            use_oauth2,
             protocol: ImapProtocol::IMAP {
                 extension_use: ImapExtensionUse {
                     idle: get_conf_val!(s["use_idle"], true)?,
@@ -1284,9 +1179,11 @@ impl ImapType {
             hasher.write(s.name.as_bytes());
             hasher.finish()
         };
+        let poll_interval = get_conf_val!(s["poll_interval"], IMAP_DEFAULT_POLL_INTERVAL)?;
         let account_name = Arc::new(s.name().to_string());
         let uid_store: Arc<UIDStore> = Arc::new(UIDStore {
             keep_offline_cache,
+            poll_interval: Arc::new(AtomicU64::new(poll_interval)),
             ..UIDStore::new(
                 account_hash,
                 account_name,
@@ -1304,6 +1201,84 @@ impl ImapType {
         }))
     }
 
+    /// Append a message to a mailbox. If `date` is given, it is sent as the message's internal
+    /// date instead of letting the server use the current time. Returns the UID of the new
+    /// message if the server supports UIDPLUS.
+    pub fn save_with_date(
+        &self,
+        bytes: Vec<u8>,
+        mailbox_hash: MailboxHash,
+        flags: Option<Flag>,
+        date: Option<crate::UnixTimestamp>,
+    ) -> ResultFuture<Option<UID>> {
+        let uid_store = self.uid_store.clone();
+        let connection = self.connection.clone();
+        Ok(Box::pin(async move {
+            let mut response = Vec::with_capacity(8 * 1024);
+            let mut conn = connection.lock().await;
+            conn.select_mailbox(mailbox_hash, &mut response, true)
+                .await?;
+            let path = {
+                let mailboxes = uid_store.mailboxes.lock().await;
+
+                let mailbox = mailboxes.get(&mailbox_hash).ok_or_else(|| {
+                    MeliError::new(format!("Mailbox with hash {} not found.", mailbox_hash))
+                })?;
+                if !mailbox.permissions.lock().unwrap().create_messages {
+                    return Err(MeliError::new(format!(
+                        "You are not allowed to create messages in mailbox {}",
+                        mailbox.path()
+                    )));
+                }
+
+                mailbox.imap_path().to_string()
+            };
+            let flags = flags.unwrap_or_else(Flag::empty);
+            let has_literal_plus: bool = uid_store
+                .capabilities
+                .lock()
+                .unwrap()
+                .iter()
+                .any(|cap| cap.eq_ignore_ascii_case(b"LITERAL+"));
+            conn.send_command(
+                append_command(&path, flags, date, bytes.len(), has_literal_plus).as_bytes(),
+            )
+            .await?;
+            if !has_literal_plus {
+                // wait for "+ Ready for literal data" reply
+                conn.wait_for_continuation_request().await?;
+            }
+            conn.send_literal(&bytes).await?;
+            conn.read_response(&mut response, RequiredResponses::empty())
+                .await?;
+            /* With UIDPLUS the server tells us the UID of the appended message */
+            let (uidvalidity, uid) = match ImapResponse::try_from(response.as_slice()) {
+                Ok(ImapResponse::Ok(ResponseCode::Appenduid(uidvalidity, uid))) => {
+                    (uidvalidity, uid)
+                }
+                _ => return Ok(None),
+            };
+            if uid_store
+                .uidvalidity
+                .lock()
+                .unwrap()
+                .get(&mailbox_hash)
+                .map(|&v| v == uidvalidity)
+                .unwrap_or(true)
+            {
+                /* Only `hash_index` is updated; the envelope is added to `uid_index` when the
+                 * server notifies us of it, otherwise it would be considered already known. */
+                let env_hash = generate_envelope_hash(&path, &uid);
+                uid_store
+                    .hash_index
+                    .lock()
+                    .unwrap()
+                    .insert(env_hash, (uid, mailbox_hash));
+            }
+            Ok(Some(uid))
+        }))
+    }
+
     pub fn shell(&mut self) {
         let mut conn = ImapConnection::new_connection(&self.server_conf, self.uid_store.clone());
 
@@ -1481,6 +1456,7 @@ impl ImapType {
             )));
         }
         get_conf_val!(s["danger_accept_invalid_certs"], false)?;
+        get_conf_val!(s["use_oauth2"], false)?;
         #[cfg(feature = "sqlite3")]
         get_conf_val!(s["offline_cache"], true)?;
         #[cfg(not(feature = "sqlite3"))]
@@ -1505,9 +1481,18 @@ impl ImapType {
             )));
         }
         let _timeout = get_conf_val!(s["timeout"], 16_u64)?;
+        get_conf_val!(s["poll_interval"], IMAP_DEFAULT_POLL_INTERVAL)?;
         Ok(())
     }
 
+    /// Sets the seconds between polls of servers without IDLE support, `0` for manual refresh
+    /// only. Takes effect at the next poll, without reconnecting.
+    pub fn set_poll_interval(&self, poll_interval: u64) {
+        self.uid_store
+            .poll_interval
+            .store(poll_interval, Ordering::Relaxed);
+    }
+
     pub fn capabilities(&self) -> Vec<String> {
         self.uid_store
             .capabilities
@@ -1524,7 +1509,7 @@ enum FetchStage {
     InitialFresh,
     InitialCache,
     ResyncCache,
-    FreshFetch { max_uid: UID },
+    FreshFetch { max_uid: UID, min_uid: UID },
     Finished,
 }
 
@@ -1565,6 +1550,7 @@ async fn fetch_hlpr(state: &mut FetchState) -> Result<Vec<Envelope>> {
                 }
                 state.stage = FetchStage::FreshFetch {
                     max_uid: select_response.uidnext - 1,
+                    min_uid: 1,
                 };
                 continue;
             }
@@ -1632,7 +1618,7 @@ async fn fetch_hlpr(state: &mut FetchState) -> Result<Vec<Envelope>> {
                 state.stage = FetchStage::InitialFresh;
                 continue;
             }
-            FetchStage::FreshFetch { max_uid } => {
+            FetchStage::FreshFetch { max_uid, min_uid } => {
                 let FetchState {
                     ref mut stage,
                     ref connection,
@@ -1659,7 +1645,9 @@ async fn fetch_hlpr(state: &mut FetchState) -> Result<Vec<Envelope>> {
                 debug!("locked for fetch {}", mailbox_path);
                 let mut response = Vec::with_capacity(8 * 1024);
                 let max_uid_left = max_uid;
+                let min_uid = std::cmp::max(min_uid, 1);
                 let chunk_size = 250;
+                let chunk_start = std::cmp::max(max_uid_left.saturating_sub(chunk_size), min_uid);
 
                 let mut payload = vec![];
                 conn.examine_mailbox(mailbox_hash, &mut response, false)
@@ -1667,18 +1655,7 @@ async fn fetch_hlpr(state: &mut FetchState) -> Result<Vec<Envelope>> {
                 if max_uid_left > 0 {
                     let mut envelopes = vec![];
                     debug!("{} max_uid_left= {}", mailbox_hash, max_uid_left);
-                    let command = if max_uid_left == 1 {
-                        "UID FETCH 1 (UID FLAGS ENVELOPE BODY.PEEK[HEADER.FIELDS (REFERENCES)] BODYSTRUCTURE)".to_string()
-                    } else {
-                        format!(
-                            "UID FETCH {}:{} (UID FLAGS ENVELOPE BODY.PEEK[HEADER.FIELDS (REFERENCES)] BODYSTRUCTURE)",
-                            std::cmp::max(
-                                std::cmp::max(max_uid_left.saturating_sub(chunk_size), 1),
-                                1
-                            ),
-                            max_uid_left
-                        )
-                    };
+                    let command = uid_fetch_envelopes_command(chunk_start, max_uid_left);
                     debug!("sending {:?}", &command);
                     conn.send_command(command.as_bytes()).await?;
                     conn.read_response(&mut response, RequiredResponses::FETCH_REQUIRED)
@@ -1812,14 +1789,12 @@ async fn fetch_hlpr(state: &mut FetchState) -> Result<Vec<Envelope>> {
                     drop(conn);
                     payload.extend(envelopes.into_iter().map(|(_, env)| env));
                 }
-                if max_uid_left <= 1 {
+                if chunk_start <= min_uid {
                     *stage = FetchStage::Finished;
                 } else {
                     *stage = FetchStage::FreshFetch {
-                        max_uid: std::cmp::max(
-                            std::cmp::max(max_uid_left.saturating_sub(chunk_size), 1),
-                            1,
-                        ),
+                        max_uid: chunk_start - 1,
+                        min_uid,
                     };
                 }
                 return Ok(payload);
@@ -1830,3 +1805,615 @@ async fn fetch_hlpr(state: &mut FetchState) -> Result<Vec<Envelope>> {
         }
     }
 }
+
+/// Builds the `UID FETCH` command that retrieves the envelopes of the UIDs `from..=to`.
+fn uid_fetch_envelopes_command(from: UID, to: UID) -> String {
+    let set = if from >= to {
+        to.to_string()
+    } else {
+        format!("{}:{}", from, to)
+    };
+    format!(
+        "UID FETCH {} (UID FLAGS RFC822.SIZE INTERNALDATE ENVELOPE BODY.PEEK[HEADER.FIELDS (REFERENCES)] BODYSTRUCTURE)",
+        set
+    )
+}
+
+/// Formats `uids` as an IMAP sequence set, e.g. `1,2,3`.
+fn uid_set(uids: &[UID]) -> String {
+    uids.iter()
+        .map(|uid| uid.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/// Builds the `UID COPY` (or `UID MOVE`, if the server supports it and `move_` is set) command
+/// that transfers `uids` to the mailbox at `dest_path` on the server side.
+fn uid_copy_command(uids: &[UID], dest_path: &str, move_: bool) -> String {
+    format!(
+        "UID {} {} \"{}\"",
+        if move_ { "MOVE" } else { "COPY" },
+        uid_set(uids),
+        dest_path
+    )
+}
+
+/// Builds the `UID STORE` command that adds (if `value` is true) or removes the flags and keywords
+/// of `flags` whose value equals `value`. Returns `None` if there are none.
+fn uid_store_command(
+    uids: &[UID],
+    flags: &[(std::result::Result<Flag, String>, bool)],
+    value: bool,
+) -> Result<Option<String>> {
+    let mut list = vec![];
+    for (f, _) in flags.iter().filter(|(_, v)| *v == value) {
+        match f {
+            Ok(flag) if *flag == Flag::REPLIED => list.push("\\Answered"),
+            Ok(flag) if *flag == Flag::FLAGGED => list.push("\\Flagged"),
+            Ok(flag) if *flag == Flag::TRASHED => list.push("\\Deleted"),
+            Ok(flag) if *flag == Flag::SEEN => list.push("\\Seen"),
+            Ok(flag) if *flag == Flag::DRAFT => list.push("\\Draft"),
+            Ok(_) => {
+                crate::log(
+                    format!(
+                        "Application error: more than one flag bit set in set_flags: {:?}",
+                        flags
+                    ),
+                    crate::ERROR,
+                );
+                return Err(MeliError::new(format!(
+                    "Application error: more than one flag bit set in set_flags: {:?}",
+                    flags
+                ))
+                .set_kind(crate::ErrorKind::Bug));
+            }
+            Err(tag) => list.push(tag.as_str()),
+        }
+    }
+    if list.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(format!(
+        "UID STORE {} {}FLAGS ({})",
+        uid_set(uids),
+        if value { "+" } else { "-" },
+        list.join(" ")
+    )))
+}
+
+/// Builds the commands that mark `uids` as `\Deleted` and expunge them. Without `UIDPLUS` a plain
+/// `EXPUNGE` is issued, which also removes any other message already marked as deleted in the
+/// mailbox.
+fn delete_commands(uids: &[UID], uidplus: bool) -> [String; 2] {
+    let set = uid_set(uids);
+    [
+        format!("UID STORE {} +FLAGS.SILENT (\\Deleted)", set),
+        if uidplus {
+            format!("UID EXPUNGE {}", set)
+        } else {
+            "EXPUNGE".to_string()
+        },
+    ]
+}
+
+/// Builds the `SUBSCRIBE` or `UNSUBSCRIBE` command for the mailbox at `imap_path`.
+fn subscription_command(imap_path: &str, subscribe: bool) -> String {
+    format!(
+        "{}SUBSCRIBE \"{}\"",
+        if subscribe { "" } else { "UN" },
+        imap_path
+    )
+}
+
+/// Translates a search `Query` into an IMAP `UID SEARCH` command.
+/// With `esearch`, asks for an ESEARCH (RFC 4731) response listing ALL matching UIDs.
+fn uid_search_command(query: &crate::search::Query, esearch: bool) -> String {
+    fn rec(q: &crate::search::Query, s: &mut String) {
+        use crate::search::{escape_double_quote, Query::*};
+        match q {
+            Subject(t) => {
+                s.push_str(" SUBJECT \"");
+                s.extend(escape_double_quote(t).chars());
+                s.push('"');
+            }
+            From(t) => {
+                s.push_str(" FROM \"");
+                s.extend(escape_double_quote(t).chars());
+                s.push('"');
+            }
+            To(t) => {
+                s.push_str(" TO \"");
+                s.extend(escape_double_quote(t).chars());
+                s.push('"');
+            }
+            Cc(t) => {
+                s.push_str(" CC \"");
+                s.extend(escape_double_quote(t).chars());
+                s.push('"');
+            }
+            Bcc(t) => {
+                s.push_str(" BCC \"");
+                s.extend(escape_double_quote(t).chars());
+                s.push('"');
+            }
+            Body(t) => {
+                s.push_str(" BODY \"");
+                s.extend(escape_double_quote(t).chars());
+                s.push('"');
+            }
+            Before(t) => {
+                s.push_str(" BEFORE ");
+                s.push_str(&imap_date(*t));
+            }
+            After(t) => {
+                s.push_str(" SINCE ");
+                s.push_str(&imap_date(*t));
+            }
+            On(t) => {
+                s.push_str(" ON ");
+                s.push_str(&imap_date(*t));
+            }
+            Between(a, b) => {
+                s.push_str(" SINCE ");
+                s.push_str(&imap_date(*a));
+                s.push_str(" BEFORE ");
+                s.push_str(&imap_date(*b));
+            }
+            AllText(t) => {
+                s.push_str(" TEXT \"");
+                s.extend(escape_double_quote(t).chars());
+                s.push('"');
+            }
+            Flags(v) => {
+                for f in v {
+                    match f.as_str() {
+                        "draft" => {
+                            s.push_str(" DRAFT");
+                        }
+                        "deleted" => {
+                            s.push_str(" DELETED");
+                        }
+                        "flagged" => {
+                            s.push_str(" FLAGGED");
+                        }
+                        "recent" => {
+                            s.push_str(" RECENT");
+                        }
+                        "seen" | "read" => {
+                            s.push_str(" SEEN");
+                        }
+                        "unseen" | "unread" => {
+                            s.push_str(" UNSEEN");
+                        }
+                        "answered" => {
+                            s.push_str(" ANSWERED");
+                        }
+                        "unanswered" => {
+                            s.push_str(" UNANSWERED");
+                        }
+                        keyword => {
+                            s.push_str(" KEYWORD ");
+                            s.push_str(keyword);
+                        }
+                    }
+                }
+            }
+            And(q1, q2) => {
+                rec(q1, s);
+                rec(q2, s);
+            }
+            Or(q1, q2) => {
+                s.push_str(" OR");
+                operand(q1, s);
+                operand(q2, s);
+            }
+            Not(q) => {
+                s.push_str(" NOT");
+                operand(q, s);
+            }
+            _ => {}
+        }
+    }
+    /// `OR` and `NOT` take single search keys as arguments, so lists of keys must be grouped.
+    fn operand(q: &crate::search::Query, s: &mut String) {
+        use crate::search::Query::*;
+        match q {
+            And(_, _) | Between(_, _) => {}
+            Flags(v) if v.len() > 1 => {}
+            _ => return rec(q, s),
+        }
+        let mut group = String::new();
+        rec(q, &mut group);
+        s.push_str(" (");
+        s.push_str(group.trim());
+        s.push(')');
+    }
+    let mut query_str = String::new();
+    rec(query, &mut query_str);
+    format!(
+        "UID SEARCH {}CHARSET UTF-8 {}",
+        if esearch { "RETURN (ALL) " } else { "" },
+        query_str.trim()
+    )
+}
+
+/// Formats `timestamp` as an IMAP `date` search key argument, e.g. `01-Feb-2020`.
+fn imap_date(timestamp: crate::UnixTimestamp) -> String {
+    crate::datetime::timestamp_to_string(timestamp, Some("%d-%b-%Y"))
+}
+
+/// Format a timestamp as an IMAP `date-time` (RFC 3501), e.g. ` 5-Mar-2020 12:00:00 +0000`. The
+/// day of month is space padded.
+fn imap_internal_date(timestamp: crate::UnixTimestamp) -> String {
+    crate::datetime::timestamp_to_string(timestamp, Some("%e-%b-%Y %H:%M:%S %z"))
+}
+
+fn append_command(
+    imap_path: &str,
+    flags: Flag,
+    date: Option<crate::UnixTimestamp>,
+    len: usize,
+    literal_plus: bool,
+) -> String {
+    let mut cmd = format!("APPEND \"{}\" ({})", imap_path, flags_to_imap_list!(flags));
+    if let Some(date) = date {
+        cmd.push_str(&format!(" \"{}\"", imap_internal_date(date)));
+    }
+    cmd.push_str(&format!(
+        " {{{}{}}}",
+        len,
+        if literal_plus { "+" } else { "" }
+    ));
+    cmd
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Serves a single IMAP connection with canned replies and returns its port, the commands it
+    /// received and the contents of the literals of APPEND commands.
+    pub(super) fn fake_imap_server() -> (u16, Arc<Mutex<Vec<String>>>, Arc<Mutex<Vec<Vec<u8>>>>) {
+        use std::io::{BufRead, BufReader, Read, Write};
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let commands: Arc<Mutex<Vec<String>>> = Default::default();
+        let commands_ = commands.clone();
+        let appended: Arc<Mutex<Vec<Vec<u8>>>> = Default::default();
+        let appended_ = appended.clone();
+        std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut writer = stream.try_clone().unwrap();
+            writer.write_all(b"* OK fake server ready\r\n").unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 0 {
+                let line = std::mem::take(&mut line);
+                let line = line.trim_end();
+                let mut parts = line.splitn(3, ' ');
+                let tag = parts.next().unwrap_or_default().to_string();
+                let command = parts.next().unwrap_or_default().to_ascii_uppercase();
+                let args = parts.next().unwrap_or_default().to_string();
+                commands_.lock().unwrap().push(command.clone());
+                let untagged = match command.as_str() {
+                    "CAPABILITY" => "* CAPABILITY IMAP4rev1\r\n".to_string(),
+                    "SELECT" | "EXAMINE" => {
+                        "* 2 EXISTS\r\n* 0 RECENT\r\n* FLAGS (\\Seen)\r\n* OK [UIDVALIDITY 1] UIDs valid\r\n* OK [UIDNEXT 3] next UID\r\n".to_string()
+                    }
+                    "APPEND" => {
+                        let len = args.rsplit('{').next().unwrap_or_default();
+                        let len = len.trim_end_matches('}');
+                        if !len.ends_with('+') {
+                            writer.write_all(b"+ Ready for literal data\r\n").unwrap();
+                        }
+                        let mut literal = vec![0; len.trim_end_matches('+').parse().unwrap()];
+                        reader.read_exact(&mut literal).unwrap();
+                        appended_.lock().unwrap().push(literal);
+                        /* The CRLF that ends the command */
+                        reader.read_line(&mut String::new()).unwrap();
+                        writer
+                            .write_all(format!("{} OK [APPENDUID 1 3] done\r\n", tag).as_bytes())
+                            .unwrap();
+                        continue;
+                    }
+                    "UID" if args.starts_with("SEARCH") => "* SEARCH 1 2\r\n".to_string(),
+                    "UID" if args.contains("ENVELOPE") => {
+                        /* The mailbox has the messages with UIDs 1 and 2 */
+                        let set = args.split_whitespace().nth(1).unwrap_or_default();
+                        let mut bounds = set.split(':').map(|n| n.parse::<usize>().unwrap());
+                        let from = bounds.next().unwrap();
+                        let to = bounds.next().unwrap_or(from);
+                        (from..=std::cmp::min(to, 2))
+                            .map(|uid| {
+                                format!(
+                                    "* {uid} FETCH (UID {uid} FLAGS (\\Seen) ENVELOPE (\"Thu, 01 Oct 2020 10:00:00 +0000\" \"{uid}\" ((NIL NIL \"a\" \"example.com\")) NIL NIL ((NIL NIL \"b\" \"example.com\")) NIL NIL NIL \"<{uid}@example.com>\") BODY[HEADER.FIELDS (REFERENCES)] {{2}}\r\n\r\n)\r\n",
+                                    uid = uid
+                                )
+                            })
+                            .collect::<String>()
+                    }
+                    "UID" if args.ends_with("BODYSTRUCTURE") => {
+                        let uid = args.split_whitespace().nth(1).unwrap_or_default();
+                        format!(
+                            "* {uid} FETCH (UID {uid} BODYSTRUCTURE ((\"text\" \"plain\" (\"charset\" \"utf-8\") NIL NIL \"7bit\" 6 1 NIL NIL NIL NIL)(\"application\" \"pdf\" (\"name\" \"a.pdf\") NIL NIL \"base64\" 4096 NIL (\"attachment\" (\"filename\" \"a.pdf\")) NIL NIL) \"mixed\" (\"boundary\" \"b\") NIL NIL NIL))\r\n",
+                            uid = uid
+                        )
+                    }
+                    "UID" if args.contains("BODY.PEEK[") => {
+                        let uid = args.split_whitespace().nth(1).unwrap_or_default();
+                        let part = args
+                            .split(|c| c == '[' || c == ']')
+                            .nth(1)
+                            .unwrap_or_default();
+                        let body = format!("part {}", part);
+                        format!(
+                            "* {uid} FETCH (UID {uid} BODY[{part}] {{{len}}}\r\n{body})\r\n",
+                            uid = uid,
+                            part = part,
+                            len = body.len(),
+                            body = body
+                        )
+                    }
+                    "UID" => {
+                        let uid = args.split_whitespace().nth(1).unwrap_or_default();
+                        let body = format!("Subject: {}\r\n\r\nbody\r\n", uid);
+                        format!(
+                            "* {uid} FETCH (UID {uid} FLAGS (\\Seen) RFC822 {{{len}}}\r\n{body})\r\n",
+                            uid = uid,
+                            len = body.len(),
+                            body = body
+                        )
+                    }
+                    _ => String::new(),
+                };
+                writer
+                    .write_all(format!("{}{} OK done\r\n", untagged, tag).as_bytes())
+                    .unwrap();
+            }
+        });
+        (port, commands, appended)
+    }
+
+    /// An `ImapType` with an `INBOX` mailbox, connected to `fake_imap_server()`.
+    pub(super) fn fake_imap_backend() -> (
+        ImapType,
+        MailboxHash,
+        Arc<Mutex<Vec<String>>>,
+        Arc<Mutex<Vec<Vec<u8>>>>,
+    ) {
+        let (port, commands, appended) = fake_imap_server();
+        let uid_store = Arc::new(UIDStore::new(
+            0,
+            Arc::new("test".to_string()),
+            BackendEventConsumer::new(Arc::new(|_, _| {})),
+            Some(Duration::from_secs(5)),
+        ));
+        let mailbox_hash = 1;
+        futures::executor::block_on(uid_store.mailboxes.lock()).insert(
+            mailbox_hash,
+            ImapMailbox {
+                hash: mailbox_hash,
+                imap_path: "INBOX".to_string(),
+                ..Default::default()
+            },
+        );
+        let server_conf = ImapServerConf {
+            server_hostname: "127.0.0.1".to_string(),
+            server_username: String::new(),
+            server_password: String::new(),
+            server_port: port,
+            use_starttls: false,
+            use_tls: false,
+            danger_accept_invalid_certs: false,
+            use_oauth2: false,
+            protocol: ImapProtocol::IMAP {
+                extension_use: ImapExtensionUse::default(),
+            },
+            timeout: Some(Duration::from_secs(5)),
+        };
+        let connection = ImapConnection::new_connection(&server_conf, uid_store.clone());
+        let backend = ImapType {
+            server_conf,
+            is_subscribed: Arc::new(IsSubscribedFn(Box::new(|_| true))),
+            connection: Arc::new(FutureMutex::new(connection)),
+            uid_store,
+        };
+        (backend, mailbox_hash, commands, appended)
+    }
+
+    #[test]
+    fn test_save() {
+        let (backend, mailbox_hash, commands, appended) = fake_imap_backend();
+        futures::executor::block_on(async { backend.connection.lock().await.connect().await })
+            .unwrap();
+        let bytes = b"From: a@example.com\r\nSubject: saved\r\n\r\nbody\r\n".to_vec();
+        futures::executor::block_on(
+            backend
+                .save(bytes.clone(), mailbox_hash, Some(Flag::SEEN))
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(*appended.lock().unwrap(), vec![bytes]);
+        assert!(commands.lock().unwrap().iter().any(|c| c == "APPEND"));
+        /* The UID from APPENDUID is recorded for the new message */
+        assert_eq!(
+            backend.uid_store.hash_index.lock().unwrap()[&generate_envelope_hash("INBOX", &3)],
+            (3, mailbox_hash)
+        );
+    }
+
+    #[test]
+    fn test_uid_copy_command() {
+        let uid_store = UIDStore::new(
+            0,
+            Arc::new("test".to_string()),
+            BackendEventConsumer::new(Arc::new(|_, _| {})),
+            None,
+        );
+        let mailbox_hash: MailboxHash = 1;
+        {
+            let mut hash_index_lck = uid_store.hash_index.lock().unwrap();
+            for (env_hash, uid) in [(10, 7), (20, 8), (30, 12)].iter() {
+                hash_index_lck.insert(*env_hash, (*uid, mailbox_hash));
+            }
+        }
+        /* Envelopes without a known UID are skipped */
+        let env_hashes = EnvelopeHashBatch::try_from(&[10, 20, 40, 30][..]).unwrap();
+        let uids = uid_store.uids(&env_hashes);
+        assert_eq!(&uids[..], &[7, 8, 12]);
+        assert_eq!(
+            uid_copy_command(&uids, "INBOX/Archive", false),
+            "UID COPY 7,8,12 \"INBOX/Archive\""
+        );
+        assert_eq!(
+            uid_copy_command(&uids, "INBOX/Archive", true),
+            "UID MOVE 7,8,12 \"INBOX/Archive\""
+        );
+    }
+
+    #[test]
+    fn test_fetch_range() {
+        let (mut backend, mailbox_hash, _, _) = fake_imap_backend();
+        let mut fetch_range = |from: usize, to: usize| {
+            let mut subjects = futures::executor::block_on(
+                backend.fetch_range(mailbox_hash, from, to).unwrap(),
+            )
+            .unwrap()
+            .iter()
+            .map(|env| env.subject().to_string())
+            .collect::<Vec<String>>();
+            subjects.sort();
+            subjects
+        };
+        /* UIDNEXT is 3, so the newest message has UID 2 */
+        assert_eq!(fetch_range(0, 1), vec!["1", "2"]);
+        assert_eq!(fetch_range(1, 1), vec!["1"]);
+        assert!(fetch_range(2, 10).is_empty());
+        assert!(fetch_range(1, 0).is_empty());
+    }
+
+    #[test]
+    fn test_uid_fetch_envelopes_command() {
+        assert_eq!(
+            uid_fetch_envelopes_command(1200, 1300),
+            "UID FETCH 1200:1300 (UID FLAGS RFC822.SIZE INTERNALDATE ENVELOPE BODY.PEEK[HEADER.FIELDS (REFERENCES)] BODYSTRUCTURE)"
+        );
+        assert_eq!(
+            uid_fetch_envelopes_command(1, 1),
+            "UID FETCH 1 (UID FLAGS RFC822.SIZE INTERNALDATE ENVELOPE BODY.PEEK[HEADER.FIELDS (REFERENCES)] BODYSTRUCTURE)"
+        );
+    }
+
+    #[test]
+    fn test_uid_search_command() {
+        use crate::search::Query;
+        use std::convert::TryFrom;
+
+        assert_eq!(
+            uid_search_command(
+                &Query::try_from("from:alice and subject:invoice").unwrap(),
+                false
+            ),
+            "UID SEARCH CHARSET UTF-8 FROM \"alice\" SUBJECT \"invoice\""
+        );
+        assert_eq!(
+            uid_search_command(&Query::try_from("body:refund").unwrap(), false),
+            "UID SEARCH CHARSET UTF-8 BODY \"refund\""
+        );
+        let since = crate::datetime::timestamp_from_string("2020-02-01", "%Y-%m-%d")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            uid_search_command(&Query::After(since), false),
+            "UID SEARCH CHARSET UTF-8 SINCE 01-Feb-2020"
+        );
+        assert_eq!(
+            uid_search_command(
+                &Query::try_from("subject:invoice or (is:seen and from:alice)").unwrap(),
+                false
+            ),
+            "UID SEARCH CHARSET UTF-8 OR SUBJECT \"invoice\" (SEEN FROM \"alice\")"
+        );
+        assert_eq!(
+            uid_search_command(&Query::try_from("body:refund").unwrap(), true),
+            "UID SEARCH RETURN (ALL) CHARSET UTF-8 BODY \"refund\""
+        );
+    }
+
+    #[test]
+    fn test_subscription_command() {
+        assert_eq!(
+            subscription_command("INBOX/Work", true),
+            "SUBSCRIBE \"INBOX/Work\""
+        );
+        assert_eq!(
+            subscription_command("INBOX/Work", false),
+            "UNSUBSCRIBE \"INBOX/Work\""
+        );
+    }
+
+    #[test]
+    fn test_append_command() {
+        /* The date is formatted in the local timezone, so only check its shape and that it
+         * refers to the same instant. */
+        for timestamp in &[1583409600, 1584403200] {
+            let date = imap_internal_date(*timestamp);
+            assert_eq!(date.len(), " 5-Mar-2020 12:00:00 +0000".len(), "{:?}", date);
+            assert_eq!(
+                protocol_parser::internal_date_to_timestamp(date.as_bytes()),
+                Some(*timestamp),
+                "{:?}",
+                date
+            );
+        }
+        let date = imap_internal_date(1583409600);
+
+        assert_eq!(
+            append_command("Sent", Flag::SEEN, None, 42, false),
+            "APPEND \"Sent\" (\\Seen) {42}"
+        );
+        assert_eq!(
+            append_command("Sent", Flag::SEEN, Some(1583409600), 42, true),
+            format!("APPEND \"Sent\" (\\Seen) \"{}\" {{42+}}", date)
+        );
+    }
+
+    #[test]
+    fn test_delete_commands() {
+        assert_eq!(
+            delete_commands(&[3, 5], true),
+            [
+                "UID STORE 3,5 +FLAGS.SILENT (\\Deleted)".to_string(),
+                "UID EXPUNGE 3,5".to_string()
+            ]
+        );
+        assert_eq!(
+            delete_commands(&[7], false),
+            [
+                "UID STORE 7 +FLAGS.SILENT (\\Deleted)".to_string(),
+                "EXPUNGE".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_uid_store_command() {
+        let flags: SmallVec<[(std::result::Result<Flag, String>, bool); 8]> = smallvec::smallvec![
+            (Ok(Flag::SEEN), true),
+            (Err("work".to_string()), true),
+            (Err("$Important".to_string()), true),
+            (Err("old".to_string()), false),
+        ];
+        assert_eq!(
+            uid_store_command(&[1, 2], &flags, true).unwrap().as_deref(),
+            Some("UID STORE 1,2 +FLAGS (\\Seen work $Important)")
+        );
+        assert_eq!(
+            uid_store_command(&[1, 2], &flags, false)
+                .unwrap()
+                .as_deref(),
+            Some("UID STORE 1,2 -FLAGS (old)")
+        );
+        assert_eq!(uid_store_command(&[1], &flags[..1], false).unwrap(), None);
+    }
+}
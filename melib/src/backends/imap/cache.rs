@@ -140,7 +140,7 @@ mod sqlite3_m {
     CREATE INDEX IF NOT EXISTS envelope_idx ON envelopes(hash);
     CREATE INDEX IF NOT EXISTS mailbox_idx ON mailbox(mailbox_hash);",
         ),
-        version: 2,
+        version: 4,
     };
 
     impl ToSql for ModSequence {
@@ -447,6 +447,9 @@ mod sqlite3_m {
                     body: _,
                     references: _,
                     envelope: Some(envelope),
+                    bodystructure: _,
+                    size: _,
+                    internal_date: _,
                     raw_fetch_value: _,
                 } = item
                 {
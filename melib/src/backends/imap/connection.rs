@@ -19,7 +19,9 @@
  * along with meli. If not, see <http://www.gnu.org/licenses/>.
  */
 
-use super::protocol_parser::{ImapLineSplit, ImapResponse, RequiredResponses, SelectResponse};
+use super::protocol_parser::{
+    literal_length, ImapLineSplit, ImapResponse, RequiredResponses, SelectResponse,
+};
 use crate::backends::{MailboxHash, RefreshEvent};
 use crate::connections::{lookup_ipv4, timeout, Connection};
 use crate::email::parser::BytesExt;
@@ -33,6 +35,7 @@ use std::convert::TryFrom;
 use std::future::Future;
 use std::iter::FromIterator;
 use std::pin::Pin;
+use std::str::FromStr;
 use std::sync::Arc;
 use std::time::{Duration, Instant, SystemTime};
 
@@ -41,6 +44,19 @@ const IMAP_PROTOCOL_TIMEOUT: Duration = Duration::from_secs(60 * 28);
 use super::protocol_parser;
 use super::{Capabilities, ImapServerConf, UIDStore};
 
+const IMAP_RECONNECT_MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// How long to wait before reconnecting after `failures` consecutive failed connection attempts:
+/// nothing at first, then 2s, 4s, 8s, ... up to `IMAP_RECONNECT_MAX_BACKOFF`.
+pub fn reconnect_backoff(failures: usize) -> Duration {
+    if failures == 0 {
+        return Duration::from_secs(0);
+    }
+    /* 2^6 seconds already exceed the maximum, don't overflow the shift */
+    let exp = std::cmp::min(failures, 6) as u32;
+    std::cmp::min(Duration::from_secs(1 << exp), IMAP_RECONNECT_MAX_BACKOFF)
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum SyncPolicy {
     None,
@@ -340,6 +356,22 @@ impl ImapStream {
                 "Could not connect to {}: server is not IMAP4rev1 compliant",
                 &server_conf.server_hostname
             )));
+        } else if server_conf.use_oauth2 {
+            if !capabilities
+                .iter()
+                .any(|cap| cap.eq_ignore_ascii_case(b"AUTH=XOAUTH2"))
+            {
+                return Err(MeliError::new(format!(
+                    "Could not connect to {}: OAUTH2 is enabled but server did not return AUTH=XOAUTH2 capability. Returned capabilities were: {}",
+                    &server_conf.server_hostname,
+                    capabilities
+                        .iter()
+                        .map(|capability| String::from_utf8_lossy(capability).to_string())
+                        .collect::<Vec<String>>()
+                        .join(" ")
+                ))
+                .set_err_kind(crate::error::ErrorKind::Authentication));
+            }
         } else if capabilities
             .iter()
             .any(|cap| cap.eq_ignore_ascii_case(b"LOGINDISABLED"))
@@ -352,20 +384,46 @@ impl ImapStream {
         }
 
         let mut capabilities = None;
-        ret.send_command(
-            format!(
-                "LOGIN \"{}\" \"{}\"",
-                &server_conf.server_username, &server_conf.server_password
+        if server_conf.use_oauth2 {
+            ret.send_command(
+                format!(
+                    "AUTHENTICATE XOAUTH2 {}",
+                    xoauth2_sasl_string(
+                        &server_conf.server_username,
+                        &server_conf.server_password
+                    )
+                )
+                .as_bytes(),
             )
-            .as_bytes(),
-        )
-        .await?;
+            .await?;
+        } else {
+            ret.send_command(
+                format!(
+                    "LOGIN \"{}\" \"{}\"",
+                    &server_conf.server_username, &server_conf.server_password
+                )
+                .as_bytes(),
+            )
+            .await?;
+        }
         let tag_start = format!("M{} ", (ret.cmd_id - 1));
 
         loop {
             ret.read_lines(&mut res, &[], false).await?;
             let mut should_break = false;
             for l in res.split_rn() {
+                if l.starts_with(b"+ ") && server_conf.use_oauth2 {
+                    /* XOAUTH2 failures are sent as a continuation request with an error
+                     * description; an empty response makes the server finish with NO. */
+                    debug!(
+                        "XOAUTH2 error: {}",
+                        base64::decode(l[b"+ ".len()..].trim())
+                            .map(|e| String::from_utf8_lossy(&e).to_string())
+                            .unwrap_or_default()
+                    );
+                    ret.send_raw(b"").await?;
+                    continue;
+                }
                 if l.starts_with(b"* CAPABILITY") {
                     capabilities = protocol_parser::capabilities(&l)
                         .map(|(_, capabilities)| {
@@ -406,11 +464,22 @@ impl ImapStream {
     }
 
     pub async fn read_response(&mut self, ret: &mut Vec<u8>) -> Result<()> {
+        self.read_response_with_progress(ret, None).await
+    }
+
+    /// Like `read_response`, but calls `progress` with the received and total bytes of the
+    /// first literal in the response (e.g. a message body) as they arrive.
+    pub async fn read_response_with_progress(
+        &mut self,
+        ret: &mut Vec<u8>,
+        progress: Option<&mut (dyn FnMut(usize, usize) + Send)>,
+    ) -> Result<()> {
         let id = match self.protocol {
             ImapProtocol::IMAP { .. } => format!("M{} ", self.cmd_id - 1).into_bytes(),
             ImapProtocol::ManageSieve => Vec::new(),
         };
-        self.read_lines(ret, &id, true).await?;
+        self.read_lines_with_progress(ret, &id, true, progress)
+            .await?;
         Ok(())
     }
 
@@ -419,19 +488,35 @@ impl ImapStream {
         ret: &mut Vec<u8>,
         termination_string: &[u8],
         keep_termination_string: bool,
+    ) -> Result<()> {
+        self.read_lines_with_progress(ret, termination_string, keep_termination_string, None)
+            .await
+    }
+
+    async fn read_lines_with_progress(
+        &mut self,
+        ret: &mut Vec<u8>,
+        termination_string: &[u8],
+        keep_termination_string: bool,
+        mut progress: Option<&mut (dyn FnMut(usize, usize) + Send)>,
     ) -> Result<()> {
         let mut buf: Vec<u8> = vec![0; Connection::IO_BUF_SIZE];
         ret.clear();
         let mut last_line_idx: usize = 0;
+        let mut bye_scanner = ByeScanner::default();
         loop {
             match timeout(self.timeout, self.stream.read(&mut buf)).await? {
                 Ok(0) => break,
                 Ok(b) => {
                     ret.extend_from_slice(&buf[0..b]);
-                    if let Some(mut pos) = ret[last_line_idx..].rfind("\r\n") {
-                        if ret[last_line_idx..].starts_with(b"* BYE") {
-                            return Err(MeliError::new("Disconnected"));
+                    if let Some(progress) = progress.as_mut() {
+                        if let Some((received, total)) = literal_progress(ret) {
+                            progress(received, total);
                         }
+                    }
+                    /* The tagged completion won't come after a BYE, so don't wait for it */
+                    bye_scanner.scan(ret)?;
+                    if let Some(mut pos) = ret[last_line_idx..].rfind("\r\n") {
                         if let Some(prev_line) =
                             ret[last_line_idx..pos + last_line_idx].rfind(b"\r\n")
                         {
@@ -492,12 +577,14 @@ impl ImapStream {
                 self.stream.flush().await?;
                 match self.protocol {
                     ImapProtocol::IMAP { .. } => {
-                        if !command.starts_with(b"LOGIN") {
+                        if command.starts_with(b"LOGIN") {
+                            debug!("sent: M{} LOGIN ..", self.cmd_id - 1);
+                        } else if command.starts_with(b"AUTHENTICATE") {
+                            debug!("sent: M{} AUTHENTICATE ..", self.cmd_id - 1);
+                        } else {
                             debug!("sent: M{} {}", self.cmd_id - 1, unsafe {
                                 std::str::from_utf8_unchecked(command)
                             });
-                        } else {
-                            debug!("sent: M{} LOGIN ..", self.cmd_id - 1);
                         }
                     }
                     ImapProtocol::ManageSieve => {}
@@ -591,8 +678,10 @@ impl ImapConnection {
             let new_stream = debug!(ImapStream::new_connection(&self.server_conf).await);
             if let Err(err) = new_stream.as_ref() {
                 self.uid_store.is_online.lock().unwrap().1 = Err(err.clone());
+                *self.uid_store.reconnect_failures.lock().unwrap() += 1;
             } else {
                 *self.uid_store.is_online.lock().unwrap() = (SystemTime::now(), Ok(()));
+                *self.uid_store.reconnect_failures.lock().unwrap() = 0;
             }
             let (capabilities, stream) = new_stream?;
             self.stream = Ok(stream);
@@ -672,11 +761,33 @@ impl ImapConnection {
         &'a mut self,
         ret: &'a mut Vec<u8>,
         required_responses: RequiredResponses,
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>> {
+        self.read_response_with_progress(ret, required_responses, None)
+    }
+
+    /// Like `read_response`, reporting the download progress of the first literal of the
+    /// response to `progress`.
+    pub fn read_response_with_progress<'a>(
+        &'a mut self,
+        ret: &'a mut Vec<u8>,
+        required_responses: RequiredResponses,
+        progress: Option<&'a mut (dyn FnMut(usize, usize) + Send)>,
     ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>> {
         Box::pin(async move {
             let mut response = Vec::new();
             ret.clear();
-            self.stream.as_mut()?.read_response(&mut response).await?;
+            if let Err(err) = self
+                .stream
+                .as_mut()?
+                .read_response_with_progress(&mut response, progress)
+                .await
+            {
+                if err.kind == ErrorKind::ConnectionBye {
+                    self.stream = Err(err.clone());
+                    self.uid_store.is_online.lock().unwrap().1 = Err(err.clone());
+                }
+                return Err(err);
+            }
             *self.uid_store.is_online.lock().unwrap() = (SystemTime::now(), Ok(()));
 
             match self.server_conf.protocol {
@@ -742,8 +853,13 @@ impl ImapConnection {
                         "check every line for required_responses: {:#?}",
                         &required_responses
                     );*/
+                    let tag = format!("M{}", self.stream.as_ref()?.cmd_id - 1).into_bytes();
                     for l in response.split_rn() {
                         /*debug!("check line: {}", &l);*/
+                        if RequiredResponses::is_complete(&tag, l) {
+                            ret.extend_from_slice(l);
+                            break;
+                        }
                         if required_responses.check(l) || !self.process_untagged(l).await? {
                             ret.extend_from_slice(l);
                         }
@@ -843,8 +959,19 @@ impl ImapConnection {
             ))
             .set_kind(crate::error::ErrorKind::Bug));
         }
-        self.send_command(format!("SELECT \"{}\"", imap_path).as_bytes())
-            .await?;
+        self.send_command(
+            format!(
+                "SELECT \"{}\"{}",
+                imap_path,
+                if self.use_condstore() {
+                    " (CONDSTORE)"
+                } else {
+                    ""
+                }
+            )
+            .as_bytes(),
+        )
+        .await?;
         self.read_response(ret, RequiredResponses::SELECT_REQUIRED)
             .await?;
         debug!(
@@ -929,8 +1056,19 @@ impl ImapConnection {
             ))
             .set_kind(crate::error::ErrorKind::Bug));
         }
-        self.send_command(format!("EXAMINE \"{}\"", &imap_path).as_bytes())
-            .await?;
+        self.send_command(
+            format!(
+                "EXAMINE \"{}\"{}",
+                &imap_path,
+                if self.use_condstore() {
+                    " (CONDSTORE)"
+                } else {
+                    ""
+                }
+            )
+            .as_bytes(),
+        )
+        .await?;
         self.read_response(ret, RequiredResponses::EXAMINE_REQUIRED)
             .await?;
         debug!("examine response {}", String::from_utf8_lossy(&ret));
@@ -998,6 +1136,25 @@ impl ImapConnection {
         Ok(())
     }
 
+    /// Whether CONDSTORE is advertised by the server and enabled in the account settings.
+    pub fn use_condstore(&self) -> bool {
+        match self.server_conf.protocol {
+            ImapProtocol::IMAP {
+                extension_use: ImapExtensionUse { condstore, .. },
+            } => {
+                condstore
+                    && self
+                        .uid_store
+                        .capabilities
+                        .lock()
+                        .unwrap()
+                        .iter()
+                        .any(|cap| cap.eq_ignore_ascii_case(b"CONDSTORE"))
+            }
+            ImapProtocol::ManageSieve => false,
+        }
+    }
+
     pub fn add_refresh_event(&mut self, ev: RefreshEvent) {
         (self.uid_store.event_consumer)(
             self.uid_store.account_hash,
@@ -1117,3 +1274,179 @@ async fn read(
     }
     None
 }
+
+/// Returns how many bytes of the literal that starts a response (e.g. `* 1 FETCH (RFC822
+/// {1024}\r\n...`) have been received so far in `buf`, and the literal's total length.
+fn literal_progress(buf: &[u8]) -> Option<(usize, usize)> {
+    /* Only look at the start of the response, so that this stays cheap as `buf` grows */
+    let head = &buf[..std::cmp::min(buf.len(), 1024)];
+    let end = head.find(b"}\r\n")? + b"}".len();
+    let total = literal_length(&head[..end])?;
+    let received = buf.len() - end - b"\r\n".len();
+    Some((std::cmp::min(received, total), total))
+}
+
+#[test]
+fn test_imap_reconnect_backoff() {
+    assert_eq!(
+        (0..9)
+            .map(|f| reconnect_backoff(f).as_secs())
+            .collect::<Vec<u64>>(),
+        vec![0, 2, 4, 8, 16, 32, 60, 60, 60]
+    );
+    assert_eq!(reconnect_backoff(usize::MAX), IMAP_RECONNECT_MAX_BACKOFF);
+}
+
+#[test]
+fn test_imap_literal_progress() {
+    assert_eq!(literal_progress(b"* 1 FETCH (UID 1 FLAGS ())\r\n"), None);
+    assert_eq!(
+        literal_progress(b"* 1 FETCH (UID 1 RFC822 {10}\r\n"),
+        Some((0, 10))
+    );
+    assert_eq!(
+        literal_progress(b"* 1 FETCH (UID 1 RFC822 {10}\r\nFrom: "),
+        Some((6, 10))
+    );
+    assert_eq!(
+        literal_progress(b"* 1 FETCH (UID 1 RFC822 {10}\r\nFrom: a@b\r\n)\r\nM1 OK\r\n"),
+        Some((10, 10))
+    );
+    assert_eq!(
+        literal_progress(b"A1 APPEND INBOX {10+}\r\nFrom: "),
+        Some((6, 10))
+    );
+}
+
+/// Looks for an untagged `BYE` response (i.e. the server is closing the connection) in the
+/// lines of a response as they are received. The contents of literals (e.g. message bodies) are
+/// skipped, since they may contain anything.
+#[derive(Debug, Default)]
+struct ByeScanner {
+    /// Offset of the first byte that hasn't been scanned yet.
+    pos: usize,
+    /// Bytes of the current literal that haven't been received yet.
+    literal_left: usize,
+}
+
+impl ByeScanner {
+    /// Scans the complete lines of `buf` received since the last call and returns a
+    /// `ConnectionBye` error if one of them is an untagged `BYE` response.
+    fn scan(&mut self, buf: &[u8]) -> Result<()> {
+        loop {
+            if self.literal_left > 0 {
+                let skip = std::cmp::min(self.literal_left, buf.len() - self.pos);
+                self.pos += skip;
+                self.literal_left -= skip;
+                if self.literal_left > 0 {
+                    return Ok(());
+                }
+            }
+            let line = match buf[self.pos..].find(b"\r\n") {
+                Some(end) => &buf[self.pos..self.pos + end],
+                None => return Ok(()),
+            };
+            if line.starts_with(b"* BYE") {
+                return Err(MeliError::new(format!(
+                    "Disconnected: {}",
+                    String::from_utf8_lossy(line).trim()
+                ))
+                .set_kind(ErrorKind::ConnectionBye));
+            }
+            self.literal_left = literal_length(line).unwrap_or(0);
+            self.pos += line.len() + b"\r\n".len();
+        }
+    }
+}
+
+#[test]
+fn test_imap_bye_scanner() {
+    assert!(ByeScanner::default()
+        .scan(b"* 1 EXISTS\r\n* 1 FETCH (FLAGS (\\Seen))\r\nM1 OK done\r\n")
+        .is_ok());
+    let err = ByeScanner::default()
+        .scan(b"* 1 EXISTS\r\n* BYE server shutting down\r\n")
+        .unwrap_err();
+    assert_eq!(err.kind, ErrorKind::ConnectionBye);
+    assert!(err.kind.is_network());
+    assert_eq!(err.details, "Disconnected: * BYE server shutting down");
+
+    /* A BYE inside a literal is message content, even when the literal arrives in pieces */
+    let mut scanner = ByeScanner::default();
+    let mut buf = b"* 1 FETCH (RFC822 {17}\r\nSubject: a\r\n* BY".to_vec();
+    assert!(scanner.scan(&buf).is_ok());
+    buf.extend_from_slice(b"E\r\n)\r\n");
+    assert!(scanner.scan(&buf).is_ok());
+    buf.extend_from_slice(b"* BYE idle for too long\r\n");
+    assert_eq!(
+        scanner.scan(&buf).unwrap_err().kind,
+        ErrorKind::ConnectionBye
+    );
+}
+
+#[test]
+fn test_imap_read_response_literal_bye() {
+    use std::io::Write;
+    use std::net::{TcpListener, TcpStream};
+
+    let read_response = |response: Vec<u8>| -> (Result<()>, Vec<u8>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (mut server, _) = listener.accept().unwrap();
+        std::thread::spawn(move || {
+            /* Send the response in two pieces, to split the literal across reads */
+            let (first, second) = response.split_at(response.len() / 2);
+            server.write_all(first).unwrap();
+            server.flush().unwrap();
+            std::thread::sleep(Duration::from_millis(50));
+            server.write_all(second).unwrap();
+        });
+        let mut stream = ImapStream {
+            cmd_id: 2,
+            stream: AsyncWrapper::new(Connection::Tcp(client)).unwrap(),
+            protocol: ImapProtocol::IMAP {
+                extension_use: ImapExtensionUse::default(),
+            },
+            current_mailbox: MailboxSelection::None,
+            timeout: Some(Duration::from_secs(5)),
+        };
+        let mut ret = Vec::new();
+        let result = futures::executor::block_on(stream.read_response(&mut ret));
+        (result, ret)
+    };
+
+    let body = "Subject: bye\r\n\r\n* BYE this line is part of the body\r\n";
+    let response = format!(
+        "* 1 FETCH (UID 1 RFC822 {{{}}}\r\n{})\r\nM1 OK done\r\n",
+        body.len(),
+        body
+    );
+    let (result, ret) = read_response(response.into_bytes());
+    assert!(result.is_ok());
+    assert!(ret.ends_with(b"M1 OK done\r\n"));
+
+    let (result, _) = read_response(b"* 1 EXISTS\r\n* BYE server shutting down\r\n".to_vec());
+    assert_eq!(result.unwrap_err().kind, ErrorKind::ConnectionBye);
+}
+
+/// Builds the base64 encoded SASL XOAUTH2 initial client response for `user` and the OAuth2
+/// access `token`.
+fn xoauth2_sasl_string(user: &str, token: &str) -> String {
+    base64::encode(format!("user={}\x01auth=Bearer {}\x01\x01", user, token))
+}
+
+#[test]
+fn test_imap_xoauth2_sasl_string() {
+    let sasl = xoauth2_sasl_string(
+        "someuser@example.com",
+        "ya29.vF9dft4qmTc2Nvb3RlckBhdHRhdmlzdGEuY29tCg",
+    );
+    assert_eq!(
+        base64::decode(&sasl).unwrap(),
+        b"user=someuser@example.com\x01auth=Bearer ya29.vF9dft4qmTc2Nvb3RlckBhdHRhdmlzdGEuY29tCg\x01\x01"
+    );
+    assert_eq!(
+        sasl,
+        "dXNlcj1zb21ldXNlckBleGFtcGxlLmNvbQFhdXRoPUJlYXJlciB5YTI5LnZGOWRmdDRxbVRjMk52YjNSbGNrQmhkSFJoZG1semRHRXVZMjl0Q2cBAQ=="
+    );
+}
@@ -103,4 +103,12 @@ impl BackendMailbox for ImapMailbox {
     fn count(&self) -> Result<(usize, usize)> {
         Ok((self.unseen.lock()?.len(), self.exists.lock()?.len()))
     }
+
+    fn recent(&self) -> Option<usize> {
+        self.select
+            .read()
+            .ok()?
+            .as_ref()
+            .map(|select_response| select_response.recent)
+    }
 }
@@ -110,6 +110,7 @@ pub fn new_managesieve_connection(
         use_starttls: true,
         use_tls: true,
         danger_accept_invalid_certs,
+        use_oauth2: false,
         protocol: ImapProtocol::ManageSieve,
         timeout,
     };
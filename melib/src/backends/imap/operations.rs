@@ -26,6 +26,9 @@ use crate::email::*;
 use crate::error::MeliError;
 use std::sync::Arc;
 
+/// Messages smaller than this are downloaded without progress reports.
+const PROGRESS_MIN_BYTES: usize = 512 * 1024;
+
 /// `BackendOp` implementor for Imap
 #[derive(Debug, Clone)]
 pub struct ImapOp {
@@ -51,7 +54,94 @@ impl ImapOp {
     }
 }
 
+fn fetch_part_command(uid: UID, part: &str) -> String {
+    format!("UID FETCH {} BODY.PEEK[{}]", uid, part)
+}
+
 impl BackendOp for ImapOp {
+    /// Fetches a single body part with `BODY.PEEK[<part>]`, so the `\Seen` flag isn't set.
+    fn fetch_part(&mut self, part: &str) -> ResultFuture<Vec<u8>> {
+        let connection = self.connection.clone();
+        let mailbox_hash = self.mailbox_hash;
+        let uid = self.uid;
+        let uid_store = self.uid_store.clone();
+        let part = part.to_string();
+        Ok(Box::pin(async move {
+            {
+                let mut bytes_cache = uid_store.byte_cache.lock()?;
+                let cache = bytes_cache.entry(uid).or_default();
+                if let Some(bytes) = cache.parts.get(&part) {
+                    return Ok(bytes.clone());
+                }
+            }
+            let mut response = Vec::with_capacity(8 * 1024);
+            {
+                let mut conn = timeout(uid_store.timeout, connection.lock()).await?;
+                conn.connect().await?;
+                conn.examine_mailbox(mailbox_hash, &mut response, false)
+                    .await?;
+                conn.send_command(fetch_part_command(uid, &part).as_bytes())
+                    .await?;
+                conn.read_response(&mut response, RequiredResponses::FETCH_REQUIRED)
+                    .await?;
+            }
+            let mut results = protocol_parser::fetch_responses(&response)?.1;
+            let body = match results.pop() {
+                Some(FetchResponse {
+                    body: Some(body), ..
+                }) if results.is_empty() => body.to_vec(),
+                _ => {
+                    return Err(MeliError::new(format!(
+                        "Invalid/unexpected response: {:?}",
+                        response
+                    ))
+                    .set_summary(format!(
+                        "part {} of message with UID {} was not found?",
+                        part, uid
+                    )));
+                }
+            };
+            let mut bytes_cache = uid_store.byte_cache.lock()?;
+            let cache = bytes_cache.entry(uid).or_default();
+            cache.parts.insert(part, body.clone());
+            Ok(body)
+        }))
+    }
+
+    fn body_structure(&mut self) -> ResultFuture<BodyStructure> {
+        let connection = self.connection.clone();
+        let mailbox_hash = self.mailbox_hash;
+        let uid = self.uid;
+        let uid_store = self.uid_store.clone();
+        Ok(Box::pin(async move {
+            let mut response = Vec::with_capacity(8 * 1024);
+            {
+                let mut conn = timeout(uid_store.timeout, connection.lock()).await?;
+                conn.connect().await?;
+                conn.examine_mailbox(mailbox_hash, &mut response, false)
+                    .await?;
+                conn.send_command(format!("UID FETCH {} BODYSTRUCTURE", uid).as_bytes())
+                    .await?;
+                conn.read_response(&mut response, RequiredResponses::FETCH_REQUIRED)
+                    .await?;
+            }
+            let mut results = protocol_parser::fetch_responses(&response)?.1;
+            match results.pop() {
+                Some(FetchResponse {
+                    bodystructure: Some(bodystructure),
+                    ..
+                }) if results.is_empty() => Ok(bodystructure),
+                _ => Err(
+                    MeliError::new(format!("Invalid/unexpected response: {:?}", response))
+                        .set_summary(format!(
+                            "structure of message with UID {} was not found?",
+                            uid
+                        )),
+                ),
+            }
+        }))
+    }
+
     fn as_bytes(&mut self) -> ResultFuture<Vec<u8>> {
         let connection = self.connection.clone();
         let mailbox_hash = self.mailbox_hash;
@@ -72,8 +162,28 @@ impl BackendOp for ImapOp {
                         .await?;
                     conn.send_command(format!("UID FETCH {} (FLAGS RFC822)", uid).as_bytes())
                         .await?;
-                    conn.read_response(&mut response, RequiredResponses::FETCH_REQUIRED)
-                        .await?;
+                    /* Report download progress of large messages in 10% steps */
+                    let mut last_step = 0;
+                    let mut progress = |done: usize, total: usize| {
+                        if total < PROGRESS_MIN_BYTES || done * 10 / total <= last_step {
+                            return;
+                        }
+                        last_step = done * 10 / total;
+                        (uid_store.event_consumer)(
+                            uid_store.account_hash,
+                            BackendEvent::Progress {
+                                description: "Downloading message".to_string(),
+                                done,
+                                total,
+                            },
+                        );
+                    };
+                    conn.read_response_with_progress(
+                        &mut response,
+                        RequiredResponses::FETCH_REQUIRED,
+                        Some(&mut progress),
+                    )
+                    .await?;
                 }
                 debug!(
                     "fetch response is {} bytes and {} lines",
@@ -153,7 +263,7 @@ impl BackendOp for ImapOp {
                     ))
                     .set_summary(format!("message with UID {} was not found?", uid)));
                 }
-                let (_uid, (_flags, _)) = v[0];
+                let (_uid, (_flags, _), _) = v[0];
                 assert_eq!(uid, uid);
                 let mut bytes_cache = uid_store.byte_cache.lock()?;
                 let cache = bytes_cache.entry(uid).or_default();
@@ -170,3 +280,154 @@ impl BackendOp for ImapOp {
         }))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backends::imap::tests::fake_imap_backend;
+
+    #[test]
+    fn test_cached_body_is_not_refetched() {
+        let (backend, mailbox_hash, commands, _) = fake_imap_backend();
+        let (connection, uid_store) = (backend.connection.clone(), backend.uid_store.clone());
+        assert!(uid_store.byte_cache.lock().unwrap().is_empty());
+        let mut op = ImapOp::new(1, mailbox_hash, connection, uid_store.clone());
+        let bytes = futures::executor::block_on(op.as_bytes().unwrap()).unwrap();
+        assert_eq!(bytes, b"Subject: 1\r\n\r\nbody\r\n".to_vec());
+        /* The first fetch fills the cache, so the second one doesn't reach the server */
+        assert_eq!(
+            uid_store.byte_cache.lock().unwrap()[&1].bytes.as_ref(),
+            Some(&bytes)
+        );
+        assert_eq!(
+            futures::executor::block_on(op.as_bytes().unwrap()).unwrap(),
+            bytes
+        );
+        assert_eq!(
+            commands
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|cmd| *cmd == "UID")
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_cached_body_is_invalidated() {
+        let (backend, mailbox_hash, _, _) = fake_imap_backend();
+        let (connection, uid_store) = (backend.connection.clone(), backend.uid_store.clone());
+        let mut op = ImapOp::new(2, mailbox_hash, connection.clone(), uid_store.clone());
+        futures::executor::block_on(op.as_bytes().unwrap()).unwrap();
+        assert!(uid_store.byte_cache.lock().unwrap()[&2].bytes.is_some());
+        uid_store
+            .uid_index
+            .lock()
+            .unwrap()
+            .insert((mailbox_hash, 2), 20);
+        uid_store
+            .msn_index
+            .lock()
+            .unwrap()
+            .insert(mailbox_hash, vec![1, 2]);
+        let mut conn = futures::executor::block_on(connection.lock());
+        /* A flag update replaces the cached flags */
+        assert!(futures::executor::block_on(
+            conn.process_untagged(b"* 2 FETCH (UID 2 FLAGS (\\Flagged))\r\n")
+        )
+        .unwrap());
+        assert_eq!(
+            uid_store.byte_cache.lock().unwrap()[&2].flags,
+            Some(Flag::FLAGGED)
+        );
+        /* An expunged message's bytes are dropped */
+        assert!(futures::executor::block_on(conn.process_untagged(b"* 2 EXPUNGE\r\n")).unwrap());
+        assert!(!uid_store.byte_cache.lock().unwrap().contains_key(&2));
+        assert_eq!(*uid_store.msn_index.lock().unwrap()[&mailbox_hash], [1]);
+    }
+
+    #[test]
+    fn test_mailbox_selection_is_cached() {
+        let (backend, mailbox_hash, commands, _) = fake_imap_backend();
+        let (connection, uid_store) = (backend.connection.clone(), backend.uid_store.clone());
+        for uid in 1..=2 {
+            let mut op = ImapOp::new(uid, mailbox_hash, connection.clone(), uid_store.clone());
+            let bytes = futures::executor::block_on(op.as_bytes().unwrap()).unwrap();
+            assert!(bytes.starts_with(format!("Subject: {}", uid).as_bytes()));
+        }
+        {
+            let mut conn = futures::executor::block_on(connection.lock());
+            let mut response = Vec::new();
+            for _ in 0..2 {
+                futures::executor::block_on(conn.select_mailbox(
+                    mailbox_hash,
+                    &mut response,
+                    false,
+                ))
+                .unwrap();
+            }
+        }
+        let commands = commands.lock().unwrap();
+        let count = |c: &str| commands.iter().filter(|cmd| *cmd == c).count();
+        assert_eq!(count("EXAMINE"), 1);
+        assert_eq!(count("SELECT"), 1);
+        assert_eq!(count("UID"), 3);
+    }
+
+    #[test]
+    fn test_body_structure() {
+        let (backend, mailbox_hash, commands, _) = fake_imap_backend();
+        let mut op = ImapOp::new(
+            2,
+            mailbox_hash,
+            backend.connection.clone(),
+            backend.uid_store.clone(),
+        );
+        let structure = futures::executor::block_on(op.body_structure().unwrap()).unwrap();
+        assert_eq!(structure.mime_type, "multipart/mixed");
+        assert_eq!(
+            structure
+                .attachments()
+                .iter()
+                .map(|p| p.part_number.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["2"]
+        );
+        /* Only the structure is fetched, not the message */
+        assert_eq!(
+            commands
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|c| *c == "UID")
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_fetch_part() {
+        assert_eq!(fetch_part_command(7, "1.2"), "UID FETCH 7 BODY.PEEK[1.2]");
+
+        let (backend, mailbox_hash, commands, _) = fake_imap_backend();
+        let (connection, uid_store) = (backend.connection.clone(), backend.uid_store.clone());
+        let mut op = ImapOp::new(2, mailbox_hash, connection, uid_store.clone());
+        for _ in 0..2 {
+            assert_eq!(
+                futures::executor::block_on(op.fetch_part("1.2").unwrap()).unwrap(),
+                b"part 1.2".to_vec()
+            );
+        }
+        assert_eq!(
+            commands
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|cmd| *cmd == "UID")
+                .count(),
+            1
+        );
+        assert!(uid_store.byte_cache.lock().unwrap()[&2].bytes.is_none());
+    }
+}
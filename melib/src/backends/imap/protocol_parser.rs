@@ -20,6 +20,7 @@
  */
 
 use super::*;
+use crate::backends::BodyStructure;
 use crate::email::address::{Address, MailboxAddress};
 use crate::email::parser::{
     generic::{byte_in_range, byte_in_slice},
@@ -69,6 +70,23 @@ bitflags! {
 }
 
 impl RequiredResponses {
+    /// Returns `true` if `line` is the tagged `OK`, `NO` or `BAD` completion result of the
+    /// command issued with `tag`, i.e. no more responses for that command will follow.
+    pub fn is_complete(tag: &[u8], line: &[u8]) -> bool {
+        if tag.is_empty() || !line.starts_with(tag) || line.get(tag.len()) != Some(&b' ') {
+            return false;
+        }
+        let status = &line[tag.len() + 1..];
+        [&b"OK"[..], b"NO", b"BAD"].iter().any(|s| {
+            status.len() >= s.len()
+                && status[..s.len()].eq_ignore_ascii_case(s)
+                && status
+                    .get(s.len())
+                    .map(|&b| b == b' ' || b == b'\r')
+                    .unwrap_or(true)
+        })
+    }
+
     pub fn check(&self, line: &[u8]) -> bool {
         if !line.starts_with(b"* ") {
             return false;
@@ -115,7 +133,7 @@ impl RequiredResponses {
             ret |= line.ends_with(b"EXPUNGE\r\n");
         }
         if self.intersects(RequiredResponses::SEARCH) {
-            ret |= line.starts_with(b"SEARCH");
+            ret |= line.starts_with(b"SEARCH") || line.starts_with(b"ESEARCH");
         }
         if self.intersects(RequiredResponses::FETCH) {
             let mut ptr = 0;
@@ -150,6 +168,29 @@ fn test_imap_required_responses() {
     assert_eq!(v.len(), 1);
 }
 
+#[test]
+fn test_imap_required_responses_is_complete() {
+    let response = &b"* 1040 FETCH (UID 1064 FLAGS ())\r\n* 1041 FETCH (UID 1065 FLAGS (\\Seen))\r\nA3 OK FETCH completed\r\n"[..];
+    let lines = response.split_rn().collect::<Vec<&[u8]>>();
+    assert_eq!(lines.len(), 3);
+    assert!(!RequiredResponses::is_complete(b"A3", lines[0]));
+    assert!(!RequiredResponses::is_complete(b"A3", lines[1]));
+    assert!(RequiredResponses::is_complete(b"A3", lines[2]));
+    /* Only the tag of the issued command completes it */
+    assert!(!RequiredResponses::is_complete(b"A2", lines[2]));
+    assert!(!RequiredResponses::is_complete(b"A", lines[2]));
+
+    let response = &b"* 12 FETCH (UID 1064 FLAGS ())\r\nA4 NO [SERVERBUG] FETCH failed\r\n"[..];
+    let lines = response.split_rn().collect::<Vec<&[u8]>>();
+    assert!(!RequiredResponses::is_complete(b"A4", lines[0]));
+    assert!(RequiredResponses::is_complete(b"A4", lines[1]));
+    assert!(RequiredResponses::is_complete(
+        b"A5",
+        b"A5 bad command unknown\r\n"
+    ));
+    assert!(!RequiredResponses::is_complete(b"A5", b"A5 NOOP\r\n"));
+}
+
 #[derive(Debug)]
 pub struct Alert(String);
 pub type ImapParseResult<'a, T> = Result<(&'a [u8], T, Option<Alert>)>;
@@ -189,6 +230,8 @@ pub enum ResponseCode {
     Uidvalidity(UID),
     /// Followed by a decimal number, indicates the number of the first message without the \Seen flag set.
     Unseen(ImapNum),
+    /// Followed by the UIDVALIDITY of the destination mailbox and the UID assigned to the appended message (RFC4315 UIDPLUS).
+    Appenduid(UID, UID),
 }
 
 impl std::fmt::Display for ResponseCode {
@@ -207,6 +250,7 @@ impl std::fmt::Display for ResponseCode {
             Uidnext(uid) => write!(fmt, "Next UID value is {}", uid),
             Uidvalidity(uid) => write!(fmt, "Next UIDVALIDITY value is {}", uid),
             Unseen(uid) => write!(fmt, "First message without the \\Seen flag is {}", uid),
+            Appenduid(_, uid) => write!(fmt, "Appended message has UID {}", uid),
         }
     }
 }
@@ -238,8 +282,23 @@ impl ResponseCode {
         } else if val.starts_with(b"UNSEEN") {
             //FIXME
             Unseen(0)
+        } else if let Some((uidvalidity, uid)) = val
+            .strip_prefix(b"APPENDUID ")
+            .and_then(|args| args.find(b"]").map(|pos| &args[..pos]))
+            .and_then(|args| {
+                let mut args = args.split(|&b| b == b' ').filter(|a| !a.is_empty());
+                let uidvalidity = std::str::from_utf8(args.next()?).ok()?.parse().ok()?;
+                let uid = std::str::from_utf8(args.next()?).ok()?.parse().ok()?;
+                Some((uidvalidity, uid))
+            })
+        {
+            Appenduid(uidvalidity, uid)
         } else {
-            let msg = &val[val.find(b"] ").unwrap() + 1..].trim();
+            let msg = val
+                .find(b"]")
+                .and_then(|pos| val.get(pos + 1..))
+                .unwrap_or(val)
+                .trim();
             Alert(String::from_utf8_lossy(msg).to_string())
         }
     }
@@ -275,16 +334,18 @@ impl TryFrom<&'_ [u8]> for ImapResponse {
             })?];
         }
 
+        /* The response text is mandatory, but don't rely on the server sending it. */
+        let text = |prefix: &[u8]| val.get(prefix.len()..).unwrap_or_default();
         Ok(if val.starts_with(b"OK") {
-            Self::Ok(ResponseCode::from(&val[b"OK ".len()..]))
+            Self::Ok(ResponseCode::from(text(b"OK ")))
         } else if val.starts_with(b"NO") {
-            Self::No(ResponseCode::from(&val[b"NO ".len()..]))
+            Self::No(ResponseCode::from(text(b"NO ")))
         } else if val.starts_with(b"BAD") {
-            Self::Bad(ResponseCode::from(&val[b"BAD ".len()..]))
+            Self::Bad(ResponseCode::from(text(b"BAD ")))
         } else if val.starts_with(b"PREAUTH") {
-            Self::Preauth(ResponseCode::from(&val[b"PREAUTH ".len()..]))
+            Self::Preauth(ResponseCode::from(text(b"PREAUTH ")))
         } else if val.starts_with(b"BYE") {
-            Self::Bye(ResponseCode::from(&val[b"BYE ".len()..]))
+            Self::Bye(ResponseCode::from(text(b"BYE ")))
         } else {
             return Err(MeliError::new(format!(
                 "Expected tagged IMAP response (OK,NO,BAD, etc) but found {:?}",
@@ -312,6 +373,37 @@ impl Into<Result<()>> for ImapResponse {
 #[test]
 fn test_imap_response() {
     assert_eq!(ImapResponse::try_from(&b"M12 NO [CANNOT] Invalid mailbox name: Name must not have \'/\' characters (0.000 + 0.098 + 0.097 secs).\r\n"[..]).unwrap(), ImapResponse::No(ResponseCode::Alert("Invalid mailbox name: Name must not have '/' characters".to_string())));
+    assert!(ImapResponse::try_from(&b"M13 WHATEVER this is not a status\r\n"[..]).is_err());
+    assert!(ImapResponse::try_from(&b"garbage"[..]).is_err());
+    assert_eq!(
+        ImapResponse::try_from(&b"M14 OK\r\n"[..]).unwrap(),
+        ImapResponse::Ok(ResponseCode::Alert(String::new()))
+    );
+    let res: Result<()> = ImapResponse::try_from(&b"M15 NO [UNKNOWNCODE\r\n"[..])
+        .unwrap()
+        .into();
+    assert!(res.is_err());
+}
+
+#[test]
+fn test_imap_response_appenduid() {
+    assert_eq!(
+        ImapResponse::try_from(&b"M16 OK [APPENDUID 38505 3955] APPEND completed\r\n"[..]).unwrap(),
+        ImapResponse::Ok(ResponseCode::Appenduid(38505, 3955))
+    );
+    /* The whole response read by `ImapType::save_with_date`, with untagged responses */
+    assert_eq!(
+        ImapResponse::try_from(
+            &b"* 3 EXISTS\r\n* 1 RECENT\r\nM17 OK [APPENDUID 1 42] Append completed.\r\n"[..]
+        )
+        .unwrap(),
+        ImapResponse::Ok(ResponseCode::Appenduid(1, 42))
+    );
+    /* Servers without UIDPLUS */
+    assert_eq!(
+        ImapResponse::try_from(&b"M18 OK Append completed.\r\n"[..]).unwrap(),
+        ImapResponse::Ok(ResponseCode::Alert("Append completed.".to_string()))
+    );
 }
 
 impl<'a> std::iter::DoubleEndedIterator for ImapLineIterator<'a> {
@@ -345,17 +437,83 @@ impl<'a> Iterator for ImapLineIterator<'a> {
 
     fn next(&mut self) -> Option<&'a [u8]> {
         if self.slice.is_empty() {
-            None
-        } else if let Some(pos) = self.slice.find(b"\r\n") {
-            let ret = self.slice.get(..pos + 2).unwrap_or_default();
-            self.slice = self.slice.get(pos + 2..).unwrap_or_default();
-            Some(ret)
-        } else {
-            let ret = self.slice;
-            self.slice = self.slice.get(ret.len()..).unwrap_or_default();
-            Some(ret)
+            return None;
         }
+        /* A line ending in a literal prefix `{n}` continues after the `n` bytes of the literal,
+         * which may themselves contain CRLFs. */
+        let mut offset = 0;
+        while let Some(pos) = self.slice.get(offset..).unwrap_or_default().find(b"\r\n") {
+            let line_end = offset + pos;
+            match literal_length(self.slice.get(offset..line_end).unwrap_or_default()) {
+                Some(length) if line_end + 2 + length < self.slice.len() => {
+                    offset = line_end + 2 + length;
+                }
+                Some(_) => {
+                    /* The literal spans past the end of the buffer */
+                    break;
+                }
+                None => {
+                    let ret = self.slice.get(..line_end + 2).unwrap_or_default();
+                    self.slice = self.slice.get(line_end + 2..).unwrap_or_default();
+                    return Some(ret);
+                }
+            }
+        }
+        let ret = self.slice;
+        self.slice = self.slice.get(ret.len()..).unwrap_or_default();
+        Some(ret)
+    }
+}
+
+/// Returns `n` if `line` ends with a literal prefix `{n}`, or `{n+}` for non-synchronizing
+/// literals (RFC 7888).
+pub(super) fn literal_length(line: &[u8]) -> Option<usize> {
+    let line = line.strip_suffix(b"}")?;
+    let start = line.rfind(b"{")?;
+    let digits = &line[start + 1..];
+    let digits = digits.strip_suffix(b"+").unwrap_or(digits);
+    if digits.is_empty() || !digits.iter().all(u8::is_ascii_digit) {
+        return None;
     }
+    usize::from_str(std::str::from_utf8(digits).ok()?).ok()
+}
+
+#[test]
+fn test_imap_literal_length() {
+    assert_eq!(literal_length(b"* 1 FETCH (RFC822 {17}"), Some(17));
+    assert_eq!(literal_length(b"A1 APPEND INBOX {5+}"), Some(5));
+    assert_eq!(literal_length(b"* 1 FETCH (FLAGS ())"), None);
+    assert_eq!(literal_length(b"* OK [ALERT] {a}"), None);
+    assert_eq!(literal_length(b"* OK {}"), None);
+}
+
+#[test]
+fn test_imap_line_iterator_literals() {
+    let response = b"* 1 FETCH (UID 7 RFC822 {35}\r\nSubject: hi\r\n\r\nline one\r\nline two\r\n)\r\n* 2 FETCH (UID 8 FLAGS (\\Seen))\r\nA3 OK done\r\n";
+    let lines: Vec<&[u8]> = response.split_rn().collect();
+    assert_eq!(
+        lines,
+        vec![
+            &b"* 1 FETCH (UID 7 RFC822 {35}\r\nSubject: hi\r\n\r\nline one\r\nline two\r\n)\r\n"[..],
+            &b"* 2 FETCH (UID 8 FLAGS (\\Seen))\r\n"[..],
+            &b"A3 OK done\r\n"[..],
+        ]
+    );
+
+    /* Buffer ends in the middle of the literal */
+    let partial = b"* 1 FETCH (UID 7 RFC822 {35}\r\nSubject: hi\r\n\r\nline";
+    assert_eq!(
+        partial.split_rn().collect::<Vec<&[u8]>>(),
+        vec![&partial[..]]
+    );
+
+    /* Braces that aren't a literal prefix don't change anything */
+    assert_eq!(
+        b"* OK {not a literal}\r\nA1 OK\r\n"
+            .split_rn()
+            .collect::<Vec<&[u8]>>(),
+        vec![&b"* OK {not a literal}\r\n"[..], &b"A1 OK\r\n"[..]]
+    );
 }
 
 pub trait ImapLineSplit {
@@ -466,6 +624,11 @@ pub struct FetchResponse<'a> {
     pub body: Option<&'a [u8]>,
     pub references: Option<&'a [u8]>,
     pub envelope: Option<Envelope>,
+    pub bodystructure: Option<BodyStructure>,
+    /// `RFC822.SIZE`, the size of the message in bytes.
+    pub size: Option<usize>,
+    /// `INTERNALDATE`, when the server received the message.
+    pub internal_date: Option<crate::UnixTimestamp>,
     pub raw_fetch_value: &'a [u8],
 }
 
@@ -523,6 +686,9 @@ pub fn fetch_response(input: &[u8]) -> ImapParseResult<FetchResponse<'_>> {
         body: None,
         references: None,
         envelope: None,
+        bodystructure: None,
+        size: None,
+        internal_date: None,
         raw_fetch_value: &[],
     };
 
@@ -537,7 +703,6 @@ pub fn fetch_response(input: &[u8]) -> ImapParseResult<FetchResponse<'_>> {
     eat_whitespace!();
     should_start_with!(&input[i..], b"FETCH (");
     i += b"FETCH (".len();
-    let mut has_attachments = false;
     while i < input.len() {
         eat_whitespace!(break);
         bounds!(break);
@@ -583,6 +748,30 @@ pub fn fetch_response(input: &[u8]) -> ImapParseResult<FetchResponse<'_>> {
                     String::from_utf8_lossy(&input)
                 ))));
             }
+        } else if input[i..].starts_with(b"RFC822.SIZE ") {
+            i += b"RFC822.SIZE ".len();
+            if let Ok((rest, size)) =
+                take_while::<_, &[u8], (&[u8], nom::error::ErrorKind)>(is_digit)(&input[i..])
+            {
+                i += input.len() - i - rest.len();
+                ret.size = usize::from_str(to_str!(size)).ok();
+            } else {
+                return debug!(Err(MeliError::new(format!(
+                    "Unexpected input while parsing RFC822.SIZE in UID FETCH response. Got: `{:.40}`",
+                    String::from_utf8_lossy(input)
+                ))));
+            }
+        } else if input[i..].starts_with(b"INTERNALDATE ") {
+            i += b"INTERNALDATE ".len();
+            if let Ok((rest, date)) = quoted(&input[i..]) {
+                i += input.len() - i - rest.len();
+                ret.internal_date = internal_date_to_timestamp(&date);
+            } else {
+                return debug!(Err(MeliError::new(format!(
+                    "Unexpected input while parsing INTERNALDATE in UID FETCH response. Got: `{:.40}`",
+                    String::from_utf8_lossy(input)
+                ))));
+            }
         } else if input[i..].starts_with(b"RFC822 {") {
             i += b"RFC822 ".len();
             if let Ok((rest, body)) =
@@ -616,9 +805,9 @@ pub fn fetch_response(input: &[u8]) -> ImapParseResult<FetchResponse<'_>> {
         } else if input[i..].starts_with(b"BODYSTRUCTURE ") {
             i += b"BODYSTRUCTURE ".len();
 
-            let (rest, _has_attachments) = bodystructure_has_attachments(&input[i..])?;
-            has_attachments = _has_attachments;
+            let (rest, bodystructure) = bodystructure(&input[i..])?;
             i += input[i..].len() - rest.len();
+            ret.bodystructure = Some(bodystructure);
         } else if input[i..].starts_with(b"BODY[HEADER.FIELDS (REFERENCES)] ") {
             i += b"BODY[HEADER.FIELDS (REFERENCES)] ".len();
             if let Ok((rest, mut references)) = astring_token(&input[i..]) {
@@ -635,6 +824,23 @@ pub fn fetch_response(input: &[u8]) -> ImapParseResult<FetchResponse<'_>> {
                     String::from_utf8_lossy(&input[i..])
                 ))));
             }
+        } else if input[i..].starts_with(b"BODY[") {
+            /* A single body section, e.g. `BODY[1.2] {512}` in reply to BODY.PEEK[1.2] */
+            i += b"BODY[".len();
+            if let Ok((rest, (_section, body))) = nom::sequence::separated_pair(
+                take_until("]"),
+                tag("] "),
+                alt((string_token, map(tag("NIL"), |_| &b""[..]))),
+            )(&input[i..])
+            {
+                ret.body = Some(body);
+                i += input.len() - i - rest.len();
+            } else {
+                return debug!(Err(MeliError::new(format!(
+                    "Unexpected input while parsing UID FETCH response. Got: `{:.40}`",
+                    String::from_utf8_lossy(&input[i..])
+                ))));
+            }
         } else if input[i..].starts_with(b")\r\n") {
             i += b")\r\n".len();
             break;
@@ -651,13 +857,30 @@ pub fn fetch_response(input: &[u8]) -> ImapParseResult<FetchResponse<'_>> {
     }
     ret.raw_fetch_value = &input[..i];
 
+    if let (Some(env), Some(bodystructure)) = (ret.envelope.as_mut(), ret.bodystructure.as_ref()) {
+        env.set_has_attachments(bodystructure.has_non_inline_parts());
+    }
     if let Some(env) = ret.envelope.as_mut() {
-        env.set_has_attachments(has_attachments);
+        env.set_size(ret.size);
+        env.set_received(ret.internal_date);
     }
 
     Ok((&input[i..], ret, None))
 }
 
+/// Parses an `INTERNALDATE` value, e.g. `17-Jul-1996 02:44:25 -0700`. The day may be padded with
+/// a space instead of a zero.
+pub fn internal_date_to_timestamp(date: &[u8]) -> Option<crate::UnixTimestamp> {
+    /* `rfc822_to_timestamp` expects the day, month and year separated by spaces */
+    let date = String::from_utf8_lossy(date)
+        .trim_start()
+        .replacen('-', " ", 2);
+    match crate::datetime::rfc822_to_timestamp(date) {
+        Ok(0) | Err(_) => None,
+        Ok(timestamp) => Some(timestamp),
+    }
+}
+
 pub fn fetch_responses(mut input: &[u8]) -> ImapParseResult<Vec<FetchResponse<'_>>> {
     let mut ret = Vec::new();
     let mut alert: Option<Alert> = None;
@@ -699,26 +922,126 @@ pub fn fetch_responses(mut input: &[u8]) -> ImapParseResult<Vec<FetchResponse<'_
     Ok((input, ret, alert))
 }
 
-pub fn uid_fetch_flags_responses(input: &[u8]) -> IResult<&[u8], Vec<(UID, (Flag, Vec<String>))>> {
+/// UID, flags and MODSEQ of a `UID FETCH (FLAGS)` response.
+pub type UidFetchFlags = (UID, (Flag, Vec<String>), Option<ModSequence>);
+
+pub fn uid_fetch_flags_responses(input: &[u8]) -> IResult<&[u8], Vec<UidFetchFlags>> {
     many0(uid_fetch_flags_response)(input)
 }
 
-pub fn uid_fetch_flags_response(input: &[u8]) -> IResult<&[u8], (UID, (Flag, Vec<String>))> {
+pub fn uid_fetch_flags_response(input: &[u8]) -> IResult<&[u8], UidFetchFlags> {
+    enum FetchItem {
+        Uid(UID),
+        Flags((Flag, Vec<String>)),
+        Modseq(Option<ModSequence>),
+    }
     let (input, _) = tag("* ")(input)?;
     let (input, _msn) = take_while(is_digit)(input)?;
     let (input, _) = tag(" FETCH (")(input)?;
-    let (input, uid_flags) = permutation((
-        preceded(
-            alt((tag("UID "), tag(" UID "))),
-            map_res(digit1, |s| UID::from_str(to_str!(s))),
-        ),
-        preceded(
-            alt((tag("FLAGS "), tag(" FLAGS "))),
-            delimited(tag("("), byte_flags, tag(")")),
-        ),
-    ))(input)?;
-    let (input, _) = tag(")\r\n")(input)?;
-    Ok((input, (uid_flags.0, uid_flags.1)))
+    let (rest, items) = separated_nonempty_list(
+        tag(" "),
+        alt((
+            map(
+                preceded(tag("UID "), map_res(digit1, |s| UID::from_str(to_str!(s)))),
+                FetchItem::Uid,
+            ),
+            map(
+                preceded(tag("FLAGS "), delimited(tag("("), byte_flags, tag(")"))),
+                FetchItem::Flags,
+            ),
+            map(
+                delimited(tag("MODSEQ ("), digit1, tag(")")),
+                |s: &[u8]| {
+                    FetchItem::Modseq(
+                        u64::from_str(to_str!(s))
+                            .ok()
+                            .and_then(std::num::NonZeroU64::new)
+                            .map(ModSequence),
+                    )
+                },
+            ),
+        )),
+    )(input)?;
+    let (rest, _) = tag(")\r\n")(rest)?;
+    let (mut uid, mut flags, mut modseq) = (None, None, None);
+    for item in items {
+        match item {
+            FetchItem::Uid(v) => uid = Some(v),
+            FetchItem::Flags(v) => flags = Some(v),
+            FetchItem::Modseq(v) => modseq = v,
+        }
+    }
+    match (uid, flags) {
+        (Some(uid), Some(flags)) => Ok((rest, (uid, flags, modseq))),
+        _ => Err(nom::Err::Error(
+            (input, "uid_fetch_flags_response(): expected UID and FLAGS").into(),
+        )),
+    }
+}
+
+#[test]
+fn test_uid_fetch_flags_response() {
+    let (rest, (uid, (flags, keywords), modseq)) = uid_fetch_flags_response(
+        b"* 3 FETCH (UID 10 MODSEQ (12345) FLAGS (\\Seen $Junk))\r\nM5 OK Fetch completed\r\n",
+    )
+    .unwrap();
+    assert_eq!(rest, &b"M5 OK Fetch completed\r\n"[..]);
+    assert_eq!(uid, 10);
+    assert_eq!(flags, Flag::SEEN);
+    assert_eq!(keywords, vec!["$Junk".to_string()]);
+    assert_eq!(
+        modseq,
+        Some(ModSequence(std::num::NonZeroU64::new(12345).unwrap()))
+    );
+
+    let (_, (uid, (flags, _), modseq)) =
+        uid_fetch_flags_response(b"* 1 FETCH (FLAGS (\\Flagged) UID 4)\r\n").unwrap();
+    assert_eq!(uid, 4);
+    assert_eq!(flags, Flag::FLAGGED);
+    assert_eq!(modseq, None);
+
+    assert!(uid_fetch_flags_response(b"* 1 FETCH (MODSEQ (3))\r\n").is_err());
+}
+
+#[test]
+fn test_fetch_response_size() {
+    let (_, response, _) =
+        fetch_response(b"* 1 FETCH (UID 4 RFC822.SIZE 4286 FLAGS (\\Seen))\r\n").unwrap();
+    assert_eq!(response.uid, Some(4));
+    assert_eq!(response.size, Some(4286));
+
+    let (_, response, _) = fetch_response(
+        b"* 2 FETCH (UID 5 RFC822.SIZE 10485760 ENVELOPE (\"Wed, 17 Jul 1996 02:23:25 -0700\" \"Big\" NIL NIL NIL NIL NIL NIL NIL \"<big@example.com>\"))\r\n",
+    )
+    .unwrap();
+    assert_eq!(response.envelope.unwrap().size(), Some(10485760));
+
+    let (_, response, _) = fetch_response(b"* 3 FETCH (UID 6 FLAGS ())\r\n").unwrap();
+    assert_eq!(response.size, None);
+}
+
+#[test]
+fn test_imap_internal_date() {
+    assert_eq!(
+        internal_date_to_timestamp(b"17-Jul-1996 02:44:25 -0700"),
+        Some(837596665)
+    );
+    assert_eq!(
+        internal_date_to_timestamp(b" 5-Jul-1996 02:44:25 +0000"),
+        Some(836534665)
+    );
+    assert_eq!(
+        internal_date_to_timestamp(b"05-Jul-1996 02:44:25 +0000"),
+        Some(836534665)
+    );
+    assert_eq!(internal_date_to_timestamp(b"yesterday"), None);
+
+    let (_, response, _) = fetch_response(
+        b"* 1 FETCH (UID 4 INTERNALDATE \" 5-Jul-1996 02:44:25 +0000\" ENVELOPE (\"Wed, 17 Jul 1996 02:23:25 -0700\" \"Late\" NIL NIL NIL NIL NIL NIL NIL \"<late@example.com>\"))\r\n",
+    )
+    .unwrap();
+    assert_eq!(response.internal_date, Some(836534665));
+    assert_eq!(response.envelope.unwrap().received(), Some(836534665));
 }
 
 macro_rules! flags_to_imap_list {
@@ -899,6 +1222,9 @@ fn test_untagged_responses() {
             body: None,
             references: None,
             envelope: None,
+            bodystructure: None,
+            size: None,
+            internal_date: None,
             raw_fetch_value: &b"* 1079 FETCH (UID 1103 MODSEQ (1365) FLAGS (\\Seen))\r\n"[..],
         })
     );
@@ -915,6 +1241,9 @@ fn test_untagged_responses() {
             body: None,
             references: None,
             envelope: None,
+            bodystructure: None,
+            size: None,
+            internal_date: None,
             raw_fetch_value: &b"* 1 FETCH (FLAGS (\\Seen))\r\n"[..],
         })
     );
@@ -955,6 +1284,121 @@ pub fn search_results_raw<'a>(input: &'a [u8]) -> IResult<&'a [u8], &'a [u8]> {
     ))(input)
 }
 
+/// An ESEARCH (RFC 4731) response, e.g. `* ESEARCH (TAG "A1") UID MIN 1 MAX 99 COUNT 42`.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct EsearchResponse {
+    pub tag: Option<String>,
+    /// Whether the results are UIDs instead of message sequence numbers.
+    pub uid: bool,
+    pub min: Option<ImapNum>,
+    pub max: Option<ImapNum>,
+    pub count: Option<ImapNum>,
+    pub all: Vec<ImapNum>,
+}
+
+/// Expands a sequence set such as `1:3,5` to `[1, 2, 3, 5]`.
+fn sequence_set(input: &[u8]) -> Option<Vec<ImapNum>> {
+    let mut ret = vec![];
+    for range in input.split(|&b| b == b',') {
+        let mut bounds = range.splitn(2, |&b| b == b':').map(|n| {
+            std::str::from_utf8(n)
+                .ok()
+                .and_then(|n| ImapNum::from_str(n).ok())
+        });
+        let start = bounds.next()??;
+        let end = bounds.next().unwrap_or(Some(start))?;
+        ret.extend(std::cmp::min(start, end)..=std::cmp::max(start, end));
+    }
+    Some(ret)
+}
+
+pub fn esearch_results(input: &[u8]) -> IResult<&[u8], EsearchResponse> {
+    let (input, _) = tag("* ESEARCH")(input)?;
+    let (input, tag_) = opt(delimited(
+        tag(" (TAG \""),
+        take_while(|b| b != b'"'),
+        tag("\")"),
+    ))(input)?;
+    let (input, uid) = opt(tag(" UID"))(input)?;
+    let (rest, data) = take_until("\r\n")(input)?;
+    let (rest, _) = tag("\r\n")(rest)?;
+    let mut ret = EsearchResponse {
+        tag: tag_.map(|t| String::from_utf8_lossy(t).to_string()),
+        uid: uid.is_some(),
+        ..EsearchResponse::default()
+    };
+    let mut tokens = data.split(|&b| b == b' ').filter(|t| !t.is_empty());
+    while let Some(key) = tokens.next() {
+        let value = tokens
+            .next()
+            .ok_or_else(|| nom::Err::Error((input, "esearch_results(): missing value").into()))?;
+        let number = || {
+            std::str::from_utf8(value)
+                .ok()
+                .and_then(|n| ImapNum::from_str(n).ok())
+                .ok_or_else(|| {
+                    nom::Err::Error((input, "esearch_results(): expected number").into())
+                })
+        };
+        if key.eq_ignore_ascii_case(b"MIN") {
+            ret.min = Some(number()?);
+        } else if key.eq_ignore_ascii_case(b"MAX") {
+            ret.max = Some(number()?);
+        } else if key.eq_ignore_ascii_case(b"COUNT") {
+            ret.count = Some(number()?);
+        } else if key.eq_ignore_ascii_case(b"ALL") {
+            ret.all = sequence_set(value).ok_or_else(|| {
+                nom::Err::Error((input, "esearch_results(): invalid sequence set").into())
+            })?;
+        }
+    }
+    Ok((rest, ret))
+}
+
+#[test]
+fn test_imap_esearch() {
+    assert_eq!(
+        esearch_results(b"* ESEARCH (TAG \"A1\") UID ALL 1:3,5\r\n").map(|(_, v)| v),
+        Ok(EsearchResponse {
+            tag: Some("A1".to_string()),
+            uid: true,
+            all: vec![1, 2, 3, 5],
+            ..EsearchResponse::default()
+        })
+    );
+    assert_eq!(
+        esearch_results(b"* ESEARCH (TAG \"A1\") UID MIN 1 MAX 99 COUNT 42\r\n").map(|(_, v)| v),
+        Ok(EsearchResponse {
+            tag: Some("A1".to_string()),
+            uid: true,
+            min: Some(1),
+            max: Some(99),
+            count: Some(42),
+            all: vec![],
+        })
+    );
+    assert_eq!(
+        esearch_results(b"* ESEARCH (TAG \"M4\") UID COUNT 4 ALL 7,10:12\r\n").map(|(_, v)| v),
+        Ok(EsearchResponse {
+            tag: Some("M4".to_string()),
+            uid: true,
+            count: Some(4),
+            all: vec![7, 10, 11, 12],
+            ..EsearchResponse::default()
+        })
+    );
+    /* No matches */
+    assert_eq!(
+        esearch_results(b"* ESEARCH (TAG \"M5\") UID\r\n").map(|(_, v)| v),
+        Ok(EsearchResponse {
+            tag: Some("M5".to_string()),
+            uid: true,
+            ..EsearchResponse::default()
+        })
+    );
+    assert!(esearch_results(b"* SEARCH 1 2\r\n").is_err());
+}
+
 #[test]
 fn test_imap_search() {
     assert_eq!(search_results(b"* SEARCH\r\n").map(|(_, v)| v), Ok(vec![]));
@@ -1436,41 +1880,169 @@ pub fn uid_fetch_envelopes_response(
             let (input, _) = tag(" ENVELOPE ")(input)?;
             let (input, env) = envelope(input.ltrim())?;
             let (input, _) = tag("BODYSTRUCTURE ")(input)?;
-            let (input, has_attachments) = bodystructure_has_attachments(input)?;
+            let (input, bodystructure) = bodystructure(input)?;
             let (input, _) = tag(")\r\n")(input)?;
             Ok((input, {
                 let mut env = env;
-                env.set_has_attachments(has_attachments);
+                env.set_has_attachments(bodystructure.has_non_inline_parts());
                 (uid_flags.0, uid_flags.1, env)
             }))
         },
     )(input)
 }
 
-pub fn bodystructure_has_attachments(input: &[u8]) -> IResult<&[u8], bool> {
+/// Generic IMAP parenthesized list value, used to walk `BODYSTRUCTURE` before interpreting it.
+#[derive(Debug)]
+enum BodyValue {
+    Nil,
+    String(Vec<u8>),
+    List(Vec<BodyValue>),
+}
+
+impl BodyValue {
+    fn as_str(&self) -> Option<String> {
+        match self {
+            BodyValue::String(s) => Some(String::from_utf8_lossy(s).into_owned()),
+            _ => None,
+        }
+    }
+
+    fn as_list(&self) -> Option<&[BodyValue]> {
+        match self {
+            BodyValue::List(l) => Some(l),
+            _ => None,
+        }
+    }
+}
+
+fn body_value(input: &[u8]) -> IResult<&[u8], BodyValue> {
     let (input, _) = eat_whitespace(input)?;
-    let (input, _) = tag("(")(input)?;
-    let (mut input, _) = eat_whitespace(input)?;
-    let mut has_attachments = false;
-    let mut first_in_line = true;
-    while !input.is_empty() && !input.starts_with(b")") {
-        if input.starts_with(b"\"") || input[0].is_ascii_alphanumeric() || input[0] == b'{' {
-            let (_input, token) = astring_token(input)?;
-            input = _input;
-            if first_in_line {
-                has_attachments |= token.eq_ignore_ascii_case(b"attachment");
+    if input.starts_with(b"(") {
+        let (mut input, _) = eat_whitespace(&input[1..])?;
+        let mut list = Vec::new();
+        while !input.starts_with(b")") {
+            if input.is_empty() {
+                return Err(nom::Err::Error(
+                    (input, "body_value(): unterminated list").into(),
+                ));
             }
-        } else if input.starts_with(b"(") {
-            let (_input, _has_attachments) = bodystructure_has_attachments(input)?;
-            has_attachments |= _has_attachments;
+            let (_input, value) = body_value(input)?;
+            let (_input, _) = eat_whitespace(_input)?;
+            list.push(value);
             input = _input;
         }
-        let (_input, _) = eat_whitespace(input)?;
-        input = _input;
-        first_in_line = false;
+        Ok((&input[1..], BodyValue::List(list)))
+    } else if input.starts_with(b"\"") || input.starts_with(b"{") {
+        let (input, s) = string_token(input)?;
+        Ok((input, BodyValue::String(s.to_vec())))
+    } else {
+        let (input, atom) = is_not(" ()\r\n")(input)?;
+        if atom.eq_ignore_ascii_case(b"NIL") {
+            Ok((input, BodyValue::Nil))
+        } else {
+            Ok((input, BodyValue::String(atom.to_vec())))
+        }
     }
-    let (input, _) = tag(")")(input)?;
-    Ok((input, has_attachments))
+}
+
+// Looks up `key` in a body-fld-param list, i.e. `("NAME" "file.pdf" "CHARSET" "utf-8")`.
+fn body_param(params: Option<&BodyValue>, key: &str) -> Option<String> {
+    params?.as_list()?.chunks(2).find_map(|kv| match kv {
+        [k, v] if k.as_str()?.eq_ignore_ascii_case(key) => v.as_str(),
+        _ => None,
+    })
+}
+
+fn body_structure_from_value(value: &[BodyValue], part_number: String) -> Option<BodyStructure> {
+    let child_number = |i: usize| {
+        if part_number.is_empty() {
+            (i + 1).to_string()
+        } else {
+            format!("{}.{}", part_number, i + 1)
+        }
+    };
+    let disposition_at = |i: usize| -> (Option<String>, Option<String>) {
+        match value.get(i).and_then(BodyValue::as_list) {
+            Some([disposition, params, ..]) => (
+                disposition.as_str().map(|d| d.to_ascii_lowercase()),
+                body_param(Some(params), "filename"),
+            ),
+            _ => (None, None),
+        }
+    };
+
+    if let Some(BodyValue::List(_)) = value.first() {
+        // body-type-mpart = 1*body SP media-subtype [SP body-ext-mpart]
+        // body-ext-mpart  = body-fld-param [SP body-fld-dsp ...]
+        let children_count = value
+            .iter()
+            .take_while(|v| matches!(v, BodyValue::List(_)))
+            .count();
+        let parts = value[..children_count]
+            .iter()
+            .enumerate()
+            .map(|(i, v)| body_structure_from_value(v.as_list()?, child_number(i)))
+            .collect::<Option<Vec<BodyStructure>>>()?;
+        let subtype = value.get(children_count)?.as_str()?;
+        let (disposition, filename) = disposition_at(children_count + 2);
+        return Some(BodyStructure {
+            part_number,
+            mime_type: format!("multipart/{}", subtype).to_ascii_lowercase(),
+            charset: None,
+            encoding: None,
+            id: None,
+            size: None,
+            disposition,
+            filename,
+            parts,
+        });
+    }
+
+    // body-type-1part = (body-type-basic / body-type-msg / body-type-text) [SP body-ext-1part]
+    // body-fields     = body-fld-param SP body-fld-id SP body-fld-desc SP body-fld-enc SP
+    //                   body-fld-octets
+    // body-ext-1part  = body-fld-md5 [SP body-fld-dsp ...]
+    let mime_type =
+        format!("{}/{}", value.first()?.as_str()?, value.get(1)?.as_str()?).to_ascii_lowercase();
+    let encoding = value.get(5).and_then(BodyValue::as_str);
+    let size = value
+        .get(6)
+        .and_then(BodyValue::as_str)
+        .and_then(|s| usize::from_str(&s).ok());
+    // text parts carry body-fld-lines, message/rfc822 parts carry envelope, body and lines.
+    let extra = if mime_type.starts_with("text/") {
+        1
+    } else if mime_type == "message/rfc822" {
+        3
+    } else {
+        0
+    };
+    let (disposition, filename) = disposition_at(7 + extra + 1);
+    Some(BodyStructure {
+        part_number: if part_number.is_empty() {
+            "1".to_string()
+        } else {
+            part_number
+        },
+        mime_type,
+        charset: body_param(value.get(2), "charset"),
+        encoding,
+        id: value.get(3).and_then(BodyValue::as_str),
+        size,
+        disposition,
+        filename: filename.or_else(|| body_param(value.get(2), "name")),
+        parts: vec![],
+    })
+}
+
+/// Parses the value of a `BODYSTRUCTURE` fetch item into a tree of MIME parts.
+pub fn bodystructure(input: &[u8]) -> IResult<&[u8], BodyStructure> {
+    let (rest, value) = body_value(input)?;
+    value
+        .as_list()
+        .and_then(|l| body_structure_from_value(l, String::new()))
+        .map(|b| (rest, b))
+        .ok_or_else(|| nom::Err::Error((input, "bodystructure(): invalid body structure").into()))
 }
 
 fn eat_whitespace(mut input: &[u8]) -> IResult<&[u8], ()> {
@@ -1665,3 +2237,57 @@ pub fn generate_envelope_hash(mailbox_path: &str, uid: &UID) -> EnvelopeHash {
     h.write(mailbox_path.as_bytes());
     h.finish()
 }
+
+#[test]
+fn test_imap_bodystructure() {
+    let (rest, simple) =
+        bodystructure(b"(\"TEXT\" \"PLAIN\" (\"CHARSET\" \"US-ASCII\") NIL NIL \"7BIT\" 3028 92) ")
+            .unwrap();
+    assert_eq!(rest, b" ");
+    assert_eq!(
+        simple,
+        BodyStructure {
+            part_number: "1".to_string(),
+            mime_type: "text/plain".to_string(),
+            charset: Some("US-ASCII".to_string()),
+            encoding: Some("7BIT".to_string()),
+            id: None,
+            size: Some(3028),
+            disposition: None,
+            filename: None,
+            parts: vec![],
+        }
+    );
+    assert!(!simple.has_non_inline_parts());
+
+    let (_, mixed) = bodystructure(b"((\"text\" \"plain\" (\"charset\" \"utf-8\") NIL NIL \"quoted-printable\" 120 4 NIL NIL NIL)(\"application\" \"pdf\" (\"name\" \"report.pdf\") NIL NIL \"base64\" 54360 NIL (\"attachment\" (\"filename\" \"report.pdf\")) NIL) \"mixed\" (\"boundary\" \"abc\") NIL NIL)").unwrap();
+    assert_eq!(mixed.mime_type, "multipart/mixed");
+    assert_eq!(mixed.part_number, "");
+    assert_eq!(mixed.parts.len(), 2);
+    assert_eq!(mixed.parts[0].part_number, "1");
+    assert_eq!(mixed.parts[1].part_number, "2");
+    assert_eq!(mixed.parts[1].mime_type, "application/pdf");
+    assert_eq!(mixed.parts[1].encoding.as_deref(), Some("base64"));
+    assert_eq!(mixed.parts[1].size, Some(54360));
+    assert_eq!(mixed.parts[1].disposition.as_deref(), Some("attachment"));
+    assert_eq!(mixed.parts[1].filename.as_deref(), Some("report.pdf"));
+    assert!(mixed.has_non_inline_parts());
+    assert_eq!(mixed.attachments(), vec![&mixed.parts[1]]);
+
+    let (_, alternative) = bodystructure(b"((\"text\" \"plain\" (\"charset\" \"utf-8\") NIL NIL \"7bit\" 40 2 NIL NIL NIL NIL)((\"text\" \"html\" (\"charset\" \"utf-8\") NIL NIL \"7bit\" 120 3 NIL NIL NIL NIL)(\"image\" \"png\" NIL \"<logo>\" NIL \"base64\" 2048 NIL (\"inline\" NIL) NIL NIL) \"related\" (\"boundary\" \"inner\") NIL NIL NIL) \"alternative\" (\"boundary\" \"outer\") NIL NIL NIL)").unwrap();
+    assert_eq!(alternative.mime_type, "multipart/alternative");
+    assert_eq!(alternative.parts[1].mime_type, "multipart/related");
+    assert_eq!(alternative.parts[1].parts[1].part_number, "2.2");
+    assert_eq!(alternative.parts[1].parts[1].mime_type, "image/png");
+    assert_eq!(alternative.parts[1].parts[1].id.as_deref(), Some("<logo>"));
+    assert!(!alternative.has_non_inline_parts());
+
+    /* Inline images referenced with cid: URLs often have no disposition */
+    let (_, related) = bodystructure(b"((\"text\" \"html\" (\"charset\" \"utf-8\") NIL NIL \"7bit\" 120 3 NIL NIL NIL NIL)(\"image\" \"png\" NIL \"<logo>\" NIL \"base64\" 2048 NIL NIL NIL NIL) \"related\" (\"boundary\" \"inner\") NIL NIL NIL)").unwrap();
+    assert!(!related.has_non_inline_parts());
+
+    /* The signature of a signed message is not an attachment */
+    let (_, signed) = bodystructure(b"((\"text\" \"plain\" (\"charset\" \"utf-8\") NIL NIL \"7bit\" 40 2 NIL NIL NIL NIL)(\"application\" \"pgp-signature\" (\"name\" \"signature.asc\") NIL \"OpenPGP digital signature\" \"7bit\" 833 NIL NIL NIL NIL) \"signed\" (\"micalg\" \"pgp-sha256\" \"protocol\" \"application/pgp-signature\" \"boundary\" \"sig\") NIL NIL NIL)").unwrap();
+    assert_eq!(signed.parts[1].mime_type, "application/pgp-signature");
+    assert!(!signed.has_non_inline_parts());
+}
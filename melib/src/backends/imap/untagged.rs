@@ -19,7 +19,7 @@
  * along with meli. If not, see <http://www.gnu.org/licenses/>.
  */
 
-use super::{ImapConnection, MailboxSelection, UID};
+use super::{ImapConnection, MailboxSelection, MessageSequenceNumber, UID};
 use crate::backends::imap::protocol_parser::{
     generate_envelope_hash, FetchResponse, ImapLineSplit, RequiredResponses, UntaggedResponse,
 };
@@ -30,7 +30,6 @@ use crate::backends::{
 };
 use crate::email::Envelope;
 use crate::error::*;
-use std::convert::TryInto;
 
 impl ImapConnection {
     pub async fn process_untagged(&mut self, line: &[u8]) -> Result<bool> {
@@ -72,15 +71,14 @@ impl ImapConnection {
                 self.uid_store.is_online.lock().unwrap().1 = Err(reason.into());
             }
             UntaggedResponse::Expunge(n) => {
-                if self
+                let deleted_uid = self
                     .uid_store
                     .msn_index
                     .lock()
                     .unwrap()
-                    .get(&mailbox_hash)
-                    .map(|i| i.len() < TryInto::<usize>::try_into(n).unwrap())
-                    .unwrap_or(true)
-                {
+                    .get_mut(&mailbox_hash)
+                    .and_then(|msn_index| expunge_msn(msn_index, n));
+                if deleted_uid.is_none() {
                     debug!(
                         "Received expunge {} but mailbox msn index is {:?}",
                         n,
@@ -128,6 +126,11 @@ impl ImapConnection {
                             .lock()
                             .unwrap()
                             .remove(&deleted_hash);
+                        self.uid_store
+                            .byte_cache
+                            .lock()
+                            .unwrap()
+                            .remove(&deleted_uid);
                         events.push((
                             deleted_uid,
                             RefreshEvent {
@@ -146,14 +149,7 @@ impl ImapConnection {
 
                     return Ok(true);
                 }
-                let deleted_uid = self
-                    .uid_store
-                    .msn_index
-                    .lock()
-                    .unwrap()
-                    .entry(mailbox_hash)
-                    .or_default()
-                    .remove(TryInto::<usize>::try_into(n).unwrap().saturating_sub(1));
+                let deleted_uid = deleted_uid.unwrap();
                 debug!("expunge {}, UID = {}", n, deleted_uid);
                 let deleted_hash: crate::email::EnvelopeHash = match self
                     .uid_store
@@ -172,6 +168,11 @@ impl ImapConnection {
                     .lock()
                     .unwrap()
                     .remove(&deleted_hash);
+                self.uid_store
+                    .byte_cache
+                    .lock()
+                    .unwrap()
+                    .remove(&deleted_uid);
                 let mut event: [(UID, RefreshEvent); 1] = [(
                     deleted_uid,
                     RefreshEvent {
@@ -230,6 +231,7 @@ impl ImapConnection {
                                 Envelope::from_bytes(body.unwrap(), flags.as_ref().map(|&(f, _)| f))
                             {
                                 env.set_hash(env_hash);
+                                env.set_size(Some(body.unwrap().len()));
                                 self.uid_store
                                     .hash_index
                                     .lock()
@@ -337,6 +339,7 @@ impl ImapConnection {
                                             body.unwrap(),
                                             flags.as_ref().map(|&(f, _)| f),
                                         ) {
+                                            env.set_size(Some(body.unwrap().len()));
                                             self.uid_store
                                                 .hash_index
                                                 .lock()
@@ -418,6 +421,9 @@ impl ImapConnection {
                 body: _,
                 references: _,
                 envelope: _,
+                bodystructure: _,
+                size: _,
+                internal_date: _,
                 raw_fetch_value: _,
             }) => {
                 if let Some(flags) = flags {
@@ -472,6 +478,10 @@ impl ImapConnection {
                                 .unwrap()
                                 .insert(env_hash, modseq);
                         }
+                        if let Some(cache) = self.uid_store.byte_cache.lock().unwrap().get_mut(&uid)
+                        {
+                            cache.flags = Some(flags.0);
+                        }
                         let mut event: [(UID, RefreshEvent); 1] = [(
                             uid,
                             RefreshEvent {
@@ -498,3 +508,38 @@ impl ImapConnection {
         Ok(true)
     }
 }
+
+/// Removes the message with sequence number `n` from `msn_index`, returning its UID. As per RFC
+/// 3501, the sequence numbers of the messages after it are decremented by one. Returns `None` if
+/// `n` is not a valid sequence number for `msn_index`.
+fn expunge_msn(msn_index: &mut Vec<UID>, n: MessageSequenceNumber) -> Option<UID> {
+    if n == 0 || n > msn_index.len() {
+        return None;
+    }
+    Some(msn_index.remove(n - 1))
+}
+
+#[test]
+fn test_imap_expunge_msn() {
+    use super::protocol_parser::untagged_responses;
+    let mut msn_index: Vec<UID> = (101..=110).collect();
+    let mut expunged = vec![];
+    for response in &[
+        &b"* 9 EXPUNGE\r\n"[..],
+        &b"* 8 EXPUNGE\r\n"[..],
+        &b"* 7 EXPUNGE\r\n"[..],
+    ] {
+        let n = match untagged_responses(response).unwrap().1 {
+            Some(UntaggedResponse::Expunge(n)) => n,
+            other => panic!("unexpected response {:?}", other),
+        };
+        expunged.push(expunge_msn(&mut msn_index, n).unwrap());
+    }
+    assert_eq!(expunged, vec![109, 108, 107]);
+    assert_eq!(msn_index, vec![101, 102, 103, 104, 105, 106, 110]);
+    /* Sequence numbers after an expunged message are decremented */
+    assert_eq!(expunge_msn(&mut msn_index, 7), Some(110));
+    assert_eq!(expunge_msn(&mut msn_index, 7), None);
+    assert_eq!(expunge_msn(&mut msn_index, 0), None);
+    assert_eq!(msn_index.len(), 6);
+}
@@ -20,6 +20,7 @@
  */
 use super::*;
 use crate::backends::SpecialUsageMailbox;
+use std::future::Future;
 use std::sync::Arc;
 
 /// Arguments for IMAP watching functions
@@ -29,6 +30,36 @@ pub struct ImapWatchKit {
     pub uid_store: Arc<UIDStore>,
 }
 
+/// Seconds between polls of servers without IDLE support, unless `poll_interval` is set.
+pub const IMAP_DEFAULT_POLL_INTERVAL: u64 = 60;
+/// How often a disabled (`0`) `poll_interval` is checked for changes.
+const POLL_INTERVAL_RECHECK: Duration = Duration::from_secs(60);
+
+/// Runs `poll` and then `sleep`s for the poll interval in seconds returned by `interval`, until
+/// `poll` fails. The interval is read before every wait, and while it is `0` `poll` is not run.
+async fn poll_loop<S, P, PF, Z, ZF>(
+    mut state: S,
+    interval: impl Fn() -> u64,
+    mut poll: P,
+    mut sleep: Z,
+) -> Result<()>
+where
+    P: FnMut(S) -> PF,
+    PF: Future<Output = Result<S>>,
+    Z: FnMut(Duration) -> ZF,
+    ZF: Future<Output = ()>,
+{
+    loop {
+        match interval() {
+            0 => sleep(POLL_INTERVAL_RECHECK).await,
+            secs => {
+                state = poll(state).await?;
+                sleep(Duration::from_secs(secs)).await;
+            }
+        }
+    }
+}
+
 pub async fn poll_with_examine(kit: ImapWatchKit) -> Result<()> {
     debug!("poll with examine");
     let ImapWatchKit {
@@ -41,13 +72,24 @@ pub async fn poll_with_examine(kit: ImapWatchKit) -> Result<()> {
         let mailboxes_lck = timeout(uid_store.timeout, uid_store.mailboxes.lock()).await?;
         mailboxes_lck.clone()
     };
-    loop {
-        for (_, mailbox) in mailboxes.clone() {
-            examine_updates(mailbox, &mut conn, &uid_store).await?;
-        }
-        //FIXME: make sleep duration configurable
-        smol::Timer::after(std::time::Duration::from_secs(3 * 60)).await;
-    }
+    poll_loop(
+        conn,
+        || uid_store.poll_interval.load(Ordering::Relaxed),
+        |mut conn| {
+            let mailboxes = mailboxes.clone();
+            let uid_store = uid_store.clone();
+            async move {
+                for (_, mailbox) in mailboxes {
+                    examine_updates(mailbox, &mut conn, &uid_store).await?;
+                }
+                Ok(conn)
+            }
+        },
+        |duration| async move {
+            smol::Timer::after(duration).await;
+        },
+    )
+    .await
 }
 
 pub async fn idle(kit: ImapWatchKit) -> Result<()> {
@@ -244,6 +286,76 @@ pub async fn examine_updates(
                 uidvalidities.insert(mailbox_hash, select_response.uidvalidity);
             }
         }
+        if conn.use_condstore() {
+            /* Fetch only the flags that changed since the last HIGHESTMODSEQ we saw */
+            let cached_highestmodseq = uid_store
+                .highestmodseqs
+                .lock()
+                .unwrap()
+                .get(&mailbox_hash)
+                .and_then(|m| m.ok());
+            let new_highestmodseq = select_response.highestmodseq.and_then(|m| m.ok());
+            if let (Some(cached_highestmodseq), Some(new_highestmodseq)) =
+                (cached_highestmodseq, new_highestmodseq)
+            {
+                if cached_highestmodseq != new_highestmodseq {
+                    conn.send_command(
+                        format!(
+                            "UID FETCH 1:* (FLAGS) (CHANGEDSINCE {})",
+                            cached_highestmodseq
+                        )
+                        .as_bytes(),
+                    )
+                    .await?;
+                    conn.read_response(&mut response, RequiredResponses::FETCH_REQUIRED)
+                        .await?;
+                    let v = protocol_parser::uid_fetch_flags_responses(&response)
+                        .map(|(_, v)| v)
+                        .map_err(MeliError::from)?;
+                    let mut refresh_events = vec![];
+                    for (uid, flags, modseq) in v {
+                        let env_hash = match uid_store
+                            .uid_index
+                            .lock()
+                            .unwrap()
+                            .get(&(mailbox_hash, uid))
+                        {
+                            Some(&env_hash) => env_hash,
+                            None => continue,
+                        };
+                        if !flags.0.intersects(crate::email::Flag::SEEN) {
+                            mailbox.unseen.lock().unwrap().insert_new(env_hash);
+                        } else {
+                            mailbox.unseen.lock().unwrap().remove(env_hash);
+                        }
+                        if let Some(modseq) = modseq {
+                            uid_store.modseq.lock().unwrap().insert(env_hash, modseq);
+                        }
+                        refresh_events.push((
+                            uid,
+                            RefreshEvent {
+                                account_hash: uid_store.account_hash,
+                                mailbox_hash,
+                                kind: RefreshEventKind::NewFlags(env_hash, flags),
+                            },
+                        ));
+                    }
+                    if uid_store.keep_offline_cache {
+                        cache_handle.update(mailbox_hash, &refresh_events)?;
+                    }
+                    for (_, ev) in refresh_events {
+                        conn.add_refresh_event(ev);
+                    }
+                }
+            }
+            if let Some(highestmodseq) = select_response.highestmodseq {
+                uid_store
+                    .highestmodseqs
+                    .lock()
+                    .unwrap()
+                    .insert(mailbox_hash, highestmodseq);
+            }
+        }
         if debug!(select_response.recent > 0) {
             /* UID SEARCH RECENT */
             conn.send_command(b"UID SEARCH RECENT").await?;
@@ -296,9 +408,11 @@ pub async fn examine_updates(
         } in v.iter_mut()
         {
             let uid = uid.unwrap();
-            *envelope = Envelope::from_bytes(body.take().unwrap(), flags.as_ref().map(|&(f, _)| f))
+            let body = body.take().unwrap();
+            *envelope = Envelope::from_bytes(body, flags.as_ref().map(|&(f, _)| f))
                 .map(|mut env| {
                     env.set_hash(generate_envelope_hash(&mailbox.imap_path(), &uid));
+                    env.set_size(Some(body.len()));
                     if let Some((_, keywords)) = flags.take() {
                         let mut tag_lck = uid_store.tag_index.write().unwrap();
                         for f in keywords {
@@ -361,3 +475,43 @@ pub async fn examine_updates(
     }
     Ok(())
 }
+
+#[test]
+fn test_imap_poll_loop() {
+    use std::cell::{Cell, RefCell};
+
+    let interval = Cell::new(0);
+    let polls = Cell::new(0);
+    let sleeps = RefCell::new(vec![]);
+    let result = futures::executor::block_on(poll_loop(
+        (),
+        || interval.get(),
+        |()| {
+            polls.set(polls.get() + 1);
+            futures::future::ready(if polls.get() < 3 {
+                Ok(())
+            } else {
+                Err(MeliError::new("disconnected"))
+            })
+        },
+        |duration| {
+            sleeps.borrow_mut().push(duration);
+            /* Enable polling after a while, as if the setting was changed */
+            if sleeps.borrow().len() == 2 {
+                interval.set(5);
+            }
+            futures::future::ready(())
+        },
+    ));
+    assert_eq!(result.unwrap_err().to_string(), "disconnected");
+    assert_eq!(polls.get(), 3);
+    assert_eq!(
+        sleeps.into_inner(),
+        vec![
+            POLL_INTERVAL_RECHECK,
+            POLL_INTERVAL_RECHECK,
+            Duration::from_secs(5),
+            Duration::from_secs(5)
+        ]
+    );
+}
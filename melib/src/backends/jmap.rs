@@ -308,6 +308,7 @@ impl MailBackend for JmapType {
             extensions: None,
             supports_tags: true,
             supports_submission: false,
+            supports_fetch_range: false,
         };
         CAPABILITIES
     }
@@ -101,14 +101,28 @@ pub async fn get_mailboxes(conn: &JmapConnection) -> Result<HashMap<MailboxHash,
         .await?;
 
     let res_text = res.text_async().await?;
-    let mut v: MethodResponse = serde_json::from_str(&res_text).unwrap();
+    let (account_id, mailboxes) = parse_mailbox_get(&res_text)?;
     *conn.store.online_status.lock().await = (std::time::Instant::now(), Ok(()));
+    *conn.store.account_id.lock().unwrap() = account_id;
+    Ok(mailboxes)
+}
+
+/// Parses a `Mailbox/get` response into the account id and its mailboxes, with each mailbox's
+/// `children` filled in from the `parentId` of the others.
+pub fn parse_mailbox_get(
+    res_text: &str,
+) -> Result<(Id<Account>, HashMap<MailboxHash, JmapMailbox>)> {
+    let mut v: MethodResponse = serde_json::from_str(res_text)?;
+    if v.method_responses.is_empty() {
+        return Err(MeliError::new(
+            "Mailbox/get response has no method responses.",
+        ));
+    }
     let m = GetResponse::<MailboxObject>::try_from(v.method_responses.remove(0))?;
     let GetResponse::<MailboxObject> {
         list, account_id, ..
     } = m;
-    *conn.store.account_id.lock().unwrap() = account_id;
-    Ok(list
+    let mut ret: HashMap<MailboxHash, JmapMailbox> = list
         .into_iter()
         .map(|r| {
             let MailboxObject {
@@ -156,7 +170,17 @@ pub async fn get_mailboxes(conn: &JmapConnection) -> Result<HashMap<MailboxHash,
                 },
             )
         })
-        .collect())
+        .collect();
+    let parents: Vec<(MailboxHash, MailboxHash)> = ret
+        .values()
+        .filter_map(|m| m.parent_hash.map(|p| (p, m.hash)))
+        .collect();
+    for (parent_hash, hash) in parents {
+        if let Some(parent) = ret.get_mut(&parent_hash) {
+            parent.children.push(hash);
+        }
+    }
+    Ok((account_id, ret))
 }
 
 pub async fn get_message_list(
@@ -337,3 +361,32 @@ pub fn keywords_to_flags(keywords: Vec<String>) -> (Flag, Vec<String>) {
     }
     (f, tags)
 }
+
+#[test]
+fn test_jmap_mailbox_get() {
+    let rights = r#"{"mayReadItems":true,"mayAddItems":true,"mayRemoveItems":true,"maySetSeen":true,"maySetKeywords":true,"mayCreateChild":true,"mayRename":false,"mayDelete":false,"maySubmit":true}"#;
+    let res_text = format!(
+        r#"{{"methodResponses":[["Mailbox/get",{{"accountId":"u33084183","state":"78540","list":[{{"id":"MB23cfa8094c0f41e6","name":"Inbox","parentId":null,"role":"inbox","sortOrder":10,"totalEmails":16307,"unreadEmails":13905,"totalThreads":5833,"unreadThreads":5128,"myRights":{rights},"isSubscribed":true}},{{"id":"MB674cc24095db49ce","name":"Important mail","parentId":"MB23cfa8094c0f41e6","role":null,"sortOrder":0,"totalEmails":1129,"unreadEmails":0,"totalThreads":1129,"unreadThreads":0,"myRights":{rights},"isSubscribed":false}}],"notFound":[]}},"0"]],"sessionState":"75128aab4b1b"}}"#,
+        rights = rights
+    );
+    let (account_id, mailboxes) = parse_mailbox_get(&res_text).unwrap();
+    assert_eq!(account_id.as_str(), "u33084183");
+    assert_eq!(mailboxes.len(), 2);
+
+    let inbox = mailboxes.values().find(|m| m.name() == "Inbox").unwrap();
+    let important = mailboxes
+        .values()
+        .find(|m| m.name() == "Important mail")
+        .unwrap();
+    assert_eq!(inbox.special_usage(), SpecialUsageMailbox::Inbox);
+    assert!(inbox.is_subscribed());
+    assert_eq!(inbox.parent(), None);
+    assert_eq!(inbox.children(), &[important.hash()]);
+    assert_eq!(inbox.count().unwrap(), (13905, 16307));
+    assert_eq!(important.special_usage(), SpecialUsageMailbox::Normal);
+    assert!(!important.is_subscribed());
+    assert_eq!(important.parent(), Some(inbox.hash()));
+    assert!(important.children().is_empty());
+
+    assert!(parse_mailbox_get(r#"{"methodResponses":[]}"#).is_err());
+}
@@ -124,6 +124,8 @@ pub struct MaildirMailbox {
     permissions: MailboxPermissions,
     pub total: Arc<Mutex<usize>>,
     pub unseen: Arc<Mutex<usize>>,
+    /// Messages found in `new/` by the last fetch or delivered there since.
+    pub recent: Arc<Mutex<usize>>,
 }
 
 impl MaildirMailbox {
@@ -141,14 +143,17 @@ impl MaildirMailbox {
 
         /* Check if mailbox path (Eg `INBOX/Lists/luddites`) is included in the subscribed
          * mailboxes in user configuration */
-        let fname = pathbuf
-            .strip_prefix(
-                PathBuf::from(&settings.root_mailbox)
-                    .expand()
-                    .parent()
-                    .unwrap_or_else(|| &Path::new("/")),
-            )
-            .ok();
+        let fname = settings
+            .root_mailboxes()
+            .into_iter()
+            .map(|r| PathBuf::from(r).expand())
+            .find(|r| pathbuf.starts_with(r))
+            .and_then(|r| {
+                pathbuf
+                    .strip_prefix(r.parent().unwrap_or_else(|| Path::new("/")))
+                    .ok()
+                    .map(Path::to_path_buf)
+            });
 
         let read_only = if let Ok(metadata) = std::fs::metadata(&pathbuf) {
             metadata.permissions().readonly()
@@ -159,7 +164,7 @@ impl MaildirMailbox {
         let ret = MaildirMailbox {
             hash: h.finish(),
             name: file_name,
-            path: fname.unwrap().to_path_buf(),
+            path: fname.unwrap(),
             fs_path: pathbuf,
             parent,
             children,
@@ -177,10 +182,17 @@ impl MaildirMailbox {
             },
             unseen: Arc::new(Mutex::new(0)),
             total: Arc::new(Mutex::new(0)),
+            recent: Arc::new(Mutex::new(0)),
         };
         if !accept_invalid {
             ret.is_valid()?;
         }
+        /* Show counts before the mailbox is fetched; fetching recounts them. */
+        if let Ok((unseen, total, recent)) = count_messages(ret.fs_path()) {
+            *ret.unseen.lock()? = unseen;
+            *ret.total.lock()? = total;
+            *ret.recent.lock()? = recent;
+        }
         Ok(ret)
     }
 
@@ -257,6 +269,31 @@ impl BackendMailbox for MaildirMailbox {
     fn count(&self) -> Result<(usize, usize)> {
         Ok((*self.unseen.lock()?, *self.total.lock()?))
     }
+
+    /// Messages in `new/` haven't been seen by any mail reader yet.
+    fn recent(&self) -> Option<usize> {
+        self.recent.lock().ok().map(|recent| *recent)
+    }
+}
+
+/// Returns `(unseen, total, recent)` message counts of the maildir at `path` by looking at file
+/// names only. Messages in `new/` are always unseen.
+fn count_messages(path: &Path) -> Result<(usize, usize, usize)> {
+    let mut unseen = 0;
+    let mut total = 0;
+    for e in path.join("new").read_dir()? {
+        e?;
+        unseen += 1;
+        total += 1;
+    }
+    let recent = total;
+    for e in path.join("cur").read_dir()? {
+        if !e?.path().flags().contains(Flag::SEEN) {
+            unseen += 1;
+        }
+        total += 1;
+    }
+    Ok((unseen, total, recent))
 }
 
 pub trait MaildirPathTrait {
@@ -266,14 +303,19 @@ pub trait MaildirPathTrait {
 impl MaildirPathTrait for Path {
     fn flags(&self) -> Flag {
         let mut flag = Flag::default();
-        let path = self.to_string_lossy();
-        if !path.contains(":2,") {
-            return flag;
-        }
+        /* Only look at the file name: a directory name might contain ":2," as well. Messages in
+         * `new/` have no info suffix and therefore no flags. */
+        let file_name = match self.file_name() {
+            Some(f) => f.to_string_lossy(),
+            None => return flag,
+        };
+        let info = match file_name.rfind(":2,") {
+            Some(pos) => &file_name[pos + ":2,".len()..],
+            None => return flag,
+        };
 
-        for f in path.chars().rev() {
+        for f in info.chars() {
             match f {
-                ',' => break,
                 'D' => flag |= Flag::DRAFT,
                 'F' => flag |= Flag::FLAGGED,
                 'P' => flag |= Flag::PASSED,
@@ -281,7 +323,7 @@ impl MaildirPathTrait for Path {
                 'S' => flag |= Flag::SEEN,
                 'T' => flag |= Flag::TRASHED,
                 _ => {
-                    debug!("DEBUG: in MaildirPathTrait::flags(), encountered unknown flag marker {:?}, path is {}", f, path);
+                    debug!("DEBUG: in MaildirPathTrait::flags(), encountered unknown flag marker {:?}, path is {}", f, self.display());
                 }
             }
         }
@@ -289,3 +331,28 @@ impl MaildirPathTrait for Path {
         flag
     }
 }
+
+#[test]
+fn test_maildir_path_flags() {
+    assert_eq!(
+        Path::new("/mail/INBOX/new/1605116226.M530P6787.host").flags(),
+        Flag::default()
+    );
+    assert_eq!(
+        Path::new("/mail/INBOX/cur/1605116226.M530P6787.host:2,").flags(),
+        Flag::default()
+    );
+    assert_eq!(
+        Path::new("/mail/INBOX/cur/1605116226.M530P6787.host:2,S").flags(),
+        Flag::SEEN
+    );
+    assert_eq!(
+        Path::new("/mail/INBOX/cur/1605116226.M530P6787.host:2,DFRST").flags(),
+        Flag::DRAFT | Flag::FLAGGED | Flag::REPLIED | Flag::SEEN | Flag::TRASHED
+    );
+    /* ":2," in a directory name must not be mistaken for an info suffix */
+    assert_eq!(
+        Path::new("/mail/odd:2,FS/new/1605116226.M530P6787.host").flags(),
+        Flag::default()
+    );
+}
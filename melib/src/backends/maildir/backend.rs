@@ -109,7 +109,9 @@ pub struct MaildirType {
     mailbox_index: Arc<Mutex<HashMap<EnvelopeHash, MailboxHash>>>,
     hash_indexes: HashIndexes,
     event_consumer: BackendEventConsumer,
+    /// Common ancestor of all root mailboxes; cache file names are relative to it.
     path: PathBuf,
+    roots: Vec<PathBuf>,
 }
 
 macro_rules! path_is_new {
@@ -172,6 +174,7 @@ impl MailBackend for MaildirType {
             extensions: None,
             supports_tags: false,
             supports_submission: false,
+            supports_fetch_range: false,
         };
         CAPABILITIES
     }
@@ -197,6 +200,7 @@ impl MailBackend for MaildirType {
         let mailbox: &MaildirMailbox = &self.mailboxes[&mailbox_hash];
         let unseen = mailbox.unseen.clone();
         let total = mailbox.total.clone();
+        let recent = mailbox.recent.clone();
         let path: PathBuf = mailbox.fs_path().into();
         let root_path = self.path.to_path_buf();
         let map = self.hash_indexes.clone();
@@ -206,6 +210,7 @@ impl MailBackend for MaildirType {
             mailbox_hash,
             unseen,
             total,
+            recent,
             path,
             root_path,
             map,
@@ -263,7 +268,6 @@ impl MailBackend for MaildirType {
                         let mut map = map.lock().unwrap();
                         let map = map.entry(mailbox_hash).or_default();
                         if map.contains_key(&hash) {
-                            map.remove(&hash);
                             current_hashes.remove(&hash);
                             continue;
                         }
@@ -308,6 +312,14 @@ impl MailBackend for MaildirType {
                         continue;
                     }
                 }
+                {
+                    /* Messages that are no longer on disk */
+                    let mut map = map.lock().unwrap();
+                    let map = map.entry(mailbox_hash).or_default();
+                    for h in current_hashes.iter() {
+                        map.remove(h);
+                    }
+                }
                 for ev in current_hashes.into_iter().map(|h| {
                     BackendEvent::Refresh(RefreshEvent {
                         account_hash,
@@ -336,16 +348,23 @@ impl MailBackend for MaildirType {
     fn watch(&self) -> ResultFuture<()> {
         let sender = self.event_consumer.clone();
         let (tx, rx) = channel();
-        let mut watcher = watcher(tx, Duration::from_secs(2)).unwrap();
+        let mut watcher = watcher(tx, Duration::from_secs(2))
+            .map_err(|e| e.to_string())
+            .map_err(MeliError::new)?;
         let account_hash = {
             let mut hasher = DefaultHasher::default();
             hasher.write(self.name.as_bytes());
             hasher.finish()
         };
         let root_path = self.path.to_path_buf();
-        watcher.watch(&root_path, RecursiveMode::Recursive).unwrap();
+        for root in &self.roots {
+            watcher
+                .watch(root, RecursiveMode::Recursive)
+                .map_err(|e| e.to_string())
+                .map_err(MeliError::new)?;
+            debug!("watching {:?}", root);
+        }
         let cache_dir = xdg::BaseDirectories::with_profile("meli", &self.name).unwrap();
-        debug!("watching {:?}", root_path);
         let hash_indexes = self.hash_indexes.clone();
         let mailbox_index = self.mailbox_index.clone();
         let root_mailbox_hash: MailboxHash = self
@@ -357,8 +376,11 @@ impl MailBackend for MaildirType {
         let mailbox_counts = self
             .mailboxes
             .iter()
-            .map(|(&k, v)| (k, (v.unseen.clone(), v.total.clone())))
-            .collect::<HashMap<MailboxHash, (Arc<Mutex<usize>>, Arc<Mutex<usize>>)>>();
+            .map(|(&k, v)| (k, (v.unseen.clone(), v.total.clone(), v.recent.clone())))
+            .collect::<HashMap<
+                MailboxHash,
+                (Arc<Mutex<usize>>, Arc<Mutex<usize>>, Arc<Mutex<usize>>),
+            >>();
         Ok(Box::pin(async move {
             // Move `watcher` in the closure's scope so that it doesn't get dropped.
             let _watcher = watcher;
@@ -379,7 +401,8 @@ impl MailBackend for MaildirType {
                         /* Create */
                         DebouncedEvent::Create(mut pathbuf) => {
                             debug!("DebouncedEvent::Create(path = {:?}", pathbuf);
-                            if path_is_new!(pathbuf) {
+                            let is_new = path_is_new!(pathbuf);
+                            if is_new {
                                 debug!("path_is_new");
                                 /* This creates a Rename event that we will receive later */
                                 pathbuf = match move_to_cur(pathbuf) {
@@ -418,6 +441,9 @@ impl MailBackend for MaildirType {
                                     *mailbox_counts[&mailbox_hash].0.lock().unwrap() += 1;
                                 }
                                 *mailbox_counts[&mailbox_hash].1.lock().unwrap() += 1;
+                                if is_new {
+                                    *mailbox_counts[&mailbox_hash].2.lock().unwrap() += 1;
+                                }
                                 (sender)(
                                     account_hash,
                                     BackendEvent::Refresh(RefreshEvent {
@@ -1038,6 +1064,7 @@ impl MailBackend for MaildirType {
             permissions: Default::default(),
             unseen: Default::default(),
             total: Default::default(),
+            recent: Default::default(),
         };
 
         self.mailboxes.insert(mailbox_hash, new_mailbox);
@@ -1109,11 +1136,20 @@ impl MaildirType {
                 )));
             }
             let mut children = Vec::new();
+            /* Maildir++ subfolders, i.e. sibling `.Parent.Child` directories */
+            let mut maildirpp_folders = Vec::new();
+            /* Only skip `cur`, `new` and `tmp` if they are the internals of a maildir mailbox;
+             * otherwise they are mailboxes themselves. */
+            let is_maildir = is_maildir(p.as_ref());
             for mut f in fs::read_dir(&p).unwrap() {
                 'entries: for f in f.iter_mut() {
                     {
                         let path = f.path();
-                        if path.ends_with("cur") || path.ends_with("new") || path.ends_with("tmp") {
+                        if is_maildir
+                            && (path.ends_with("cur")
+                                || path.ends_with("new")
+                                || path.ends_with("tmp"))
+                        {
                             continue 'entries;
                         }
                         if path.is_dir() {
@@ -1127,10 +1163,18 @@ impl MaildirType {
                             ) {
                                 f.children = recurse_mailboxes(mailboxes, settings, &path)?;
                                 for c in &f.children {
-                                    if let Some(f) = mailboxes.get_mut(c) {
-                                        f.parent = Some(f.hash);
+                                    if let Some(c) = mailboxes.get_mut(c) {
+                                        c.parent = Some(f.hash);
                                     }
                                 }
+                                let file_name = path.file_name().unwrap().to_str().unwrap();
+                                if file_name.len() > 1 && file_name.starts_with('.') {
+                                    let folder = file_name[1..].to_string();
+                                    if let Some(name) = folder.rsplit('.').next() {
+                                        f.name = name.to_string();
+                                    }
+                                    maildirpp_folders.push((folder, f.hash));
+                                }
                                 children.push(f.hash);
                                 mailboxes.insert(f.hash, f);
                             } else {
@@ -1149,8 +1193,8 @@ impl MaildirType {
                                         &settings,
                                     ) {
                                         for c in &f.children {
-                                            if let Some(f) = mailboxes.get_mut(c) {
-                                                f.parent = Some(f.hash);
+                                            if let Some(c) = mailboxes.get_mut(c) {
+                                                c.parent = Some(f.hash);
                                             }
                                         }
                                         children.push(f.hash);
@@ -1162,52 +1206,42 @@ impl MaildirType {
                     }
                 }
             }
+            link_maildirpp_folders(mailboxes, &mut children, &maildirpp_folders);
             Ok(children)
         };
-        let root_path = PathBuf::from(settings.root_mailbox()).expand();
-        if !root_path.exists() {
-            return Err(MeliError::new(format!(
-                "Configuration error ({}): root_path `{}` is not a valid directory.",
-                settings.name(),
-                settings.root_mailbox.as_str()
-            )));
-        } else if !root_path.is_dir() {
-            return Err(MeliError::new(format!(
-                "Configuration error ({}): root_path `{}` is not a directory.",
-                settings.name(),
-                settings.root_mailbox.as_str()
-            )));
-        }
-
-        if let Ok(f) = MaildirMailbox::new(
-            root_path.to_str().unwrap().to_string(),
-            root_path.file_name().unwrap().to_str().unwrap().to_string(),
-            None,
-            Vec::with_capacity(0),
-            false,
-            settings,
-        ) {
-            mailboxes.insert(f.hash, f);
-        }
-
-        if mailboxes.is_empty() {
-            let children = recurse_mailboxes(&mut mailboxes, settings, &root_path)?;
-            for c in &children {
-                if let Some(f) = mailboxes.get_mut(c) {
-                    f.parent = None;
+        Self::validate_config(settings)?;
+        let roots = settings
+            .root_mailboxes()
+            .into_iter()
+            .map(|r| PathBuf::from(r).expand())
+            .collect::<Vec<PathBuf>>();
+        for root_path in &roots {
+            if let Ok(mut f) = MaildirMailbox::new(
+                root_path.to_str().unwrap().to_string(),
+                root_path.file_name().unwrap().to_str().unwrap().to_string(),
+                None,
+                Vec::with_capacity(0),
+                false,
+                settings,
+            ) {
+                let root_hash = f.hash;
+                f.children = recurse_mailboxes(&mut mailboxes, settings, root_path)?;
+                for c in &f.children {
+                    if let Some(c) = mailboxes.get_mut(c) {
+                        c.parent = Some(root_hash);
+                    }
                 }
-            }
-        } else {
-            let root_hash = *mailboxes.keys().next().unwrap();
-            let children = recurse_mailboxes(&mut mailboxes, settings, &root_path)?;
-            for c in &children {
-                if let Some(f) = mailboxes.get_mut(c) {
-                    f.parent = Some(root_hash);
+                mailboxes.insert(root_hash, f);
+            } else {
+                /* The root itself is not a maildir mailbox, so its children are top level
+                 * mailboxes */
+                let children = recurse_mailboxes(&mut mailboxes, settings, root_path)?;
+                for c in &children {
+                    if let Some(f) = mailboxes.get_mut(c) {
+                        f.parent = None;
+                    }
                 }
             }
-            if let Some(f) = mailboxes.get_mut(&root_hash) {
-                f.children = children;
-            }
         }
         for f in mailboxes.values_mut() {
             if is_subscribed(f.path()) {
@@ -1232,7 +1266,8 @@ impl MaildirType {
             hash_indexes: Arc::new(Mutex::new(hash_indexes)),
             mailbox_index: Default::default(),
             event_consumer,
-            path: root_path,
+            path: common_ancestor(&roots),
+            roots,
         }))
     }
 
@@ -1306,25 +1341,83 @@ impl MaildirType {
     }
 
     pub fn validate_config(s: &AccountSettings) -> Result<()> {
-        let root_path = PathBuf::from(s.root_mailbox()).expand();
-        if !root_path.exists() {
-            return Err(MeliError::new(format!(
-                "Configuration error ({}): root_path `{}` is not a valid directory.",
-                s.name(),
-                s.root_mailbox.as_str()
-            )));
-        } else if !root_path.is_dir() {
-            return Err(MeliError::new(format!(
-                "Configuration error ({}): root_path `{}` is not a directory.",
-                s.name(),
-                s.root_mailbox.as_str()
-            )));
+        for root_mailbox in s.root_mailboxes() {
+            let root_path = PathBuf::from(root_mailbox).expand();
+            if !root_path.exists() {
+                return Err(MeliError::new(format!(
+                    "Configuration error ({}): root_path `{}` is not a valid directory.",
+                    s.name(),
+                    root_mailbox
+                )));
+            } else if !root_path.is_dir() {
+                return Err(MeliError::new(format!(
+                    "Configuration error ({}): root_path `{}` is not a directory.",
+                    s.name(),
+                    root_mailbox
+                )));
+            }
         }
 
         Ok(())
     }
 }
 
+/// Returns whether `path` has the `cur`, `new` and `tmp` subdirectories of a maildir mailbox.
+fn is_maildir(path: &Path) -> bool {
+    ["cur", "new", "tmp"].iter().all(|d| path.join(d).is_dir())
+}
+
+/// Moves Maildir++ folders (`.Parent.Child` directories, given without their leading dot) under
+/// the sibling folder named after their longest existing prefix. Folders without one stay in
+/// `children`.
+fn link_maildirpp_folders(
+    mailboxes: &mut HashMap<MailboxHash, MaildirMailbox>,
+    children: &mut Vec<MailboxHash>,
+    folders: &[(String, MailboxHash)],
+) {
+    let by_name: HashMap<&str, MailboxHash> = folders
+        .iter()
+        .map(|(name, hash)| (name.as_str(), *hash))
+        .collect();
+    for (name, hash) in folders {
+        let mut prefix = name.as_str();
+        let parent_hash = loop {
+            match prefix.rfind('.') {
+                Some(idx) => prefix = &prefix[..idx],
+                None => break None,
+            }
+            if let Some(parent_hash) = by_name.get(prefix) {
+                break Some(*parent_hash);
+            }
+        };
+        if let Some(parent_hash) = parent_hash {
+            children.retain(|c| c != hash);
+            if let Some(parent) = mailboxes.get_mut(&parent_hash) {
+                parent.children.push(*hash);
+            }
+            if let Some(child) = mailboxes.get_mut(hash) {
+                child.parent = Some(parent_hash);
+            }
+        }
+    }
+}
+
+/// Returns the longest path that all of `paths` start with.
+fn common_ancestor(paths: &[PathBuf]) -> PathBuf {
+    let mut ret = match paths.first() {
+        Some(p) => p.clone(),
+        None => return PathBuf::new(),
+    };
+    for p in &paths[1..] {
+        while !p.starts_with(&ret) {
+            if !ret.pop() {
+                break;
+            }
+        }
+    }
+    ret
+}
+
 fn add_path_to_index(
     hash_index: &HashIndexes,
     mailbox_hash: MailboxHash,
@@ -1370,3 +1463,171 @@ fn add_path_to_index(
     }
     Ok(env)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TempDir;
+
+    /* Creates the `cur`, `new` and `tmp` directories of a maildir mailbox at `path` */
+    fn create_mailbox(path: &Path) {
+        for d in &["cur", "new", "tmp"] {
+            fs::create_dir_all(path.join(d)).unwrap();
+        }
+    }
+
+    fn settings(root: &Path) -> AccountSettings {
+        AccountSettings {
+            name: "maildir-test".to_string(),
+            root_mailbox: root.display().to_string(),
+            format: "maildir".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn backend(root: &Path, event_consumer: BackendEventConsumer) -> Box<dyn MailBackend> {
+        MaildirType::new(&settings(root), Box::new(|_| true), event_consumer).unwrap()
+    }
+
+    #[test]
+    fn test_maildir_recurse_mailboxes() {
+        let root = TempDir::new("maildir");
+        for mailbox in &["INBOX", "INBOX/Lists", "archive/cur", "concur"] {
+            create_mailbox(&root.join(mailbox));
+        }
+        let backend = backend(&root, BackendEventConsumer::new(Arc::new(|_, _| {})));
+        let mailboxes = smol::block_on(backend.mailboxes().unwrap()).unwrap();
+
+        let mut paths = mailboxes
+            .values()
+            .map(|m| m.path().to_string())
+            .collect::<Vec<String>>();
+        paths.sort();
+        let root_name = root.file_name().unwrap().to_str().unwrap();
+        assert_eq!(
+            paths,
+            ["INBOX", "INBOX/Lists", "archive", "archive/cur", "concur"]
+                .iter()
+                .map(|p| format!("{}/{}", root_name, p))
+                .collect::<Vec<String>>()
+        );
+        let by_path = |p: &str| {
+            mailboxes
+                .values()
+                .find(|m| m.path() == format!("{}/{}", root_name, p))
+                .unwrap()
+        };
+        assert_eq!(
+            by_path("INBOX/Lists").parent(),
+            Some(by_path("INBOX").hash())
+        );
+        assert_eq!(
+            by_path("archive/cur").parent(),
+            Some(by_path("archive").hash())
+        );
+        assert!(by_path("INBOX").parent().is_none());
+        assert!(by_path("concur").parent().is_none());
+    }
+
+    #[test]
+    fn test_maildir_plusplus_folders() {
+        let root = TempDir::new("maildirpp");
+        for mailbox in &["", ".INBOX.Sent", ".INBOX.Sent.2023", ".Drafts"] {
+            create_mailbox(&root.join(mailbox));
+        }
+        let backend = backend(&root, BackendEventConsumer::new(Arc::new(|_, _| {})));
+        let mailboxes = smol::block_on(backend.mailboxes().unwrap()).unwrap();
+
+        assert_eq!(mailboxes.len(), 4);
+        let root_name = root.file_name().unwrap().to_str().unwrap();
+        let by_path = |p: &str| {
+            mailboxes
+                .values()
+                .find(|m| m.path() == format!("{}{}", root_name, p))
+                .unwrap()
+        };
+        let root_mailbox = by_path("");
+        let sent = by_path("/.INBOX.Sent");
+        let sent_2023 = by_path("/.INBOX.Sent.2023");
+        let drafts = by_path("/.Drafts");
+        assert_eq!(sent.name(), "Sent");
+        assert_eq!(sent_2023.name(), "2023");
+        assert_eq!(drafts.name(), "Drafts");
+        assert_eq!(sent_2023.parent(), Some(sent.hash()));
+        assert_eq!(sent.children(), &[sent_2023.hash()]);
+        assert_eq!(sent.parent(), Some(root_mailbox.hash()));
+        assert_eq!(drafts.parent(), Some(root_mailbox.hash()));
+        let mut root_children = root_mailbox.children().to_vec();
+        root_children.sort();
+        let mut expected = vec![sent.hash(), drafts.hash()];
+        expected.sort();
+        assert_eq!(root_children, expected);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_maildir_watch() {
+        let root = TempDir::new("maildir-watch");
+        create_mailbox(&root);
+        let (tx, rx) = channel();
+        let tx = Mutex::new(tx);
+        let backend = backend(
+            &root,
+            BackendEventConsumer::new(Arc::new(move |_, event| {
+                let _ = tx.lock().unwrap().send(event);
+            })),
+        );
+        let root_hash = *smol::block_on(backend.mailboxes().unwrap())
+            .unwrap()
+            .keys()
+            .next()
+            .unwrap();
+        let watch = backend.watch().unwrap();
+        std::thread::spawn(move || smol::block_on(watch));
+
+        fs::write(root.join("new").join("1.host"), b"Subject: test\n\nbody\n").unwrap();
+        let event = rx.recv_timeout(Duration::from_secs(10));
+        match event {
+            Ok(BackendEvent::Refresh(RefreshEvent {
+                mailbox_hash,
+                kind: Create(env),
+                ..
+            })) => {
+                assert_eq!(mailbox_hash, root_hash);
+                assert_eq!(env.subject(), "test");
+            }
+            other => panic!("expected a Create refresh event, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_maildir_mailbox_count() {
+        let root = TempDir::new("maildir-count");
+        create_mailbox(&root);
+        for f in &[
+            "new/1.host",
+            "new/2.host",
+            "cur/3.host:2,S",
+            "cur/4.host:2,RS",
+            "cur/5.host:2,F",
+            "cur/6.host:2,",
+            "tmp/7.host",
+        ] {
+            fs::write(root.join(f), b"Subject: test\n\nbody\n").unwrap();
+        }
+        let mailbox = MaildirMailbox::new(
+            root.display().to_string(),
+            "INBOX".to_string(),
+            None,
+            Vec::new(),
+            false,
+            &settings(&root),
+        )
+        .unwrap();
+        assert_eq!(mailbox.count().unwrap(), (4, 6));
+        assert_eq!(mailbox.recent(), Some(2));
+        /* The recent count is cached instead of read from the file system */
+        fs::remove_file(root.join("new/1.host")).unwrap();
+        assert_eq!(mailbox.recent(), Some(2));
+    }
+}
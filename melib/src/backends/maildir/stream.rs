@@ -45,18 +45,25 @@ impl MaildirStream {
         mailbox_hash: MailboxHash,
         unseen: Arc<Mutex<usize>>,
         total: Arc<Mutex<usize>>,
+        recent: Arc<Mutex<usize>>,
         mut path: PathBuf,
         root_path: PathBuf,
         map: HashIndexes,
         mailbox_index: Arc<Mutex<HashMap<EnvelopeHash, MailboxHash>>>,
     ) -> Result<Pin<Box<dyn Stream<Item = Result<Vec<Envelope>>> + Send + 'static>>> {
         let chunk_size = 2048;
+        /* Counts are recalculated from scratch by the chunks below */
+        *unseen.lock().unwrap() = 0;
+        *total.lock().unwrap() = 0;
         path.push("new");
+        let mut new_count = 0;
         for d in path.read_dir()? {
             if let Ok(p) = d {
                 move_to_cur(p.path()).ok().take();
+                new_count += 1;
             }
         }
+        *recent.lock().unwrap() = new_count;
         path.pop();
         path.push("cur");
         let iter = path.read_dir()?;
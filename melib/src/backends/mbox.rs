@@ -710,6 +710,7 @@ impl MailBackend for MboxType {
             extensions: None,
             supports_tags: false,
             supports_submission: false,
+            supports_fetch_range: false,
         };
         CAPABILITIES
     }
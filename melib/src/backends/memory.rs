@@ -0,0 +1,476 @@
+/*
+ * meli - memory backend
+ *
+ * Copyright 2020 Manos Pitsidianakis
+ *
+ * This file is part of meli.
+ *
+ * meli is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * meli is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with meli. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! In-memory mail backend.
+//!
+//! Messages and mailboxes only live in memory, which makes this backend useful for deterministic
+//! tests that would otherwise need a maildir on disk. It is only compiled with the `test-utils`
+//! feature.
+//!
+//! ```ignore
+//! let backend = MemoryType::builder()
+//!     .mailbox("INBOX")
+//!     .message("INBOX", b"From: a@example.com\nSubject: hi\n\nhello\n".to_vec())
+//!     .build(event_consumer)?;
+//! ```
+
+use super::*;
+use crate::get_path_hash;
+use std::sync::Mutex;
+
+#[derive(Debug, Clone)]
+struct MemoryMailbox {
+    hash: MailboxHash,
+    name: String,
+    path: String,
+    children: Vec<MailboxHash>,
+    parent: Option<MailboxHash>,
+    usage: SpecialUsageMailbox,
+    is_subscribed: bool,
+    envelopes: Arc<Mutex<Vec<Envelope>>>,
+}
+
+impl BackendMailbox for MemoryMailbox {
+    fn hash(&self) -> MailboxHash {
+        self.hash
+    }
+
+    fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    fn path(&self) -> &str {
+        self.path.as_str()
+    }
+
+    fn change_name(&mut self, s: &str) {
+        self.name = s.to_string();
+    }
+
+    fn clone(&self) -> Mailbox {
+        Box::new(std::clone::Clone::clone(self))
+    }
+
+    fn children(&self) -> &[MailboxHash] {
+        &self.children
+    }
+
+    fn parent(&self) -> Option<MailboxHash> {
+        self.parent
+    }
+
+    fn special_usage(&self) -> SpecialUsageMailbox {
+        self.usage
+    }
+
+    fn permissions(&self) -> MailboxPermissions {
+        MailboxPermissions {
+            create_messages: true,
+            remove_messages: true,
+            set_flags: true,
+            create_child: false,
+            rename_messages: true,
+            delete_messages: true,
+            delete_mailbox: false,
+            change_permissions: false,
+        }
+    }
+
+    fn is_subscribed(&self) -> bool {
+        self.is_subscribed
+    }
+
+    fn set_is_subscribed(&mut self, new_val: bool) -> Result<()> {
+        self.is_subscribed = new_val;
+        Ok(())
+    }
+
+    fn set_special_usage(&mut self, new_val: SpecialUsageMailbox) -> Result<()> {
+        self.usage = new_val;
+        Ok(())
+    }
+
+    fn count(&self) -> Result<(usize, usize)> {
+        let envelopes = self.envelopes.lock()?;
+        Ok((
+            envelopes.iter().filter(|e| !e.is_seen()).count(),
+            envelopes.len(),
+        ))
+    }
+}
+
+/// `BackendOp` implementor for `MemoryType`.
+#[derive(Debug)]
+pub struct MemoryOp {
+    hash: EnvelopeHash,
+    bytes: Arc<Mutex<HashMap<EnvelopeHash, Vec<u8>>>>,
+    mailboxes: Arc<Mutex<HashMap<MailboxHash, MemoryMailbox>>>,
+}
+
+impl BackendOp for MemoryOp {
+    fn as_bytes(&mut self) -> ResultFuture<Vec<u8>> {
+        let ret =
+            self.bytes.lock()?.get(&self.hash).cloned().ok_or_else(|| {
+                MeliError::new(format!("Message with hash {} not found.", self.hash))
+            });
+        Ok(Box::pin(async move { ret }))
+    }
+
+    fn fetch_flags(&self) -> ResultFuture<Flag> {
+        let hash = self.hash;
+        let ret = self
+            .mailboxes
+            .lock()?
+            .values()
+            .find_map(|m| {
+                m.envelopes
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .find(|e| e.hash() == hash)
+                    .map(Envelope::flags)
+            })
+            .ok_or_else(|| MeliError::new(format!("Message with hash {} not found.", hash)));
+        Ok(Box::pin(async move { ret }))
+    }
+}
+
+/// Builder for `MemoryType`, used to seed mailboxes and messages.
+#[derive(Debug, Default)]
+pub struct MemoryTypeBuilder {
+    mailboxes: Vec<String>,
+    messages: Vec<(String, Vec<u8>, Option<Flag>)>,
+}
+
+impl MemoryTypeBuilder {
+    /// Add a mailbox. Mailbox paths use `/` as a separator; parents must be added before their
+    /// children.
+    pub fn mailbox(mut self, path: &str) -> Self {
+        self.mailboxes.push(path.to_string());
+        self
+    }
+
+    /// Add a message to the mailbox at `path`.
+    pub fn message(self, path: &str, bytes: Vec<u8>) -> Self {
+        self.message_with_flags(path, bytes, None)
+    }
+
+    pub fn message_with_flags(mut self, path: &str, bytes: Vec<u8>, flags: Option<Flag>) -> Self {
+        self.messages.push((path.to_string(), bytes, flags));
+        self
+    }
+
+    pub fn build(self, event_consumer: BackendEventConsumer) -> Result<MemoryType> {
+        let mut mailboxes: HashMap<MailboxHash, MemoryMailbox> = HashMap::default();
+        for path in self.mailboxes {
+            let hash = get_path_hash!(&path);
+            let (parent, name) = match path.rfind('/') {
+                Some(pos) => (
+                    Some(get_path_hash!(&path[..pos])),
+                    path[pos + 1..].to_string(),
+                ),
+                None => (None, path.clone()),
+            };
+            if let Some(parent) = parent {
+                mailboxes
+                    .get_mut(&parent)
+                    .ok_or_else(|| {
+                        MeliError::new(format!(
+                            "Parent of mailbox {} was not added before it.",
+                            path
+                        ))
+                    })?
+                    .children
+                    .push(hash);
+            }
+            mailboxes.insert(
+                hash,
+                MemoryMailbox {
+                    hash,
+                    usage: SpecialUsageMailbox::detect_usage(&name).unwrap_or_default(),
+                    name,
+                    path,
+                    children: vec![],
+                    parent,
+                    is_subscribed: true,
+                    envelopes: Arc::new(Mutex::new(vec![])),
+                },
+            );
+        }
+        let mut bytes_index: HashMap<EnvelopeHash, Vec<u8>> = HashMap::default();
+        for (path, bytes, flags) in self.messages {
+            let mailbox = mailboxes
+                .get(&get_path_hash!(&path))
+                .ok_or_else(|| MeliError::new(format!("Mailbox {} not found.", path)))?;
+            let env = Envelope::from_bytes(&bytes, flags)?;
+            bytes_index.insert(env.hash(), bytes);
+            mailbox.envelopes.lock()?.push(env);
+        }
+        Ok(MemoryType {
+            account_hash: 0,
+            mailboxes: Arc::new(Mutex::new(mailboxes)),
+            bytes: Arc::new(Mutex::new(bytes_index)),
+            set_flags_calls: Default::default(),
+            event_consumer,
+        })
+    }
+}
+
+/// In-memory mail backend.
+#[derive(Debug)]
+pub struct MemoryType {
+    account_hash: AccountHash,
+    mailboxes: Arc<Mutex<HashMap<MailboxHash, MemoryMailbox>>>,
+    bytes: Arc<Mutex<HashMap<EnvelopeHash, Vec<u8>>>>,
+    set_flags_calls: Mutex<Vec<(EnvelopeHashBatch, MailboxHash, FlagChanges)>>,
+    event_consumer: BackendEventConsumer,
+}
+
+type FlagChanges = SmallVec<[(std::result::Result<Flag, String>, bool); 8]>;
+
+impl MemoryType {
+    pub fn builder() -> MemoryTypeBuilder {
+        MemoryTypeBuilder::default()
+    }
+
+    /// The arguments of every `set_flags` call so far, oldest first.
+    pub fn set_flags_calls(&self) -> Vec<(EnvelopeHashBatch, MailboxHash, FlagChanges)> {
+        self.set_flags_calls.lock().unwrap().clone()
+    }
+
+    fn envelopes(&self, mailbox_hash: MailboxHash) -> Result<Arc<Mutex<Vec<Envelope>>>> {
+        Ok(self
+            .mailboxes
+            .lock()?
+            .get(&mailbox_hash)
+            .ok_or_else(|| {
+                MeliError::new(format!("Mailbox with hash {} not found.", mailbox_hash))
+            })?
+            .envelopes
+            .clone())
+    }
+
+    fn send_refresh_event(&self, mailbox_hash: MailboxHash, kind: RefreshEventKind) {
+        (self.event_consumer)(
+            self.account_hash,
+            BackendEvent::Refresh(RefreshEvent {
+                account_hash: self.account_hash,
+                mailbox_hash,
+                kind,
+            }),
+        );
+    }
+}
+
+impl MailBackend for MemoryType {
+    fn capabilities(&self) -> MailBackendCapabilities {
+        MailBackendCapabilities {
+            is_async: false,
+            is_remote: false,
+            extensions: None,
+            supports_search: false,
+            supports_tags: false,
+            supports_submission: false,
+            supports_fetch_range: false,
+        }
+    }
+
+    fn fetch(
+        &mut self,
+        mailbox_hash: MailboxHash,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<Vec<Envelope>>> + Send + 'static>>> {
+        let envelopes = self.envelopes(mailbox_hash)?.lock()?.clone();
+        Ok(Box::pin(futures::stream::iter(Some(Ok(envelopes)))))
+    }
+
+    fn refresh(&mut self, _mailbox_hash: MailboxHash) -> ResultFuture<()> {
+        Ok(Box::pin(async { Ok(()) }))
+    }
+
+    fn watch(&self) -> ResultFuture<()> {
+        Ok(Box::pin(async { Ok(()) }))
+    }
+
+    fn mailboxes(&self) -> ResultFuture<HashMap<MailboxHash, Mailbox>> {
+        let ret = Ok(self
+            .mailboxes
+            .lock()?
+            .iter()
+            .map(|(h, f)| (*h, BackendMailbox::clone(f)))
+            .collect());
+        Ok(Box::pin(async { ret }))
+    }
+
+    fn operation(&self, hash: EnvelopeHash) -> Result<Box<dyn BackendOp>> {
+        Ok(Box::new(MemoryOp {
+            hash,
+            bytes: self.bytes.clone(),
+            mailboxes: self.mailboxes.clone(),
+        }))
+    }
+
+    fn save(
+        &self,
+        bytes: Vec<u8>,
+        mailbox_hash: MailboxHash,
+        flags: Option<Flag>,
+    ) -> ResultFuture<()> {
+        let envelopes = self.envelopes(mailbox_hash)?;
+        let env = Envelope::from_bytes(&bytes, flags)?;
+        self.bytes.lock()?.insert(env.hash(), bytes);
+        envelopes.lock()?.push(env.clone());
+        self.send_refresh_event(mailbox_hash, RefreshEventKind::Create(Box::new(env)));
+        Ok(Box::pin(async { Ok(()) }))
+    }
+
+    fn copy_messages(
+        &mut self,
+        env_hashes: EnvelopeHashBatch,
+        source_mailbox_hash: MailboxHash,
+        destination_mailbox_hash: MailboxHash,
+        move_: bool,
+    ) -> ResultFuture<()> {
+        let source = self.envelopes(source_mailbox_hash)?;
+        let destination = self.envelopes(destination_mailbox_hash)?;
+        let mut copied = vec![];
+        {
+            let mut source_lck = source.lock()?;
+            for env_hash in env_hashes.iter() {
+                if let Some(pos) = source_lck.iter().position(|e| e.hash() == env_hash) {
+                    if move_ {
+                        copied.push(source_lck.remove(pos));
+                    } else {
+                        copied.push(source_lck[pos].clone());
+                    }
+                }
+            }
+        }
+        for env in copied {
+            if move_ {
+                self.send_refresh_event(source_mailbox_hash, RefreshEventKind::Remove(env.hash()));
+            }
+            destination.lock()?.push(env.clone());
+            self.send_refresh_event(
+                destination_mailbox_hash,
+                RefreshEventKind::Create(Box::new(env)),
+            );
+        }
+        Ok(Box::pin(async { Ok(()) }))
+    }
+
+    fn set_flags(
+        &mut self,
+        env_hashes: EnvelopeHashBatch,
+        mailbox_hash: MailboxHash,
+        flags: SmallVec<[(std::result::Result<Flag, String>, bool); 8]>,
+    ) -> ResultFuture<()> {
+        self.set_flags_calls
+            .lock()?
+            .push((env_hashes.clone(), mailbox_hash, flags.clone()));
+        let envelopes = self.envelopes(mailbox_hash)?;
+        let mut changed = vec![];
+        for env in envelopes
+            .lock()?
+            .iter_mut()
+            .filter(|e| env_hashes.iter().any(|h| h == e.hash()))
+        {
+            for (f, value) in flags.iter() {
+                if let Ok(f) = f {
+                    env.set_flag(*f, *value);
+                }
+            }
+            changed.push((env.hash(), env.flags()));
+        }
+        for (env_hash, flags) in changed {
+            self.send_refresh_event(
+                mailbox_hash,
+                RefreshEventKind::NewFlags(env_hash, (flags, vec![])),
+            );
+        }
+        Ok(Box::pin(async { Ok(()) }))
+    }
+
+    fn delete_messages(
+        &mut self,
+        env_hashes: EnvelopeHashBatch,
+        mailbox_hash: MailboxHash,
+    ) -> ResultFuture<()> {
+        let envelopes = self.envelopes(mailbox_hash)?;
+        envelopes
+            .lock()?
+            .retain(|e| !env_hashes.iter().any(|h| h == e.hash()));
+        for env_hash in env_hashes.iter() {
+            self.bytes.lock()?.remove(&env_hash);
+            self.send_refresh_event(mailbox_hash, RefreshEventKind::Remove(env_hash));
+        }
+        Ok(Box::pin(async { Ok(()) }))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[test]
+fn test_memory_backend() {
+    use futures::stream::StreamExt;
+
+    let mut backend = MemoryType::builder()
+        .mailbox("INBOX")
+        .mailbox("INBOX/Archive")
+        .message(
+            "INBOX",
+            b"From: a@example.com\nSubject: hello\nMessage-ID: <1@example.com>\n\nhello\n".to_vec(),
+        )
+        .build(BackendEventConsumer::new(Arc::new(|_, _| {})))
+        .unwrap();
+    let inbox_hash = get_path_hash!("INBOX");
+    let archive_hash = get_path_hash!("INBOX/Archive");
+
+    let mailboxes = futures::executor::block_on(backend.mailboxes().unwrap()).unwrap();
+    assert_eq!(mailboxes.len(), 2);
+    assert_eq!(mailboxes[&archive_hash].parent(), Some(inbox_hash));
+    assert_eq!(mailboxes[&inbox_hash].children(), &[archive_hash]);
+
+    let envelopes = futures::executor::block_on(backend.fetch(inbox_hash).unwrap().next())
+        .unwrap()
+        .unwrap();
+    assert_eq!(envelopes.len(), 1);
+    let env_hash = envelopes[0].hash();
+    assert_eq!(envelopes[0].subject(), "hello");
+    let bytes =
+        futures::executor::block_on(backend.operation(env_hash).unwrap().as_bytes().unwrap())
+            .unwrap();
+    assert!(bytes.ends_with(b"\n\nhello\n"));
+
+    futures::executor::block_on(
+        backend
+            .copy_messages(env_hash.into(), inbox_hash, archive_hash, true)
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(mailboxes[&inbox_hash].count().unwrap(), (0, 0));
+    assert_eq!(mailboxes[&archive_hash].count().unwrap(), (1, 1));
+}
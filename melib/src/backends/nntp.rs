@@ -184,6 +184,7 @@ impl MailBackend for NntpType {
             extensions: Some(extensions),
             supports_tags: false,
             supports_submission: false,
+            supports_fetch_range: false,
         }
     }
 
@@ -438,6 +438,7 @@ impl MailBackend for NotmuchDb {
             extensions: None,
             supports_tags: true,
             supports_submission: false,
+            supports_fetch_range: false,
         };
         CAPABILITIES
     }
@@ -28,6 +28,10 @@ use std::collections::HashMap;
 pub struct AccountSettings {
     pub name: String,
     pub root_mailbox: String,
+    /// All root mailbox paths, if more than one was configured. `root_mailbox` is the first of
+    /// them.
+    #[serde(default)]
+    pub root_mailboxes: Vec<String>,
     pub format: String,
     pub identity: String,
     pub read_only: bool,
@@ -54,6 +58,13 @@ impl AccountSettings {
     pub fn root_mailbox(&self) -> &str {
         &self.root_mailbox
     }
+    pub fn root_mailboxes(&self) -> Vec<&str> {
+        if self.root_mailboxes.is_empty() {
+            vec![self.root_mailbox.as_str()]
+        } else {
+            self.root_mailboxes.iter().map(String::as_str).collect()
+        }
+    }
     pub fn identity(&self) -> &str {
         &self.identity
     }
@@ -63,6 +74,14 @@ impl AccountSettings {
     pub fn display_name(&self) -> Option<&String> {
         self.display_name.as_ref()
     }
+    /// The address to put in the From: header of outgoing mail, e.g. `Name <user@example.com>`.
+    pub fn make_display_name(&self) -> String {
+        if let Some(d) = self.display_name.as_ref() {
+            format!("{} <{}>", d, self.identity)
+        } else {
+            self.identity.to_string()
+        }
+    }
 
     pub fn subscribed_mailboxes(&self) -> &Vec<String> {
         &self.subscribed_mailboxes
@@ -74,8 +93,8 @@ impl AccountSettings {
     }
 }
 
-#[serde(default)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct MailboxConf {
     #[serde(alias = "rename")]
     pub alias: Option<String>,
@@ -94,6 +94,21 @@ pub fn timestamp_to_string(timestamp: UnixTimestamp, fmt: Option<&str>) -> Strin
     String::from_utf8_lossy(&vec[0..ret]).into_owned()
 }
 
+/// Formats the time elapsed between `timestamp` and `now` in compact units, e.g. `30s`, `5m`,
+/// `3h` or `2d`. Returns `None` if it is a week or more.
+pub fn timestamp_to_relative_string(
+    timestamp: UnixTimestamp,
+    now: UnixTimestamp,
+) -> Option<String> {
+    match now.saturating_sub(timestamp) {
+        n if n < 60 => Some(format!("{}s", n)),
+        n if n < 60 * 60 => Some(format!("{}m", n / 60)),
+        n if n < 24 * 60 * 60 => Some(format!("{}h", n / (60 * 60))),
+        n if n < 7 * 24 * 60 * 60 => Some(format!("{}d", n / (24 * 60 * 60))),
+        _ => None,
+    }
+}
+
 fn tm_to_secs(tm: ::libc::tm) -> std::result::Result<i64, ()> {
     let mut is_leap = false;
     let mut year = tm.tm_year;
@@ -348,6 +363,32 @@ fn test_timestamp() {
     timestamp_to_string(0, None);
 }
 
+#[test]
+fn test_timestamp_to_relative_string() {
+    let date = 1594728000;
+    assert_eq!(
+        timestamp_to_relative_string(date, date + 30),
+        Some("30s".to_string())
+    );
+    assert_eq!(
+        timestamp_to_relative_string(date, date + 90 * 60),
+        Some("1h".to_string())
+    );
+    assert_eq!(
+        timestamp_to_relative_string(date, date + 26 * 60 * 60),
+        Some("1d".to_string())
+    );
+    assert_eq!(
+        timestamp_to_relative_string(date, date + 10 * 24 * 60 * 60),
+        None
+    );
+    /* Dates in the future count as now */
+    assert_eq!(
+        timestamp_to_relative_string(date, date - 60),
+        Some("0s".to_string())
+    );
+}
+
 #[test]
 fn test_rfcs() {
     /* Some tests were lazily stolen from https://rachelbythebay.com/w/2013/06/11/time/ */
@@ -207,6 +207,10 @@ pub struct Envelope {
     pub flags: Flag,
     pub has_attachments: bool,
     pub labels: SmallVec<[u64; 8]>,
+    /// Size of the raw message in bytes, if the backend reported it.
+    pub size: Option<usize>,
+    /// When the server received the message (IMAP `INTERNALDATE`), if the backend reported it.
+    pub received: Option<UnixTimestamp>,
 }
 
 impl core::fmt::Debug for Envelope {
@@ -250,6 +254,8 @@ impl Envelope {
             has_attachments: false,
             flags: Flag::default(),
             labels: SmallVec::new(),
+            size: None,
+            received: None,
         }
     }
 
@@ -416,6 +422,15 @@ impl Envelope {
         &self.date
     }
 
+    /// Formats the parsed date in the local timezone with the strftime(3) pattern `fmt`. If the
+    /// `Date` header could not be parsed, returns it verbatim.
+    pub fn date_formatted(&self, fmt: &str) -> String {
+        if self.timestamp == 0 {
+            return self.date.clone();
+        }
+        crate::datetime::timestamp_to_string(self.timestamp, Some(fmt))
+    }
+
     pub fn from(&self) -> &[Address] {
         self.from.as_slice()
     }
@@ -478,6 +493,10 @@ impl Envelope {
         self.to.as_slice()
     }
 
+    pub fn cc(&self) -> &[Address] {
+        self.cc.as_slice()
+    }
+
     pub fn field_to_to_string(&self) -> String {
         if self.to.is_empty() {
             self.other_headers
@@ -738,6 +757,22 @@ impl Envelope {
         self.has_attachments
     }
 
+    pub fn set_size(&mut self, new_val: Option<usize>) {
+        self.size = new_val;
+    }
+
+    pub fn size(&self) -> Option<usize> {
+        self.size
+    }
+
+    pub fn set_received(&mut self, new_val: Option<UnixTimestamp>) {
+        self.received = new_val;
+    }
+
+    pub fn received(&self) -> Option<UnixTimestamp> {
+        self.received
+    }
+
     pub fn labels(&self) -> &SmallVec<[u64; 8]> {
         &self.labels
     }
@@ -766,3 +801,111 @@ impl PartialEq for Envelope {
         self.hash == other.hash
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_envelope_date_formatted() {
+        let envelope = Envelope::from_bytes(
+            b"From: user@example.com\nDate: Tue, 14 Jul 2020 12:00:00 +0000\nSubject: test\n\nbody\n",
+            None,
+        )
+        .unwrap();
+        assert_eq!(envelope.date(), 1594728000);
+        /* The date is shown in the local timezone, which is at most 14 hours away from UTC */
+        assert_eq!(envelope.date_formatted("%b %Y"), "Jul 2020");
+        assert_eq!(
+            envelope.date_formatted("%d %b %Y %H:%M"),
+            crate::datetime::timestamp_to_string(1594728000, Some("%d %b %Y %H:%M"))
+        );
+
+        let envelope = Envelope::from_bytes(
+            b"From: user@example.com\nDate: sometime last week\nSubject: test\n\nbody\n",
+            None,
+        )
+        .unwrap();
+        assert_eq!(envelope.date_formatted("%Y-%m-%d"), "sometime last week");
+    }
+
+    #[test]
+    fn test_envelope_body_charset() {
+        let raw: &[u8] = b"From: user@example.com\nSubject: =?Shift_JIS?B?grGC8YLJgr+CzQ==?=\nContent-Type: text/plain; charset=\"windows-1252\"\nContent-Transfer-Encoding: 8bit\n\n\x93quoted\x94 \x96 it\x92s\n";
+        let envelope = Envelope::from_bytes(raw, None).unwrap();
+        assert_eq!(envelope.subject(), "こんにちは");
+        let body = envelope.body_bytes(raw);
+        assert_eq!(
+            String::from_utf8(attachments::decode_rec(&body, None)).unwrap(),
+            "\u{201c}quoted\u{201d} \u{2013} it\u{2019}s\n"
+        );
+
+        let raw: &[u8] =
+            b"From: user@example.com\nSubject: test\nContent-Type: text/plain; charset=iso-8859-1\n\n\x93caf\xe9\x94\n";
+        let envelope = Envelope::from_bytes(raw, None).unwrap();
+        let body = envelope.body_bytes(raw);
+        assert_eq!(
+            String::from_utf8(attachments::decode_rec(&body, None)).unwrap(),
+            "\u{201c}caf\u{e9}\u{201d}\n"
+        );
+    }
+
+    #[test]
+    fn test_envelope_from_bytes() {
+        let raw: &[u8] = b"Date: Wed, 15 Jul 2020 09:30:00 +0200
+From: Alice Example <alice@example.com>
+To: Bob <bob@example.com>, carol@example.com
+Cc: Dave <dave@example.com>
+Subject: =?UTF-8?Q?Quarterly_report?=
+Message-ID: <report-3@example.com>
+In-Reply-To: <report-2@example.com>
+References: <report-1@example.com> <report-2@example.com>
+MIME-Version: 1.0
+Content-Type: multipart/mixed; boundary=\"sep\"
+
+--sep
+Content-Type: text/plain; charset=utf-8
+
+See attached.
+--sep
+Content-Type: application/pdf; name=\"report.pdf\"
+Content-Disposition: attachment; filename=\"report.pdf\"
+Content-Transfer-Encoding: base64
+
+JVBERi0xLjQK
+--sep--
+";
+        let envelope = Envelope::from_bytes(raw, Some(Flag::SEEN)).unwrap();
+        assert_eq!(
+            envelope.field_from_to_string(),
+            "Alice Example <alice@example.com>"
+        );
+        assert_eq!(
+            envelope.field_to_to_string(),
+            "Bob <bob@example.com>, carol@example.com"
+        );
+        assert_eq!(envelope.field_cc_to_string(), "Dave <dave@example.com>");
+        assert_eq!(envelope.subject(), "Quarterly report");
+        assert_eq!(envelope.message_id_display(), "<report-3@example.com>");
+        assert_eq!(
+            envelope.in_reply_to_display().unwrap(),
+            "<report-2@example.com>"
+        );
+        assert_eq!(
+            envelope
+                .references()
+                .iter()
+                .map(|r| String::from_utf8_lossy(r.val()).into_owned())
+                .collect::<Vec<String>>(),
+            vec![
+                "<report-1@example.com>".to_string(),
+                "<report-2@example.com>".to_string()
+            ]
+        );
+        assert_eq!(envelope.datetime(), 1594798200);
+        assert!(envelope.has_attachments());
+        assert!(envelope.is_seen());
+
+        assert!(Envelope::from_bytes(b"", None).is_err());
+    }
+}
@@ -51,6 +51,7 @@ pub enum Charset {
     BIG5,
     ISO2022JP,
     EUCJP,
+    ShiftJIS,
     KOI8R,
     KOI8U,
 }
@@ -149,11 +150,18 @@ impl<'a> From<&'a [u8]> for Charset {
             b if b.eq_ignore_ascii_case(b"big5") => Charset::BIG5,
             b if b.eq_ignore_ascii_case(b"iso-2022-jp") => Charset::ISO2022JP,
             b if b.eq_ignore_ascii_case(b"euc-jp") => Charset::EUCJP,
+            b if b.eq_ignore_ascii_case(b"shift_jis")
+                || b.eq_ignore_ascii_case(b"shift-jis")
+                || b.eq_ignore_ascii_case(b"sjis")
+                || b.eq_ignore_ascii_case(b"windows-31j") =>
+            {
+                Charset::ShiftJIS
+            }
             b if b.eq_ignore_ascii_case(b"koi8-r") => Charset::KOI8R,
             b if b.eq_ignore_ascii_case(b"koi8-u") => Charset::KOI8U,
             _ => {
                 debug!("unknown tag is {:?}", str::from_utf8(b));
-                Charset::Ascii
+                Charset::UTF8
             }
         }
     }
@@ -187,6 +195,7 @@ impl Display for Charset {
             Charset::BIG5 => write!(f, "big5"),
             Charset::ISO2022JP => write!(f, "iso-2022-jp"),
             Charset::EUCJP => write!(f, "euc-jp"),
+            Charset::ShiftJIS => write!(f, "shift_jis"),
             Charset::KOI8R => write!(f, "koi8-r"),
             Charset::KOI8U => write!(f, "koi8-u"),
         }
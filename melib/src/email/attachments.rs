@@ -806,10 +806,14 @@ pub fn interpret_format_flowed(_t: &str) -> String {
 
 type Filter<'a> = Box<dyn FnMut(&Attachment, &mut Vec<u8>) -> () + 'a>;
 
-fn decode_rec_helper<'a, 'b>(a: &'a Attachment, filter: &mut Option<Filter<'b>>) -> Vec<u8> {
+fn decode_rec_helper<'a, 'b>(
+    a: &'a Attachment,
+    filter: &mut Option<Filter<'b>>,
+    lossy: bool,
+) -> Vec<u8> {
     match a.content_type {
         ContentType::Other { .. } => Vec::new(),
-        ContentType::Text { .. } => decode_helper(a, filter),
+        ContentType::Text { .. } => decode_helper(a, filter, lossy),
         ContentType::OctetStream { ref name } => {
             name.clone().unwrap_or_else(|| a.mime_type()).into_bytes()
         }
@@ -817,7 +821,7 @@ fn decode_rec_helper<'a, 'b>(a: &'a Attachment, filter: &mut Option<Filter<'b>>)
         ContentType::MessageRfc822 => {
             if a.content_disposition.kind.is_inline() {
                 let b = AttachmentBuilder::new(a.body()).build();
-                let ret = decode_rec_helper(&b, filter);
+                let ret = decode_rec_helper(&b, filter, lossy);
                 ret
             } else {
                 b"message/rfc822 attachment".to_vec()
@@ -834,34 +838,34 @@ fn decode_rec_helper<'a, 'b>(a: &'a Attachment, filter: &mut Option<Filter<'b>>)
                         kind: Text::Plain, ..
                     } = a.content_type
                     {
-                        return decode_helper(a, filter);
+                        return decode_helper(a, filter, lossy);
                     }
                 }
-                decode_helper(a, filter)
+                decode_helper(a, filter, lossy)
             }
             MultipartType::Signed => {
                 let mut vec = Vec::new();
                 for a in parts {
-                    vec.extend(decode_rec_helper(a, filter));
+                    vec.extend(decode_rec_helper(a, filter, lossy));
                 }
-                vec.extend(decode_helper(a, filter));
+                vec.extend(decode_helper(a, filter, lossy));
                 vec
             }
             MultipartType::Encrypted => {
                 let mut vec = Vec::new();
                 for a in parts {
                     if a.content_type == "application/octet-stream" {
-                        vec.extend(decode_rec_helper(a, filter));
+                        vec.extend(decode_rec_helper(a, filter, lossy));
                     }
                 }
-                vec.extend(decode_helper(a, filter));
+                vec.extend(decode_helper(a, filter, lossy));
                 vec
             }
             _ => {
                 let mut vec = Vec::new();
                 for a in parts {
                     if a.content_disposition.kind.is_inline() {
-                        vec.extend(decode_rec_helper(a, filter));
+                        vec.extend(decode_rec_helper(a, filter, lossy));
                     }
                 }
                 vec
@@ -871,10 +875,20 @@ fn decode_rec_helper<'a, 'b>(a: &'a Attachment, filter: &mut Option<Filter<'b>>)
 }
 
 pub fn decode_rec<'a, 'b>(a: &'a Attachment, mut filter: Option<Filter<'b>>) -> Vec<u8> {
-    decode_rec_helper(a, &mut filter)
+    decode_rec_helper(a, &mut filter, false)
 }
 
-fn decode_helper<'a, 'b>(a: &'a Attachment, filter: &mut Option<Filter<'b>>) -> Vec<u8> {
+/// Like `decode_rec`, but malformed transfer encodings and charsets are decoded on a best-effort
+/// basis instead of falling back to the raw body. Meant for displaying text.
+pub fn decode_rec_lossy<'a, 'b>(a: &'a Attachment, mut filter: Option<Filter<'b>>) -> Vec<u8> {
+    decode_rec_helper(a, &mut filter, true)
+}
+
+fn decode_helper<'a, 'b>(
+    a: &'a Attachment,
+    filter: &mut Option<Filter<'b>>,
+    lossy: bool,
+) -> Vec<u8> {
     let charset = match a.content_type {
         ContentType::Text { charset: c, .. } => c,
         _ => Default::default(),
@@ -883,6 +897,7 @@ fn decode_helper<'a, 'b>(a: &'a Attachment, filter: &mut Option<Filter<'b>>) ->
     let bytes = match a.content_transfer_encoding {
         ContentTransferEncoding::Base64 => match BASE64_MIME.decode(a.body()) {
             Ok(v) => v,
+            _ if lossy => parser::encodings::base64_lossy(a.body()),
             _ => a.body().to_vec(),
         },
         ContentTransferEncoding::QuotedPrintable => {
@@ -898,6 +913,8 @@ fn decode_helper<'a, 'b>(a: &'a Attachment, filter: &mut Option<Filter<'b>>) ->
     let mut ret = if a.content_type.is_text() {
         if let Ok(v) = parser::encodings::decode_charset(&bytes, charset) {
             v.into_bytes()
+        } else if lossy {
+            String::from_utf8_lossy(&bytes).into_owned().into_bytes()
         } else {
             a.body().to_vec()
         }
@@ -912,5 +929,10 @@ fn decode_helper<'a, 'b>(a: &'a Attachment, filter: &mut Option<Filter<'b>>) ->
 }
 
 pub fn decode<'a, 'b>(a: &'a Attachment, mut filter: Option<Filter<'b>>) -> Vec<u8> {
-    decode_helper(a, &mut filter)
+    decode_helper(a, &mut filter, false)
+}
+
+/// Like `decode`, but a broken part is decoded on a best-effort basis. See `decode_rec_lossy`.
+pub fn decode_lossy<'a, 'b>(a: &'a Attachment, mut filter: Option<Filter<'b>>) -> Vec<u8> {
+    decode_helper(a, &mut filter, true)
 }
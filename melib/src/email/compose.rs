@@ -110,82 +110,130 @@ impl Draft {
         self
     }
 
-    pub fn new_reply(envelope: &Envelope, bytes: &[u8], reply_to_all: bool) -> Self {
-        let mut ret = Draft::default();
-        ret.headers_mut().insert(
-            HeaderName::new_unchecked("References"),
-            format!(
-                "{} {}",
-                envelope
-                    .references()
-                    .iter()
-                    .fold(String::new(), |mut acc, x| {
-                        if !acc.is_empty() {
-                            acc.push(' ');
-                        }
-                        acc.push_str(&x.to_string());
-                        acc
-                    }),
-                envelope.message_id_display()
-            ),
-        );
-        ret.headers_mut().insert(
-            HeaderName::new_unchecked("In-Reply-To"),
-            envelope.message_id_display().into(),
+    /// Sets `References` and `In-Reply-To` so that a reply to `envelope` is threaded under it:
+    /// the parent's own references (or its `In-Reply-To` if it has none) followed by the parent's
+    /// `Message-ID`.
+    pub fn set_reply_headers(&mut self, envelope: &Envelope) -> &mut Self {
+        let mut references = envelope.references();
+        if let Some(in_reply_to) = envelope.in_reply_to() {
+            if !references.contains(&in_reply_to) {
+                references.push(in_reply_to);
+            }
+        }
+        if !references.contains(&envelope.message_id()) {
+            references.push(envelope.message_id());
+        }
+        self.set_header(
+            "References",
+            references
+                .iter()
+                .map(|r| r.to_string())
+                .collect::<Vec<String>>()
+                .join(" "),
         );
+        self.set_header("In-Reply-To", envelope.message_id_display().into());
+        self
+    }
+
+    /// Sets the recipients of a reply to `envelope`. A reply to all also goes to the mailing
+    /// list's posting address and the other recipients, without duplicates and without our own
+    /// address `ours`.
+    pub fn set_reply_recipients(
+        &mut self,
+        envelope: &Envelope,
+        reply_to_all: bool,
+        ours: Option<&Address>,
+    ) -> &mut Self {
+        fn push(list: &mut Vec<Address>, addr: Address, ours: Option<&Address>) {
+            if Some(&addr) != ours && !list.contains(&addr) {
+                list.push(addr);
+            }
+        }
+
         // "Mail-Followup-To/(To+Cc+(Mail-Reply-To/Reply-To/From)) for follow-up,
         // Mail-Reply-To/Reply-To/From for reply-to-author."
         // source: https://cr.yp.to/proto/replyto.html
-        if reply_to_all {
-            if let Some(reply_to) = envelope.other_headers().get("Mail-Followup-To") {
-                ret.headers_mut()
-                    .insert(HeaderName::new_unchecked("To"), reply_to.to_string());
+        if !reply_to_all {
+            let to = if let Some(reply_to) = envelope.other_headers().get("Mail-Reply-To") {
+                reply_to.to_string()
+            } else if let Some(reply_to) = envelope.other_headers().get("Reply-To") {
+                reply_to.to_string()
             } else {
-                if let Some(reply_to) = envelope.other_headers().get("Reply-To") {
-                    ret.headers_mut()
-                        .insert(HeaderName::new_unchecked("To"), reply_to.to_string());
-                } else {
-                    ret.headers_mut().insert(
-                        HeaderName::new_unchecked("To"),
-                        envelope.field_from_to_string(),
-                    );
-                }
-                // FIXME: add To/Cc
+                envelope.field_from_to_string()
+            };
+            return self.set_header("To", to);
+        }
+        let header_address = |name: &str| -> Option<Address> {
+            envelope
+                .other_headers()
+                .get(name)
+                .and_then(|v| v.as_str().try_into().ok())
+        };
+        let mut to = vec![];
+        if let Some(list_management::ListAction::Email(list_post_addr)) =
+            list_management::ListActions::detect(envelope)
+                .and_then(|actions| actions.post)
+                .map(|post| post[0])
+        {
+            if let Ok((_, mailto)) = parser::generic::mailto(list_post_addr) {
+                push(&mut to, mailto.address, ours);
             }
+        }
+        if let Some(followup_to) = header_address("Mail-Followup-To") {
+            push(&mut to, followup_to, ours);
+        } else if let Some(reply_to) = header_address("Reply-To") {
+            push(&mut to, reply_to, ours);
         } else {
-            if let Some(reply_to) = envelope.other_headers().get("Mail-Reply-To") {
-                ret.headers_mut()
-                    .insert(HeaderName::new_unchecked("To"), reply_to.to_string());
-            } else if let Some(reply_to) = envelope.other_headers().get("Reply-To") {
-                ret.headers_mut()
-                    .insert(HeaderName::new_unchecked("To"), reply_to.to_string());
-            } else {
-                ret.headers_mut().insert(
-                    HeaderName::new_unchecked("To"),
-                    envelope.field_from_to_string(),
-                );
+            for addr in envelope.from() {
+                push(&mut to, addr.clone(), ours);
             }
         }
-        ret.headers_mut().insert(
-            HeaderName::new_unchecked("Cc"),
-            envelope.field_cc_to_string(),
-        );
+        for addr in envelope.to() {
+            push(&mut to, addr.clone(), ours);
+        }
+        let mut cc = vec![];
+        for addr in envelope.cc() {
+            if !to.contains(addr) {
+                push(&mut cc, addr.clone(), ours);
+            }
+        }
+        let join = |list: Vec<Address>| {
+            list.iter()
+                .map(Address::to_string)
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+        self.set_header("To", join(to));
+        self.set_header("Cc", join(cc))
+    }
+
+    /// Creates a reply draft to `envelope`, whose raw bytes are `bytes`: threading headers,
+    /// `Re: ` subject, recipients and the quoted original body with an attribution line. A reply
+    /// to all leaves out our own address `ours`.
+    pub fn new_reply(
+        envelope: &Envelope,
+        bytes: &[u8],
+        reply_to_all: bool,
+        ours: Option<&Address>,
+    ) -> Self {
+        let mut ret = Draft::default();
+        ret.set_reply_headers(envelope);
+        ret.set_header("Subject", reply_subject(&envelope.subject()));
+        ret.set_reply_recipients(envelope, reply_to_all, ours);
         let body = envelope.body_bytes(bytes);
         ret.body = {
             let reply_body_bytes = decode_rec(&body, None);
             let reply_body = String::from_utf8_lossy(&reply_body_bytes);
-            let lines: Vec<&str> = reply_body.lines().collect();
             let mut ret = format!(
-                "On {} {} wrote:\n",
+                "On {}, {} wrote:\n",
                 envelope.date_as_str(),
-                &ret.headers()["To"]
+                envelope.field_from_to_string()
             );
-            for l in lines {
-                ret.push('>');
+            for l in reply_body.lines() {
+                ret.push_str("> ");
                 ret.push_str(l);
                 ret.push('\n');
             }
-            ret.pop();
             ret
         };
 
@@ -308,6 +356,15 @@ fn build_multipart(ret: &mut String, kind: MultipartType, parts: Vec<AttachmentB
     ret.push_str("--\n");
 }
 
+/// The transfer encoding of an encapsulated message `raw`, which must not be encoded.
+fn message_transfer_encoding(raw: &[u8]) -> ContentTransferEncoding {
+    if raw.is_ascii() {
+        ContentTransferEncoding::_7Bit
+    } else {
+        ContentTransferEncoding::_8Bit
+    }
+}
+
 fn print_attachment(ret: &mut String, a: AttachmentBuilder) {
     use ContentType::*;
     match a.content_type {
@@ -345,6 +402,10 @@ fn print_attachment(ret: &mut String, a: AttachmentBuilder) {
                 a.content_type
             ));
             ret.push_str("Content-Disposition: attachment\r\n");
+            ret.push_str(&format!(
+                "Content-Transfer-Encoding: {}\r\n",
+                message_transfer_encoding(a.raw())
+            ));
             ret.push_str("\r\n");
             ret.push_str(&String::from_utf8_lossy(a.raw()));
             ret.push_str("\r\n");
@@ -361,10 +422,13 @@ fn print_attachment(ret: &mut String, a: AttachmentBuilder) {
             ret.push_str("\r\n");
         }
         _ => {
-            let content_transfer_encoding: ContentTransferEncoding = if a.raw().is_ascii() {
-                ContentTransferEncoding::_8Bit
-            } else {
-                ContentTransferEncoding::Base64
+            let content_transfer_encoding: ContentTransferEncoding = match a.content_type {
+                /* RFC 2046 forbids encoding message/rfc822 parts other than as 7bit or 8bit */
+                Other { ref tag, .. } if tag.eq_ignore_ascii_case(b"message/rfc822") => {
+                    message_transfer_encoding(a.raw())
+                }
+                _ if a.raw().is_ascii() => ContentTransferEncoding::_8Bit,
+                _ => ContentTransferEncoding::Base64,
             };
             if let Some(name) = a.content_type().name() {
                 ret.extend(
@@ -402,6 +466,33 @@ fn print_attachment(ret: &mut String, a: AttachmentBuilder) {
     }
 }
 
+/// Prefixes `subject` with `Re: ` unless it already starts with a reply prefix.
+pub fn reply_subject(subject: &str) -> String {
+    if subject
+        .get(.."re:".len())
+        .map(|p| p.eq_ignore_ascii_case("re:"))
+        .unwrap_or(false)
+    {
+        subject.to_string()
+    } else {
+        format!("Re: {}", subject)
+    }
+}
+
+/// Prefixes `subject` with `Fwd: ` unless it already starts with a forward prefix.
+pub fn forward_subject(subject: &str) -> String {
+    if ["fwd:", "fw:"].iter().any(|prefix| {
+        subject
+            .get(..prefix.len())
+            .map(|p| p.eq_ignore_ascii_case(prefix))
+            .unwrap_or(false)
+    }) {
+        subject.to_string()
+    } else {
+        format!("Fwd: {}", subject)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -426,6 +517,222 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_reply_appends_parent_message_id() {
+        let parent = Envelope::from_bytes(
+            b"From: alice@example.com\r\n\
+Message-ID: <c@example.com>\r\n\
+In-Reply-To: <b@example.com>\r\n\
+References: <a@example.com> <b@example.com>\r\n\
+Subject: test\r\n\
+\r\n\
+body\r\n",
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            parent
+                .references()
+                .iter()
+                .map(|r| r.to_string())
+                .collect::<Vec<String>>(),
+            vec!["<a@example.com>", "<b@example.com>"]
+        );
+        assert_eq!(parent.in_reply_to_raw().as_deref(), Some("b@example.com"));
+
+        let mut reply = Draft::default();
+        reply.set_reply_headers(&parent);
+        assert_eq!(
+            &reply.headers()["References"],
+            "<a@example.com> <b@example.com> <c@example.com>"
+        );
+        assert_eq!(&reply.headers()["In-Reply-To"], "<c@example.com>");
+
+        let root = Envelope::from_bytes(
+            b"From: alice@example.com\r\nMessage-ID: <a@example.com>\r\n\r\nbody\r\n",
+            None,
+        )
+        .unwrap();
+        reply.set_reply_headers(&root);
+        assert_eq!(&reply.headers()["References"], "<a@example.com>");
+    }
+
+    #[test]
+    fn test_reply_subject() {
+        assert_eq!(reply_subject("hello"), "Re: hello");
+        assert_eq!(reply_subject("Re: hello"), "Re: hello");
+        assert_eq!(reply_subject("RE: hello"), "RE: hello");
+        assert_eq!(reply_subject("re:hello"), "re:hello");
+        assert_eq!(reply_subject("Regarding hello"), "Re: Regarding hello");
+        assert_eq!(reply_subject(""), "Re: ");
+    }
+
+    #[test]
+    fn test_forward_subject() {
+        assert_eq!(forward_subject("hello"), "Fwd: hello");
+        assert_eq!(forward_subject("Fwd: hello"), "Fwd: hello");
+        assert_eq!(forward_subject("FW: hello"), "FW: hello");
+        assert_eq!(forward_subject("Re: hello"), "Fwd: Re: hello");
+        assert_eq!(forward_subject("Fwdx hello"), "Fwd: Fwdx hello");
+    }
+
+    #[test]
+    fn test_new_reply_to_all() {
+        let parent = Envelope::from_bytes(
+            b"From: alice@example.com\r\n\
+To: bob@example.com, carol@example.com\r\n\
+Cc: dave@example.com\r\n\
+Message-ID: <a@example.com>\r\n\
+Date: Mon, 13 Jul 2020 09:02:15 +0300\r\n\
+Subject: Re: plans\r\n\
+\r\n\
+first line\r\n\
+second line\r\n",
+            None,
+        )
+        .unwrap();
+        let bytes = b"From: alice@example.com\r\n\r\nfirst line\r\nsecond line\r\n";
+
+        let reply = Draft::new_reply(&parent, bytes, true, None);
+        assert_eq!(&reply.headers()["Subject"], "Re: plans");
+        assert_eq!(
+            &reply.headers()["To"],
+            "alice@example.com, bob@example.com, carol@example.com"
+        );
+        assert_eq!(&reply.headers()["Cc"], "dave@example.com");
+        assert_eq!(&reply.headers()["In-Reply-To"], "<a@example.com>");
+        assert!(reply
+            .body()
+            .ends_with(" alice@example.com wrote:\n> first line\n> second line\n"));
+
+        let reply = Draft::new_reply(&parent, bytes, false, None);
+        assert_eq!(&reply.headers()["To"], "alice@example.com");
+        assert_eq!(&reply.headers()["Cc"], "");
+    }
+
+    #[test]
+    fn test_reply_recipients() {
+        let parent = Envelope::from_bytes(
+            b"From: Alice <alice@example.com>\r\n\
+To: bob@example.com, me@example.com, bob@example.com\r\n\
+Cc: carol@example.com, Me <me@example.com>, alice@example.com, carol@example.com\r\n\
+Reply-To: alice-reply@example.com\r\n\
+Subject: Lunch\r\n\
+\r\n\
+Noon?\r\n",
+            None,
+        )
+        .unwrap();
+        let ours: Address = "me@example.com".try_into().unwrap();
+        let recipients = |reply_to_all: bool| {
+            let mut draft = Draft::default();
+            draft.set_reply_recipients(&parent, reply_to_all, Some(&ours));
+            (
+                draft.headers()["To"].to_string(),
+                draft.headers()["Cc"].to_string(),
+            )
+        };
+
+        /* A reply goes to Reply-To */
+        assert_eq!(
+            recipients(false),
+            ("alice-reply@example.com".to_string(), String::new())
+        );
+        /* A reply to all goes to everyone else once */
+        assert_eq!(
+            recipients(true),
+            (
+                "alice-reply@example.com, bob@example.com".to_string(),
+                "carol@example.com, alice@example.com".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_mime_type_from_extension() {
+        assert_eq!(
+            mime_type_from_extension(Path::new("report.PDF")),
+            Some("application/pdf")
+        );
+        assert_eq!(
+            mime_type_from_extension(Path::new("/tmp/photo.jpeg")),
+            Some("image/jpeg")
+        );
+        assert_eq!(mime_type_from_extension(Path::new("Makefile")), None);
+        assert_eq!(mime_type_from_extension(Path::new("archive.xyz")), None);
+    }
+
+    #[test]
+    fn test_attach_message() {
+        let dir = crate::TempDir::new("attach-message");
+        let eml_path = dir.join("forwarded.eml");
+        std::fs::write(
+            &eml_path,
+            "From: alice@example.com\nSubject: Grüße\n\nΚαλημέρα\n",
+        )
+        .unwrap();
+
+        let mut draft = Draft::default();
+        draft.set_body("body text".to_string());
+        draft
+            .attachments_mut()
+            .push(attachment_from_file(&eml_path).unwrap());
+        let bytes = draft.finalise().unwrap();
+        /* Encapsulated messages are never base64 encoded */
+        assert!(bytes.contains("Content-Type: message/rfc822"));
+        assert!(bytes.contains("Content-Transfer-Encoding: 8bit"));
+        assert!(!bytes.contains("base64"));
+        assert!(bytes.contains("Καλημέρα"));
+
+        let mut ascii_message = AttachmentBuilder::default();
+        ascii_message
+            .set_raw(b"Subject: hi\r\n\r\nhello\r\n".to_vec())
+            .set_content_type(ContentType::MessageRfc822);
+        let mut draft = Draft::default();
+        draft.attachments_mut().push(ascii_message);
+        let bytes = draft.finalise().unwrap();
+        assert!(bytes.contains("Content-Transfer-Encoding: 7bit"));
+    }
+
+    #[test]
+    fn test_attachments_multipart() {
+        let dir = crate::TempDir::new("attachments");
+        let text_path = dir.join("notes.txt");
+        let binary_path = dir.join("image.png");
+        std::fs::write(&text_path, "some notes\n").unwrap();
+        std::fs::write(&binary_path, b"\x89PNG\r\n\x1a\n\x00\xff").unwrap();
+
+        let mut draft = Draft::default();
+        draft.set_body("body text".to_string());
+        draft
+            .attachments_mut()
+            .push(attachment_from_file(&text_path).unwrap());
+        draft
+            .attachments_mut()
+            .push(attachment_from_file(&binary_path).unwrap());
+        let bytes = draft.finalise().unwrap();
+
+        let envelope = Envelope::from_bytes(bytes.as_bytes(), None).unwrap();
+        let body = envelope.body_bytes(bytes.as_bytes());
+        if let ContentType::Multipart {
+            kind: MultipartType::Mixed,
+            ref parts,
+            ..
+        } = body.content_type
+        {
+            assert_eq!(parts.len(), 3);
+            assert_eq!(parts[0].text(), "body text");
+            assert_eq!(parts[1].content_type().to_string(), "text/plain");
+            assert_eq!(parts[2].content_type().to_string(), "image/png");
+            assert_eq!(
+                decode(&parts[2], None),
+                b"\x89PNG\r\n\x1a\n\x00\xff"
+            );
+        } else {
+            panic!("expected multipart/mixed, got {:?}", body.content_type);
+        }
+    }
+
     #[test]
     fn test_attachments() {
         /*
@@ -450,7 +757,43 @@ mod tests {
     }
 }
 
-/// Reads file from given path, and returns an 'application/octet-stream' AttachmentBuilder object
+/// Guesses a MIME type from the extension of `path` for common file types.
+pub fn mime_type_from_extension(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    Some(match extension.as_str() {
+        "txt" | "text" | "log" => "text/plain",
+        "html" | "htm" => "text/html",
+        "csv" => "text/csv",
+        "md" => "text/markdown",
+        "patch" | "diff" => "text/x-diff",
+        "ics" => "text/calendar",
+        "vcf" => "text/vcard",
+        "eml" => "message/rfc822",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "gz" => "application/gzip",
+        "tar" => "application/x-tar",
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "asc" | "sig" => "application/pgp-signature",
+        "doc" => "application/msword",
+        "docx" => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        "odt" => "application/vnd.oasis.opendocument.text",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "webp" => "image/webp",
+        "mp3" => "audio/mpeg",
+        "ogg" => "audio/ogg",
+        "mp4" => "video/mp4",
+        _ => return None,
+    })
+}
+
+/// Reads file from given path, and returns an AttachmentBuilder object whose content type is
+/// guessed from the file extension, falling back to `xdg-mime` and then
+/// 'application/octet-stream'.
 pub fn attachment_from_file<I>(path: &I) -> Result<AttachmentBuilder>
 where
     I: AsRef<OsStr>,
@@ -470,7 +813,9 @@ where
         .set_body_to_raw()
         .set_content_type(ContentType::Other {
             name: path.file_name().map(|s| s.to_string_lossy().into()),
-            tag: if let Ok(mime_type) = query_mime_info(&path) {
+            tag: if let Some(mime_type) = mime_type_from_extension(&path) {
+                mime_type.as_bytes().to_vec()
+            } else if let Ok(mime_type) = query_mime_info(&path) {
                 mime_type
             } else {
                 b"application/octet-stream".to_vec()
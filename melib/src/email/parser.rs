@@ -1875,7 +1875,9 @@ pub mod encodings {
     pub fn decode_charset(s: &[u8], charset: Charset) -> Result<String> {
         match charset {
             Charset::UTF8 | Charset::Ascii => Ok(String::from_utf8_lossy(s).to_string()),
-            Charset::ISO8859_1 => Ok(ISO_8859_1.decode(s, DecoderTrap::Strict)?),
+            // Like web browsers, treat ISO-8859-1 as its windows-1252 superset: mislabeled
+            // messages commonly use the C1 range for curly quotes and dashes.
+            Charset::ISO8859_1 => Ok(WINDOWS_1252.decode(s, DecoderTrap::Strict)?),
             Charset::ISO8859_2 => Ok(ISO_8859_2.decode(s, DecoderTrap::Strict)?),
             Charset::ISO8859_3 => Ok(ISO_8859_3.decode(s, DecoderTrap::Strict)?),
             Charset::ISO8859_4 => Ok(ISO_8859_4.decode(s, DecoderTrap::Strict)?),
@@ -1904,6 +1906,7 @@ pub mod encodings {
             }
             Charset::ISO2022JP => Ok(ISO_2022_JP.decode(s, DecoderTrap::Strict)?),
             Charset::EUCJP => Ok(EUC_JP.decode(s, DecoderTrap::Strict)?),
+            Charset::ShiftJIS => Ok(WINDOWS_31J.decode(s, DecoderTrap::Strict)?),
         }
     }
 
@@ -1943,6 +1946,33 @@ pub mod encodings {
         )))(input)
     }
 
+    /// Decode base64 content, skipping any bytes outside the base64 alphabet instead of failing.
+    /// Decoding stops at the first padding character and a trailing incomplete byte is dropped.
+    pub fn base64_lossy(input: &[u8]) -> Vec<u8> {
+        let mut ret = Vec::with_capacity(input.len() * 3 / 4);
+        let mut acc: u32 = 0;
+        let mut bits: u32 = 0;
+        for &c in input {
+            let v = match c {
+                b'A'..=b'Z' => c - b'A',
+                b'a'..=b'z' => c - b'a' + 26,
+                b'0'..=b'9' => c - b'0' + 52,
+                b'+' => 62,
+                b'/' => 63,
+                b'=' => break,
+                _ => continue,
+            };
+            acc = (acc << 6) | v as u32;
+            bits += 6;
+            if bits >= 8 {
+                bits -= 8;
+                ret.push((acc >> bits) as u8);
+                acc &= (1 << bits) - 1;
+            }
+        }
+        ret
+    }
+
     pub fn space(input: &[u8]) -> IResult<&[u8], ()> {
         let (rest, _) =
             take_while(|c: u8| c == b' ' || c == b'\t' || c == b'\r' || c == b'\n')(input)?;
@@ -2871,6 +2901,26 @@ border=3D=220=22>
     </td>
    </tr>"#)
         );
+        /* Invalid escapes are emitted verbatim */
+        assert_eq!(
+            quoted_printable_bytes(b"100=% sure=ZZ =3D ok=")
+                .unwrap()
+                .1
+                .as_slice(),
+            b"100=% sure=ZZ = ok=".as_ref()
+        );
+    }
+
+    #[test]
+    fn test_base64_lossy() {
+        assert_eq!(base64_lossy(b"aGVsbG8gd29ybGQ=").as_slice(), b"hello world");
+        assert_eq!(
+            base64_lossy(b"aGVs\r\nbG8*gd2!9y~bGQ=").as_slice(),
+            b"hello world"
+        );
+        /* Trailing garbage after padding and a truncated final quantum */
+        assert_eq!(base64_lossy(b"aGVsbG8=\xff\xfe").as_slice(), b"hello");
+        assert_eq!(base64_lossy(b"aGVsbG8gd29ybG").as_slice(), b"hello worl");
     }
 
     #[test]
@@ -42,6 +42,8 @@ pub enum ErrorKind {
     Bug,
     Network,
     Timeout,
+    /// The server closed the connection with an untagged `BYE` response.
+    ConnectionBye,
 }
 
 impl fmt::Display for ErrorKind {
@@ -56,15 +58,17 @@ impl fmt::Display for ErrorKind {
                 ErrorKind::Bug => "Bug, please report this!",
                 ErrorKind::Network => "Network",
                 ErrorKind::Timeout => "Timeout",
+                ErrorKind::ConnectionBye => "Connection closed by server",
             }
         )
     }
 }
 
 impl ErrorKind {
+    /// Whether the connection was lost, including the server closing it with `BYE`.
     pub fn is_network(&self) -> bool {
         match self {
-            ErrorKind::Network => true,
+            ErrorKind::Network | ErrorKind::ConnectionBye => true,
             _ => false,
         }
     }
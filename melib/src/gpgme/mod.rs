@@ -20,7 +20,7 @@
  */
 
 use crate::email::{
-    pgp::{DecryptionMetadata, Recipient},
+    pgp::{DecryptionMetadata, Recipient, SignatureMetadata},
     Address,
 };
 use crate::error::{ErrorKind, IntoMeliError, MeliError, Result, ResultIntoMeliError};
@@ -459,7 +459,7 @@ impl Context {
         &mut self,
         mut signature: Data,
         mut text: Data,
-    ) -> Result<impl Future<Output = Result<()>> + Send> {
+    ) -> Result<impl Future<Output = Result<SignatureMetadata>> + Send> {
         unsafe {
             gpgme_error_try(
                 &self.inner.lib,
@@ -538,25 +538,63 @@ impl Context {
                 io_state_lck.receiver.clone()
             };
             let _ = rcv.recv().await;
-            {
-                let verify_result =
-                    unsafe { call!(&ctx.lib, gpgme_op_verify_result)(ctx.inner.as_ptr()) };
-                if verify_result.is_null() {
-                    return Err(MeliError::new(
-                        "Unspecified libgpgme error: gpgme_op_verify_result returned NULL.",
-                    )
-                    .set_err_kind(ErrorKind::External));
-                }
-                drop(verify_result);
-            }
             let io_state_lck = io_state.lock().unwrap();
-            let ret = io_state_lck
+            io_state_lck
                 .done
                 .lock()
                 .unwrap()
                 .take()
-                .unwrap_or_else(|| Err(MeliError::new("Unspecified libgpgme error")));
-            ret
+                .unwrap_or_else(|| Err(MeliError::new("Unspecified libgpgme error")))?;
+
+            let verify_result =
+                unsafe { call!(&ctx.lib, gpgme_op_verify_result)(ctx.inner.as_ptr()) };
+            if verify_result.is_null() {
+                return Err(MeliError::new(
+                    "Unspecified libgpgme error: gpgme_op_verify_result returned NULL.",
+                )
+                .set_err_kind(ErrorKind::External));
+            }
+            let mut signatures = vec![];
+            let is_mime;
+            let file_name;
+            unsafe {
+                is_mime = (*verify_result).is_mime() > 0;
+                file_name = if !(*verify_result).file_name.is_null() {
+                    Some(
+                        CStr::from_ptr((*verify_result).file_name)
+                            .to_string_lossy()
+                            .to_string(),
+                    )
+                } else {
+                    None
+                };
+                let mut signature_iter = (*verify_result).signatures;
+                while !signature_iter.is_null() {
+                    let fpr = if !(*signature_iter).fpr.is_null() {
+                        Some(
+                            CStr::from_ptr((*signature_iter).fpr)
+                                .to_string_lossy()
+                                .to_string(),
+                        )
+                    } else {
+                        None
+                    };
+                    signatures.push(Recipient {
+                        status: signature_status(
+                            &ctx.lib,
+                            (*signature_iter).status,
+                            fpr.as_deref(),
+                        ),
+                        keyid: fpr,
+                    });
+                    signature_iter = (*signature_iter).next;
+                }
+            }
+            Ok(SignatureMetadata {
+                signatures,
+                file_name,
+                is_mime,
+            })
         })
     }
 
@@ -1124,6 +1162,27 @@ impl Context {
     }
 }
 
+/* libgpg-error codes, which are the lower 16 bits of a `gpgme_error_t` */
+const GPG_ERR_CODE_MASK: GpgmeError = 0xFFFF;
+const GPG_ERR_BAD_SIGNATURE: GpgmeError = 8;
+const GPG_ERR_NO_PUBKEY: GpgmeError = 9;
+
+/// Describes the `status` of a verified signature made by the key with fingerprint `fpr`.
+fn signature_status(
+    lib: &libloading::Library,
+    status: GpgmeError,
+    fpr: Option<&str>,
+) -> Result<()> {
+    match status & GPG_ERR_CODE_MASK {
+        GPG_ERR_BAD_SIGNATURE => Err(MeliError::new("BAD signature")),
+        GPG_ERR_NO_PUBKEY => Err(MeliError::new(format!(
+            "signer unknown, no public key {}",
+            fpr.unwrap_or_default()
+        ))),
+        _ => gpgme_error_try(lib, status),
+    }
+}
+
 fn gpgme_error_try(lib: &libloading::Library, error_code: GpgmeError) -> Result<()> {
     const ERR_MAX_LEN: usize = 256;
     if error_code == 0 {
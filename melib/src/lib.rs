@@ -129,7 +129,7 @@ pub mod sqlite3;
 #[macro_use]
 extern crate serde_derive;
 /* parser */
-extern crate data_encoding;
+pub extern crate data_encoding;
 extern crate encoding;
 pub extern crate nom;
 
@@ -171,6 +171,39 @@ impl core::fmt::Display for Bytes {
     }
 }
 
+/// A scratch directory `meli-test-{name}-{pid}` in [`std::env::temp_dir`], removed along with its
+/// contents when dropped. Used by tests.
+#[cfg(any(test, feature = "test-utils"))]
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct TempDir(std::path::PathBuf);
+
+#[cfg(any(test, feature = "test-utils"))]
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("meli-test-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+impl core::ops::Deref for TempDir {
+    type Target = std::path::Path;
+
+    fn deref(&self) -> &std::path::Path {
+        &self.0
+    }
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
 pub use shellexpand::ShellExpandTrait;
 pub mod shellexpand {
 
@@ -144,6 +144,47 @@ pub mod query_parser {
         .map(Query::Bcc)
     }
 
+    fn body<'a>() -> impl Parser<'a, Query> {
+        prefix(
+            whitespace_wrap(match_literal("body:")),
+            whitespace_wrap(literal()),
+        )
+        .map(Query::Body)
+    }
+
+    /// Parses a `YYYY-MM-DD` date into a timestamp.
+    fn date<'a>() -> impl Parser<'a, UnixTimestamp> {
+        move |input| {
+            map(
+                one_or_more(pred(any_char, |c| c.is_ascii_digit() || *c == '-')),
+                |chars| chars.into_iter().collect::<String>(),
+            )
+            .parse(input)
+            .and_then(|(rest, s)| {
+                match crate::datetime::timestamp_from_string(s.as_str(), "%Y-%m-%d") {
+                    Ok(Some(timestamp)) => Ok((rest, timestamp)),
+                    _ => Err(input),
+                }
+            })
+        }
+    }
+
+    fn since<'a>() -> impl Parser<'a, Query> {
+        prefix(
+            whitespace_wrap(match_literal("since:")),
+            whitespace_wrap(date()),
+        )
+        .map(Query::After)
+    }
+
+    fn before<'a>() -> impl Parser<'a, Query> {
+        prefix(
+            whitespace_wrap(match_literal("before:")),
+            whitespace_wrap(date()),
+        )
+        .map(Query::Before)
+    }
+
     fn or<'a>() -> impl Parser<'a, Query> {
         move |input| {
             whitespace_wrap(match_literal_anycase("or"))
@@ -250,6 +291,9 @@ pub mod query_parser {
                 .or_else(|_| cc().parse(input))
                 .or_else(|_| bcc().parse(input))
                 .or_else(|_| subject().parse(input))
+                .or_else(|_| body().parse(input))
+                .or_else(|_| since().parse(input))
+                .or_else(|_| before().parse(input))
                 .or_else(|_| flags().parse(input))
                 .or_else(|_| has_attachment().parse(input))
             {
@@ -380,6 +424,22 @@ pub mod query_parser {
             Ok(("", Flags(vec!["f".to_string()]))),
             query().parse_complete("tags:f")
         );
+        assert_eq!(
+            Ok(("", Body("invoice".to_string()))),
+            query().parse_complete("body:invoice")
+        );
+        assert_eq!(
+            Ok((
+                "",
+                After(
+                    crate::datetime::timestamp_from_string("2020-02-01", "%Y-%m-%d")
+                        .unwrap()
+                        .unwrap()
+                )
+            )),
+            query().parse_complete("since:2020-02-01")
+        );
+        assert!(query().parse_complete("since:yesterday").is_err());
     }
 }
 
@@ -382,6 +382,7 @@ impl SmtpConnection {
             server_conf: server_conf.clone(),
         };
         let no_auth_needed: bool;
+        let auth_mechanisms: Vec<String>;
         {
             let pre_auth_extensions_reply = ret
                 .read_lines(&mut res, Some((ReplyCode::_250, &[])))
@@ -398,6 +399,17 @@ impl SmtpConnection {
                         pre_auth_extensions_reply
                     )));
             }
+            auth_mechanisms = pre_auth_extensions_reply
+                .lines
+                .iter()
+                .find(|l| l.starts_with("AUTH"))
+                .map(|l| {
+                    l["AUTH".len()..]
+                        .split_whitespace()
+                        .map(|m| m.to_ascii_uppercase())
+                        .collect()
+                })
+                .unwrap_or_default();
             no_auth_needed =
                 ret.server_conf.auth == SmtpAuth::None || !ret.server_conf.auth.require_auth();
             if no_auth_needed {
@@ -410,15 +422,8 @@ impl SmtpConnection {
                 SmtpAuth::Auto {
                     username, password, ..
                 } => {
-                    // # RFC 4616 The PLAIN SASL Mechanism
-                    // # https://www.ietf.org/rfc/rfc4616.txt
-                    // message   = [authzid] UTF8NUL authcid UTF8NUL passwd
-                    // authcid   = 1*SAFE ; MUST accept up to 255 octets
-                    // authzid   = 1*SAFE ; MUST accept up to 255 octets
-                    // passwd    = 1*SAFE ; MUST accept up to 255 octets
-                    // UTF8NUL   = %x00 ; UTF-8 encoded NUL character
-                    let username_password = match password {
-                        Password::Raw(p) => base64::encode(format!("\0{}\0{}", username, p)),
+                    let password = match password {
+                        Password::Raw(p) => p.as_bytes().to_vec(),
                         Password::CommandEval(command) => {
                             let _command = command.clone();
 
@@ -439,22 +444,48 @@ impl SmtpConnection {
                                     String::from_utf8_lossy(&output.stderr)
                                 )));
                             }
-                            let mut buf =
-                                Vec::with_capacity(2 + username.len() + output.stdout.len());
-                            buf.push(b'\0');
-                            buf.extend(username.as_bytes().to_vec());
-                            buf.push(b'\0');
                             if output.stdout.ends_with(b"\n") {
                                 output.stdout.pop();
                             }
-                            buf.extend(output.stdout);
-                            base64::encode(buf)
+                            output.stdout
                         }
                     };
-                    let mut auth_command: SmallVec<[&[u8]; 16]> = SmallVec::new();
-                    auth_command.push(b"AUTH PLAIN ");
-                    auth_command.push(username_password.as_bytes());
-                    ret.send_command(&auth_command).await?;
+                    let username = username.clone();
+                    if auth_mechanisms.iter().any(|m| m == "PLAIN")
+                        || !auth_mechanisms.iter().any(|m| m == "LOGIN")
+                    {
+                        // # RFC 4616 The PLAIN SASL Mechanism
+                        // # https://www.ietf.org/rfc/rfc4616.txt
+                        // message   = [authzid] UTF8NUL authcid UTF8NUL passwd
+                        // authcid   = 1*SAFE ; MUST accept up to 255 octets
+                        // authzid   = 1*SAFE ; MUST accept up to 255 octets
+                        // passwd    = 1*SAFE ; MUST accept up to 255 octets
+                        // UTF8NUL   = %x00 ; UTF-8 encoded NUL character
+                        let mut buf = Vec::with_capacity(2 + username.len() + password.len());
+                        buf.push(b'\0');
+                        buf.extend(username.as_bytes().to_vec());
+                        buf.push(b'\0');
+                        buf.extend(password);
+                        let username_password = base64::encode(buf);
+                        let mut auth_command: SmallVec<[&[u8]; 16]> = SmallVec::new();
+                        auth_command.push(b"AUTH PLAIN ");
+                        auth_command.push(username_password.as_bytes());
+                        ret.send_command(&auth_command).await?;
+                    } else {
+                        // LOGIN mechanism: the server prompts for the base64 encoded username and
+                        // password with 334 replies.
+                        ret.send_command(&[b"AUTH LOGIN"]).await?;
+                        ret.read_lines(&mut res, Some((ReplyCode::_334, &[])))
+                            .await
+                            .chain_err_kind(crate::error::ErrorKind::Authentication)?;
+                        ret.send_command(&[base64::encode(username.as_bytes()).as_bytes()])
+                            .await?;
+                        ret.read_lines(&mut res, Some((ReplyCode::_334, &[])))
+                            .await
+                            .chain_err_kind(crate::error::ErrorKind::Authentication)?;
+                        ret.send_command(&[base64::encode(&password).as_bytes()])
+                            .await?;
+                    }
                     ret.read_lines(&mut res, Some((ReplyCode::_235, &[])))
                         .await
                         .chain_err_kind(crate::error::ErrorKind::Authentication)?;
@@ -571,11 +602,10 @@ impl SmtpConnection {
             current_command.clear();
             current_command.push(b"RCPT TO:<");
             current_command.push(addr.address_spec_raw().trim());
+            current_command.push(b">");
             if let Some(dsn_notify) = dsn_notify.as_ref() {
                 current_command.push(b" NOTIFY=");
                 current_command.push(dsn_notify.as_bytes());
-            } else {
-                current_command.push(b">");
             }
             self.send_command(&current_command).await?;
 
@@ -643,13 +673,6 @@ impl SmtpConnection {
                 .chain_err_kind(crate::error::ErrorKind::Network)?;
         }
 
-        if !mail.ends_with('\n') {
-            self.stream
-                .write_all(b".\r\n")
-                .await
-                .chain_err_kind(crate::error::ErrorKind::Network)?;
-        }
-
         //The mail data are terminated by a line containing only a period, that is, the character
         //sequence "<CRLF>.<CRLF>", where the first <CRLF> is actually the terminator of the
         //previous line (see Section 4.5.2). This is the end of mail data indication.
@@ -713,6 +736,8 @@ pub enum ReplyCode {
     _252,
     ///PRDR specific, eg "content analysis has started|
     _353,
+    ///Server challenge for the SASL mechanism in use
+    _334,
     ///Start mail input; end with <CRLF>.<CRLF>
     _354,
     ///<domain> Service not available, closing transmission channel (This may be a reply to any command if the service knows it must shut down)
@@ -765,6 +790,7 @@ impl ReplyCode {
             _235 => "Authentication successful",
             _251 => "User not local; will forward",
             _252 => "Cannot VRFY user, but will accept message and attempt delivery",
+            _334 => "Server challenge",
             _353 => "PRDR specific notice",
             _354 => "Start mail input; end with <CRLF>.<CRLF>",
             _421 => "Service not available, closing transmission channel",
@@ -815,6 +841,8 @@ impl TryFrom<&'_ str> for ReplyCode {
             "250" => Ok(_250),
             "251" => Ok(_251),
             "252" => Ok(_252),
+            "334" => Ok(_334),
+            "353" => Ok(_353),
             "354" => Ok(_354),
             "421" => Ok(_421),
             "450" => Ok(_450),
@@ -934,3 +962,101 @@ async fn read_lines<'r>(
     }
     Ok(reply)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_smtp_mock_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut stream = stream;
+            let mut received: Vec<String> = vec![];
+            let mut read_line = |received: &mut Vec<String>| {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                received.push(line.trim_end_matches("\r\n").to_string());
+            };
+            stream.write_all(b"220 mock.example.com ESMTP\r\n").unwrap();
+            read_line(&mut received);
+            stream
+                .write_all(b"250-mock.example.com\r\n250-AUTH LOGIN\r\n250 8BITMIME\r\n")
+                .unwrap();
+            read_line(&mut received);
+            stream.write_all(b"334 VXNlcm5hbWU6\r\n").unwrap();
+            read_line(&mut received);
+            stream.write_all(b"334 UGFzc3dvcmQ6\r\n").unwrap();
+            read_line(&mut received);
+            stream
+                .write_all(b"235 2.7.0 Authentication successful\r\n")
+                .unwrap();
+            read_line(&mut received);
+            stream
+                .write_all(b"250-mock.example.com\r\n250 AUTH LOGIN\r\n")
+                .unwrap();
+            read_line(&mut received);
+            stream.write_all(b"250 2.1.0 Ok\r\n").unwrap();
+            read_line(&mut received);
+            stream.write_all(b"250 2.1.5 Ok\r\n").unwrap();
+            read_line(&mut received);
+            stream
+                .write_all(b"354 End data with <CR><LF>.<CR><LF>\r\n")
+                .unwrap();
+            loop {
+                read_line(&mut received);
+                if received.last().unwrap() == "." {
+                    break;
+                }
+            }
+            stream.write_all(b"250 2.0.0 Ok: queued\r\n").unwrap();
+            received
+        });
+
+        let conf = SmtpServerConf {
+            hostname: "127.0.0.1".into(),
+            port,
+            envelope_from: String::new(),
+            auth: SmtpAuth::Auto {
+                username: "user".into(),
+                password: Password::Raw("hunter2".into()),
+                require_auth: true,
+            },
+            security: SmtpSecurity::None,
+            extensions: SmtpExtensionSupport::default(),
+        };
+        let mail = "From: user@example.com\r\nTo: rcpt@example.com\r\nSubject: test\r\nMessage-ID: <test@example.com>\r\n\r\n.leading dot\r\nbody\r\n";
+        smol::block_on(async move {
+            let mut conn = SmtpConnection::new_connection(conf).await?;
+            conn.mail_transaction(mail, None).await
+        })
+        .unwrap();
+
+        assert_eq!(
+            server.join().unwrap(),
+            vec![
+                "EHLO meli.delivery".to_string(),
+                "AUTH LOGIN".to_string(),
+                base64::encode("user"),
+                base64::encode("hunter2"),
+                "EHLO meli.delivery".to_string(),
+                "MAIL FROM:<user@example.com>".to_string(),
+                "RCPT TO:<rcpt@example.com>".to_string(),
+                "DATA".to_string(),
+                "From: user@example.com".to_string(),
+                "To: rcpt@example.com".to_string(),
+                "Subject: test".to_string(),
+                "Message-ID: <test@example.com>".to_string(),
+                "".to_string(),
+                "..leading dot".to_string(),
+                "body".to_string(),
+                ".".to_string(),
+            ]
+        );
+    }
+}
@@ -1011,12 +1011,42 @@ mod alg {
 }
 
 pub fn split_lines_reflow(text: &str, reflow: Reflow, width: Option<usize>) -> Vec<String> {
+    split_lines_reflow_numbered(text, reflow, width).0
+}
+
+/// Like [`split_lines_reflow`], but also returns the 1-based number of the line of `text` that
+/// each returned line starts, or `0` for lines that continue a previous one because they were
+/// wrapped or reflowed.
+pub fn split_lines_reflow_numbered(
+    text: &str,
+    reflow: Reflow,
+    width: Option<usize>,
+) -> (Vec<String>, Vec<usize>) {
+    /* Numbers the lines pushed to `ret` after its first `start` lines: the first one with `line`,
+     * the rest as its continuations */
+    fn number(ret: &[String], numbers: &mut Vec<usize>, start: usize, line: usize) {
+        if ret.len() > start {
+            numbers.push(line);
+            numbers.resize(ret.len(), 0);
+        }
+    }
+    /* The lines left after trimming `text`, numbered from the first non blank line */
+    let trimmed = || {
+        let first_line = text[..text.len() - text.trim_start().len()]
+            .matches('\n')
+            .count()
+            + 1;
+        let ret: Vec<String> = text.trim().split('\n').map(str::to_string).collect();
+        let numbers = (first_line..first_line + ret.len()).collect();
+        (ret, numbers)
+    };
     match reflow {
         Reflow::FormatFlowed => {
             /* rfc3676 - The Text/Plain Format and DelSp Parameters
              * https://tools.ietf.org/html/rfc3676 */
 
             let mut ret = Vec::new();
+            let mut numbers = Vec::new();
             /*
              * - Split lines with indices using str::match_indices()
              * - Iterate and reflow flow regions, and pass fixed regions through
@@ -1026,6 +1056,13 @@ pub fn split_lines_reflow(text: &str, reflow: Reflow, width: Option<usize>) -> V
             let mut in_paragraph = false;
             let mut paragraph_start = 0;
 
+            /* Number of the line `paragraph` starts at `offset`, after the newline it starts with
+             * if any */
+            let line_of = |offset: usize, paragraph: &str| {
+                lines_indices.partition_point(|&n| n < offset)
+                    + 1
+                    + usize::from(paragraph.starts_with('\n'))
+            };
             let mut prev_quote_depth = 0;
             for i in &lines_indices {
                 let line = &text[prev_index..*i];
@@ -1060,20 +1097,43 @@ pub fn split_lines_reflow(text: &str, reflow: Reflow, width: Option<usize>) -> V
                     } else {
                         /*Malformed line, different quote depths can't be in the same paragraph. */
                         let paragraph = &text[paragraph_start..prev_index];
+                        let start = ret.len();
                         reflow_helper(&mut ret, paragraph, prev_quote_depth, in_paragraph, width);
+                        number(
+                            &ret,
+                            &mut numbers,
+                            start,
+                            line_of(paragraph_start, paragraph),
+                        );
 
                         paragraph_start = prev_index;
                     }
                 } else {
                     if prev_quote_depth == quote_depth || !in_paragraph {
                         let paragraph = &text[paragraph_start..*i];
+                        let start = ret.len();
                         reflow_helper(&mut ret, paragraph, quote_depth, in_paragraph, width);
+                        number(
+                            &ret,
+                            &mut numbers,
+                            start,
+                            line_of(paragraph_start, paragraph),
+                        );
                     } else {
                         /*Malformed line, different quote depths can't be in the same paragraph. */
                         let paragraph = &text[paragraph_start..prev_index];
+                        let start = ret.len();
                         reflow_helper(&mut ret, paragraph, prev_quote_depth, in_paragraph, width);
+                        number(
+                            &ret,
+                            &mut numbers,
+                            start,
+                            line_of(paragraph_start, paragraph),
+                        );
                         let paragraph = &text[prev_index..*i];
+                        let start = ret.len();
                         reflow_helper(&mut ret, paragraph, quote_depth, false, width);
+                        number(&ret, &mut numbers, start, line_of(prev_index, paragraph));
                     }
                     paragraph_start = *i;
                     in_paragraph = false;
@@ -1082,15 +1142,25 @@ pub fn split_lines_reflow(text: &str, reflow: Reflow, width: Option<usize>) -> V
                 prev_index = *i;
             }
             let paragraph = &text[paragraph_start..text.len()];
+            let start = ret.len();
             reflow_helper(&mut ret, paragraph, prev_quote_depth, in_paragraph, width);
-            ret
+            number(
+                &ret,
+                &mut numbers,
+                start,
+                line_of(paragraph_start, paragraph),
+            );
+            (ret, numbers)
         }
         Reflow::All => {
             if let Some(width) = width {
                 let mut ret = Vec::new();
+                /* Where each line of `text` starts in `ret` */
+                let mut line_starts = Vec::new();
                 let width = width.saturating_sub(2);
 
-                for line in text.lines() {
+                for (n, line) in text.lines().enumerate() {
+                    line_starts.push((ret.len(), n + 1));
                     if line.grapheme_len() <= width {
                         ret.push(line.to_string());
                         continue;
@@ -1142,12 +1212,19 @@ pub fn split_lines_reflow(text: &str, reflow: Reflow, width: Option<usize>) -> V
                         prev = new_off;
                     }
                 }
-                ret
+                /* Lines that were split into nothing share their start with the next line */
+                let mut numbers = vec![0; ret.len()];
+                for (start, n) in line_starts {
+                    if start < ret.len() {
+                        numbers[start] = n;
+                    }
+                }
+                (ret, numbers)
             } else {
-                text.trim().split('\n').map(str::to_string).collect()
+                trimmed()
             }
         }
-        Reflow::No => text.trim().split('\n').map(str::to_string).collect(),
+        Reflow::No => trimmed(),
     }
 }
 
@@ -1245,6 +1322,43 @@ easy to take MORE than nothing.'"#;
     }
 }
 
+#[test]
+fn test_reflow_long_line() {
+    /* A 200 column flowed line (it ends with a space) */
+    let line = format!("{}dolor i ", "lorem ipsum ".repeat(16));
+    assert_eq!(line.len(), 200);
+    let text = format!("{}\nend", line);
+    let all = split_lines_reflow(&text, Reflow::All, Some(80));
+    let no = split_lines_reflow(&text, Reflow::No, Some(80));
+    let flowed = split_lines_reflow(&text, Reflow::FormatFlowed, Some(80));
+    assert_eq!(no.len(), 2);
+    assert_eq!(no[0].len(), 200);
+    assert!(all.len() > 2);
+    assert!(all.iter().all(|l| l.grapheme_width() <= 80));
+    assert!(all[1].starts_with('⤷'));
+    assert!(flowed.len() > 2);
+    assert!(flowed.iter().all(|l| !l.starts_with('⤷')));
+    assert_ne!(all, flowed);
+}
+
+#[test]
+fn test_reflow_line_numbers() {
+    let text = "\nflowed \nparagraph\nfixed line\n>quoted \n>paragraph\nend";
+    let (_, numbers) = split_lines_reflow_numbered(text, Reflow::FormatFlowed, Some(80));
+    assert_eq!(numbers, vec![1, 2, 4, 5, 7]);
+    /* Flowed paragraphs are wrapped without a continuation marker */
+    let (lines, numbers) = split_lines_reflow_numbered(text, Reflow::FormatFlowed, Some(10));
+    assert_eq!(lines.len(), numbers.len());
+    assert_eq!(numbers, vec![1, 2, 0, 4, 5, 0, 7]);
+
+    let (lines, numbers) =
+        split_lines_reflow_numbered("short\na line that is too long\nend", Reflow::All, Some(12));
+    assert_eq!(lines.len(), numbers.len());
+    assert_eq!(numbers, vec![1, 2, 0, 3]);
+
+    let (_, numbers) = split_lines_reflow_numbered("\n\nfirst\nsecond\n", Reflow::No, None);
+    assert_eq!(numbers, vec![3, 4]);
+}
 
 mod segment_tree {
     /*! Simple segment tree implementation for maximum in range queries. This is useful if given an
@@ -83,6 +83,50 @@ impl KMP for str {
     }
 }
 
+/// Case-insensitive fuzzy match: `pattern` matches if its graphemes appear in `text` in order,
+/// not necessarily consecutively. Returns the number of skipped graphemes between the first and
+/// last match (lower is a better match), or `None` if `pattern` does not match. An empty
+/// `pattern` matches everything with a score of `0`.
+pub fn fuzzy_match(text: &str, pattern: &str) -> Option<usize> {
+    let text = text.to_lowercase();
+    let pattern = pattern.to_lowercase();
+    let pattern = pattern.split_graphemes();
+    let mut pattern_iter = pattern.iter().peekable();
+    let mut first = None;
+    let mut score = 0;
+    for (i, g) in text.split_graphemes().into_iter().enumerate() {
+        match pattern_iter.peek() {
+            None => break,
+            Some(p) if **p == g => {
+                pattern_iter.next();
+                if first.is_none() {
+                    first = Some(i);
+                }
+            }
+            Some(_) if first.is_some() => {
+                score += 1;
+            }
+            Some(_) => {}
+        }
+    }
+    if pattern_iter.peek().is_some() {
+        None
+    } else {
+        Some(score)
+    }
+}
+
+#[test]
+fn test_fuzzy_match() {
+    assert_eq!(fuzzy_match("INBOX", ""), Some(0));
+    assert_eq!(fuzzy_match("INBOX", "inbox"), Some(0));
+    assert_eq!(fuzzy_match("INBOX/Lists/luddites", "inlud"), Some(10));
+    assert_eq!(fuzzy_match("Archive/2020", "a2020"), Some(7));
+    assert_eq!(fuzzy_match("Sent", "snt"), Some(1));
+    assert_eq!(fuzzy_match("Sent", "tn"), None);
+    assert_eq!(fuzzy_match("Drafts", "draftss"), None);
+}
+
 #[test]
 fn test_search() {
     use super::_ALICE_CHAPTER_1;
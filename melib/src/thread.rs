@@ -234,14 +234,51 @@ impl SubjectPrefix for &[u8] {
 
 #[derive(Debug, Clone, PartialEq, Copy, Deserialize, Serialize)]
 pub enum SortOrder {
+    #[serde(alias = "asc")]
     Asc,
+    #[serde(alias = "desc")]
     Desc,
 }
 
 #[derive(Debug, Clone, PartialEq, Copy, Deserialize, Serialize)]
 pub enum SortField {
+    #[serde(alias = "subject")]
     Subject,
+    #[serde(alias = "date")]
     Date,
+    #[serde(alias = "from")]
+    From,
+    /// When the server received the message, or its date if that is unknown.
+    #[serde(alias = "received")]
+    Received,
+}
+
+impl SortField {
+    /// Compares two envelopes by this field in ascending order.
+    pub fn cmp_envelopes(self, a: &Envelope, b: &Envelope) -> Ordering {
+        match self {
+            SortField::Date => a.date().cmp(&b.date()),
+            SortField::Subject => a.subject().cmp(&b.subject()),
+            SortField::From => a
+                .field_from_to_string()
+                .to_lowercase()
+                .cmp(&b.field_from_to_string().to_lowercase()),
+            SortField::Received => a
+                .received()
+                .unwrap_or_else(|| a.date())
+                .cmp(&b.received().unwrap_or_else(|| b.date())),
+        }
+    }
+}
+
+impl SortOrder {
+    /// Applies this order to an ascending `ordering`.
+    pub fn apply(self, ordering: Ordering) -> Ordering {
+        match self {
+            SortOrder::Asc => ordering,
+            SortOrder::Desc => ordering.reverse(),
+        }
+    }
 }
 
 impl Default for SortField {
@@ -262,6 +299,8 @@ impl FromStr for SortField {
         match s.trim() {
             "subject" | "s" | "sub" | "sbj" | "subj" => Ok(SortField::Subject),
             "date" | "d" => Ok(SortField::Date),
+            "from" | "f" | "sender" => Ok(SortField::From),
+            "received" | "r" => Ok(SortField::Received),
             _ => Err(()),
         }
     }
@@ -994,6 +1033,18 @@ impl Threads {
                     mb.subject().as_ref().cmp(&ma.subject())
                 }
             }
+            (field @ SortField::From, order) | (field @ SortField::Received, order) => {
+                let a = &self.thread_nodes[&self.thread_ref(*a).root()].message();
+                let b = &self.thread_nodes[&self.thread_ref(*b).root()].message();
+                match (a, b) {
+                    (Some(a), Some(b)) => {
+                        order.apply(field.cmp_envelopes(&envelopes[a], &envelopes[b]))
+                    }
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                }
+            }
         });
     }
     pub fn node_inner_sort_by(
@@ -1074,6 +1125,18 @@ impl Threads {
                     mb.subject().as_ref().cmp(&ma.subject())
                 }
             }
+            (field @ SortField::From, order) | (field @ SortField::Received, order) => {
+                let a = &self.thread_nodes[a].message();
+                let b = &self.thread_nodes[b].message();
+                match (a, b) {
+                    (Some(a), Some(b)) => {
+                        order.apply(field.cmp_envelopes(&envelopes[a], &envelopes[b]))
+                    }
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                }
+            }
         });
     }
     fn inner_sort_by(&self, sort: (SortField, SortOrder), envelopes: &Envelopes) {
@@ -1150,6 +1213,18 @@ impl Threads {
                     mb.subject().as_ref().cmp(&ma.subject())
                 }
             }
+            (field @ SortField::From, order) | (field @ SortField::Received, order) => {
+                let a = &self.thread_nodes[a].message();
+                let b = &self.thread_nodes[b].message();
+                match (a, b) {
+                    (Some(a), Some(b)) => {
+                        order.apply(field.cmp_envelopes(&envelopes[a], &envelopes[b]))
+                    }
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                }
+            }
         });
     }
 
@@ -1378,3 +1453,166 @@ fn save_graph(
     }
 }
 */
+
+/// Builds an envelope with Message-ID `id` that replies to the last of `refs`, dated the `date`th
+/// of June 2020. Used by tests.
+#[cfg(any(test, feature = "test-utils"))]
+#[doc(hidden)]
+pub fn test_envelope(id: &str, subject: &str, date: u32, refs: &[&str]) -> Envelope {
+    let mut s = format!(
+        "From: a@example.com\r\nSubject: {}\r\nDate: Mon, {:02} Jun 2020 10:00:00 +0000\r\nMessage-ID: <{}>\r\n",
+        subject, date, id
+    );
+    if let Some(parent) = refs.last() {
+        s.push_str(&format!("In-Reply-To: <{}>\r\n", parent));
+        s.push_str(&format!(
+            "References: {}\r\n",
+            refs.iter()
+                .map(|r| format!("<{}>", r))
+                .collect::<Vec<String>>()
+                .join(" ")
+        ));
+    }
+    s.push_str("\r\n");
+    Envelope::from_bytes(s.as_bytes(), None).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sort_field_cmp_envelopes() {
+        let envelopes: Vec<Envelope> = [
+            "From: Carol <carol@example.com>\r\nSubject: beta\r\nDate: Wed, 03 Jun 2020 10:00:00 +0000\r\nMessage-ID: <1@example.com>\r\n\r\n",
+            "From: alice@example.com\r\nSubject: gamma\r\nDate: Mon, 01 Jun 2020 10:00:00 +0000\r\nMessage-ID: <2@example.com>\r\n\r\n",
+            "From: Bob <bob@example.com>\r\nSubject: alpha\r\nDate: Tue, 02 Jun 2020 10:00:00 +0000\r\nMessage-ID: <3@example.com>\r\n\r\n",
+        ]
+        .iter()
+        .map(|raw| Envelope::from_bytes(raw.as_bytes(), None).unwrap())
+        .collect();
+        let sorted = |field: SortField, order: SortOrder| -> Vec<String> {
+            let mut envelopes = envelopes.iter().collect::<Vec<&Envelope>>();
+            envelopes.sort_by(|a, b| order.apply(field.cmp_envelopes(a, b)));
+            envelopes.iter().map(|e| e.subject().to_string()).collect()
+        };
+
+        assert_eq!(
+            sorted(SortField::Date, SortOrder::Desc),
+            vec!["beta", "alpha", "gamma"]
+        );
+        assert_eq!(
+            sorted(SortField::Date, SortOrder::Asc),
+            vec!["gamma", "alpha", "beta"]
+        );
+        assert_eq!(
+            sorted(SortField::Subject, SortOrder::Asc),
+            vec!["alpha", "beta", "gamma"]
+        );
+        assert_eq!(
+            sorted(SortField::Subject, SortOrder::Desc),
+            vec!["gamma", "beta", "alpha"]
+        );
+        assert_eq!(
+            sorted(SortField::From, SortOrder::Asc),
+            vec!["gamma", "alpha", "beta"]
+        );
+        assert_eq!(
+            sorted(SortField::From, SortOrder::Desc),
+            vec!["beta", "alpha", "gamma"]
+        );
+        assert_eq!("from".parse::<SortField>(), Ok(SortField::From));
+
+        /* Envelopes without a receipt time are sorted by their date */
+        let mut envelopes = envelopes;
+        let (beta_date, gamma_date) = (envelopes[0].date(), envelopes[1].date());
+        envelopes[0].set_received(Some(gamma_date - 1));
+        envelopes[2].set_received(Some(beta_date + 1));
+        let mut sorted_envelopes = envelopes.iter().collect::<Vec<&Envelope>>();
+        sorted_envelopes
+            .sort_by(|a, b| SortOrder::Asc.apply(SortField::Received.cmp_envelopes(a, b)));
+        assert_eq!(
+            sorted_envelopes
+                .iter()
+                .map(|e| e.subject().to_string())
+                .collect::<Vec<String>>(),
+            vec!["beta", "gamma", "alpha"]
+        );
+        assert_eq!("received".parse::<SortField>(), Ok(SortField::Received));
+    }
+
+    #[test]
+    fn test_threads_references() {
+        /* Inserted children first, to make sure parents are attached when they arrive later. */
+        let envelopes: Vec<Envelope> = vec![
+            test_envelope("c@a", "Re: hi", 3, &["a@a", "b@a"]),
+            test_envelope("f@b", "Re: hi", 6, &["e@b"]),
+            test_envelope("a@a", "hi", 1, &[]),
+            test_envelope("b@a", "Re: hi", 2, &["a@a"]),
+            test_envelope("e@b", "hi", 5, &[]),
+            test_envelope("d@a", "Re: hi", 4, &["a@a"]),
+            test_envelope("g@c", "Re: hi", 7, &["missing@c"]),
+        ];
+        let ids: HashMap<EnvelopeHash, String> = envelopes
+            .iter()
+            .map(|e| (e.hash(), e.message_id().to_string()))
+            .collect();
+        let hashes: Vec<EnvelopeHash> = envelopes.iter().map(Envelope::hash).collect();
+        let envelopes: Envelopes = Arc::new(RwLock::new(
+            envelopes.into_iter().map(|e| (e.hash(), e)).collect(),
+        ));
+        let mut threads = Threads::new(hashes.len());
+        for h in hashes {
+            threads.insert(&envelopes, h);
+        }
+
+        let message_id = |node: ThreadNodeHash| -> Option<String> {
+            threads.thread_nodes()[&node]
+                .message()
+                .map(|h| ids[&h].clone())
+        };
+        let parent_of = |id: &str| -> Option<Option<String>> {
+            let (node_hash, _) = threads
+                .thread_nodes()
+                .iter()
+                .find(|(n, _)| message_id(**n).as_deref() == Some(id))?;
+            Some(
+                threads.thread_nodes()[node_hash]
+                    .parent()
+                    .and_then(message_id),
+            )
+        };
+        assert_eq!(parent_of("<a@a>"), Some(None));
+        assert_eq!(parent_of("<b@a>"), Some(Some("<a@a>".to_string())));
+        assert_eq!(parent_of("<c@a>"), Some(Some("<b@a>".to_string())));
+        assert_eq!(parent_of("<d@a>"), Some(Some("<a@a>".to_string())));
+        assert_eq!(parent_of("<e@b>"), Some(None));
+        assert_eq!(parent_of("<f@b>"), Some(Some("<e@b>".to_string())));
+        /* The parent of g is known only by its Message-ID, so it is an empty node. */
+        assert_eq!(parent_of("<g@c>"), Some(None));
+
+        /* The two "hi" threads and the orphan reply are not grouped by subject. */
+        let mut groups: Vec<Vec<String>> = threads
+            .roots()
+            .into_iter()
+            .map(|root| {
+                let mut ids: Vec<String> = threads
+                    .thread_group_iter(root)
+                    .filter_map(|(_, node)| message_id(node))
+                    .collect();
+                ids.sort();
+                ids
+            })
+            .filter(|ids| !ids.is_empty())
+            .collect();
+        groups.sort();
+        assert_eq!(
+            groups,
+            vec![
+                vec!["<a@a>", "<b@a>", "<c@a>", "<d@a>"],
+                vec!["<e@b>", "<f@b>"],
+                vec!["<g@c>"],
+            ]
+        );
+    }
+}
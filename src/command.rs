@@ -35,6 +35,7 @@ use melib::nom::{
 pub use melib::thread::{SortField, SortOrder};
 use melib::MeliError;
 pub mod actions;
+use crate::conf::FlagFilter;
 use actions::MailboxOperation;
 use std::collections::HashSet;
 pub mod history;
@@ -261,6 +262,31 @@ define_commands!([
                        }
                    )
                  },
+                 { tags: ["undo"],
+                   desc: "undo the last deletion or move of messages",
+                   tokens: &[One(Literal("undo"))],
+                   parser: (
+                       fn undo(input: &[u8]) -> IResult<&[u8], Action> {
+                           let (input, _) = tag("undo")(input.trim())?;
+                           let (input, _) = eof(input)?;
+                           Ok((input, Listing(Undo)))
+                       }
+                   )
+                 },
+                 { tags: ["report-spam", "report-ham"],
+                   desc: "pipe message to the account's spam_command or ham_command and move it to the junk mailbox or inbox",
+                   tokens: &[One(Alternatives(&[to_stream!(One(Literal("report-spam"))), to_stream!(One(Literal("report-ham")))]))],
+                   parser: (
+                       fn report(input: &[u8]) -> IResult<&[u8], Action> {
+                           let (input, ret) = alt((
+                               map(tag("report-spam"), |_| Listing(ReportSpam)),
+                               map(tag("report-ham"), |_| Listing(ReportHam)),
+                           ))(input.trim())?;
+                           let (input, _) = eof(input)?;
+                           Ok((input, ret))
+                       }
+                   )
+                 },
                  { tags: ["copyto", "moveto"],
                    desc: "copy/move message",
                    tokens: &[One(Alternatives(&[to_stream!(One(Literal("copyto"))), to_stream!(One(Literal("moveto")))])), ZeroOrOne(AccountName), One(MailboxPath)],
@@ -329,6 +355,19 @@ define_commands!([
                        }
                    )
                  },
+                 { tags: ["close right"],
+                   desc: "close tabs to the right of the current tab",
+                   tokens: &[One(Literal("close")), One(Literal("right"))],
+                   parser: (
+                       fn close_right(input: &[u8]) -> IResult<&[u8], Action> {
+                           let (input, _) = tag("close")(input)?;
+                           let (input, _) = is_a(" ")(input)?;
+                           let (input, _) = tag("right")(input)?;
+                           let (input, _) = eof(input)?;
+                           Ok( (input, { Tab(CloseRight) }))
+                       }
+                   )
+                 },
                  { tags: ["go"],
                    desc: "go [n], switch to nth mailbox in this account",
                    tokens: &[One(Literal("goto")), One(MailboxIndexValue)],
@@ -356,8 +395,8 @@ define_commands!([
                    )
                  },
                 { tags: ["sort"],
-                  desc: "sort [date/subject] [asc/desc], sorts threads.",
-                   tokens: &[One(Literal("sort")), One(Alternatives(&[to_stream!(One(Literal("date"))), to_stream!(One(Literal("subject")))])), One(Alternatives(&[to_stream!(One(Literal("asc"))), to_stream!(One(Literal("desc")))])) ],
+                  desc: "sort [date/subject/from/received] [asc/desc], sorts threads.",
+                   tokens: &[One(Literal("sort")), One(Alternatives(&[to_stream!(One(Literal("date"))), to_stream!(One(Literal("subject"))), to_stream!(One(Literal("from"))), to_stream!(One(Literal("received")))])), One(Alternatives(&[to_stream!(One(Literal("asc"))), to_stream!(One(Literal("desc")))])) ],
                   parser: (
                       fn sort(input: &[u8]) -> IResult<&[u8], Action> {
                           let (input, _) = tag("sort")(input)?;
@@ -380,6 +419,19 @@ define_commands!([
                       }
                   )
                 },
+                { tags: ["toggle threads"],
+                  desc: "toggle between the plain listing and the mailbox's threaded listing style",
+                  tokens: &[One(Literal("toggle")), One(Literal("threads"))],
+                  parser: (
+                      fn toggle_threads(input: &[u8]) -> IResult<&[u8], Action> {
+                          let (input, _) = tag("toggle")(input.trim())?;
+                          let (input, _) = is_a(" ")(input)?;
+                          let (input, _) = tag("threads")(input)?;
+                          let (input, _) = eof(input)?;
+                          Ok((input, Listing(ToggleThreads)))
+                      }
+                  )
+                },
                 { tags: ["toggle thread_snooze"],
                   desc: "turn off new notifications for this thread",
                   tokens: &[One(Literal("toggle thread_snooze"))],
@@ -406,6 +458,51 @@ define_commands!([
                       }
                   )
                 },
+                { tags: ["filter "],
+                  desc: "filter unread/flagged/clear, shows only unread or flagged envelopes in the listing",
+                  tokens: &[One(Literal("filter")), One(Alternatives(&[to_stream!(One(Literal("unread"))), to_stream!(One(Literal("flagged"))), to_stream!(One(Literal("clear")))]))],
+                  parser:(
+                      fn filter_flags(input: &[u8]) -> IResult<&[u8], Action> {
+                          let (input, _) = tag("filter")(input.trim())?;
+                          let (input, _) = is_a(" ")(input)?;
+                          let (input, ret) = alt((
+                              map(tag("unread"), |_| Listing(FilterFlags(Some(FlagFilter::Unread))))
+                              , map(tag("flagged"), |_| Listing(FilterFlags(Some(FlagFilter::Flagged))))
+                              , map(tag("clear"), |_| Listing(FilterFlags(None)))
+                          ))(input)?;
+                          let (input, _) = eof(input)?;
+                          Ok((input, ret))
+                      }
+                  )
+                },
+                { tags: ["collapse all", "expand all"],
+                  desc: "collapse all/expand all, collapses or expands every thread in the threaded listing",
+                  tokens: &[One(Alternatives(&[to_stream!(One(Literal("collapse all"))), to_stream!(One(Literal("expand all")))]))],
+                  parser:(
+                      fn collapse_all(input: &[u8]) -> IResult<&[u8], Action> {
+                          let (input, ret) = alt((
+                              map(tag("collapse all"), |_| Listing(CollapseAll))
+                              , map(tag("expand all"), |_| Listing(ExpandAll))
+                          ))(input.trim())?;
+                          let (input, _) = eof(input)?;
+                          Ok((input, ret))
+                      }
+                  )
+                },
+                { tags: ["select all", "deselect all"],
+                  desc: "select all/deselect all, changes the selection of every envelope in the current listing",
+                  tokens: &[One(Alternatives(&[to_stream!(One(Literal("select all"))), to_stream!(One(Literal("deselect all")))]))],
+                  parser:(
+                      fn select_all(input: &[u8]) -> IResult<&[u8], Action> {
+                          let (input, ret) = alt((
+                              map(tag("select all"), |_| Listing(SelectAll))
+                              , map(tag("deselect all"), |_| Listing(DeselectAll))
+                          ))(input.trim())?;
+                          let (input, _) = eof(input)?;
+                          Ok((input, ret))
+                      }
+                  )
+                },
                 { tags: ["select"],
                   desc: "select <TERM>, selects envelopes matching with given term",
                   tokens: &[One(Literal("select")), One(RestOfStringValue)],
@@ -497,6 +594,37 @@ define_commands!([
                       }
                   )
                 },
+                /* Pipe the viewed envelope to binary and show its output */
+                { tags: ["pipe-envelope "],
+                  desc: "pipe-envelope EXECUTABLE ARGS",
+                  tokens: &[One(Literal("pipe-envelope")), One(Filepath), ZeroOrMore(QuotedStringValue)],
+                  parser:(
+                      fn pipe_envelope<'a>(input: &'a [u8]) -> IResult<&'a [u8], Action> {
+                          alt((
+                                  |input: &'a [u8]| -> IResult<&'a [u8], Action> {
+                                      let (input, _) = tag("pipe-envelope")(input.trim())?;
+                                      let (input, _) = is_a(" ")(input)?;
+                                      let (input, bin) = quoted_argument(input)?;
+                                      let (input, _) = is_a(" ")(input)?;
+                                      let (input, args) = separated_list(is_a(" "), quoted_argument)(input)?;
+                                      let (input, _) = eof(input)?;
+                                      Ok((input, {
+                                          View(PipeEnvelope(bin.to_string(), args.into_iter().map(String::from).collect::<Vec<String>>()))
+                                      }))
+                                  },
+                                  |input: &'a [u8]| -> IResult<&'a [u8], Action> {
+                                      let (input, _) = tag("pipe-envelope")(input.trim())?;
+                                      let (input, _) = is_a(" ")(input)?;
+                                      let (input, bin) = quoted_argument(input.trim())?;
+                                      let (input, _) = eof(input)?;
+                                      Ok((input, {
+                                          View(PipeEnvelope(bin.to_string(), Vec::new()))
+                                      }))
+                                  }
+                          ))(input)
+                      }
+                  )
+                },
                 { tags: ["add-attachment ", "add-attachment-file-picker "],
                   desc: "add-attachment PATH",
                   tokens: &[One(
@@ -675,6 +803,17 @@ Alternatives(&[to_stream!(One(Literal("add-attachment")), One(Filepath)), to_str
                       }
                   )
                 },
+                { tags: ["refresh"],
+                  desc: "refresh the currently selected mailbox",
+                  tokens: &[One(Literal("refresh"))],
+                  parser:(
+                      fn refresh(input: &[u8]) -> IResult<&[u8], Action> {
+                          let (input, _) = tag("refresh")(input.trim())?;
+                          let (input, _) = eof(input)?;
+                          Ok((input, Listing(Refresh)))
+                      }
+                  )
+                },
                 { tags: ["open-in-tab"],
                   desc: "opens envelope view in new tab",
                   tokens: &[One(Literal("open-in-tab"))],
@@ -686,6 +825,19 @@ Alternatives(&[to_stream!(One(Literal("add-attachment")), One(Filepath)), to_str
                       }
                   )
                 },
+                { tags: ["view "],
+                  desc: "view FILESYSTEM_PATH, opens an e-mail or mbox file in new tabs",
+                  tokens: &[One(Literal("view")), One(Filepath)],
+                  parser:(
+                      fn view_file(input: &[u8]) -> IResult<&[u8], Action> {
+                          let (input, _) = tag("view")(input.trim())?;
+                          let (input, _) = is_a(" ")(input)?;
+                          let (input, path) = quoted_argument(input.trim())?;
+                          let (input, _) = eof(input)?;
+                          Ok((input, ViewFile(path.to_string().into())))
+                      }
+                  )
+                },
                 { tags: ["save-attachment "],
                   desc: "save-attachment INDEX PATH",
                   tokens: &[One(Literal("save-attachment")), One(AttachmentIndexValue), One(Filepath)],
@@ -701,12 +853,12 @@ Alternatives(&[to_stream!(One(Literal("add-attachment")), One(Filepath)), to_str
                       }
                   )
                 },
-                { tags: ["export-mail "],
+                { tags: ["export-mail ", "export "],
                   desc: "export-mail PATH",
                   tokens: &[One(Literal("export-mail")), One(Filepath)],
                   parser:(
                       fn export_mail(input: &[u8]) -> IResult<&[u8], Action> {
-                          let (input, _) = tag("export-mail")(input.trim())?;
+                          let (input, _) = alt((tag("export-mail"), tag("export")))(input.trim())?;
                           let (input, _) = is_a(" ")(input)?;
                           let (input, path) = quoted_argument(input.trim())?;
                           let (input, _) = eof(input)?;
@@ -714,6 +866,21 @@ Alternatives(&[to_stream!(One(Literal("add-attachment")), One(Filepath)), to_str
                       }
                   )
                 },
+                { tags: ["reply", "reply-all", "forward"],
+                  desc: "reply/reply-all/forward, opens a draft replying to or forwarding the viewed envelope",
+                  tokens: &[One(Alternatives(&[to_stream!(One(Literal("reply"))), to_stream!(One(Literal("reply-all"))), to_stream!(One(Literal("forward")))]))],
+                  parser:(
+                      fn reply_forward(input: &[u8]) -> IResult<&[u8], Action> {
+                          let (input, ret) = alt((
+                              map(tag("reply-all"), |_| View(ReplyToAll)),
+                              map(tag("reply"), |_| View(Reply)),
+                              map(tag("forward"), |_| View(Forward)),
+                          ))(input.trim())?;
+                          let (input, _) = eof(input)?;
+                          Ok((input, ret))
+                      }
+                  )
+                },
                 { tags: ["tag", "tag add", "tag remove"],
                    desc: "tag [add/remove], edits message's tags.",
                    tokens: &[One(Literal("tag")), One(Alternatives(&[to_stream!(One(Literal("add"))), to_stream!(One(Literal("remove")))]))],
@@ -780,6 +947,19 @@ Alternatives(&[to_stream!(One(Literal("add-attachment")), One(Filepath)), to_str
                       }
                   )
                 },
+                { tags: ["theme "],
+                  desc: "theme NAME, switch to theme NAME",
+                  tokens: &[One(Literal("theme")), One(QuotedStringValue)],
+                  parser:(
+                      fn set_theme(input: &[u8]) -> IResult<&[u8], Action> {
+                          let (input, _) = tag("theme")(input.trim())?;
+                          let (input, _) = is_a(" ")(input)?;
+                          let (input, name) = quoted_argument(input)?;
+                          let (input, _) = eof(input)?;
+                          Ok((input, SetTheme(name.to_string())))
+                      }
+                  )
+                },
                 { tags: ["quit"],
                   desc: "quit meli",
                   tokens: &[One(Literal("quit"))],
@@ -835,12 +1015,19 @@ fn listing_action(input: &[u8]) -> IResult<&[u8], Action> {
         toggle,
         seen_flag,
         delete_message,
+        undo,
+        report,
         copymove,
         import,
         search,
+        select_all,
         select,
+        filter_flags,
         toggle_thread_snooze,
+        toggle_threads,
+        collapse_all,
         open_in_new_tab,
+        refresh,
         _tag,
     ))(input)
 }
@@ -859,8 +1046,18 @@ fn account_action(input: &[u8]) -> IResult<&[u8], Action> {
     alt((reindex, print_account_setting))(input)
 }
 
+fn tab(input: &[u8]) -> IResult<&[u8], Action> {
+    alt((close, close_right))(input)
+}
+
 fn view(input: &[u8]) -> IResult<&[u8], Action> {
-    alt((pipe, save_attachment, export_mail))(input)
+    alt((
+        pipe,
+        pipe_envelope,
+        save_attachment,
+        export_mail,
+        reply_forward,
+    ))(input)
 }
 
 pub fn parse_command(input: &[u8]) -> Result<Action, MeliError> {
@@ -869,7 +1066,7 @@ pub fn parse_command(input: &[u8]) -> Result<Action, MeliError> {
         listing_action,
         sort,
         subsort,
-        close,
+        tab,
         mailinglist,
         setenv,
         printenv,
@@ -883,6 +1080,8 @@ pub fn parse_command(input: &[u8]) -> Result<Action, MeliError> {
         account_action,
         print_setting,
         toggle_mouse,
+        set_theme,
+        view_file,
         quit,
     ))(input)
     .map(|(_, v)| v)
@@ -931,6 +1130,18 @@ fn test_parser() {
     println!("alright");
 }
 
+#[test]
+fn test_parse_pipe_envelope() {
+    assert!(matches!(
+        parse_command(b"pipe less").unwrap(),
+        View(Pipe(ref bin, ref args)) if bin == "less" && args.is_empty()
+    ));
+    assert!(matches!(
+        parse_command(b"pipe-envelope grep -i foo").unwrap(),
+        View(PipeEnvelope(ref bin, ref args)) if bin == "grep" && args == &["-i", "foo"]
+    ));
+}
+
 /// Get command suggestions for input
 pub fn command_completion_suggestions(input: &str) -> Vec<String> {
     use crate::melib::ShellExpandTrait;
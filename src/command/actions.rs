@@ -42,8 +42,13 @@ pub enum ListingAction {
     SetThreaded,
     SetCompact,
     SetConversations,
+    ToggleThreads,
+    CollapseAll,
+    ExpandAll,
     Search(String),
     Select(String),
+    SelectAll,
+    DeselectAll,
     SetSeen,
     SetUnseen,
     CopyTo(MailboxPath),
@@ -53,13 +58,24 @@ pub enum ListingAction {
     Import(PathBuf, MailboxPath),
     Delete,
     OpenInNewTab,
+    Refresh,
     Tag(TagAction),
     ToggleThreadSnooze,
+    /// Show only envelopes matching the filter, or all of them if `None`.
+    FilterFlags(Option<crate::conf::FlagFilter>),
+    /// Reverse the most recent deletion or move in the current account.
+    Undo,
+    /// Pipe to the account's `spam_command` and move to its junk mailbox.
+    ReportSpam,
+    /// Pipe to the account's `ham_command` and move to its inbox.
+    ReportHam,
 }
 
 #[derive(Debug)]
 pub enum TabAction {
     Close,
+    /// Close the non-pinned tabs to the right of the current one.
+    CloseRight,
     Kill(Uuid),
     New(Option<Box<dyn Component>>),
 }
@@ -74,8 +90,12 @@ pub enum MailingListAction {
 #[derive(Debug)]
 pub enum ViewAction {
     Pipe(String, Vec<String>),
+    PipeEnvelope(String, Vec<String>),
     SaveAttachment(usize, String),
     ExportMail(String),
+    Reply,
+    ReplyToAll,
+    Forward,
 }
 
 #[derive(Debug)]
@@ -122,6 +142,9 @@ pub enum Action {
     AccountAction(AccountName, AccountAction),
     PrintSetting(String),
     ToggleMouse,
+    SetTheme(String),
+    /// Open the e-mail or mbox file at the given path in new tabs.
+    ViewFile(PathBuf),
     Quit,
 }
 
@@ -142,6 +165,8 @@ impl Action {
             Action::AccountAction(_, _) => false,
             Action::PrintSetting(_) => false,
             Action::ToggleMouse => false,
+            Action::SetTheme(_) => false,
+            Action::ViewFile(_) => false,
             Action::Quit => true,
         }
     }
@@ -20,7 +20,7 @@
  */
 
 use std::fs::OpenOptions;
-use std::io::{Read, Write};
+use std::io::{Read, Seek, SeekFrom, Write};
 use std::sync::{Arc, Mutex};
 
 thread_local!(static CMD_HISTORY_FILE: Arc<Mutex<std::fs::File>> = Arc::new(Mutex::new({
@@ -31,19 +31,123 @@ OpenOptions::new().append(true) /* writes will append to a file instead of overw
                          .open(data_dir.place_data_file("cmd_history").unwrap()).unwrap()
 })));
 
-pub fn log_cmd(mut cmd: String) {
-    CMD_HISTORY_FILE.with(|f| {
+/// Appends `cmd` to `history` unless it is the same as the last entry, then drops the oldest
+/// entries beyond `max`. Returns false if `cmd` was not recorded.
+pub fn push_history(history: &mut Vec<String>, cmd: String, max: usize) -> bool {
+    if history.last() == Some(&cmd) {
+        return false;
+    }
+    history.push(cmd);
+    if history.len() > max {
+        history.drain(..history.len() - max);
+    }
+    true
+}
+
+/// Collapses consecutive duplicates of the history file contents and keeps the last `max`
+/// entries.
+fn parse_history(contents: &str, max: usize) -> Vec<String> {
+    let mut ret = Vec::new();
+    for l in contents.lines() {
+        push_history(&mut ret, l.to_string(), max);
+    }
+    ret
+}
+
+fn log_cmd_to_file(f: &mut std::fs::File, cmd: String, max: usize) -> std::io::Result<()> {
+    let mut contents = String::new();
+    f.seek(SeekFrom::Start(0))?;
+    f.read_to_string(&mut contents)?;
+    let old_len = contents.lines().count();
+    let mut history = parse_history(&contents, max);
+    let prev_len = history.len();
+    if !push_history(&mut history, cmd, max) {
+        return Ok(());
+    }
+    if old_len == prev_len && history.len() == prev_len + 1 {
+        /* Nothing was trimmed, so the file only needs the new entry. */
+        let mut cmd = history.pop().unwrap();
         cmd.push('\n');
-        f.lock().unwrap().write_all(cmd.as_bytes()).unwrap();
+        return f.write_all(cmd.as_bytes());
+    }
+    let mut contents = history.join("\n");
+    contents.push('\n');
+    f.set_len(0)?;
+    f.write_all(contents.as_bytes())
+}
+
+/// Persists `cmd` to the history file, keeping at most `max` entries.
+pub fn log_cmd(cmd: String, max: usize) {
+    CMD_HISTORY_FILE.with(|f| {
+        if let Err(err) = log_cmd_to_file(&mut f.lock().unwrap(), cmd, max) {
+            debug!("Could not write to command history file: {}", err);
+        }
     });
 }
 
-pub fn old_cmd_history() -> Vec<String> {
+/// Returns the last `max` entries of the history file.
+pub fn old_cmd_history(max: usize) -> Vec<String> {
     let mut ret = Vec::new();
     CMD_HISTORY_FILE.with(|f| {
         let mut old_history = String::new();
-        f.lock().unwrap().read_to_string(&mut old_history).unwrap();
-        ret.extend(old_history.lines().map(|s| s.to_string()));
+        let mut f = f.lock().unwrap();
+        if f.seek(SeekFrom::Start(0)).is_ok() && f.read_to_string(&mut old_history).is_ok() {
+            ret = parse_history(&old_history, max);
+        }
     });
     ret
 }
+
+#[test]
+fn test_cmd_history_trim() {
+    let mut history = Vec::new();
+    for i in 0..5 {
+        assert!(push_history(&mut history, format!("cmd {}", i), 3));
+    }
+    assert_eq!(history, vec!["cmd 2", "cmd 3", "cmd 4"]);
+
+    let dir = melib::TempDir::new("cmd-history");
+    let path = dir.join("cmd_history");
+    let mut f = OpenOptions::new()
+        .append(true)
+        .create(true)
+        .read(true)
+        .open(&path)
+        .unwrap();
+    for i in 0..5 {
+        log_cmd_to_file(&mut f, format!("cmd {}", i), 3).unwrap();
+    }
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "cmd 2\ncmd 3\ncmd 4\n"
+    );
+    /* A file written before the cap existed is trimmed on the next write */
+    log_cmd_to_file(&mut f, "cmd 5".to_string(), 2).unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "cmd 4\ncmd 5\n");
+}
+
+#[test]
+fn test_cmd_history_dedup() {
+    let mut history = vec!["open 1".to_string()];
+    assert!(!push_history(&mut history, "open 1".to_string(), 10));
+    assert!(push_history(&mut history, "open 2".to_string(), 10));
+    /* Only consecutive duplicates are collapsed */
+    assert!(push_history(&mut history, "open 1".to_string(), 10));
+    assert_eq!(history, vec!["open 1", "open 2", "open 1"]);
+    assert_eq!(
+        parse_history("a\na\nb\nb\nb\na\n", 10),
+        vec!["a".to_string(), "b".to_string(), "a".to_string()]
+    );
+
+    let dir = melib::TempDir::new("cmd-dedup");
+    let path = dir.join("cmd_history");
+    let mut f = OpenOptions::new()
+        .append(true)
+        .create(true)
+        .read(true)
+        .open(&path)
+        .unwrap();
+    log_cmd_to_file(&mut f, "sort date".to_string(), 10).unwrap();
+    log_cmd_to_file(&mut f, "sort date".to_string(), 10).unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "sort date\n");
+}
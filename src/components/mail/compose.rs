@@ -21,16 +21,17 @@
 
 use super::*;
 use melib::email::attachment_types::{ContentType, MultipartType};
+use melib::email::compose::forward_subject;
 use melib::list_management;
 use melib::Draft;
 
 use crate::conf::accounts::JobRequest;
 use crate::jobs::JoinHandle;
 use crate::terminal::embed::EmbedGrid;
-use indexmap::IndexSet;
 use nix::sys::wait::WaitStatus;
 use std::convert::TryInto;
 use std::future::Future;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use std::str::FromStr;
 use std::sync::{Arc, Mutex};
@@ -95,6 +96,13 @@ pub struct Composer {
     dirty: bool,
     has_changes: bool,
     initialized: bool,
+    /// Identifies the autosave file of this draft.
+    autosave_id: Uuid,
+    autosave_timer: Option<crate::jobs::Timer>,
+    /// Contents of the last autosave, to skip writing unchanged drafts.
+    last_autosave: Option<String>,
+    /// The account's signature, see [`Composer::toggle_signature`].
+    signature: Option<String>,
     id: ComponentId,
 }
 
@@ -165,15 +173,17 @@ impl Composer {
             embed_area: ((0, 0), (0, 0)),
             embed: None,
             initialized: false,
+            autosave_id: Uuid::new_v4(),
+            autosave_timer: None,
+            last_autosave: None,
+            signature: None,
             id: ComponentId::new_v4(),
         }
     }
 
     pub fn with_account(account_hash: AccountHash, context: &Context) -> Self {
-        let mut ret = Composer {
-            account_hash,
-            ..Composer::new(context)
-        };
+        let mut ret = Composer::new(context);
+        ret.account_hash = account_hash;
         for (h, v) in
             account_settings!(context[account_hash].composing.default_header_values).iter()
         {
@@ -188,9 +198,86 @@ impl Composer {
                 format!("meli {}", option_env!("CARGO_PKG_VERSION").unwrap_or("0.0")),
             );
         }
+        ret.set_signature(signature(account_hash, context));
+        let autosave_interval =
+            *account_settings!(context[account_hash].composing.autosave_interval);
+        if autosave_interval > 0 {
+            let interval = std::time::Duration::from_secs(autosave_interval);
+            ret.autosave_timer = Some(
+                context
+                    .job_executor
+                    .clone()
+                    .create_timer(interval, interval),
+            );
+        }
         ret
     }
 
+    /// Opens an autosaved draft found by [`orphaned_autosaves`]. Further autosaves overwrite the
+    /// same file.
+    pub fn restore_autosave(
+        account_hash: AccountHash,
+        path: &Path,
+        context: &Context,
+    ) -> Result<Self> {
+        let mut ret = Composer::with_account(account_hash, context);
+        let contents = std::fs::read_to_string(path)?;
+        ret.set_draft(Draft::from_str(&contents)?);
+        if let Some(autosave_id) = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .and_then(|n| Uuid::parse_str(n).ok())
+        {
+            ret.autosave_id = autosave_id;
+        }
+        ret.last_autosave = Some(contents);
+        ret.has_changes = true;
+        Ok(ret)
+    }
+
+    fn autosave_path(&self, context: &Context) -> Result<PathBuf> {
+        let data_dir =
+            xdg::BaseDirectories::with_profile("meli", context.accounts[&self.account_hash].name())
+                .map_err(|err| MeliError::new(err.to_string()))?;
+        Ok(data_dir.place_data_file(format!("drafts/{}", self.autosave_id))?)
+    }
+
+    fn autosave(&mut self, context: &mut Context) {
+        if !self.has_changes {
+            return;
+        }
+        self.update_draft();
+        let contents = match self.draft.to_string() {
+            Ok(contents) => contents,
+            Err(_) => return,
+        };
+        if self.last_autosave.as_ref() == Some(&contents) {
+            return;
+        }
+        if let Err(err) = self
+            .autosave_path(context)
+            .and_then(|path| Ok(std::fs::write(path, &contents)?))
+        {
+            context
+                .replies
+                .push_back(UIEvent::StatusEvent(StatusEvent::DisplayMessage(format!(
+                    "Could not autosave draft: {}",
+                    err
+                ))));
+            return;
+        }
+        self.last_autosave = Some(contents);
+    }
+
+    /// Removes the autosave file of this draft, if any was written.
+    fn remove_autosave(&mut self, context: &Context) {
+        if self.last_autosave.take().is_some() {
+            if let Ok(path) = self.autosave_path(context) {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+    }
+
     pub fn edit(
         account_hash: AccountHash,
         env_hash: EnvelopeHash,
@@ -215,122 +302,53 @@ impl Composer {
         let mut ret = Composer::with_account(coordinates.0, context);
         let account = &context.accounts[&coordinates.0];
         let envelope = account.collection.get_env(coordinates.2);
-        let subject = envelope.subject();
-        ret.draft.set_header(
-            "Subject",
-            if !subject.starts_with("Re: ") {
-                format!("Re: {}", subject)
-            } else {
-                subject.into()
-            },
-        );
-        ret.draft.set_header(
-            "References",
-            format!(
-                "{} {}",
-                envelope
-                    .references()
-                    .iter()
-                    .fold(String::new(), |mut acc, x| {
-                        if !acc.is_empty() {
-                            acc.push(' ');
-                        }
-                        acc.push_str(&x.to_string());
-                        acc
-                    }),
-                envelope.message_id_display()
-            ),
-        );
-        ret.draft
-            .set_header("In-Reply-To", envelope.message_id_display().into());
-
-        // "Mail-Followup-To/(To+Cc+(Mail-Reply-To/Reply-To/From)) for follow-up,
-        // Mail-Reply-To/Reply-To/From for reply-to-author."
-        // source: https://cr.yp.to/proto/replyto.html
-        if reply_to_all {
-            let mut to = IndexSet::new();
-
-            if let Some(actions) = list_management::ListActions::detect(&envelope) {
-                if let Some(post) = actions.post {
-                    if let list_management::ListAction::Email(list_post_addr) = post[0] {
-                        if let Ok(list_address) =
-                            melib::email::parser::generic::mailto(list_post_addr)
-                                .map(|(_, m)| m.address)
-                        {
-                            to.insert(list_address);
-                        }
-                    }
-                }
-            }
-            if let Some(reply_to) = envelope
-                .other_headers()
-                .get("Mail-Followup-To")
-                .and_then(|v| v.as_str().try_into().ok())
-            {
-                to.insert(reply_to);
-            } else {
-                if let Some(reply_to) = envelope
-                    .other_headers()
-                    .get("Reply-To")
-                    .and_then(|v| v.as_str().try_into().ok())
-                {
-                    to.insert(reply_to);
-                } else {
-                    to.extend(envelope.from().iter().cloned());
-                }
-            }
-            to.extend(envelope.to().iter().cloned());
-            if let Some(ours) = TryInto::<Address>::try_into(
+        let ours = if reply_to_all {
+            TryInto::<Address>::try_into(
                 crate::components::mail::get_display_name(context, coordinates.0).as_str(),
             )
             .ok()
-            {
-                to.remove(&ours);
-            }
-            ret.draft.set_header("To", {
-                let mut ret: String =
-                    to.into_iter()
-                        .fold(String::new(), |mut s: String, n: Address| {
-                            s.extend(n.to_string().chars());
-                            s.push_str(", ");
-                            s
-                        });
-                ret.pop();
-                ret.pop();
-                ret
-            });
-            ret.draft.set_header("Cc", envelope.field_cc_to_string());
         } else {
-            if let Some(reply_to) = envelope.other_headers().get("Mail-Reply-To") {
-                ret.draft.set_header("To", reply_to.to_string());
-            } else if let Some(reply_to) = envelope.other_headers().get("Reply-To") {
-                ret.draft.set_header("To", reply_to.to_string());
-            } else {
-                ret.draft.set_header("To", envelope.field_from_to_string());
+            None
+        };
+        let reply = Draft::new_reply(&envelope, bytes, reply_to_all, ours.as_ref());
+        /* Keep the account's headers, e.g. `From`, unless the reply sets them */
+        for (header, value) in reply.headers().iter() {
+            if !value.is_empty() {
+                ret.draft
+                    .headers_mut()
+                    .insert(header.clone(), value.clone());
             }
         }
-        let body = envelope.body_bytes(bytes);
-        ret.draft.body = {
-            let reply_body_bytes = decode_rec(&body, None);
-            let reply_body = String::from_utf8_lossy(&reply_body_bytes);
-            let mut ret = format!(
-                "On {} {} wrote:\n",
-                envelope.date_as_str(),
-                envelope.from()[0],
-            );
-            for l in reply_body.lines() {
-                ret.push('>');
-                ret.push_str(l);
-                ret.push('\n');
-            }
-            ret
-        };
+        ret.draft.body = reply.body;
+        if let Some(ref signature) = ret.signature {
+            append_signature(&mut ret.draft.body, signature);
+        }
 
         ret.account_hash = coordinates.0;
         ret.reply_context = Some((coordinates.1, coordinates.2));
         ret
     }
 
+    pub fn forward(
+        coordinates: (AccountHash, MailboxHash, EnvelopeHash),
+        bytes: &[u8],
+        context: &mut Context,
+    ) -> Self {
+        let mut ret = Composer::with_account(coordinates.0, context);
+        let as_attachment =
+            *account_settings!(context[coordinates.0].composing.forward_as_attachment);
+        let envelope = context.accounts[&coordinates.0]
+            .collection
+            .get_env(coordinates.2);
+        forward_draft(&mut ret.draft, &envelope, bytes, as_attachment);
+        if let Some(ref signature) = ret.signature {
+            if !as_attachment {
+                append_signature(&mut ret.draft.body, signature);
+            }
+        }
+        ret
+    }
+
     pub fn reply_to_select(
         coordinates: (AccountHash, MailboxHash, EnvelopeHash),
         bytes: &[u8],
@@ -395,6 +413,28 @@ impl Composer {
         Composer::reply_to(coordinates, bytes, context, true)
     }
 
+    /// Sets the signature of the draft and appends it to the body.
+    fn set_signature(&mut self, signature: Option<String>) {
+        if let Some(ref signature) = signature {
+            append_signature(&mut self.draft.body, signature);
+        }
+        self.signature = signature;
+    }
+
+    /// Removes the signature block from the end of the body, or appends it if it isn't there.
+    fn toggle_signature(&mut self) {
+        if let Some(signature) = self.signature.clone() {
+            /* The form is rebuilt from the draft's headers, keep their edits */
+            self.update_draft();
+            if !strip_signature(&mut self.draft.body, &signature) {
+                append_signature(&mut self.draft.body, &signature);
+            }
+            self.has_changes = true;
+            self.initialized = false;
+            self.set_dirty(true);
+        }
+    }
+
     pub fn set_draft(&mut self, draft: Draft) {
         self.draft = draft;
         self.update_form();
@@ -440,17 +480,7 @@ impl Composer {
                             .accounts
                             .values()
                             .map(|acc| {
-                                let addr = if let Some(display_name) =
-                                    acc.settings.account.display_name()
-                                {
-                                    format!(
-                                        "{} <{}>",
-                                        display_name,
-                                        acc.settings.account.identity()
-                                    )
-                                } else {
-                                    acc.settings.account.identity().to_string()
-                                };
+                                let addr = acc.settings.account.make_display_name();
                                 let desc =
                                     match account_settings!(c[acc.hash()].composing.send_mail) {
                                         crate::conf::composing::SendMail::ShellCommand(ref cmd) => {
@@ -640,6 +670,14 @@ impl Composer {
     }
 }
 
+impl Drop for Composer {
+    fn drop(&mut self) {
+        if let Some(ref timer) = self.autosave_timer {
+            timer.disable();
+        }
+    }
+}
+
 impl Component for Composer {
     fn draw(&mut self, grid: &mut CellBuffer, area: Area, context: &mut Context) {
         let upper_left = upper_left!(area);
@@ -1026,6 +1064,7 @@ impl Component for Composer {
                     match key {
                         'x' => {
                             context.replies.push_back(UIEvent::Action(Tab(Kill(*u))));
+                            self.remove_autosave(context);
                             return true;
                         }
                         'n' => {}
@@ -1038,6 +1077,7 @@ impl Component for Composer {
                                 self.account_hash,
                             );
                             context.replies.push_back(UIEvent::Action(Tab(Kill(*u))));
+                            self.remove_autosave(context);
                             return true;
                         }
                         _ => {}
@@ -1097,6 +1137,7 @@ impl Component for Composer {
                     context
                         .replies
                         .push_back(UIEvent::Action(Tab(Kill(self.id))));
+                    self.remove_autosave(context);
                 }
                 return true;
             }
@@ -1149,6 +1190,16 @@ impl Component for Composer {
             UIEvent::Resize => {
                 self.set_dirty(true);
             }
+            UIEvent::Timer(id)
+                if self
+                    .autosave_timer
+                    .as_ref()
+                    .map(|t| t.id() == id)
+                    .unwrap_or(false) =>
+            {
+                self.autosave(context);
+                return true;
+            }
             /*
             /* Switch e-mail From: field to the `left` configured account. */
             UIEvent::Input(Key::Left) if self.cursor == Cursor::From => {
@@ -1218,6 +1269,13 @@ impl Component for Composer {
                 };
                 self.dirty = true;
             }
+            UIEvent::Input(ref key)
+                if shortcut!(key == shortcuts[Self::DESCRIPTION]["toggle_signature"])
+                    && self.mode.is_edit() =>
+            {
+                self.toggle_signature();
+                return true;
+            }
             UIEvent::Input(ref key)
                 if shortcut!(key == shortcuts[Self::DESCRIPTION]["send_mail"])
                     && self.mode.is_edit() =>
@@ -1554,9 +1612,21 @@ impl Component for Composer {
                     .stdout(Stdio::inherit())
                     .spawn()
                 {
-                    Ok(mut child) => {
-                        let _ = child.wait();
-                    }
+                    Ok(mut child) => match child.wait() {
+                        Ok(status) if !status.success() => {
+                            /* Treat a failed editor as a cancelled edit and keep the draft as
+                             * it was */
+                            context.replies.push_back(UIEvent::Notification(
+                                None,
+                                format!("{} has exited with {}, draft unchanged", editor, status),
+                                Some(NotificationType::Error(melib::error::ErrorKind::External)),
+                            ));
+                            context.replies.push_back(UIEvent::Fork(ForkType::Finished));
+                            self.dirty = true;
+                            return true;
+                        }
+                        _ => {}
+                    },
                     Err(err) => {
                         context.replies.push_back(UIEvent::Notification(
                             Some(format!("Failed to execute {}: {}", editor, err)),
@@ -1911,6 +1981,77 @@ impl Component for Composer {
     }
 }
 
+/// Fills in a draft forwarding `envelope`, whose source is `bytes`: either attached as a
+/// `message/rfc822` part or quoted in the body.
+fn forward_draft(draft: &mut Draft, envelope: &Envelope, bytes: &[u8], as_attachment: bool) {
+    draft.set_header("Subject", forward_subject(&envelope.subject()));
+    if as_attachment {
+        let mut attachment = AttachmentBuilder::default();
+        attachment
+            .set_raw(bytes.to_vec())
+            .set_body_to_raw()
+            .set_content_type(ContentType::MessageRfc822);
+        draft.attachments_mut().push(attachment);
+    } else {
+        let body = decode_rec(&envelope.body_bytes(bytes), None);
+        draft.body = format!(
+            "\n---------- Forwarded message ----------\nFrom: {}\nDate: {}\nSubject: {}\nTo: {}\n\n{}",
+            envelope.field_from_to_string(),
+            envelope.date_as_str(),
+            envelope.subject(),
+            envelope.field_to_to_string(),
+            String::from_utf8_lossy(&body)
+        );
+    }
+}
+
+/// Returns the signature configured for the account, reading `signature_file` if it is set.
+fn signature(account_hash: AccountHash, context: &Context) -> Option<String> {
+    if let Some(path) = account_settings!(context[account_hash].composing.signature_file).as_ref() {
+        match std::fs::read_to_string(Path::new(path).expand()) {
+            Ok(signature) => return Some(signature),
+            Err(err) => {
+                log(
+                    format!("Could not read signature file {}: {}", path, err),
+                    ERROR,
+                );
+            }
+        }
+    }
+    account_settings!(context[account_hash].composing.signature).clone()
+}
+
+/// The block appended to a body for `signature`: the `-- ` delimiter line and the signature.
+fn signature_block(signature: &str) -> String {
+    format!("\n-- \n{}\n", signature.trim_end_matches('\n'))
+}
+
+fn append_signature(body: &mut String, signature: &str) {
+    body.push_str(&signature_block(signature));
+}
+
+/// Removes the block added by [`append_signature`] from the end of `body`, if it is there.
+fn strip_signature(body: &mut String, signature: &str) -> bool {
+    let block = signature_block(signature);
+    if body.ends_with(&block) {
+        body.truncate(body.len() - block.len());
+        true
+    } else {
+        false
+    }
+}
+
+/// Returns the `From:` address of `draft`, which is required to send it.
+fn draft_from_address(draft: &Draft) -> Result<Address> {
+    match draft.headers().get("From").map(|f| f.trim()) {
+        None | Some("") => Err(MeliError::new(
+            "No From: address; set `identity` for this account or the EMAIL environment variable.",
+        )),
+        Some(from) => TryInto::<Address>::try_into(from)
+            .map_err(|err| MeliError::new(format!("Invalid From: address `{}`: {}", from, err))),
+    }
+}
+
 pub fn send_draft(
     _sign_mail: ToggleFlag,
     context: &mut Context,
@@ -1920,6 +2061,7 @@ pub fn send_draft(
     flags: Flag,
     complete_in_background: bool,
 ) -> Result<Option<JoinHandle<Result<()>>>> {
+    draft_from_address(&draft)?;
     let format_flowed = *account_settings!(context[account_hash].composing.format_flowed);
     /*    if sign_mail.is_true() {
         let mut content_type = ContentType::default();
@@ -2050,6 +2192,83 @@ pub fn save_draft(
     }
 }
 
+/// Lists autosaved drafts left behind by a previous session, e.g. after a crash.
+pub fn orphaned_autosaves(context: &Context) -> Vec<(AccountHash, PathBuf)> {
+    let mut ret = vec![];
+    for (account_hash, account) in context.accounts.iter() {
+        if let Ok(data_dir) = xdg::BaseDirectories::with_profile("meli", account.name()) {
+            ret.extend(
+                data_dir
+                    .list_data_files("drafts")
+                    .into_iter()
+                    .map(|path| (*account_hash, path)),
+            );
+        }
+    }
+    ret
+}
+
+/// Offers to reopen orphaned autosaved drafts in new compose tabs. Drafts that are not selected
+/// are left on disk and offered again on the next startup.
+pub fn restore_autosaves_dialog(context: &Context) -> Option<UIEvent> {
+    let autosaves = orphaned_autosaves(context);
+    if autosaves.is_empty() {
+        return None;
+    }
+    let entries = autosaves
+        .into_iter()
+        .map(|(account_hash, path)| {
+            let subject = std::fs::read_to_string(&path)
+                .ok()
+                .and_then(|contents| Draft::from_str(&contents).ok())
+                .map(|draft| draft.headers()["Subject"].to_string())
+                .unwrap_or_default();
+            let label = format!(
+                "{}: {}",
+                context.accounts[&account_hash].name(),
+                if subject.is_empty() {
+                    "(no subject)"
+                } else {
+                    subject.as_str()
+                }
+            );
+            ((account_hash, path), label, true)
+        })
+        .collect();
+    let dialog: UIDialog<(AccountHash, PathBuf)> = Selector::new_with_defaults(
+        "restore autosaved drafts?",
+        entries,
+        false,
+        Some(Box::new(
+            move |_id: ComponentId, results: &[(AccountHash, PathBuf)]| {
+                let results = results.to_vec();
+                Some(UIEvent::Callback(CallbackFn(Box::new(
+                    move |context: &mut Context| {
+                        for (account_hash, path) in results {
+                            match Composer::restore_autosave(account_hash, &path, context) {
+                                Ok(composer) => {
+                                    context.replies.push_back(UIEvent::Action(Tab(New(Some(
+                                        Box::new(composer),
+                                    )))));
+                                }
+                                Err(err) => {
+                                    context.replies.push_back(UIEvent::Notification(
+                                        Some("Could not restore autosaved draft".into()),
+                                        err.to_string(),
+                                        Some(NotificationType::Error(err.kind)),
+                                    ));
+                                }
+                            }
+                        }
+                    },
+                ))))
+            },
+        )),
+        context,
+    );
+    Some(UIEvent::GlobalUIDialog(Box::new(dialog)))
+}
+
 pub fn send_draft_async(
     #[cfg(feature = "gpgme")] gpg_state: gpg::GpgComposeState,
     context: &mut Context,
@@ -2058,6 +2277,7 @@ pub fn send_draft_async(
     mailbox_type: SpecialUsageMailbox,
     flags: Flag,
 ) -> Result<Pin<Box<dyn Future<Output = Result<()>> + Send>>> {
+    draft_from_address(&draft)?;
     let format_flowed = *account_settings!(context[account_hash].composing.format_flowed);
     let event_sender = context.sender.clone();
     #[cfg(feature = "gpgme")]
@@ -2151,3 +2371,86 @@ pub fn send_draft_async(
         ret
     }))
 }
+
+#[test]
+fn test_compose_draft_file_round_trip() {
+    let mut draft = Draft::default();
+    draft
+        .set_header("To", "Jane Doe <jane@example.com>".to_string())
+        .set_header("Subject", "Καλημέρα".to_string())
+        .set_body("First line\n\n> quoted\nLast line\n".to_string());
+    let f = create_temp_file(draft.to_string().unwrap().as_bytes(), None, None, true);
+    assert_eq!(Draft::from_str(&f.read_to_string()).unwrap(), draft);
+}
+
+#[test]
+fn test_compose_signature() {
+    let context = Context::new_mock();
+    let mut composer = Composer::new(&context);
+    composer.set_signature(Some("Jane Doe\nhttps://example.com\n".to_string()));
+    assert_eq!(
+        composer.draft.body(),
+        "\n-- \nJane Doe\nhttps://example.com\n"
+    );
+
+    composer.draft.body = format!("Hello,\n\nsee you.\n{}", composer.draft.body());
+    composer.toggle_signature();
+    assert_eq!(composer.draft.body(), "Hello,\n\nsee you.\n");
+    composer.toggle_signature();
+    assert!(composer
+        .draft
+        .body()
+        .ends_with("\n-- \nJane Doe\nhttps://example.com\n"));
+    composer.toggle_signature();
+    assert_eq!(composer.draft.body(), "Hello,\n\nsee you.\n");
+
+    let mut body = "Hi\n-- \nJane Doe\nsomething else\n".to_string();
+    assert!(!strip_signature(&mut body, "Jane Doe"));
+    assert_eq!(body, "Hi\n-- \nJane Doe\nsomething else\n");
+}
+
+#[test]
+fn test_forward_drafts() {
+    let envelope = Envelope::from_bytes(
+        b"From: Alice <alice@example.com>\nTo: bob@example.com, me@example.com\nCc: carol@example.com\nReply-To: alice-reply@example.com\nSubject: Lunch\nMessage-ID: <1@example.com>\nDate: Thu, 01 Oct 2020 10:00:00 +0000\n\nNoon?\n",
+        None,
+    )
+    .unwrap();
+    let bytes = b"From: Alice <alice@example.com>\nTo: bob@example.com, me@example.com\nCc: carol@example.com\nReply-To: alice-reply@example.com\nSubject: Lunch\nMessage-ID: <1@example.com>\nDate: Thu, 01 Oct 2020 10:00:00 +0000\n\nNoon?\n";
+
+    /* Forwarding as an attachment leaves the recipients empty */
+    let mut draft = Draft::default();
+    forward_draft(&mut draft, &envelope, bytes, true);
+    assert_eq!(&draft.headers()["Subject"], "Fwd: Lunch");
+    assert_eq!(&draft.headers()["To"], "");
+    assert_eq!(draft.attachments().len(), 1);
+    assert_eq!(
+        draft.attachments()[0].content_type(),
+        &ContentType::MessageRfc822
+    );
+    assert_eq!(draft.attachments()[0].raw(), &bytes[..]);
+
+    /* Forwarding inline quotes the message in the body */
+    let mut draft = Draft::default();
+    forward_draft(&mut draft, &envelope, bytes, false);
+    assert_eq!(&draft.headers()["Subject"], "Fwd: Lunch");
+    assert!(draft.attachments().is_empty());
+    assert!(draft.body().starts_with(
+        "\n---------- Forwarded message ----------\nFrom: Alice <alice@example.com>\n"
+    ));
+    assert!(draft
+        .body()
+        .ends_with("Subject: Lunch\nTo: bob@example.com, me@example.com\n\nNoon?\n"));
+}
+
+#[test]
+fn test_draft_from_address() {
+    let mut draft = Draft::default();
+    draft.set_header("From", String::new());
+    assert!(draft_from_address(&draft).is_err());
+    draft.set_header("From", "Jane Doe <jane@example.com>".to_string());
+    assert_eq!(
+        draft_from_address(&draft).unwrap().get_email(),
+        "jane@example.com"
+    );
+}
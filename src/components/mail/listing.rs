@@ -20,7 +20,7 @@
  */
 
 use super::*;
-use crate::conf::accounts::JobRequest;
+use crate::conf::accounts::{JobRequest, MailboxStatus};
 use crate::types::segment_tree::SegmentTree;
 use melib::backends::EnvelopeHashBatch;
 use smallvec::SmallVec;
@@ -153,6 +153,23 @@ struct AccountMenuEntry {
     entries: SmallVec<[(usize, u32, bool, MailboxHash); 16]>,
 }
 
+/// Returns the selected entries, or `cursor` if nothing is selected.
+pub fn selection_or_cursor<K: Copy + Eq + std::hash::Hash>(
+    selection: &HashMap<K, bool>,
+    cursor: K,
+) -> SmallVec<[K; 8]> {
+    let ret: SmallVec<[K; 8]> = selection
+        .iter()
+        .filter(|(_, v)| **v)
+        .map(|(k, _)| *k)
+        .collect();
+    if ret.is_empty() {
+        smallvec::smallvec![cursor]
+    } else {
+        ret
+    }
+}
+
 pub trait MailListingTrait: ListingTrait {
     fn perform_action(
         &mut self,
@@ -255,24 +272,25 @@ pub trait MailListingTrait: ListingTrait {
                 }
             }
             ListingAction::Delete => {
-                let job = account
-                    .backend
-                    .write()
-                    .unwrap()
-                    .delete_messages(env_hashes.clone(), mailbox_hash);
-                match job {
-                    Err(err) => {
-                        context.replies.push_back(UIEvent::StatusEvent(
-                            StatusEvent::DisplayMessage(err.to_string()),
-                        ));
-                    }
-                    Ok(fut) => {
-                        let handle = account.job_executor.spawn_specialized(fut);
-                        account.insert_job(
-                            handle.job_id,
-                            JobRequest::DeleteMessages { env_hashes, handle },
-                        );
-                    }
+                if let Err(err) = account.delete_messages(env_hashes, mailbox_hash) {
+                    context
+                        .replies
+                        .push_back(UIEvent::StatusEvent(StatusEvent::DisplayMessage(
+                            err.to_string(),
+                        )));
+                }
+            }
+            ListingAction::ReportSpam | ListingAction::ReportHam => {
+                if let Err(err) = account.report_messages(
+                    env_hashes,
+                    mailbox_hash,
+                    matches!(a, ListingAction::ReportSpam),
+                ) {
+                    context
+                        .replies
+                        .push_back(UIEvent::StatusEvent(StatusEvent::DisplayMessage(
+                            err.to_string(),
+                        )));
                 }
             }
             ListingAction::CopyTo(ref mailbox_path) => {
@@ -313,33 +331,22 @@ pub trait MailListingTrait: ListingTrait {
                     )));
             }
             ListingAction::MoveTo(ref mailbox_path) => {
-                match account
-                    .mailbox_by_path(mailbox_path)
-                    .and_then(|destination_mailbox_hash| {
-                        account.backend.write().unwrap().copy_messages(
-                            env_hashes,
-                            mailbox_hash,
-                            destination_mailbox_hash,
-                            /* move? */ true,
-                        )
-                    }) {
-                    Err(err) => {
-                        context.replies.push_back(UIEvent::StatusEvent(
-                            StatusEvent::DisplayMessage(err.to_string()),
-                        ));
-                    }
-                    Ok(fut) => {
-                        let handle = account.job_executor.spawn_specialized(fut);
-                        account.insert_job(
-                            handle.job_id,
-                            JobRequest::Generic {
-                                name: "message moving".into(),
-                                handle,
-                                on_finish: None,
-                                logging_level: melib::LoggingLevel::INFO,
-                            },
-                        );
-                    }
+                if let Err(err) =
+                    account
+                        .mailbox_by_path(mailbox_path)
+                        .and_then(|destination_mailbox_hash| {
+                            account.move_messages(
+                                env_hashes,
+                                mailbox_hash,
+                                destination_mailbox_hash,
+                            )
+                        })
+                {
+                    context
+                        .replies
+                        .push_back(UIEvent::StatusEvent(StatusEvent::DisplayMessage(
+                            err.to_string(),
+                        )));
                 }
             }
             ListingAction::MoveToOtherAccount(ref _account_name, ref _mailbox_path) => {
@@ -377,6 +384,7 @@ pub trait MailListingTrait: ListingTrait {
 pub trait ListingTrait: Component {
     fn coordinates(&self) -> (AccountHash, MailboxHash);
     fn set_coordinates(&mut self, _: (AccountHash, MailboxHash));
+    fn set_sort(&mut self, _: (SortField, SortOrder)) {}
     fn draw_list(&mut self, grid: &mut CellBuffer, area: Area, context: &mut Context);
     fn highlight_line(&mut self, grid: &mut CellBuffer, area: Area, idx: usize, context: &Context);
     fn filter(
@@ -393,6 +401,10 @@ pub trait ListingTrait: Component {
         None
     }
     fn set_movement(&mut self, mvm: PageMovement);
+    /// Row of the selected entry, used to tell which page of the mailbox is visible.
+    fn cursor_row(&self) -> usize {
+        0
+    }
 }
 
 #[derive(Debug)]
@@ -487,6 +499,10 @@ pub struct Listing {
     ratio: usize, // right/(container width) * 100
     menu_width: WidgetWidth,
     focus: ListingFocus,
+    /// Id of the open `go_to_mailbox` picker, if any.
+    mailbox_picker: Option<ComponentId>,
+    /// Mailbox and last row already requested with `Account::fetch_range`.
+    prefetched: Option<(MailboxHash, usize)>,
 }
 
 impl fmt::Display for Listing {
@@ -586,11 +602,23 @@ impl Component for Listing {
             self.component
                 .draw(grid, (set_x(upper_left, mid + 1), bottom_right), context);
         }
+        if right_component_width != 0 {
+            self.prefetch_page(get_y(bottom_right) - get_y(upper_left) + 1, context);
+        }
         self.dirty = false;
     }
 
     fn process_event(&mut self, event: &mut UIEvent, context: &mut Context) -> bool {
         match event {
+            UIEvent::FinishedUIDialog(id, ref mut results) if self.mailbox_picker == Some(*id) => {
+                self.mailbox_picker = None;
+                if let Some(Some((account_hash, mailbox_hash))) =
+                    results.downcast_mut::<Option<(AccountHash, MailboxHash)>>()
+                {
+                    self.go_to_mailbox(*account_hash, *mailbox_hash, context);
+                }
+                return true;
+            }
             UIEvent::StartupCheck(ref f) => {
                 if self.component.coordinates().1 == *f {
                     if !self.startup_checks_rate.tick() {
@@ -834,6 +862,22 @@ impl Component for Listing {
                         self.component.set_style(IndexStyle::Conversations);
                         return true;
                     }
+                    Action::Listing(ListingAction::ToggleThreads) => {
+                        if let Plain(_) = self.component {
+                            let (account_hash, mailbox_hash) = self.component.coordinates();
+                            let index_style = match mailbox_settings!(
+                                context[account_hash][&mailbox_hash].listing.index_style
+                            ) {
+                                IndexStyle::Plain => IndexStyle::Threaded,
+                                style => *style,
+                            };
+                            self.component.set_style(index_style);
+                        } else {
+                            self.component.set_style(IndexStyle::Plain);
+                        }
+                        self.set_dirty(true);
+                        return true;
+                    }
                     Action::Listing(ListingAction::Import(file_path, mailbox_path)) => {
                         let account = &mut context.accounts[self.cursor_pos.0];
                         if let Err(err) = account
@@ -856,9 +900,70 @@ impl Component for Listing {
                         }
                         return true;
                     }
+                    Action::Listing(ListingAction::Refresh) => {
+                        self.refresh_selected_mailbox(context);
+                        return true;
+                    }
+                    Action::Listing(ListingAction::FilterFlags(filter)) => {
+                        let account_hash = self.accounts[self.cursor_pos.0].hash;
+                        context.accounts[&account_hash].flag_filter = *filter;
+                        self.component.refresh_mailbox(context, true);
+                        self.set_dirty(true);
+                        context
+                            .replies
+                            .push_back(UIEvent::StatusEvent(StatusEvent::Notify(
+                                match filter {
+                                    Some(FlagFilter::Unread) => "Showing only unread envelopes.",
+                                    Some(FlagFilter::Flagged) => "Showing only flagged envelopes.",
+                                    None => "Showing all envelopes.",
+                                }
+                                .to_string(),
+                            )));
+                        return true;
+                    }
+                    Action::Listing(ListingAction::Undo) => {
+                        let account = &mut context.accounts[self.cursor_pos.0];
+                        if let Some(description) = account.undo_description() {
+                            context.replies.push_back(UIEvent::StatusEvent(
+                                StatusEvent::DisplayMessage(format!("Undoing {}.", description)),
+                            ));
+                        }
+                        if let Err(err) = account.undo() {
+                            context.replies.push_back(UIEvent::StatusEvent(
+                                StatusEvent::DisplayMessage(err.to_string()),
+                            ));
+                        }
+                        return true;
+                    }
+                    Action::Listing(ListingAction::CollapseAll)
+                    | Action::Listing(ListingAction::ExpandAll) => {
+                        context.replies.push_back(UIEvent::StatusEvent(
+                            StatusEvent::DisplayMessage(
+                                "Threads can only be collapsed in the threaded listing."
+                                    .to_string(),
+                            ),
+                        ));
+                        return true;
+                    }
+                    Action::Listing(a @ ListingAction::SelectAll)
+                    | Action::Listing(a @ ListingAction::DeselectAll) => {
+                        let value = matches!(a, ListingAction::SelectAll);
+                        let mut row_updates: SmallVec<[ThreadHash; 8]> = SmallVec::new();
+                        for (k, v) in self.component.selection().iter_mut() {
+                            if *v != value {
+                                *v = value;
+                                row_updates.push(*k);
+                            }
+                        }
+                        self.component.row_updates().extend(row_updates.drain(..));
+                        self.component.set_dirty(true);
+                        return true;
+                    }
                     Action::Listing(a @ ListingAction::SetSeen)
                     | Action::Listing(a @ ListingAction::SetUnseen)
                     | Action::Listing(a @ ListingAction::Delete)
+                    | Action::Listing(a @ ListingAction::ReportSpam)
+                    | Action::Listing(a @ ListingAction::ReportHam)
                     | Action::Listing(a @ ListingAction::CopyTo(_))
                     | Action::Listing(a @ ListingAction::MoveTo(_))
                     | Action::Listing(a @ ListingAction::CopyToOtherAccount(_, _))
@@ -1007,6 +1112,12 @@ impl Component for Listing {
                     self.menu_visibility = !self.menu_visibility;
                     self.set_dirty(true);
                 }
+                UIEvent::Input(ref key)
+                    if shortcut!(key == shortcuts[Listing::DESCRIPTION]["go_to_mailbox"]) =>
+                {
+                    self.open_mailbox_picker(context);
+                    return true;
+                }
                 UIEvent::Input(ref key)
                     if shortcut!(key == shortcuts[Listing::DESCRIPTION]["search"]) =>
                 {
@@ -1027,18 +1138,25 @@ impl Component for Listing {
                     }
                 }
                 UIEvent::Input(ref key)
-                    if shortcut!(key == shortcuts[Listing::DESCRIPTION]["refresh"]) =>
+                    if shortcut!(key == shortcuts[Listing::DESCRIPTION]["report_spam"]) =>
                 {
-                    let account = &mut context.accounts[self.cursor_pos.0];
-                    if let Some(&mailbox_hash) = account.mailboxes_order.get(self.cursor_pos.1) {
-                        if let Err(err) = account.refresh(mailbox_hash) {
-                            context.replies.push_back(UIEvent::Notification(
-                                Some("Could not refresh.".to_string()),
-                                err.to_string(),
-                                Some(NotificationType::Error(err.kind)),
-                            ));
-                        }
+                    let mut event = UIEvent::Action(Action::Listing(ListingAction::ReportSpam));
+                    if self.process_event(&mut event, context) {
+                        return true;
                     }
+                }
+                UIEvent::Input(ref key)
+                    if shortcut!(key == shortcuts[Listing::DESCRIPTION]["report_ham"]) =>
+                {
+                    let mut event = UIEvent::Action(Action::Listing(ListingAction::ReportHam));
+                    if self.process_event(&mut event, context) {
+                        return true;
+                    }
+                }
+                UIEvent::Input(ref key)
+                    if shortcut!(key == shortcuts[Listing::DESCRIPTION]["refresh"]) =>
+                {
+                    self.refresh_selected_mailbox(context);
                     return true;
                 }
                 UIEvent::Input(ref key)
@@ -1076,6 +1194,12 @@ impl Component for Listing {
                     self.set_dirty(true);
                     return true;
                 }
+                UIEvent::Input(ref key)
+                    if shortcut!(key == shortcuts[Listing::DESCRIPTION]["go_to_mailbox"]) =>
+                {
+                    self.open_mailbox_picker(context);
+                    return true;
+                }
                 UIEvent::Input(ref k)
                     if shortcut!(k == shortcuts[Listing::DESCRIPTION]["open_mailbox"]) =>
                 {
@@ -1261,6 +1385,12 @@ impl Component for Listing {
                     .push_back(UIEvent::Action(Tab(New(Some(Box::new(composer))))));
                 return true;
             }
+            UIEvent::Action(Action::SetTheme(_)) => {
+                /* Listings cache the theme attributes of their rows, so rebuild the current one */
+                let coordinates = self.component.coordinates();
+                self.component = Offline(OfflineListing::new(coordinates));
+                self.change_account(context);
+            }
             UIEvent::StartupCheck(_)
             | UIEvent::MailboxUpdate(_)
             | UIEvent::EnvelopeUpdate(_)
@@ -1341,7 +1471,6 @@ impl Component for Listing {
         };
 
         let account = &context.accounts[self.cursor_pos.0];
-        use crate::conf::accounts::MailboxStatus;
         match account[&mailbox_hash].status {
             MailboxStatus::Available | MailboxStatus::Parsing(_, _) => {
                 let (unseen, total) = account[&mailbox_hash]
@@ -1349,11 +1478,11 @@ impl Component for Listing {
                     .count()
                     .ok()
                     .unwrap_or((0, 0));
-                format!(
-                    "Mailbox: {}, Messages: {}, New: {}",
+                mailbox_status(
                     account[&mailbox_hash].name(),
                     total,
-                    unseen
+                    unseen,
+                    account[&mailbox_hash].ref_mailbox.recent(),
                 )
             }
             MailboxStatus::Failed(_) | MailboxStatus::None => account[&mailbox_hash].status(),
@@ -1363,6 +1492,40 @@ impl Component for Listing {
 
 impl Listing {
     pub const DESCRIPTION: &'static str = "listing";
+    /// Rows past the visible page to request along with it.
+    const PREFETCH_MARGIN: usize = 100;
+
+    /// While a mailbox is still loading, ask its backend for the page the cursor is on so
+    /// scrolling ahead of the full fetch doesn't show an empty list.
+    fn prefetch_page(&mut self, rows: usize, context: &mut Context) {
+        if let Offline(_) = self.component {
+            return;
+        }
+        let (account_hash, mailbox_hash) = self.component.coordinates();
+        let rows = std::cmp::max(rows, 1);
+        let page_start = self.component.cursor_row() / rows * rows;
+        let to = page_start + rows + Self::PREFETCH_MARGIN;
+        match self.prefetched {
+            Some((h, end)) if h == mailbox_hash && end >= to => return,
+            _ => {}
+        }
+        let account = &mut context.accounts[&account_hash];
+        if !account.mailbox_entries.contains_key(&mailbox_hash)
+            || !matches!(account[&mailbox_hash].status, MailboxStatus::Parsing(_, _))
+            || account.collection.get_mailbox(mailbox_hash).len() > to
+        {
+            return;
+        }
+        self.prefetched = Some((mailbox_hash, to));
+        if let Err(err) = account.fetch_range(mailbox_hash, page_start, to) {
+            context
+                .replies
+                .push_back(UIEvent::StatusEvent(StatusEvent::DisplayMessage(
+                    err.to_string(),
+                )));
+        }
+    }
+
     pub fn new(context: &mut Context) -> Self {
         let account_entries: Vec<AccountMenuEntry> = context
             .accounts
@@ -1400,6 +1563,8 @@ impl Listing {
             menu_width: WidgetWidth::Unset,
             focus: ListingFocus::Mailbox,
             cmd_buf: String::with_capacity(4),
+            mailbox_picker: None,
+            prefetched: None,
         };
         ret.change_account(context);
         ret
@@ -1458,7 +1623,8 @@ impl Listing {
             && self.cursor_pos.0 == a.index)
             || (self.focus == ListingFocus::Menu && self.menu_cursor_pos.0 == a.index);
 
-        let mut lines: Vec<(usize, usize, u32, bool, MailboxHash, Option<usize>)> = Vec::new();
+        let mut lines: Vec<(usize, usize, u32, bool, MailboxHash, Option<(usize, usize)>)> =
+            Vec::new();
 
         for (i, &(depth, indentation, has_sibling, mailbox_hash)) in a.entries.iter().enumerate() {
             if mailboxes[&mailbox_hash].is_subscribed() {
@@ -1473,7 +1639,7 @@ impl Listing {
                             indentation,
                             has_sibling,
                             mailbox_hash,
-                            mailboxes[&mailbox_hash].count().ok().map(|(v, _)| v),
+                            mailboxes[&mailbox_hash].count().ok(),
                         ));
                     }
                 }
@@ -1512,6 +1678,8 @@ impl Listing {
         let lines_len = lines.len();
         let mut idx = 0;
         let mut branches = String::with_capacity(16);
+        let show_total_count: bool =
+            *account_settings!(context[a.hash].listing.sidebar_show_total_count);
 
         for y in get_y(upper_left) + 1..get_y(bottom_right) {
             if idx == lines_len {
@@ -1646,15 +1814,15 @@ impl Listing {
             );
 
             /* Unread message count */
-            let count_string = if let Some(c) = count {
-                if c > 0 {
-                    format!(" {}", c)
-                } else {
-                    String::new()
+            let count_string = match count {
+                Some((unseen, total)) if show_total_count && total > 0 => {
+                    format!(" {}/{}", unseen, total)
                 }
-            } else {
-                " ...".to_string()
+                Some((unseen, _)) if unseen > 0 => format!(" {}", unseen),
+                Some(_) => String::new(),
+                None => " ...".to_string(),
             };
+            let count = count.map(|(unseen, _)| unseen);
 
             let (x, _) = write_string_to_grid(
                 &count_string,
@@ -1691,6 +1859,12 @@ impl Listing {
 
     fn change_account(&mut self, context: &mut Context) {
         let account_hash = context.accounts[self.cursor_pos.0].hash();
+        /* Undoing an operation of an account that is no longer shown would be surprising */
+        for account in context.accounts.values_mut() {
+            if account.hash() != account_hash {
+                account.undo_stack.clear();
+            }
+        }
         self.accounts[self.cursor_pos.0].entries = context.accounts[self.cursor_pos.0]
             .list_mailboxes()
             .into_iter()
@@ -1713,6 +1887,10 @@ impl Listing {
             let index_style =
                 mailbox_settings!(context[account_hash][mailbox_hash].listing.index_style);
             self.component.set_style(*index_style);
+            self.component.set_sort((
+                *mailbox_settings!(context[account_hash][mailbox_hash].listing.sort_field),
+                *mailbox_settings!(context[account_hash][mailbox_hash].listing.sort_order),
+            ));
         } else {
             /* Set to dummy */
             self.component = Offline(OfflineListing::new((account_hash, 0)));
@@ -1725,4 +1903,138 @@ impl Listing {
             )));
         self.menu_cursor_pos = self.cursor_pos;
     }
+
+    /// Opens a filterable list of the subscribed mailboxes of all accounts. Choosing one switches
+    /// the listing to it.
+    fn open_mailbox_picker(&mut self, context: &mut Context) {
+        let multiple_accounts = self.accounts.len() > 1;
+        let entries = self
+            .accounts
+            .iter()
+            .flat_map(|a| {
+                let account = &context.accounts[a.index];
+                a.entries.iter().map(move |&(_, _, _, mailbox_hash)| {
+                    let path = account[&mailbox_hash].ref_mailbox.path();
+                    (
+                        (a.hash, mailbox_hash),
+                        if multiple_accounts {
+                            format!("{}: {}", a.name, path)
+                        } else {
+                            path.to_string()
+                        },
+                    )
+                })
+            })
+            .collect::<Vec<((AccountHash, MailboxHash), String)>>();
+        let picker = UIDialog::new_filterable(
+            "go to mailbox",
+            entries,
+            Some(Box::new(
+                move |id: ComponentId, results: &[(AccountHash, MailboxHash)]| {
+                    Some(UIEvent::FinishedUIDialog(
+                        id,
+                        Box::new(results.first().cloned()),
+                    ))
+                },
+            )),
+            context,
+        );
+        self.mailbox_picker = Some(picker.id());
+        context
+            .replies
+            .push_back(UIEvent::GlobalUIDialog(Box::new(picker)));
+    }
+
+    fn go_to_mailbox(
+        &mut self,
+        account_hash: AccountHash,
+        mailbox_hash: MailboxHash,
+        context: &mut Context,
+    ) {
+        let account_idx = match self.accounts.iter().position(|a| a.hash == account_hash) {
+            Some(idx) => idx,
+            None => return,
+        };
+        if let Some(mailbox_idx) = self.accounts[account_idx]
+            .entries
+            .iter()
+            .position(|&(_, _, _, h)| h == mailbox_hash)
+        {
+            self.cursor_pos = (account_idx, mailbox_idx);
+            self.change_account(context);
+            self.focus = ListingFocus::Mailbox;
+            self.ratio = 90;
+        }
+    }
+
+    /// Asks the backend to sync the mailbox currently shown in the listing.
+    fn refresh_selected_mailbox(&mut self, context: &mut Context) {
+        let (account_hash, mailbox_hash) = self.component.coordinates();
+        if !context.accounts.contains_key(&account_hash)
+            || !context.accounts[&account_hash]
+                .mailbox_entries
+                .contains_key(&mailbox_hash)
+        {
+            return;
+        }
+        if let Err(err) = context.accounts[&account_hash].refresh(mailbox_hash) {
+            context.replies.push_back(UIEvent::Notification(
+                Some("Could not refresh.".to_string()),
+                err.to_string(),
+                Some(NotificationType::Error(err.kind)),
+            ));
+        }
+    }
+}
+
+#[test]
+fn test_listing_bulk_selection() {
+    let env_hashes: Vec<EnvelopeHash> = (1..=5).collect();
+    let mut selection: HashMap<EnvelopeHash, bool> =
+        env_hashes.iter().map(|&h| (h, false)).collect();
+    /* Nothing selected: act on the cursor row */
+    assert_eq!(
+        selection_or_cursor(&selection, env_hashes[4]).as_slice(),
+        &[env_hashes[4]]
+    );
+
+    for &h in &[env_hashes[0], env_hashes[2], env_hashes[3]] {
+        selection.entry(h).and_modify(|e| *e = !*e);
+    }
+    let focused = selection_or_cursor(&selection, env_hashes[4]);
+    let batch = EnvelopeHashBatch::try_from(focused.as_slice()).unwrap();
+    let mut batch_hashes: Vec<EnvelopeHash> = batch.iter().collect();
+    batch_hashes.sort();
+    assert_eq!(batch.len(), 3);
+    assert_eq!(
+        batch_hashes,
+        vec![env_hashes[0], env_hashes[2], env_hashes[3]]
+    );
+}
+
+/// Status line of a mailbox, e.g. "Mailbox: INBOX, 120 messages, 3 unread, 1 recent".
+fn mailbox_status(name: &str, total: usize, unseen: usize, recent: Option<usize>) -> String {
+    let mut ret = format!(
+        "Mailbox: {}, {} message{}, {} unread",
+        name,
+        total,
+        if total == 1 { "" } else { "s" },
+        unseen
+    );
+    if let Some(recent) = recent {
+        ret.push_str(&format!(", {} recent", recent));
+    }
+    ret
+}
+
+#[test]
+fn test_listing_mailbox_status() {
+    assert_eq!(
+        mailbox_status("INBOX", 120, 3, Some(1)),
+        "Mailbox: INBOX, 120 messages, 3 unread, 1 recent"
+    );
+    assert_eq!(
+        mailbox_status("Archive", 1, 0, None),
+        "Mailbox: Archive, 1 message, 0 unread"
+    );
 }
@@ -24,7 +24,6 @@ use crate::components::PageMovement;
 use crate::jobs::JoinHandle;
 use std::cmp;
 use std::convert::TryInto;
-use std::iter::FromIterator;
 
 macro_rules! address_list {
     (($name:expr) as comma_sep_list) => {{
@@ -166,22 +165,10 @@ impl MailListingTrait for CompactListing {
     }
 
     fn get_focused_items(&self, _context: &Context) -> SmallVec<[ThreadHash; 8]> {
-        let is_selection_empty = self.selection.values().cloned().any(std::convert::identity);
-        let i = [self.get_thread_under_cursor(self.cursor_pos.2)];
-        let cursor_iter;
-        let sel_iter = if is_selection_empty {
-            cursor_iter = None;
-            Some(self.selection.iter().filter(|(_, v)| **v).map(|(k, _)| k))
-        } else {
-            cursor_iter = Some(i.iter());
-            None
-        };
-        let iter = sel_iter
-            .into_iter()
-            .flatten()
-            .chain(cursor_iter.into_iter().flatten())
-            .cloned();
-        SmallVec::from_iter(iter.into_iter())
+        selection_or_cursor(
+            &self.selection,
+            self.get_thread_under_cursor(self.cursor_pos.2),
+        )
     }
 
     /// Fill the `self.data_columns` `CellBuffers` with the contents of the account mailbox the user has
@@ -261,6 +248,13 @@ impl MailListingTrait for CompactListing {
             self.sort,
             &context.accounts[&self.cursor_pos.0].collection.envelopes,
         );
+        if let Some(filter) = context.accounts[&self.cursor_pos.0].flag_filter {
+            filter.retain_roots(
+                &mut roots,
+                &threads,
+                &context.accounts[&self.cursor_pos.0].collection.envelopes,
+            );
+        }
 
         self.redraw_threads_list(
             context,
@@ -452,6 +446,10 @@ impl ListingTrait for CompactListing {
         (self.new_cursor_pos.0, self.new_cursor_pos.1)
     }
 
+    fn set_sort(&mut self, sort: (SortField, SortOrder)) {
+        self.sort = sort;
+    }
+
     fn set_coordinates(&mut self, coordinates: (AccountHash, MailboxHash)) {
         self.new_cursor_pos = (coordinates.0, coordinates.1, 0);
         self.unfocused = false;
@@ -873,6 +871,10 @@ impl ListingTrait for CompactListing {
         self.movement = Some(mvm);
         self.set_dirty(true);
     }
+
+    fn cursor_row(&self) -> usize {
+        self.new_cursor_pos.2
+    }
 }
 
 impl fmt::Display for CompactListing {
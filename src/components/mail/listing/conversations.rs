@@ -21,8 +21,8 @@
 
 use super::*;
 use crate::components::PageMovement;
+use crate::conf::DateStyle;
 use crate::jobs::JoinHandle;
-use std::iter::FromIterator;
 
 macro_rules! row_attr {
     ($field:ident, $color_cache:expr, $unseen:expr, $highlighted:expr, $selected:expr  $(,)*) => {{
@@ -134,22 +134,10 @@ impl MailListingTrait for ConversationsListing {
     }
 
     fn get_focused_items(&self, _context: &Context) -> SmallVec<[ThreadHash; 8]> {
-        let is_selection_empty = self.selection.values().cloned().any(std::convert::identity);
-        let i = [self.get_thread_under_cursor(self.cursor_pos.2)];
-        let cursor_iter;
-        let sel_iter = if is_selection_empty {
-            cursor_iter = None;
-            Some(self.selection.iter().filter(|(_, v)| **v).map(|(k, _)| k))
-        } else {
-            cursor_iter = Some(i.iter());
-            None
-        };
-        let iter = sel_iter
-            .into_iter()
-            .flatten()
-            .chain(cursor_iter.into_iter().flatten())
-            .cloned();
-        SmallVec::from_iter(iter.into_iter())
+        selection_or_cursor(
+            &self.selection,
+            self.get_thread_under_cursor(self.cursor_pos.2),
+        )
     }
 
     /// Fill the `self.data_columns` `CellBuffers` with the contents of the account mailbox the user has
@@ -230,6 +218,13 @@ impl MailListingTrait for ConversationsListing {
             self.sort,
             &context.accounts[&self.cursor_pos.0].collection.envelopes,
         );
+        if let Some(filter) = context.accounts[&self.cursor_pos.0].flag_filter {
+            filter.retain_roots(
+                &mut roots,
+                &threads,
+                &context.accounts[&self.cursor_pos.0].collection.envelopes,
+            );
+        }
 
         self.redraw_threads_list(
             context,
@@ -511,6 +506,10 @@ impl ListingTrait for ConversationsListing {
         (self.new_cursor_pos.0, self.new_cursor_pos.1)
     }
 
+    fn set_sort(&mut self, sort: (SortField, SortOrder)) {
+        self.sort = sort;
+    }
+
     fn set_coordinates(&mut self, coordinates: (AccountHash, MailboxHash)) {
         self.new_cursor_pos = (coordinates.0, coordinates.1, 0);
         self.unfocused = false;
@@ -888,6 +887,10 @@ impl ListingTrait for ConversationsListing {
         self.movement = Some(mvm);
         self.set_dirty(true);
     }
+
+    fn cursor_row(&self) -> usize {
+        self.new_cursor_pos.2
+    }
 }
 
 impl fmt::Display for ConversationsListing {
@@ -1001,6 +1004,25 @@ impl ConversationsListing {
     }
 
     pub(super) fn format_date(context: &Context, epoch: UnixTimestamp) -> String {
+        let datetime_fmt = context
+            .settings
+            .listing
+            .datetime_fmt
+            .as_deref()
+            .or(Some("%Y-%m-%d %T"));
+        match context.settings.listing.date_style {
+            Some(DateStyle::Relative) => {
+                return melib::datetime::timestamp_to_relative_string(
+                    epoch,
+                    melib::datetime::now(),
+                )
+                .unwrap_or_else(|| melib::datetime::timestamp_to_string(epoch, datetime_fmt));
+            }
+            Some(DateStyle::Absolute) => {
+                return melib::datetime::timestamp_to_string(epoch, datetime_fmt);
+            }
+            None => {}
+        }
         let d = std::time::UNIX_EPOCH + std::time::Duration::from_secs(epoch);
         let now: std::time::Duration = std::time::SystemTime::now()
             .duration_since(d)
@@ -1021,16 +1043,7 @@ impl ConversationsListing {
                 n / (24 * 60 * 60),
                 if n / (24 * 60 * 60) == 1 { "" } else { "s" }
             ),
-            _ => melib::datetime::timestamp_to_string(
-                epoch,
-                context
-                    .settings
-                    .listing
-                    .datetime_fmt
-                    .as_ref()
-                    .map(String::as_str)
-                    .or(Some("%Y-%m-%d %T")),
-            ),
+            _ => melib::datetime::timestamp_to_string(epoch, datetime_fmt),
         }
     }
 
@@ -1606,22 +1619,13 @@ impl Component for ConversationsListing {
                     }
                     Action::Sort(field, order) if !self.unfocused => {
                         debug!("Sort {:?} , {:?}", field, order);
-                        // FIXME sort
-                        /*
                         self.sort = (*field, *order);
                         if !self.filtered_selection.is_empty() {
-                            let threads = &context.accounts[&self.cursor_pos.0].collection.threads
-                                [&self.cursor_pos.1];
-                            threads.vec_inner_sort_by(
-                                &mut self.filtered_selection,
-                                self.sort,
-                                &context.accounts[&self.cursor_pos.0].collection.envelopes,
-                            );
+                            // FIXME: perform sort
                             self.dirty = true;
                         } else {
                             self.refresh_mailbox(context, false);
                         }
-                            */
                         return true;
                     }
                     Action::Listing(ToggleThreadSnooze) if !self.unfocused => {
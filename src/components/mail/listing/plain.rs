@@ -24,7 +24,6 @@ use super::*;
 use crate::components::PageMovement;
 use crate::jobs::{JobId, JoinHandle};
 use std::cmp;
-use std::iter::FromIterator;
 
 macro_rules! address_list {
     (($name:expr) as comma_sep_list) => {{
@@ -247,17 +246,23 @@ impl MailListingTrait for PlainListing {
                 return;
             }
         }
+        let env_lck = context.accounts[&self.cursor_pos.0]
+            .collection
+            .envelopes
+            .read()
+            .unwrap();
+        let flag_filter = context.accounts[&self.cursor_pos.0].flag_filter;
         self.local_collection = context.accounts[&self.cursor_pos.0]
             .collection
             .get_mailbox(self.cursor_pos.1)
             .iter()
+            .filter(|h| {
+                flag_filter
+                    .map(|f| f.matches(env_lck[h].flags()))
+                    .unwrap_or(true)
+            })
             .cloned()
             .collect();
-        let env_lck = context.accounts[&self.cursor_pos.0]
-            .collection
-            .envelopes
-            .read()
-            .unwrap();
         self.thread_node_hashes = context.accounts[&self.cursor_pos.0]
             .collection
             .get_mailbox(self.cursor_pos.1)
@@ -286,6 +291,9 @@ impl MailListingTrait for PlainListing {
                 let mb = &env_lck[b];
                 mb.subject().cmp(&ma.subject())
             }
+            (field @ SortField::From, order) | (field @ SortField::Received, order) => {
+                order.apply(field.cmp_envelopes(&env_lck[a], &env_lck[b]))
+            }
         });
         for &env_hash in &self.local_collection {
             self.all_envelopes.insert(env_hash);
@@ -338,6 +346,10 @@ impl ListingTrait for PlainListing {
         (self.new_cursor_pos.0, self.new_cursor_pos.1)
     }
 
+    fn set_sort(&mut self, sort: (SortField, SortOrder)) {
+        self.sort = sort;
+    }
+
     fn set_coordinates(&mut self, coordinates: (AccountHash, MailboxHash)) {
         self.new_cursor_pos = (coordinates.0, coordinates.1, 0);
         self.unfocused = false;
@@ -711,6 +723,10 @@ impl ListingTrait for PlainListing {
         self.movement = Some(mvm);
         self.set_dirty(true);
     }
+
+    fn cursor_row(&self) -> usize {
+        self.new_cursor_pos.2
+    }
 }
 
 impl fmt::Display for PlainListing {
@@ -794,7 +810,7 @@ impl PlainListing {
         let mut subject = e.subject().to_string();
         subject.truncate_at_boundary(150);
         EntryStrings {
-            date: DateString(PlainListing::format_date(&e)),
+            date: DateString(ConversationsListing::format_date(context, e.date())),
             subject: SubjectString(subject),
             flag: FlagString(format!("{}", if e.has_attachments() { "📎" } else { "" },)),
             from: FromString(address_list!((e.from()) as comma_sep_list)),
@@ -1030,32 +1046,26 @@ impl PlainListing {
         }
     }
 
-    fn format_date(envelope: &Envelope) -> String {
-        let d = std::time::UNIX_EPOCH + std::time::Duration::from_secs(envelope.date());
-        let now: std::time::Duration = std::time::SystemTime::now()
-            .duration_since(d)
-            .unwrap_or_else(|_| std::time::Duration::new(std::u64::MAX, 0));
-        match now.as_secs() {
-            n if n < 10 * 60 * 60 => format!("{} hours ago{}", n / (60 * 60), " ".repeat(8)),
-            n if n < 24 * 60 * 60 => format!("{} hours ago{}", n / (60 * 60), " ".repeat(7)),
-            n if n < 4 * 24 * 60 * 60 => {
-                format!("{} days ago{}", n / (24 * 60 * 60), " ".repeat(9))
-            }
-            _ => melib::datetime::timestamp_to_string(envelope.datetime(), None),
+    fn perform_action(
+        &mut self,
+        context: &mut Context,
+        env_hashes: SmallVec<[EnvelopeHash; 8]>,
+        a: &ListingAction,
+    ) {
+        if env_hashes.is_empty() {
+            return;
         }
-    }
-
-    fn perform_action(&mut self, context: &mut Context, env_hash: EnvelopeHash, a: &ListingAction) {
+        let env_hash_batch = EnvelopeHashBatch::try_from(env_hashes.as_slice()).unwrap();
         let account = &mut context.accounts[&self.cursor_pos.0];
         match {
             match a {
                 ListingAction::SetSeen => account.backend.write().unwrap().set_flags(
-                    env_hash.into(),
+                    env_hash_batch.clone(),
                     self.cursor_pos.1,
                     smallvec::smallvec![(Ok(Flag::SEEN), true)],
                 ),
                 ListingAction::SetUnseen => account.backend.write().unwrap().set_flags(
-                    env_hash.into(),
+                    env_hash_batch.clone(),
                     self.cursor_pos.1,
                     smallvec::smallvec![(Ok(Flag::SEEN), false)],
                 ),
@@ -1078,7 +1088,7 @@ impl PlainListing {
                 self.active_jobs.insert(handle.job_id, handle);
             }
         }
-        self.row_updates.push(env_hash);
+        self.row_updates.extend(env_hashes);
     }
 }
 
@@ -1206,6 +1216,7 @@ impl Component for PlainListing {
                     Action::Sort(field, order) if !self.unfocused => {
                         debug!("Sort {:?} , {:?}", field, order);
                         self.sort = (*field, *order);
+                        self.refresh_mailbox(context, false);
                         return true;
                     }
                     Action::Listing(a @ ListingAction::SetSeen)
@@ -1213,32 +1224,31 @@ impl Component for PlainListing {
                     | Action::Listing(a @ ListingAction::Delete)
                         if !self.unfocused =>
                     {
-                        let is_selection_empty =
-                            self.selection.values().cloned().any(std::convert::identity);
-                        let i = [self.get_env_under_cursor(self.cursor_pos.2, context)];
-                        let cursor_iter;
-                        let sel_iter = if is_selection_empty {
-                            cursor_iter = None;
-                            Some(self.selection.iter().filter(|(_, v)| **v).map(|(k, _)| k))
-                        } else {
-                            cursor_iter = Some(i.iter());
-                            None
-                        };
-                        let iter = sel_iter
-                            .into_iter()
-                            .flatten()
-                            .chain(cursor_iter.into_iter().flatten())
-                            .cloned();
-                        let stack: SmallVec<[_; 8]> = SmallVec::from_iter(iter.into_iter());
-                        for i in stack {
-                            self.perform_action(context, i, a);
-                        }
+                        let env_hashes = selection_or_cursor(
+                            &self.selection,
+                            self.get_env_under_cursor(self.cursor_pos.2, context),
+                        );
+                        self.perform_action(context, env_hashes, a);
                         self.dirty = true;
                         for v in self.selection.values_mut() {
                             *v = false;
                         }
                         return true;
                     }
+                    Action::Listing(a @ ListingAction::SelectAll)
+                    | Action::Listing(a @ ListingAction::DeselectAll)
+                        if !self.unfocused =>
+                    {
+                        let value = matches!(a, ListingAction::SelectAll);
+                        for (k, v) in self.selection.iter_mut() {
+                            if *v != value {
+                                *v = value;
+                                self.row_updates.push(*k);
+                            }
+                        }
+                        self.dirty = true;
+                        return true;
+                    }
 
                     _ => {}
                 },
@@ -1403,3 +1413,70 @@ impl Component for PlainListing {
         self.id = id;
     }
 }
+
+#[test]
+fn test_plain_listing_bulk_set_seen() {
+    use melib::backends::memory::MemoryType;
+    use std::sync::Arc;
+
+    let mut builder = MemoryType::builder().mailbox("INBOX");
+    for i in 1..=5 {
+        builder = builder.message(
+            "INBOX",
+            format!(
+                "From: a@example.com\nSubject: {i}\nMessage-ID: <{i}@example.com>\n\n{i}\n",
+                i = i
+            )
+            .into_bytes(),
+        );
+    }
+    let mut backend = builder
+        .build(BackendEventConsumer::new(Arc::new(|_, _| {})))
+        .unwrap();
+    let inbox_hash = melib::get_path_hash!("INBOX");
+    let env_hashes: Vec<EnvelopeHash> = futures::executor::block_on(futures::StreamExt::next(
+        &mut backend.fetch(inbox_hash).unwrap(),
+    ))
+    .unwrap()
+    .unwrap()
+    .iter()
+    .map(Envelope::hash)
+    .collect();
+
+    let mut context = Context::new_mock();
+    let account_hash = 1;
+    let account = Account::new_mock(
+        account_hash,
+        "test".to_string(),
+        Box::new(backend),
+        context.job_executor.clone(),
+        context.sender.clone(),
+    );
+    context.accounts.insert(account_hash, account);
+
+    let mut listing = PlainListing::new((account_hash, inbox_hash));
+    listing.cursor_pos = (account_hash, inbox_hash, 0);
+    listing.selection = env_hashes.iter().map(|&h| (h, false)).collect();
+    for &h in &[env_hashes[0], env_hashes[2], env_hashes[3]] {
+        listing.selection.insert(h, true);
+    }
+    let focused = selection_or_cursor(&listing.selection, env_hashes[4]);
+    listing.perform_action(&mut context, focused, &ListingAction::SetSeen);
+
+    /* One set_flags call for the whole selection, not one per envelope */
+    let backend = context.accounts[&account_hash].backend.read().unwrap();
+    let calls = backend
+        .as_any()
+        .downcast_ref::<MemoryType>()
+        .unwrap()
+        .set_flags_calls();
+    assert_eq!(calls.len(), 1);
+    let (batch, mailbox_hash, flags) = &calls[0];
+    let mut batch_hashes: Vec<EnvelopeHash> = batch.iter().collect();
+    batch_hashes.sort_unstable();
+    let mut selected = vec![env_hashes[0], env_hashes[2], env_hashes[3]];
+    selected.sort_unstable();
+    assert_eq!(batch_hashes, selected);
+    assert_eq!(*mailbox_hash, inbox_hash);
+    assert_eq!(flags.as_slice(), &[(Ok(Flag::SEEN), true)]);
+}
@@ -119,6 +119,8 @@ pub struct ThreadListing {
     row_updates: SmallVec<[ThreadHash; 8]>,
     selection: HashMap<ThreadHash, bool>,
     order: HashMap<EnvelopeHash, usize>,
+    /// Threads that only show their first row.
+    collapsed: HashSet<ThreadHash>,
     /// If we must redraw on next redraw event
     dirty: bool,
     /// If `self.view` is focused or not.
@@ -151,6 +153,7 @@ impl MailListingTrait for ThreadListing {
         {
             self.cursor_pos.2 = 0;
             self.new_cursor_pos.2 = 0;
+            self.collapsed.clear();
         }
         self.cursor_pos.1 = self.new_cursor_pos.1;
         self.cursor_pos.0 = self.new_cursor_pos.0;
@@ -215,6 +218,13 @@ impl MailListingTrait for ThreadListing {
             self.sort,
             &context.accounts[&self.cursor_pos.0].collection.envelopes,
         );
+        if let Some(filter) = context.accounts[&self.cursor_pos.0].flag_filter {
+            filter.retain_roots(
+                &mut roots,
+                &threads,
+                &context.accounts[&self.cursor_pos.0].collection.envelopes,
+            );
+        }
 
         self.redraw_threads_list(
             context,
@@ -273,7 +283,9 @@ impl MailListingTrait for ThreadListing {
         let roots = items
             .filter_map(|r| threads.groups[&r].root().map(|r| r.root))
             .collect::<_>();
-        let mut iter = threads.threads_group_iter(roots).peekable();
+        let collapsed = self.collapsed.clone();
+        let mut iter =
+            visible_thread_rows(&threads, threads.threads_group_iter(roots), &collapsed).peekable();
         let thread_nodes: &HashMap<ThreadNodeHash, ThreadNode> = &threads.thread_nodes();
         /* This is just a desugared for loop so that we can use .peek() */
         let mut idx = 0;
@@ -310,6 +322,12 @@ impl MailListingTrait for ThreadListing {
                     has_sibling,
                     is_root,
                 ));
+                if collapsed.contains(&prev_group) {
+                    if let Some(label) = collapsed_thread_label(threads.thread_ref(prev_group)) {
+                        entry_strings.subject.0.push(' ');
+                        entry_strings.subject.0.push_str(&label);
+                    }
+                }
                 row_widths.1.push(
                     entry_strings
                         .date
@@ -413,10 +431,45 @@ impl MailListingTrait for ThreadListing {
     }
 }
 
+/// Drops the rows of collapsed threads, except for the first one with a message.
+fn visible_thread_rows<'a>(
+    threads: &'a Threads,
+    rows: impl Iterator<Item = (usize, ThreadNodeHash, bool)> + 'a,
+    collapsed: &'a HashSet<ThreadHash>,
+) -> impl Iterator<Item = (usize, ThreadNodeHash, bool)> + 'a {
+    let mut shown_group = None;
+    rows.filter(move |(_, thread_node_hash, _)| {
+        let thread_node = &threads.thread_nodes()[thread_node_hash];
+        let group = threads.find_group(thread_node.group);
+        if !collapsed.contains(&group) {
+            return true;
+        }
+        if thread_node.has_message() && shown_group != Some(group) {
+            shown_group = Some(group);
+            true
+        } else {
+            false
+        }
+    })
+}
+
+/// The `+N` count of hidden messages shown next to a collapsed thread.
+fn collapsed_thread_label(thread: &Thread) -> Option<String> {
+    if thread.len() > 1 {
+        Some(format!("+{}", thread.len() - 1))
+    } else {
+        None
+    }
+}
+
 impl ListingTrait for ThreadListing {
     fn coordinates(&self) -> (AccountHash, MailboxHash) {
         (self.new_cursor_pos.0, self.new_cursor_pos.1)
     }
+    fn set_sort(&mut self, sort: (SortField, SortOrder)) {
+        self.sort = sort;
+    }
+
     fn set_coordinates(&mut self, coordinates: (AccountHash, MailboxHash)) {
         self.new_cursor_pos = (coordinates.0, coordinates.1, 0);
         self.unfocused = false;
@@ -742,6 +795,10 @@ impl ListingTrait for ThreadListing {
         self.movement = Some(mvm);
         self.set_dirty(true);
     }
+
+    fn cursor_row(&self) -> usize {
+        self.new_cursor_pos.2
+    }
 }
 
 impl fmt::Display for ThreadListing {
@@ -765,6 +822,7 @@ impl ThreadListing {
             row_updates: SmallVec::new(),
             selection: HashMap::default(),
             order: HashMap::default(),
+            collapsed: HashSet::default(),
             dirty: true,
             unfocused: false,
             view: None,
@@ -774,6 +832,32 @@ impl ThreadListing {
         }
     }
 
+    fn toggle_thread_collapse(&mut self, context: &mut Context) {
+        if self.length == 0 {
+            return;
+        }
+        let env_hash = self.get_env_under_cursor(self.cursor_pos.2, context);
+        let group = {
+            let account = &context.accounts[&self.cursor_pos.0];
+            let threads = account.collection.get_threads(self.cursor_pos.1);
+            let thread_node_hash = account.collection.get_env(env_hash).thread();
+            threads.find_group(threads.thread_nodes()[&thread_node_hash].group)
+        };
+        if !self.collapsed.remove(&group) {
+            self.collapsed.insert(group);
+        }
+        self.refresh_mailbox(context, false);
+        /* Keep the cursor on the thread */
+        let account = &context.accounts[&self.cursor_pos.0];
+        let threads = account.collection.get_threads(self.cursor_pos.1);
+        if let Some(((idx, _, _, _), _)) = self.rows.iter().find(|((_, _, _, env_hash), _)| {
+            let thread_node_hash = account.collection.get_env(*env_hash).thread();
+            threads.find_group(threads.thread_nodes()[&thread_node_hash].group) == group
+        }) {
+            self.new_cursor_pos.2 = *idx;
+        }
+    }
+
     fn highlight_line_self(&mut self, _idx: usize, _context: &Context) {
         /*
          * FIXME
@@ -1192,7 +1276,18 @@ impl Component for ThreadListing {
                 return true;
             }
         }
+        let shortcuts = self.get_shortcuts(context);
         match *event {
+            UIEvent::Input(ref key)
+                if !self.unfocused
+                    && shortcut!(
+                        key == shortcuts[Listing::DESCRIPTION]["toggle_thread_collapse"]
+                    ) =>
+            {
+                self.toggle_thread_collapse(context);
+                self.dirty = true;
+                return true;
+            }
             UIEvent::Input(Key::Char('\n')) if !self.unfocused => {
                 self.unfocused = true;
                 self.dirty = true;
@@ -1280,6 +1375,25 @@ impl Component for ThreadListing {
                     self.refresh_mailbox(context, false);
                     return true;
                 }
+                Action::Listing(ListingAction::CollapseAll) => {
+                    self.collapsed = context.accounts[&self.cursor_pos.0]
+                        .collection
+                        .get_threads(self.cursor_pos.1)
+                        .roots()
+                        .into_iter()
+                        .collect();
+                    self.new_cursor_pos.2 = 0;
+                    self.dirty = true;
+                    self.refresh_mailbox(context, false);
+                    return true;
+                }
+                Action::Listing(ListingAction::ExpandAll) => {
+                    self.collapsed.clear();
+                    self.new_cursor_pos.2 = 0;
+                    self.dirty = true;
+                    self.refresh_mailbox(context, false);
+                    return true;
+                }
                 _ => {}
             },
             _ => {}
@@ -1296,10 +1410,14 @@ impl Component for ThreadListing {
         self.dirty = value;
     }
     fn get_shortcuts(&self, context: &Context) -> ShortcutMaps {
-        self.view
+        let mut map = self
+            .view
             .as_ref()
             .map(|p| p.get_shortcuts(context))
-            .unwrap_or_default()
+            .unwrap_or_default();
+        let config_map = context.settings.shortcuts.listing.key_values();
+        map.insert(Listing::DESCRIPTION, config_map);
+        map
     }
 
     fn id(&self) -> ComponentId {
@@ -1309,3 +1427,58 @@ impl Component for ThreadListing {
         self.id = id;
     }
 }
+
+#[test]
+fn test_thread_listing_collapse() {
+    use std::sync::{Arc, RwLock};
+    let raw = |id: &str, refs: &[&str]| melib::thread::test_envelope(id, "hi", 1, refs);
+    let envelopes: Vec<Envelope> = vec![
+        raw("1@a", &[]),
+        raw("2@a", &["1@a"]),
+        raw("3@a", &["1@a", "2@a"]),
+        raw("4@a", &["1@a"]),
+        raw("5@a", &["1@a", "4@a"]),
+        raw("6@b", &[]),
+    ];
+    let hashes: Vec<EnvelopeHash> = envelopes.iter().map(Envelope::hash).collect();
+    let envelopes = Arc::new(RwLock::new(
+        envelopes
+            .into_iter()
+            .map(|e| (e.hash(), e))
+            .collect::<HashMap<EnvelopeHash, Envelope>>(),
+    ));
+    let mut threads = Threads::new(hashes.len());
+    for h in &hashes {
+        threads.insert(&envelopes, *h);
+    }
+    let group = |env_hash: EnvelopeHash| -> ThreadHash {
+        let thread_node_hash = envelopes.read().unwrap()[&env_hash].thread();
+        threads.find_group(threads.thread_nodes()[&thread_node_hash].group)
+    };
+    let long_thread = group(hashes[0]);
+    let row_count = |collapsed: &HashSet<ThreadHash>| -> usize {
+        let roots = threads
+            .roots()
+            .into_iter()
+            .filter_map(|r| threads.groups[&r].root().map(|r| r.root))
+            .collect();
+        visible_thread_rows(&threads, threads.threads_group_iter(roots), collapsed)
+            .filter(|(_, thread_node_hash, _)| {
+                threads.thread_nodes()[thread_node_hash].has_message()
+            })
+            .count()
+    };
+
+    let mut collapsed = HashSet::default();
+    assert_eq!(row_count(&collapsed), 6);
+    collapsed.insert(long_thread);
+    assert_eq!(row_count(&collapsed), 2);
+    assert_eq!(
+        collapsed_thread_label(threads.thread_ref(long_thread)),
+        Some("+4".to_string())
+    );
+    assert_eq!(
+        collapsed_thread_label(threads.thread_ref(group(hashes[5]))),
+        None
+    );
+}
@@ -35,13 +35,31 @@ pub async fn decrypt(raw: Vec<u8>) -> Result<(melib_pgp::DecryptionMetadata, Vec
     ctx.decrypt(cipher)?.await
 }
 
-pub async fn verify(a: Attachment) -> Result<()> {
+/// Verifies a `multipart/signed` attachment, and describes the signature if it is good.
+pub async fn verify(a: Attachment) -> Result<String> {
     let (data, sig) =
         melib_pgp::verify_signature(&a).chain_err_summary(|| "Could not verify signature.")?;
     let mut ctx = Context::new()?;
     let sig = ctx.new_data_mem(&sig.body().trim())?;
     let data = ctx.new_data_mem(&data)?;
-    ctx.verify(sig, data)?.await
+    let metadata = ctx.verify(sig, data)?.await?;
+    let signature = metadata
+        .signatures
+        .into_iter()
+        .next()
+        .ok_or_else(|| MeliError::new("no signature status reported"))?;
+    signature.status?;
+    let fpr = signature.keyid.unwrap_or_default();
+    /* The signer's key is known, otherwise the status would be an error */
+    let signer = ctx
+        .keylist(false, Some(fpr.clone()))?
+        .await
+        .ok()
+        .and_then(|keys| keys.first().and_then(Key::primary_uid));
+    Ok(format!(
+        "Good signature from {}",
+        signer.map(|uid| uid.to_string()).unwrap_or(fpr)
+    ))
 }
 
 pub fn sign_filter(
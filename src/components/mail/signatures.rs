@@ -0,0 +1,198 @@
+/*
+ * meli
+ *
+ * Copyright 2020 Manos Pitsidianakis
+ *
+ * This file is part of meli.
+ *
+ * meli is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * meli is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with meli. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Verification of `multipart/signed` e-mail with external commands such as `gpg` and `openssl`.
+
+use crate::types::create_temp_file;
+use melib::email::Attachment;
+use melib::email::{attachment_types::ContentType, attachments::decode, pgp as melib_pgp};
+use melib::error::*;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Exit status of `sh -c` when the command could not be found.
+const COMMAND_NOT_FOUND: i32 = 127;
+
+fn shell_quote(path: &Path) -> String {
+    format!("'{}'", path.display().to_string().replace('\'', "'\\''"))
+}
+
+/// Substitutes the paths of the detached signature and of the signed data in a verification
+/// command's `{signature}` and `{data}` placeholders.
+pub fn detached_verify_invocation(command: &str, signature: &Path, data: &Path) -> String {
+    command
+        .replace("{signature}", &shell_quote(signature))
+        .replace("{data}", &shell_quote(data))
+}
+
+/// Describes the return code of an `ERRSIG` status line, see `doc/DETAILS` in GnuPG.
+fn errsig_reason(rc: &str) -> &'static str {
+    match rc {
+        "4" => "unsupported algorithm",
+        "9" => "no public key",
+        _ => "signature could not be checked",
+    }
+}
+
+/// Interprets the `--status-fd` output of `gpg --verify`. Returns a description of a good
+/// signature or an error describing why it is not one.
+pub fn gpg_verify_status(status: &str) -> Result<String> {
+    let mut good = None;
+    let mut unknown_key = None;
+    let mut errsig = None;
+    for line in status.lines() {
+        let line = line.trim_start_matches("[GNUPG:] ");
+        let mut fields = line.splitn(3, ' ');
+        let (keyword, key_id, user_id) = (
+            fields.next().unwrap_or_default(),
+            fields.next().unwrap_or_default(),
+            fields.next().unwrap_or_default(),
+        );
+        match keyword {
+            "BADSIG" => {
+                return Err(MeliError::new(format!("BAD signature from {}", user_id)));
+            }
+            "GOODSIG" => good = Some(format!("Good signature from {}", user_id)),
+            "EXPKEYSIG" => good = Some(format!("Good signature from {} (expired key)", user_id)),
+            "REVKEYSIG" => good = Some(format!("Good signature from {} (revoked key)", user_id)),
+            "NO_PUBKEY" => unknown_key = Some(key_id.to_string()),
+            "ERRSIG" => {
+                /* ERRSIG <keyid> <pkalgo> <hashalgo> <sig_class> <time> <rc> [<fpr>] */
+                let rc = user_id.split(' ').nth(4).unwrap_or_default();
+                errsig = Some((key_id.to_string(), errsig_reason(rc)));
+            }
+            _ => {}
+        }
+    }
+    match (good, unknown_key, errsig) {
+        (Some(description), _, _) => Ok(description),
+        (None, Some(key_id), _) => Err(MeliError::new(format!(
+            "signer unknown, no public key {}",
+            key_id
+        ))),
+        (None, None, Some((key_id, reason))) => Err(MeliError::new(format!(
+            "could not verify signature of key {}: {}",
+            key_id, reason
+        ))),
+        (None, None, None) => Err(MeliError::new("no signature status reported")),
+    }
+}
+
+/// Interprets the result of `openssl smime -verify` from its success and standard error.
+pub fn smime_verify_status(success: bool, stderr: &str) -> Result<String> {
+    if success {
+        return Ok("Good signature".to_string());
+    }
+    if stderr.contains("certificate verify error")
+        || stderr.contains("unable to get local issuer certificate")
+        || stderr.contains("self signed certificate")
+    {
+        return Err(MeliError::new("signer unknown, certificate not trusted"));
+    }
+    if stderr.contains("digest failure") || stderr.contains("signature failure") {
+        return Err(MeliError::new("BAD signature"));
+    }
+    Err(MeliError::new(stderr.trim().to_string()))
+}
+
+/// Verifies a `multipart/signed` attachment by writing its signed part and signature to
+/// temporary files and running `command` on them. The output is interpreted according to the
+/// signature's protocol, see [`gpg_verify_status`] and [`smime_verify_status`].
+pub fn verify(a: &Attachment, command: &str) -> Result<String> {
+    let (data, signature) =
+        melib_pgp::verify_signature(a).chain_err_summary(|| "Could not verify signature.")?;
+    let is_smime = signature.content_type == ContentType::CMSSignature;
+    let signature_file = create_temp_file(&decode(signature, None), None, None, true);
+    let data_file = create_temp_file(&data, None, None, true);
+    let invocation = detached_verify_invocation(command, signature_file.path(), data_file.path());
+    let output = Command::new("sh")
+        .args(&["-c", &invocation])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .chain_err_summary(|| format!("Could not run `{}`.", invocation))?;
+    if output.status.code() == Some(COMMAND_NOT_FOUND) {
+        return Err(MeliError::new(format!(
+            "`{}` was not found, signature was not verified",
+            command.split_whitespace().next().unwrap_or_default()
+        )));
+    }
+    if is_smime {
+        smime_verify_status(
+            output.status.success(),
+            &String::from_utf8_lossy(&output.stderr),
+        )
+    } else {
+        gpg_verify_status(&String::from_utf8_lossy(&output.stdout))
+    }
+}
+
+#[test]
+fn test_detached_verify_invocation() {
+    assert_eq!(
+        detached_verify_invocation(
+            crate::conf::DEFAULT_PGP_VERIFY_COMMAND,
+            Path::new("/tmp/meli/sig.asc"),
+            Path::new("/tmp/meli/it's data"),
+        ),
+        "gpg --batch --status-fd 1 --verify '/tmp/meli/sig.asc' '/tmp/meli/it'\\''s data'"
+    );
+    assert_eq!(
+        detached_verify_invocation(
+            crate::conf::DEFAULT_SMIME_VERIFY_COMMAND,
+            Path::new("/tmp/sig.p7s"),
+            Path::new("/tmp/data"),
+        ),
+        "openssl smime -verify -inform DER -in '/tmp/sig.p7s' -content '/tmp/data' -out /dev/null"
+    );
+}
+
+#[test]
+fn test_gpg_verify_status() {
+    assert_eq!(
+        gpg_verify_status(
+            "[GNUPG:] NEWSIG\n[GNUPG:] GOODSIG 0123456789ABCDEF Jane Doe <jane@example.com>\n[GNUPG:] VALIDSIG 0123\n"
+        )
+        .unwrap(),
+        "Good signature from Jane Doe <jane@example.com>"
+    );
+    assert_eq!(
+        gpg_verify_status("[GNUPG:] BADSIG 0123456789ABCDEF Jane Doe <jane@example.com>\n")
+            .unwrap_err()
+            .to_string(),
+        "BAD signature from Jane Doe <jane@example.com>"
+    );
+    assert_eq!(
+        gpg_verify_status(
+            "[GNUPG:] ERRSIG 0123456789ABCDEF 1 8 00 1600000000 9 -\n[GNUPG:] NO_PUBKEY 0123456789ABCDEF\n"
+        )
+        .unwrap_err()
+        .to_string(),
+        "signer unknown, no public key 0123456789ABCDEF"
+    );
+    assert_eq!(
+        gpg_verify_status("[GNUPG:] ERRSIG 0123456789ABCDEF 99 8 00 1600000000 4 -\n")
+            .unwrap_err()
+            .to_string(),
+        "could not verify signature of key 0123456789ABCDEF: unsupported algorithm"
+    );
+}
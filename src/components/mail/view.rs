@@ -48,6 +48,8 @@ use xdg_utils::query_default_app;
 enum Source {
     Decoded,
     Raw,
+    /// Raw source with header names and the header/body boundary highlighted.
+    RawHighlighted,
 }
 
 #[derive(PartialEq, Debug)]
@@ -105,7 +107,7 @@ pub enum AttachmentDisplay {
     SignedPending {
         inner: Attachment,
         display: Vec<AttachmentDisplay>,
-        handle: JoinHandle<Result<()>>,
+        handle: JoinHandle<Result<String>>,
         job_id: JobId,
     },
     SignedFailed {
@@ -157,6 +159,13 @@ pub struct MailView {
     theme_default: ThemeAttribute,
     active_jobs: HashSet<JobId>,
     state: MailViewState,
+    load_remote_content: bool,
+    /// Fetch the envelope even if it is larger than `pager.max_autofetch_size`.
+    fetch_confirmed: bool,
+    /// The MIME structure of an envelope that wasn't fetched because of its size.
+    unfetched_parts: UnfetchedParts,
+    /// The `pipe-envelope` command that is running, and its output.
+    pipe_job: Option<(String, JoinHandle<Result<Vec<u8>>>)>,
 
     cmd_buf: String,
     id: ComponentId,
@@ -167,6 +176,7 @@ pub enum PendingReplyAction {
     Reply,
     ReplyToAuthor,
     ReplyToAll,
+    Forward,
 }
 
 #[derive(Debug)]
@@ -190,6 +200,42 @@ enum MailViewState {
     },
 }
 
+/// The MIME structure of an envelope larger than `pager.max_autofetch_size`, which backends such
+/// as IMAP can report without fetching the envelope.
+#[derive(Debug)]
+enum UnfetchedParts {
+    None,
+    Loading(JoinHandle<Result<BodyStructure>>),
+    Loaded {
+        structure: BodyStructure,
+        /// The message's text part, fetched on its own as a preview.
+        text: Option<String>,
+        text_handle: Option<JoinHandle<Result<Vec<u8>>>>,
+    },
+    /// The backend can't report it.
+    Unavailable,
+}
+
+impl Default for UnfetchedParts {
+    fn default() -> Self {
+        UnfetchedParts::None
+    }
+}
+
+impl UnfetchedParts {
+    /// The job that is fetching the structure or the text part.
+    fn job_id(&self) -> Option<JobId> {
+        match self {
+            UnfetchedParts::Loading(handle) => Some(handle.job_id),
+            UnfetchedParts::Loaded {
+                text_handle: Some(handle),
+                ..
+            } => Some(handle.job_id),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 enum LinkKind {
     Url,
@@ -222,6 +268,8 @@ impl Clone for MailView {
             attachment_paths: self.attachment_paths.clone(),
             state: MailViewState::default(),
             active_jobs: self.active_jobs.clone(),
+            unfetched_parts: UnfetchedParts::default(),
+            pipe_job: None,
             ..*self
         }
     }
@@ -259,6 +307,10 @@ impl MailView {
             theme_default: crate::conf::value(context, "mail.view.body"),
             active_jobs: Default::default(),
             state: MailViewState::default(),
+            load_remote_content: false,
+            fetch_confirmed: false,
+            unfetched_parts: UnfetchedParts::default(),
+            pipe_job: None,
 
             cmd_buf: String::with_capacity(4),
             id: ComponentId::new_v4(),
@@ -270,6 +322,29 @@ impl MailView {
 
     fn init_futures(&mut self, context: &mut Context) {
         debug!("init_futures");
+        if self.unfetched_size(context).is_some() {
+            if !matches!(self.state, MailViewState::Init { .. }) {
+                self.state = MailViewState::default();
+            }
+            if let UnfetchedParts::None = self.unfetched_parts {
+                let account = &mut context.accounts[&self.coordinates.0];
+                self.unfetched_parts = match account
+                    .operation(self.coordinates.2)
+                    .and_then(|mut op| op.body_structure())
+                {
+                    Ok(fut) => {
+                        let handle = account.job_executor.spawn_specialized(fut);
+                        self.active_jobs.insert(handle.job_id);
+                        context
+                            .replies
+                            .push_back(UIEvent::StatusEvent(StatusEvent::NewJob(handle.job_id)));
+                        UnfetchedParts::Loading(handle)
+                    }
+                    Err(_) => UnfetchedParts::Unavailable,
+                };
+            }
+            return;
+        }
         let mut pending_action = None;
         let account = &mut context.accounts[&self.coordinates.0];
         if debug!(account.contains_key(self.coordinates.2)) {
@@ -309,6 +384,7 @@ impl MailView {
                                         context,
                                         self.coordinates,
                                         &mut self.active_jobs,
+                                        self.load_remote_content,
                                     );
                                     let (paths, attachment_tree_s) =
                                         self.attachment_displays_to_tree(&display);
@@ -380,6 +456,138 @@ impl MailView {
         }
     }
 
+    fn unfetched_parts_job_finished(&mut self, context: &mut Context) {
+        match self.unfetched_parts {
+            UnfetchedParts::Loading(ref mut handle) => {
+                self.unfetched_parts =
+                    match handle.chan.try_recv() {
+                        Ok(Some(Ok(structure))) => {
+                            /* Show the text part as a preview, without fetching the rest */
+                            let account = &mut context.accounts[&self.coordinates.0];
+                            let text_handle = structure
+                                .text_part()
+                                .and_then(|part| {
+                                    account
+                                        .operation(self.coordinates.2)
+                                        .and_then(|mut op| op.fetch_part(&part.part_number))
+                                        .ok()
+                                })
+                                .map(|fut| account.job_executor.spawn_specialized(fut));
+                            if let Some(ref handle) = text_handle {
+                                self.active_jobs.insert(handle.job_id);
+                                context.replies.push_back(UIEvent::StatusEvent(
+                                    StatusEvent::NewJob(handle.job_id),
+                                ));
+                            }
+                            UnfetchedParts::Loaded {
+                                structure,
+                                text: None,
+                                text_handle,
+                            }
+                        }
+                        _ => UnfetchedParts::Unavailable,
+                    };
+            }
+            UnfetchedParts::Loaded {
+                ref structure,
+                ref mut text,
+                ref mut text_handle,
+            } => {
+                if let (Some(part), Some(mut handle)) = (structure.text_part(), text_handle.take())
+                {
+                    if let Ok(Some(Ok(bytes))) = handle.chan.try_recv() {
+                        *text = Some(decode_part(part, &bytes));
+                    }
+                }
+            }
+            UnfetchedParts::None | UnfetchedParts::Unavailable => {}
+        }
+    }
+
+    /// The size of the envelope if it is larger than `pager.max_autofetch_size` and the user hasn't
+    /// asked to fetch it yet.
+    fn unfetched_size(&self, context: &Context) -> Option<usize> {
+        let account = &context.accounts[&self.coordinates.0];
+        if self.fetch_confirmed || !account.contains_key(self.coordinates.2) {
+            return None;
+        }
+        let size = account.collection.get_env(self.coordinates.2).size()?;
+        let max_autofetch_size = *mailbox_settings!(
+            context[self.coordinates.0][&self.coordinates.1]
+                .pager
+                .max_autofetch_size
+        );
+        if exceeds_autofetch_size(size, max_autofetch_size) {
+            Some(size)
+        } else {
+            None
+        }
+    }
+
+    /// Pipes the envelope to `bin` in a job, whose output is shown in a subview once it finishes.
+    /// The raw source is piped when viewing it, and the decoded text otherwise.
+    fn pipe_message(&mut self, bin: &str, args: &[String], context: &mut Context) {
+        let input = match self.state {
+            MailViewState::Loaded { ref bytes, .. }
+                if self.mode == ViewMode::Source(Source::Raw)
+                    || self.mode == ViewMode::Source(Source::RawHighlighted) =>
+            {
+                bytes.clone()
+            }
+            MailViewState::Loaded { ref body_text, .. } => body_text.as_bytes().to_vec(),
+            _ => {
+                context
+                    .replies
+                    .push_back(UIEvent::StatusEvent(StatusEvent::DisplayMessage(
+                        "Envelope is not loaded yet.".to_string(),
+                    )));
+                return;
+            }
+        };
+        let command = if args.is_empty() {
+            bin.to_string()
+        } else {
+            format!("{} {}", bin, args.join(" "))
+        };
+        let (bin, args) = (bin.to_string(), args.to_vec());
+        let handle = context
+            .job_executor
+            .spawn_blocking(async move { pipe_to_command(&bin, &args, input) });
+        self.active_jobs.insert(handle.job_id);
+        context
+            .replies
+            .push_back(UIEvent::StatusEvent(StatusEvent::NewJob(handle.job_id)));
+        self.pipe_job = Some((command, handle));
+    }
+
+    fn pipe_job_finished(&mut self, context: &mut Context) {
+        let (command, mut handle) = match self.pipe_job.take() {
+            Some(job) => job,
+            None => return,
+        };
+        let output = match handle.chan.try_recv() {
+            Ok(Some(output)) => output,
+            _ => return,
+        };
+        match output.and_then(|output| {
+            crate::terminal::ansi::ansi_to_cellbuffer(&String::from_utf8_lossy(&output))
+                .ok_or_else(|| MeliError::new("Could not display command output."))
+        }) {
+            Ok(buf) => {
+                self.subview = Some(Box::new(RawBuffer::new(buf, Some(command))));
+                self.mode = ViewMode::Subview;
+                self.set_dirty(true);
+            }
+            Err(err) => {
+                context.replies.push_back(UIEvent::Notification(
+                    Some(format!("Could not pipe to {}", command)),
+                    err.to_string(),
+                    Some(NotificationType::Error(err.kind)),
+                ));
+            }
+        }
+    }
+
     fn perform_action(&mut self, action: PendingReplyAction, context: &mut Context) {
         let bytes = match self.state {
             MailViewState::Init {
@@ -410,6 +618,9 @@ impl MailView {
             PendingReplyAction::ReplyToAll => {
                 Box::new(Composer::reply_to_all(self.coordinates, bytes, context))
             }
+            PendingReplyAction::Forward => {
+                Box::new(Composer::forward(self.coordinates, bytes, context))
+            }
         };
 
         context
@@ -556,6 +767,7 @@ impl MailView {
         context: &mut Context,
         coordinates: (AccountHash, MailboxHash, EnvelopeHash),
         active_jobs: &mut HashSet<JobId>,
+        load_remote_content: bool,
     ) -> Vec<AttachmentDisplay> {
         let mut ret = vec![];
         fn rec(
@@ -564,16 +776,35 @@ impl MailView {
             coordinates: (AccountHash, MailboxHash, EnvelopeHash),
             acc: &mut Vec<AttachmentDisplay>,
             active_jobs: &mut HashSet<JobId>,
+            load_remote_content: bool,
         ) {
             if a.content_disposition.kind.is_attachment() {
                 acc.push(AttachmentDisplay::Attachment { inner: a.clone() });
             } else if a.content_type().is_text_html() {
-                let bytes = decode(a, None);
+                let bytes = decode_lossy(a, None);
                 let filter_invocation =
                     mailbox_settings!(context[coordinates.0][&coordinates.1].pager.html_filter)
                         .as_ref()
                         .map(|s| s.as_str())
                         .unwrap_or("w3m -I utf-8 -T text/html");
+                let (filtered_bytes, blocked) = if load_remote_content
+                    || *mailbox_settings!(
+                        context[coordinates.0][&coordinates.1]
+                            .pager
+                            .load_remote_content
+                    ) {
+                    (bytes.clone(), false)
+                } else {
+                    block_remote_content(&bytes)
+                };
+                let banner = if blocked {
+                    format!(
+                        "Remote content blocked. Press `{}` to load it. ",
+                        context.settings.shortcuts.envelope_view.load_remote_content
+                    )
+                } else {
+                    String::new()
+                };
                 let command_obj = Command::new("sh")
                     .args(&["-c", filter_invocation])
                     .stdin(Stdio::piped())
@@ -604,11 +835,11 @@ impl MailView {
                             .stdin
                             .as_mut()
                             .unwrap()
-                            .write_all(&bytes)
+                            .write_all(&filtered_bytes)
                             .expect("Failed to write to stdin");
                         let mut s = format!(
-                            "Text piped through `{}`. Press `v` to open in web browser. \n\n",
-                            filter_invocation
+                            "Text piped through `{}`. Press `v` to open in web browser. {}\n\n",
+                            filter_invocation, banner
                         );
                         s.push_str(&String::from_utf8_lossy(
                             &html_filter.wait_with_output().unwrap().stdout,
@@ -620,7 +851,7 @@ impl MailView {
                     }
                 }
             } else if a.is_text() {
-                let bytes = decode(a, None);
+                let bytes = decode_lossy(a, None);
                 acc.push(AttachmentDisplay::InlineText {
                     inner: a.clone(),
                     text: String::from_utf8_lossy(&bytes).to_string(),
@@ -636,63 +867,110 @@ impl MailView {
                         if let Some(text_attachment_pos) =
                             parts.iter().position(|a| a.content_type == "text/plain")
                         {
-                            let bytes = decode(&parts[text_attachment_pos], None);
+                            let bytes = decode_lossy(&parts[text_attachment_pos], None);
                             acc.push(AttachmentDisplay::InlineText {
                                 inner: a.clone(),
                                 text: String::from_utf8_lossy(&bytes).to_string(),
                             });
                         } else {
                             for a in parts {
-                                rec(a, context, coordinates, acc, active_jobs);
+                                rec(
+                                    a,
+                                    context,
+                                    coordinates,
+                                    acc,
+                                    active_jobs,
+                                    load_remote_content,
+                                );
                             }
                         }
                     }
                     MultipartType::Signed => {
-                        #[cfg(not(feature = "gpgme"))]
-                        {
+                        let display = {
+                            let mut v = vec![];
+                            rec(
+                                &parts[0],
+                                context,
+                                coordinates,
+                                &mut v,
+                                active_jobs,
+                                load_remote_content,
+                            );
+                            v
+                        };
+                        if *mailbox_settings!(
+                            context[coordinates.0][&coordinates.1]
+                                .pgp
+                                .auto_verify_signatures
+                        ) {
+                            let is_smime = parts
+                                .iter()
+                                .any(|p| p.content_type == ContentType::CMSSignature);
+                            let handle = if is_smime {
+                                let command = mailbox_settings!(
+                                    context[coordinates.0][&coordinates.1]
+                                        .pgp
+                                        .smime_verify_command
+                                )
+                                .clone();
+                                let a = a.clone();
+                                context.job_executor.spawn_blocking(async move {
+                                    crate::components::mail::signatures::verify(&a, &command)
+                                })
+                            } else {
+                                #[cfg(feature = "gpgme")]
+                                {
+                                    if let Some(command) = mailbox_settings!(
+                                        context[coordinates.0][&coordinates.1]
+                                            .pgp
+                                            .pgp_verify_command
+                                    )
+                                    .clone()
+                                    {
+                                        let a = a.clone();
+                                        context.job_executor.spawn_blocking(async move {
+                                            crate::components::mail::signatures::verify(
+                                                &a, &command,
+                                            )
+                                        })
+                                    } else {
+                                        context.job_executor.spawn_specialized(
+                                            crate::components::mail::pgp::verify(a.clone()),
+                                        )
+                                    }
+                                }
+                                #[cfg(not(feature = "gpgme"))]
+                                {
+                                    let command = mailbox_settings!(
+                                        context[coordinates.0][&coordinates.1]
+                                            .pgp
+                                            .pgp_verify_command
+                                    )
+                                    .clone();
+                                    let a = a.clone();
+                                    context.job_executor.spawn_blocking(async move {
+                                        crate::components::mail::signatures::verify(&a, &command)
+                                    })
+                                }
+                            };
+                            active_jobs.insert(handle.job_id);
+                            context
+                                .replies
+                                .push_back(UIEvent::StatusEvent(StatusEvent::NewJob(
+                                    handle.job_id,
+                                )));
+                            acc.push(AttachmentDisplay::SignedPending {
+                                inner: a.clone(),
+                                job_id: handle.job_id,
+                                display,
+                                handle,
+                            });
+                        } else {
                             acc.push(AttachmentDisplay::SignedUnverified {
                                 inner: a.clone(),
-                                display: {
-                                    let mut v = vec![];
-                                    rec(&parts[0], context, coordinates, &mut v, active_jobs);
-                                    v
-                                },
+                                display,
                             });
                         }
-                        #[cfg(feature = "gpgme")]
-                        {
-                            if *mailbox_settings!(
-                                context[coordinates.0][&coordinates.1]
-                                    .pgp
-                                    .auto_verify_signatures
-                            ) {
-                                let verify_fut = crate::components::mail::pgp::verify(a.clone());
-                                let handle = context.job_executor.spawn_specialized(verify_fut);
-                                active_jobs.insert(handle.job_id);
-                                context.replies.push_back(UIEvent::StatusEvent(
-                                    StatusEvent::NewJob(handle.job_id),
-                                ));
-                                acc.push(AttachmentDisplay::SignedPending {
-                                    inner: a.clone(),
-                                    job_id: handle.job_id,
-                                    display: {
-                                        let mut v = vec![];
-                                        rec(&parts[0], context, coordinates, &mut v, active_jobs);
-                                        v
-                                    },
-                                    handle,
-                                });
-                            } else {
-                                acc.push(AttachmentDisplay::SignedUnverified {
-                                    inner: a.clone(),
-                                    display: {
-                                        let mut v = vec![];
-                                        rec(&parts[0], context, coordinates, &mut v, active_jobs);
-                                        v
-                                    },
-                                });
-                            }
-                        }
                     }
                     MultipartType::Encrypted => {
                         for a in parts {
@@ -733,13 +1011,27 @@ impl MailView {
                     }
                     _ => {
                         for a in parts {
-                            rec(a, context, coordinates, acc, active_jobs);
+                            rec(
+                                a,
+                                context,
+                                coordinates,
+                                acc,
+                                active_jobs,
+                                load_remote_content,
+                            );
                         }
                     }
                 }
             }
         };
-        rec(body, context, coordinates, &mut ret, active_jobs);
+        rec(
+            body,
+            context,
+            coordinates,
+            &mut ret,
+            active_jobs,
+            load_remote_content,
+        );
         ret
     }
 
@@ -751,6 +1043,8 @@ impl MailView {
         self.coordinates = new_coordinates;
         self.mode = ViewMode::Normal;
         self.initialised = false;
+        self.fetch_confirmed = false;
+        self.unfetched_parts = UnfetchedParts::None;
         self.init_futures(context);
         self.set_dirty(true);
     }
@@ -842,7 +1136,7 @@ impl MailView {
         }
         context
             .replies
-            .push_back(UIEvent::StatusEvent(StatusEvent::DisplayMessage(format!(
+            .push_back(UIEvent::StatusEvent(StatusEvent::Notify(format!(
                 "Attachment `{}` not found.",
                 lidx
             ))));
@@ -887,55 +1181,104 @@ impl Component for MailView {
                         .headers_sticky
                 ) || height_p < height;
                 let (_, mut y) = upper_left;
+                let header_wrap = *mailbox_settings!(
+                    context[self.coordinates.0][&self.coordinates.1]
+                        .pager
+                        .header_wrap
+                );
                 macro_rules! print_header {
-                    ($($string:expr)+) => {
-                        $({
-                            if sticky || skip_header_ctr == 0 {
-                                let (_x, _y) = write_string_to_grid(
-                                    &$string,
-                                    grid,
-                                    headers.fg,
-                                    headers.bg,
-                                    headers.attrs,
-                                    (set_y(upper_left, y), bottom_right),
-                                    Some(get_x(upper_left)),
-                                );
-                            clear_area(grid, ((_x, _y), (get_x(bottom_right), _y)), headers);
-                            y = _y + 1;
+                    ($name:expr, $value:expr) => {{
+                        if sticky || skip_header_ctr == 0 {
+                            clear_area(
+                                grid,
+                                (set_y(upper_left, y), set_y(bottom_right, y)),
+                                headers,
+                            );
+                            let (value_x, _) = write_string_to_grid(
+                                &format!("{}: ", $name),
+                                grid,
+                                headers.fg,
+                                headers.bg,
+                                headers.attrs,
+                                (set_y(upper_left, y), bottom_right),
+                                None,
+                            );
+                            /* Continuation lines are indented to align under the value,
+                             * unless that leaves too little room */
+                            let line_break = if !header_wrap {
+                                None
+                            } else if value_x + 10 < get_x(bottom_right) {
+                                Some(value_x)
+                            } else {
+                                Some(get_x(upper_left))
+                            };
+                            let (_x, _y) = write_string_to_grid(
+                                &$value,
+                                grid,
+                                headers.fg,
+                                headers.bg,
+                                headers.attrs,
+                                ((value_x, y), bottom_right),
+                                line_break,
+                            );
+                            if header_wrap {
+                                if _y > y && line_break > Some(get_x(upper_left)) {
+                                    clear_area(
+                                        grid,
+                                        ((get_x(upper_left), y + 1), (value_x - 1, _y)),
+                                        headers,
+                                    );
+                                }
+                                clear_area(grid, ((_x, _y), (get_x(bottom_right), _y)), headers);
+                                y = _y + 1;
+                            } else {
+                                if _y == y {
+                                    clear_area(
+                                        grid,
+                                        ((_x, _y), (get_x(bottom_right), _y)),
+                                        headers,
+                                    );
+                                }
+                                y += 1;
+                            }
                         } else {
                             skip_header_ctr -= 1;
                         }
                         self.headers_no += 1;
-                        })+
-                    };
+                    }};
                 }
-                print_header!(
-                    format!("Date: {}", envelope.date_as_str())
-                    format!("From: {}", envelope.field_from_to_string())
-                    format!("To: {}", envelope.field_to_to_string())
-                );
-                if envelope.other_headers().contains_key("Cc")
-                    && !envelope.other_headers()["Cc"].is_empty()
-                {
-                    print_header!(format!("Cc: {}", envelope.field_cc_to_string()));
+                let header_names = mailbox_settings!(
+                    context[self.coordinates.0][&self.coordinates.1]
+                        .pager
+                        .headers
+                )
+                .clone();
+                let date_format = mailbox_settings!(
+                    context[self.coordinates.0][&self.coordinates.1]
+                        .pager
+                        .date_format
+                )
+                .clone();
+                for name in header_names.iter() {
+                    if name.eq_ignore_ascii_case("List-ID") {
+                        /* Shown below along with the available list actions */
+                        continue;
+                    }
+                    if let Some(value) = header_value(&envelope, name, date_format.as_deref()) {
+                        print_header!(name, value);
+                    }
                 }
-                print_header!(
-                    format!("Subject: {}", envelope.subject())
-                    format!("Message-ID: <{}>", envelope.message_id_raw())
-                );
                 if self.expand_headers {
                     if let Some(val) = envelope.in_reply_to_display() {
+                        print_header!("In-Reply-To", val);
                         print_header!(
-                            format!("In-Reply-To: {}", val)
-                            format!(
-                                "References: {}",
-                                envelope
+                            "References",
+                            envelope
                                 .references()
                                 .iter()
                                 .map(std::string::ToString::to_string)
                                 .collect::<Vec<String>>()
                                 .join(", ")
-                            )
                         );
                     }
                 }
@@ -1122,6 +1465,38 @@ impl Component for MailView {
                     (set_y(upper_left, y), bottom_right),
                     self.theme_default,
                 );
+                if let Some(size) = self.unfetched_size(context) {
+                    let mut lines = vec![format!(
+                        "Message is {} \u{2014} press {} to fetch",
+                        melib::Bytes(size),
+                        context.settings.shortcuts.envelope_view.fetch_message
+                    )];
+                    if let UnfetchedParts::Loaded {
+                        ref structure,
+                        ref text,
+                        ..
+                    } = self.unfetched_parts
+                    {
+                        lines.push(String::new());
+                        lines.extend(unfetched_parts_lines(structure));
+                        if let Some(text) = text {
+                            lines.push(String::new());
+                            lines.extend(text.lines().map(str::to_string));
+                        }
+                    }
+                    let height = get_y(bottom_right).saturating_sub(y);
+                    for (i, line) in lines.iter().take(height).enumerate() {
+                        write_string_to_grid(
+                            line,
+                            grid,
+                            self.theme_default.fg,
+                            self.theme_default.bg,
+                            self.theme_default.attrs,
+                            (set_y(upper_left, y + 1 + i), bottom_right),
+                            None,
+                        );
+                    }
+                }
                 context
                     .dirty_areas
                     .push_back((set_y(upper_left, y), bottom_right));
@@ -1186,6 +1561,9 @@ impl Component for MailView {
                     self.initialised = false;
                 }
                 ViewMode::Subview | ViewMode::ContactSelector(_) => {}
+                ViewMode::Source(Source::RawHighlighted) => {
+                    self.pager = Pager::from_buf(highlight_raw_source(bytes, context), None);
+                }
                 ViewMode::Source(source) => {
                     let text = {
                         if source == Source::Raw {
@@ -1266,9 +1644,7 @@ impl Component for MailView {
                             })
                             .collect::<Vec<Link>>();
                     }
-                    for (lidx, l) in links.iter().enumerate().rev() {
-                        text.insert_str(l.start, &format!("[{}]", lidx));
-                    }
+                    text = url_view_text(&text, links, context.settings.pager.url_display);
                     if !text.ends_with("\n\n") {
                         text.push_str("\n\n");
                     }
@@ -1394,6 +1770,14 @@ impl Component for MailView {
                     self.pager.set_dirty(true);
                     return true;
                 }
+                UIEvent::StatusEvent(StatusEvent::JobFinished(ref job_id))
+                    if self.unfetched_parts.job_id() == Some(*job_id) =>
+                {
+                    self.active_jobs.remove(job_id);
+                    self.unfetched_parts_job_finished(context);
+                    self.set_dirty(true);
+                    return true;
+                }
                 UIEvent::StatusEvent(StatusEvent::JobFinished(ref job_id))
                     if self.active_jobs.contains(job_id) =>
                 {
@@ -1422,6 +1806,7 @@ impl Component for MailView {
                                         context,
                                         self.coordinates,
                                         &mut self.active_jobs,
+                                        self.load_remote_content,
                                     );
                                     let (paths, attachment_tree_s) =
                                         self.attachment_displays_to_tree(&display);
@@ -1460,14 +1845,14 @@ impl Component for MailView {
                                         caught = true;
                                         self.initialised = false;
                                         match handle.chan.try_recv().unwrap().unwrap() {
-                                            Ok(()) => {
+                                            Ok(description) => {
                                                 *d = AttachmentDisplay::SignedVerified {
                                                     inner: std::mem::replace(
                                                         inner,
                                                         AttachmentBuilder::new(&[]).build(),
                                                     ),
                                                     display: std::mem::replace(display, vec![]),
-                                                    description: String::new(),
+                                                    description,
                                                 };
                                             }
                                             Err(error) => {
@@ -1497,6 +1882,7 @@ impl Component for MailView {
                                                     context,
                                                     self.coordinates,
                                                     &mut self.active_jobs,
+                                                    self.load_remote_content,
                                                 );
                                                 *d = AttachmentDisplay::EncryptedSuccess {
                                                     inner: std::mem::replace(
@@ -1548,6 +1934,11 @@ impl Component for MailView {
                     self.active_jobs.remove(job_id);
                     self.set_dirty(true);
                 }
+                /* Count prefixes belong to us, so that they can be used with our own shortcuts
+                 * (e.g. attachment and url indices) as well as the pager's jumps. */
+                UIEvent::Input(Key::Char(c))
+                    if is_count_digit(*c, &shortcuts[MailView::DESCRIPTION]) => {}
+                UIEvent::Input(_) if !self.cmd_buf.is_empty() => {}
                 _ => {
                     if self.pager.process_event(event, context) {
                         return true;
@@ -1558,6 +1949,35 @@ impl Component for MailView {
 
         let shortcuts = &self.get_shortcuts(context);
         match *event {
+            UIEvent::Input(ref key)
+                if self.mode == ViewMode::Normal
+                    && !self.cmd_buf.is_empty()
+                    && (shortcut!(key == shortcuts[Pager::DESCRIPTION]["go_to_line"])
+                        || shortcut!(key == shortcuts[Pager::DESCRIPTION]["go_to_percentage"])) =>
+            {
+                let count = self.cmd_buf.parse::<usize>().unwrap_or(usize::MAX);
+                self.cmd_buf.clear();
+                context
+                    .replies
+                    .push_back(UIEvent::StatusEvent(StatusEvent::BufClear));
+                if shortcut!(key == shortcuts[Pager::DESCRIPTION]["go_to_line"]) {
+                    self.pager.go_to_line(count);
+                } else {
+                    self.pager.go_to_percentage(count);
+                }
+                return true;
+            }
+            UIEvent::Input(ref key)
+                if shortcut!(key == shortcuts[MailView::DESCRIPTION]["pipe_message"]) =>
+            {
+                context
+                    .replies
+                    .push_back(UIEvent::CmdInput(Key::Paste("pipe-envelope ".to_string())));
+                context
+                    .replies
+                    .push_back(UIEvent::ChangeMode(UIMode::Command));
+                return true;
+            }
             UIEvent::Input(ref key)
                 if shortcut!(key == shortcuts[MailView::DESCRIPTION]["reply"]) =>
             {
@@ -1576,6 +1996,33 @@ impl Component for MailView {
                 self.perform_action(PendingReplyAction::ReplyToAuthor, context);
                 return true;
             }
+            UIEvent::Input(ref key)
+                if shortcut!(key == shortcuts[MailView::DESCRIPTION]["fetch_message"])
+                    && self.unfetched_size(context).is_some() =>
+            {
+                self.fetch_confirmed = true;
+                self.init_futures(context);
+                self.set_dirty(true);
+                return true;
+            }
+            UIEvent::Input(ref key)
+                if shortcut!(key == shortcuts[MailView::DESCRIPTION]["forward"]) =>
+            {
+                self.perform_action(PendingReplyAction::Forward, context);
+                return true;
+            }
+            UIEvent::Action(View(ViewAction::Reply)) => {
+                self.perform_action(PendingReplyAction::Reply, context);
+                return true;
+            }
+            UIEvent::Action(View(ViewAction::ReplyToAll)) => {
+                self.perform_action(PendingReplyAction::ReplyToAll, context);
+                return true;
+            }
+            UIEvent::Action(View(ViewAction::Forward)) => {
+                self.perform_action(PendingReplyAction::Forward, context);
+                return true;
+            }
             UIEvent::Input(ref key)
                 if shortcut!(key == shortcuts[MailView::DESCRIPTION]["edit"]) =>
             {
@@ -1688,7 +2135,9 @@ impl Component for MailView {
                     .push_back(UIEvent::StatusEvent(StatusEvent::BufClear));
                 return true;
             }
-            UIEvent::Input(Key::Char(c)) if c >= '0' && c <= '9' => {
+            UIEvent::Input(Key::Char(c))
+                if is_count_digit(c, &shortcuts[MailView::DESCRIPTION]) =>
+            {
                 self.cmd_buf.push(c);
                 context
                     .replies
@@ -1701,11 +2150,13 @@ impl Component for MailView {
                 if (self.mode == ViewMode::Normal
                     || self.mode == ViewMode::Subview
                     || self.mode == ViewMode::Source(Source::Decoded)
-                    || self.mode == ViewMode::Source(Source::Raw))
+                    || self.mode == ViewMode::Source(Source::Raw)
+                    || self.mode == ViewMode::Source(Source::RawHighlighted))
                     && shortcut!(key == shortcuts[MailView::DESCRIPTION]["view_raw_source"]) =>
             {
                 self.mode = match self.mode {
                     ViewMode::Source(Source::Decoded) => ViewMode::Source(Source::Raw),
+                    ViewMode::Source(Source::Raw) => ViewMode::Source(Source::RawHighlighted),
                     _ => ViewMode::Source(Source::Decoded),
                 };
                 self.set_dirty(true);
@@ -1718,7 +2169,8 @@ impl Component for MailView {
                     || self.mode == ViewMode::Subview
                     || self.mode == ViewMode::Url
                     || self.mode == ViewMode::Source(Source::Decoded)
-                    || self.mode == ViewMode::Source(Source::Raw))
+                    || self.mode == ViewMode::Source(Source::Raw)
+                    || self.mode == ViewMode::Source(Source::RawHighlighted))
                     && shortcut!(
                         key == shortcuts[MailView::DESCRIPTION]["return_to_normal_view"]
                     ) =>
@@ -1877,6 +2329,43 @@ impl Component for MailView {
                 }
                 return true;
             }
+            UIEvent::Input(ref key)
+                if self.mode == ViewMode::Normal
+                    && !self.load_remote_content
+                    && shortcut!(
+                        key == shortcuts[MailView::DESCRIPTION]["load_remote_content"]
+                    ) =>
+            {
+                self.load_remote_content = true;
+                if let MailViewState::Loaded { ref body, .. } = self.state {
+                    let body = body.clone();
+                    let display = Self::attachment_to(
+                        &body,
+                        context,
+                        self.coordinates,
+                        &mut self.active_jobs,
+                        self.load_remote_content,
+                    );
+                    let (paths, attachment_tree_s) = self.attachment_displays_to_tree(&display);
+                    self.attachment_tree = attachment_tree_s;
+                    self.attachment_paths = paths;
+                    let body_text = self.attachment_displays_to_text(&display, context);
+                    if let MailViewState::Loaded {
+                        display: ref mut d,
+                        body_text: ref mut t,
+                        ref mut links,
+                        ..
+                    } = self.state
+                    {
+                        *d = display;
+                        *t = body_text;
+                        links.clear();
+                    }
+                }
+                self.initialised = false;
+                self.set_dirty(true);
+                return true;
+            }
             UIEvent::Input(ref key)
                 if (self.mode == ViewMode::Normal || self.mode == ViewMode::Url)
                     && shortcut!(
@@ -1890,8 +2379,10 @@ impl Component for MailView {
             UIEvent::Input(ref key)
                 if !self.cmd_buf.is_empty()
                     && self.mode == ViewMode::Url
-                    && shortcut!(key == shortcuts[MailView::DESCRIPTION]["go_to_url"]) =>
+                    && (shortcut!(key == shortcuts[MailView::DESCRIPTION]["go_to_url"])
+                        || shortcut!(key == shortcuts[MailView::DESCRIPTION]["yank_url"])) =>
             {
+                let yank = shortcut!(key == shortcuts[MailView::DESCRIPTION]["yank_url"]);
                 let lidx = self.cmd_buf.parse::<usize>().unwrap();
                 self.cmd_buf.clear();
                 context
@@ -1926,27 +2417,30 @@ impl Component for MailView {
                             }
                         };
 
-                        match Command::new("xdg-open")
-                            .arg(url)
-                            .stdin(Stdio::piped())
-                            .stdout(Stdio::piped())
-                            .spawn()
-                        {
-                            Ok(child) => {
-                                context.children.push(child);
-                            }
-                            Err(err) => {
-                                context.replies.push_back(UIEvent::Notification(
-                                    Some("Failed to launch xdg-open".to_string()),
-                                    err.to_string(),
-                                    Some(NotificationType::Error(melib::ErrorKind::External)),
-                                ));
-                            }
+                        if yank {
+                            copy_to_clipboard(url, context);
+                        } else {
+                            open_url(url, context);
                         }
                     }
                 }
                 return true;
             }
+            UIEvent::Input(ref key)
+                if shortcut!(key == shortcuts[MailView::DESCRIPTION]["yank_message_id"]) =>
+            {
+                let account = &context.accounts[&self.coordinates.0];
+                if !account.contains_key(self.coordinates.2) {
+                    return true;
+                }
+                let message_id = account
+                    .collection
+                    .get_env(self.coordinates.2)
+                    .message_id_display()
+                    .to_string();
+                copy_to_clipboard(&message_id, context);
+                return true;
+            }
             UIEvent::Input(ref key)
                 if (self.mode == ViewMode::Normal || self.mode == ViewMode::Url)
                     && shortcut!(key == shortcuts[MailView::DESCRIPTION]["toggle_url_mode"]) =>
@@ -1963,6 +2457,18 @@ impl Component for MailView {
             UIEvent::EnvelopeRename(old_hash, new_hash) if self.coordinates.2 == old_hash => {
                 self.coordinates.2 = new_hash;
             }
+            UIEvent::StatusEvent(StatusEvent::JobFinished(ref job_id))
+                if self.pipe_job.as_ref().map(|(_, handle)| handle.job_id) == Some(*job_id) =>
+            {
+                self.active_jobs.remove(job_id);
+                self.pipe_job_finished(context);
+                return true;
+            }
+            UIEvent::Action(View(ViewAction::PipeEnvelope(ref bin, ref args))) => {
+                let (bin, args) = (bin.clone(), args.clone());
+                self.pipe_message(&bin, &args, context);
+                return true;
+            }
             UIEvent::Action(View(ViewAction::ExportMail(ref path))) => {
                 // Save entire message as eml
                 let account = &context.accounts[&self.coordinates.0];
@@ -1993,8 +2499,9 @@ impl Component for MailView {
 
                 if path.is_dir() {
                     let envelope: EnvelopeRef = account.collection.get_env(self.coordinates.2);
-                    path.push(format!("{}.eml", envelope.message_id_raw()));
+                    path.push(eml_filename(&envelope.subject()));
                 }
+                let path = unique_path(path);
                 match save_attachment(&path, bytes) {
                     Err(err) => {
                         context.replies.push_back(UIEvent::Notification(
@@ -2122,7 +2629,7 @@ impl Component for MailView {
                 } else {
                     context
                         .replies
-                        .push_back(UIEvent::StatusEvent(StatusEvent::DisplayMessage(format!(
+                        .push_back(UIEvent::StatusEvent(StatusEvent::Notify(format!(
                             "Attachment `{}` not found.",
                             a_i
                         ))));
@@ -2206,24 +2713,10 @@ impl Component for MailView {
                                         }
                                     }
                                     list_management::ListAction::Url(url) => {
-                                        match Command::new("xdg-open")
-                                            .arg(String::from_utf8_lossy(url).into_owned())
-                                            .stdin(Stdio::piped())
-                                            .stdout(Stdio::piped())
-                                            .spawn()
-                                        {
-                                            Ok(child) => {
-                                                context.children.push(child);
-                                            }
-                                            Err(err) => {
-                                                context.replies.push_back(UIEvent::StatusEvent(
-                                                    StatusEvent::DisplayMessage(format!(
-                                                        "Couldn't launch xdg-open: {}",
-                                                        err
-                                                    )),
-                                                ));
-                                            }
-                                        }
+                                        let url = String::from_utf8_lossy(url).into_owned();
+                                        drop(detect);
+                                        drop(envelope);
+                                        open_url(&url, context);
                                         return true;
                                     }
                                     list_management::ListAction::No => {}
@@ -2231,23 +2724,10 @@ impl Component for MailView {
                             }
                         }
                         MailingListAction::ListArchive if actions.archive.is_some() => {
-                            /* open archive url with xdg-open */
-                            match Command::new("xdg-open")
-                                .arg(actions.archive.unwrap())
-                                .stdin(Stdio::piped())
-                                .stdout(Stdio::piped())
-                                .spawn()
-                            {
-                                Ok(child) => context.children.push(child),
-                                Err(err) => {
-                                    context.replies.push_back(UIEvent::StatusEvent(
-                                        StatusEvent::DisplayMessage(format!(
-                                            "Couldn't launch xdg-open: {}",
-                                            err
-                                        )),
-                                    ));
-                                }
-                            }
+                            let url = actions.archive.unwrap().to_string();
+                            drop(detect);
+                            drop(envelope);
+                            open_url(&url, context);
                             return true;
                         }
                         _ => { /* error print message to user */ }
@@ -2310,12 +2790,14 @@ impl Component for MailView {
             || self.mode == ViewMode::Subview
             || self.mode == ViewMode::Source(Source::Decoded)
             || self.mode == ViewMode::Source(Source::Raw)
+            || self.mode == ViewMode::Source(Source::RawHighlighted)
             || self.mode == ViewMode::Url)
         {
             our_map.remove("return_to_normal_view");
         }
         if self.mode != ViewMode::Url {
             our_map.remove("go_to_url");
+            our_map.remove("yank_url");
         }
         if !(self.mode == ViewMode::Normal || self.mode == ViewMode::Url) {
             our_map.remove("toggle_url_mode");
@@ -2342,6 +2824,184 @@ impl Component for MailView {
     }
 }
 
+/// Renders the untouched source of an e-mail, with header field names in the `mail.view.headers`
+/// theme colors and the blank line separating headers from the body highlighted.
+fn highlight_raw_source(bytes: &[u8], context: &Context) -> CellBuffer {
+    let text = String::from_utf8_lossy(bytes);
+    let lines: Vec<&str> = text.split('\n').map(|l| l.trim_end_matches('\r')).collect();
+    let width = lines
+        .iter()
+        .map(|l| l.grapheme_width() + l.matches('\t').count())
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let body_theme = crate::conf::value(context, "mail.view.body");
+    let headers_theme = crate::conf::value(context, "mail.view.headers");
+    let mut empty_cell = Cell::with_char(' ');
+    empty_cell.set_fg(body_theme.fg);
+    empty_cell.set_bg(body_theme.bg);
+    let mut ret = CellBuffer::new_with_context(width, lines.len().max(1), empty_cell, context);
+    let mut in_headers = true;
+    for (y, line) in lines.into_iter().enumerate() {
+        let row = ((0, y), (width - 1, y));
+        if in_headers && line.is_empty() {
+            in_headers = false;
+            change_colors(&mut ret, row, headers_theme.fg, headers_theme.bg);
+            continue;
+        }
+        /* Continuation lines of folded headers start with whitespace */
+        if in_headers && !line.starts_with(' ') && !line.starts_with('\t') {
+            if let Some(pos) = line.find(':') {
+                let (x, _) = write_string_to_grid(
+                    &line[..=pos],
+                    &mut ret,
+                    headers_theme.fg,
+                    headers_theme.bg,
+                    headers_theme.attrs,
+                    row,
+                    None,
+                );
+                if x > width - 1 {
+                    continue;
+                }
+                write_string_to_grid(
+                    &line[pos + 1..],
+                    &mut ret,
+                    body_theme.fg,
+                    body_theme.bg,
+                    body_theme.attrs,
+                    ((x, y), (width - 1, y)),
+                    None,
+                );
+                continue;
+            }
+        }
+        write_string_to_grid(
+            line,
+            &mut ret,
+            body_theme.fg,
+            body_theme.bg,
+            body_theme.attrs,
+            row,
+            None,
+        );
+    }
+    ret
+}
+
+/// Labels `links` of `text` with their index, either inline or as a list of references after the
+/// text.
+fn url_view_text(text: &str, links: &[Link], style: crate::conf::pager::UrlDisplay) -> String {
+    use crate::conf::pager::UrlDisplay;
+    let mut ret = text.to_string();
+    match style {
+        UrlDisplay::Inline => {
+            for (lidx, l) in links.iter().enumerate().rev() {
+                ret.insert_str(l.start, &format!("[{}]", lidx));
+            }
+        }
+        UrlDisplay::References => {
+            for (lidx, l) in links.iter().enumerate().rev() {
+                ret.replace_range(l.start..l.end, &format!("[{}]", lidx));
+            }
+            if !links.is_empty() {
+                if !ret.ends_with('\n') {
+                    ret.push('\n');
+                }
+                ret.push_str("\nLinks:\n");
+                for (lidx, l) in links.iter().enumerate() {
+                    ret.push_str(&format!("[{}] {}\n", lidx, &text[l.start..l.end]));
+                }
+            }
+        }
+    }
+    ret
+}
+
+/// Builds the argument vector of the `url_launcher` command `template` for `url`.
+fn url_launcher_argv(template: &str, url: &str) -> Vec<String> {
+    let mut argv: Vec<String> = template
+        .split_whitespace()
+        .map(|arg| arg.replace("{}", url))
+        .collect();
+    if !template.split_whitespace().any(|arg| arg.contains("{}")) {
+        argv.push(url.to_string());
+    }
+    argv
+}
+
+/// Opens `url` with the configured `url_launcher`, notifying the user on failure.
+fn open_url(url: &str, context: &mut Context) {
+    let argv = url_launcher_argv(&context.settings.pager.url_launcher, url);
+    if argv.is_empty() {
+        return;
+    }
+    match Command::new(&argv[0])
+        .args(&argv[1..])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => {
+            context.children.push(child);
+        }
+        Err(err) => {
+            context.replies.push_back(UIEvent::Notification(
+                Some(format!("Failed to launch {}", &argv[0])),
+                err.to_string(),
+                Some(NotificationType::Error(melib::ErrorKind::External)),
+            ));
+        }
+    }
+}
+
+/// Copies `text` to the clipboard, notifying the user of the outcome.
+fn copy_to_clipboard(text: &str, context: &mut Context) {
+    match crate::terminal::clipboard::copy_to_clipboard(
+        text,
+        context.settings.terminal.clipboard_command.as_deref(),
+    ) {
+        Ok(()) => {
+            context
+                .replies
+                .push_back(UIEvent::StatusEvent(StatusEvent::Notify(format!(
+                    "Copied `{}` to clipboard.",
+                    text
+                ))));
+        }
+        Err(err) => {
+            context.replies.push_back(UIEvent::Notification(
+                Some("Could not copy to clipboard".to_string()),
+                err.to_string(),
+                Some(NotificationType::Error(melib::ErrorKind::External)),
+            ));
+        }
+    }
+}
+
+/// Returns the value of header `name` as displayed in mail view, or `None` if `envelope` doesn't
+/// have it or it is empty.
+fn header_value(envelope: &Envelope, name: &str, date_format: Option<&str>) -> Option<String> {
+    let value = match name.to_ascii_lowercase().as_str() {
+        "date" => match date_format {
+            Some(fmt) => envelope.date_formatted(fmt),
+            None => envelope.date_as_str().to_string(),
+        },
+        "from" => envelope.field_from_to_string(),
+        "to" => envelope.field_to_to_string(),
+        "cc" => envelope.field_cc_to_string(),
+        "bcc" => envelope.field_bcc_to_string(),
+        "subject" => envelope.subject().to_string(),
+        "message-id" => format!("<{}>", envelope.message_id_raw()),
+        _ => envelope.other_headers().get(name)?.to_string(),
+    };
+    if value.trim().is_empty() {
+        None
+    } else {
+        Some(value)
+    }
+}
+
 fn attachment_tree(
     (idx, (depth, att)): (&mut usize, (usize, &Attachment)),
     branches: &mut SmallVec<[bool; 8]>,
@@ -2412,3 +3072,285 @@ fn save_attachment(path: &std::path::Path, bytes: &[u8]) -> Result<()> {
     f.flush()?;
     Ok(())
 }
+
+/// Maximum length in characters of a file name derived from a message's subject.
+const EML_FILENAME_MAX_LEN: usize = 100;
+
+/// Derives a file name for an exported message from its subject, dropping path separators and
+/// control characters.
+fn eml_filename(subject: &str) -> String {
+    let stem = subject
+        .chars()
+        .filter(|c| *c != '/' && !c.is_control())
+        .take(EML_FILENAME_MAX_LEN)
+        .collect::<String>();
+    let stem = stem.trim().trim_start_matches('.');
+    if stem.is_empty() {
+        "message.eml".to_string()
+    } else {
+        format!("{}.eml", stem)
+    }
+}
+
+/// Returns `path` if nothing exists there, otherwise the first free path made by appending
+/// `-1`, `-2`, ... to its file stem.
+fn unique_path(path: std::path::PathBuf) -> std::path::PathBuf {
+    if !path.exists() {
+        return path;
+    }
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    (1..)
+        .map(|i| path.with_file_name(format!("{}-{}{}", stem, i, extension)))
+        .find(|p| !p.exists())
+        .unwrap()
+}
+
+/// Lists the leaf parts of an unfetched envelope, one per line, e.g.
+/// `2  application/pdf  53.1 KiB  report.pdf (attachment)`.
+fn unfetched_parts_lines(structure: &BodyStructure) -> Vec<String> {
+    let attachments = structure.attachments();
+    let mut ret = vec![];
+    let mut stack = vec![structure];
+    while let Some(part) = stack.pop() {
+        if part.is_multipart() {
+            stack.extend(part.parts.iter().rev());
+            continue;
+        }
+        let mut line = format!("{}  {}", part.part_number, part.mime_type);
+        if let Some(size) = part.size {
+            line.push_str(&format!("  {}", melib::Bytes(size)));
+        }
+        if let Some(ref filename) = part.filename {
+            line.push_str(&format!("  {}", filename));
+        }
+        if attachments.iter().any(|a| std::ptr::eq(*a, part)) {
+            line.push_str(" (attachment)");
+        }
+        ret.push(line);
+    }
+    ret
+}
+
+/// Decodes the body of a single part, fetched without its MIME headers.
+fn decode_part(part: &BodyStructure, bytes: &[u8]) -> String {
+    let mut raw = format!("Content-Type: {}", part.mime_type).into_bytes();
+    if let Some(ref charset) = part.charset {
+        raw.extend_from_slice(format!("; charset=\"{}\"", charset).as_bytes());
+    }
+    if let Some(ref encoding) = part.encoding {
+        raw.extend_from_slice(format!("\r\nContent-Transfer-Encoding: {}", encoding).as_bytes());
+    }
+    raw.extend_from_slice(b"\r\n\r\n");
+    raw.extend_from_slice(bytes);
+    AttachmentBuilder::new(&raw).build().text()
+}
+
+/// Whether a message of `size` bytes must be fetched on demand instead of automatically.
+fn exceeds_autofetch_size(size: usize, max_autofetch_size: Option<usize>) -> bool {
+    max_autofetch_size.map(|max| size > max).unwrap_or(false)
+}
+
+/// Runs `bin` with `input` on its standard input and returns its standard output.
+fn pipe_to_command(bin: &str, args: &[String], input: Vec<u8>) -> Result<Vec<u8>> {
+    let mut child = Command::new(bin)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .chain_err_summary(|| format!("Could not execute {}", bin))?;
+    let mut stdin = child.stdin.take().unwrap();
+    /* Write in another thread so that a command with large output can't block us */
+    let writer = std::thread::spawn(move || stdin.write_all(&input));
+    let output = child.wait_with_output()?;
+    /* The command may exit without reading all of its input, e.g. `head` */
+    let _ = writer.join();
+    if !output.status.success() {
+        return Err(MeliError::new(format!(
+            "{} exited with {}: {}",
+            bin,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(output.stdout)
+}
+
+/// Digits prefix shortcuts with a count (e.g. the index of the attachment to open), unless they
+/// have been bound to a shortcut themselves.
+fn is_count_digit(c: char, shortcuts: &ShortcutMap) -> bool {
+    c.is_ascii_digit() && !shortcuts.values().any(|k| *k == Key::Char(c))
+}
+
+#[test]
+fn test_is_count_digit() {
+    let mut shortcuts = EnvelopeViewShortcuts::default().key_values();
+    assert!(is_count_digit('0', &shortcuts));
+    assert!(is_count_digit('7', &shortcuts));
+    assert!(!is_count_digit('g', &shortcuts));
+    shortcuts.insert("go_to_url", Key::Char('7'));
+    assert!(!is_count_digit('7', &shortcuts));
+    assert!(is_count_digit('8', &shortcuts));
+}
+
+#[test]
+fn test_unfetched_parts_lines() {
+    let part =
+        |part_number: &str, mime_type: &str, size: usize, filename: Option<&str>| BodyStructure {
+            part_number: part_number.to_string(),
+            mime_type: mime_type.to_string(),
+            charset: None,
+            encoding: None,
+            id: None,
+            size: Some(size),
+            disposition: None,
+            filename: filename.map(str::to_string),
+            parts: vec![],
+        };
+    let structure = BodyStructure {
+        part_number: String::new(),
+        mime_type: "multipart/mixed".to_string(),
+        size: None,
+        parts: vec![
+            part("1", "text/plain", 120, None),
+            part("2", "application/pdf", 54360, Some("report.pdf")),
+        ],
+        ..part("", "", 0, None)
+    };
+    assert_eq!(
+        unfetched_parts_lines(&structure),
+        vec![
+            format!("1  text/plain  {}", melib::Bytes(120)),
+            format!(
+                "2  application/pdf  {}  report.pdf (attachment)",
+                melib::Bytes(54360)
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_decode_part() {
+    let part = BodyStructure {
+        part_number: "1".to_string(),
+        mime_type: "text/plain".to_string(),
+        charset: Some("iso-8859-1".to_string()),
+        encoding: Some("quoted-printable".to_string()),
+        id: None,
+        size: Some(17),
+        disposition: None,
+        filename: None,
+        parts: vec![],
+    };
+    assert_eq!(
+        decode_part(&part, b"caf=E9 au lait=\r\n!"),
+        "caf\u{e9} au lait!"
+    );
+}
+
+#[test]
+fn test_exceeds_autofetch_size() {
+    assert!(!exceeds_autofetch_size(10 * 1024 * 1024, None));
+    assert!(!exceeds_autofetch_size(1024, Some(1024)));
+    assert!(exceeds_autofetch_size(1025, Some(1024)));
+    assert!(!exceeds_autofetch_size(0, Some(0)));
+}
+
+#[test]
+fn test_pipe_to_command() {
+    assert_eq!(
+        pipe_to_command(
+            "tr",
+            &["a-z".to_string(), "A-Z".to_string()],
+            b"hello".to_vec()
+        )
+        .unwrap(),
+        b"HELLO".to_vec()
+    );
+    let input = "line\n".repeat(100_000).into_bytes();
+    assert_eq!(pipe_to_command("cat", &[], input.clone()).unwrap(), input);
+    assert!(pipe_to_command("/nonexistent/command", &[], vec![]).is_err());
+    let err = pipe_to_command(
+        "sh",
+        &["-c".to_string(), "echo oops >&2; exit 3".to_string()],
+        vec![],
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("oops"), "{}", err);
+}
+
+#[test]
+fn test_url_launcher_argv() {
+    assert_eq!(
+        url_launcher_argv("xdg-open", "https://example.com"),
+        vec!["xdg-open", "https://example.com"]
+    );
+    assert_eq!(
+        url_launcher_argv("firefox --new-tab {}", "https://example.com"),
+        vec!["firefox", "--new-tab", "https://example.com"]
+    );
+    assert_eq!(
+        url_launcher_argv("open -a Safari {} --args", "https://example.com"),
+        vec!["open", "-a", "Safari", "https://example.com", "--args"]
+    );
+}
+
+#[test]
+fn test_url_view_text() {
+    use crate::conf::pager::UrlDisplay;
+    let text = "See https://example.com and mail a@example.com.\n";
+    let links = [
+        Link {
+            start: 4,
+            end: 23,
+            kind: LinkKind::Url,
+        },
+        Link {
+            start: 33,
+            end: 46,
+            kind: LinkKind::Email,
+        },
+    ];
+    assert_eq!(
+        url_view_text(text, &links, UrlDisplay::Inline),
+        "See [0]https://example.com and mail [1]a@example.com.\n"
+    );
+    assert_eq!(
+        url_view_text(text, &links, UrlDisplay::References),
+        "See [0] and mail [1].\n\nLinks:\n[0] https://example.com\n[1] a@example.com\n"
+    );
+    assert_eq!(
+        url_view_text("no links\n", &[], UrlDisplay::References),
+        "no links\n"
+    );
+}
+
+#[test]
+fn test_eml_filename() {
+    assert_eq!(eml_filename("Re: meeting notes"), "Re: meeting notes.eml");
+    assert_eq!(eml_filename("../../etc/passwd"), "etcpasswd.eml");
+    assert_eq!(eml_filename("tab\there\r\nnewline "), "tabherenewline.eml");
+    assert_eq!(eml_filename(" \t/ "), "message.eml");
+    assert_eq!(eml_filename(""), "message.eml");
+    let long = "a".repeat(500);
+    assert_eq!(eml_filename(&long), format!("{}.eml", "a".repeat(100)));
+    assert_eq!(eml_filename(&"é".repeat(150)).chars().count(), 104);
+}
+
+#[test]
+fn test_unique_path() {
+    let dir = melib::TempDir::new("unique-path");
+    let path = dir.join("subject.eml");
+    assert_eq!(unique_path(path.clone()), path);
+    std::fs::write(&path, b"").unwrap();
+    assert_eq!(unique_path(path.clone()), dir.join("subject-1.eml"));
+    std::fs::write(dir.join("subject-1.eml"), b"").unwrap();
+    assert_eq!(unique_path(path), dir.join("subject-2.eml"));
+}
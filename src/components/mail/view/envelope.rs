@@ -21,7 +21,9 @@
 
 use super::*;
 use linkify::{Link, LinkFinder};
+use std::collections::HashMap;
 use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
 
 use xdg_utils::query_default_app;
 
@@ -54,6 +56,7 @@ pub struct EnvelopeView {
     mail: Mail,
 
     account_hash: AccountHash,
+    load_remote_content: bool,
     cmd_buf: String,
     id: ComponentId,
 }
@@ -65,6 +68,43 @@ impl fmt::Display for EnvelopeView {
 }
 
 impl EnvelopeView {
+    /// Most messages of a file the `view` command opens, one tab each.
+    pub const MAX_FILE_TABS: usize = 10;
+
+    /// Reads the e-mail messages in `path`, which can be a single message or an mbox file.
+    pub fn read_mail_file(path: &std::path::Path) -> Result<Vec<Mail>> {
+        let bytes = std::fs::read(path)
+            .chain_err_summary(|| format!("Could not read `{}`", path.display()))?;
+        if bytes.starts_with(b"From ") {
+            let index = Arc::new(Mutex::new(HashMap::default()));
+            if let Ok((_, envelopes)) =
+                melib::backends::mbox::mbox_parse(index.clone(), &bytes, 0, None)
+            {
+                let index = index.lock().unwrap();
+                let mails = envelopes
+                    .into_iter()
+                    .filter_map(|envelope| {
+                        let (offset, length) = *index.get(&envelope.hash())?;
+                        Some(Mail {
+                            envelope,
+                            bytes: bytes[offset..offset + length].to_vec(),
+                        })
+                    })
+                    .collect::<Vec<Mail>>();
+                if !mails.is_empty() {
+                    return Ok(mails);
+                }
+            }
+        }
+        let mail = Mail::new(bytes, Some(Flag::SEEN))
+            .ok()
+            .filter(|m| !m.other_headers().is_empty())
+            .ok_or_else(|| {
+                MeliError::new(format!("`{}` is not an e-mail message", path.display()))
+            })?;
+        Ok(vec![mail])
+    }
+
     pub fn new(
         mail: Mail,
         pager: Option<Pager>,
@@ -78,6 +118,7 @@ impl EnvelopeView {
             mode: ViewMode::Normal,
             mail,
             account_hash,
+            load_remote_content: false,
             cmd_buf: String::with_capacity(4),
             id: ComponentId::new_v4(),
         }
@@ -86,7 +127,7 @@ impl EnvelopeView {
     /// Returns the string to be displayed in the Viewer
     fn attachment_to_text(&self, body: &Attachment, context: &mut Context) -> String {
         let finder = LinkFinder::new();
-        let body_text = String::from_utf8_lossy(&decode_rec(
+        let body_text = String::from_utf8_lossy(&decode_rec_lossy(
             &body,
             Some(Box::new(|a: &Attachment, v: &mut Vec<u8>| {
                 if a.content_type().is_text_html() {
@@ -110,15 +151,30 @@ impl EnvelopeView {
                                 return;
                             }
                             Ok(mut html_filter) => {
+                                let (filtered, blocked) = if self.load_remote_content
+                                    || settings.pager.load_remote_content
+                                {
+                                    (v.clone(), false)
+                                } else {
+                                    block_remote_content(v)
+                                };
                                 html_filter
                                     .stdin
                                     .as_mut()
                                     .unwrap()
-                                    .write_all(&v)
+                                    .write_all(&filtered)
                                     .expect("Failed to write to stdin");
                                 *v = format!(
-                            "Text piped through `{}`. Press `v` to open in web browser. \n\n",
-                            filter_invocation
+                            "Text piped through `{}`. Press `v` to open in web browser. {}\n\n",
+                            filter_invocation,
+                            if blocked {
+                                format!(
+                                    "Remote content blocked. Press `{}` to load it. ",
+                                    settings.shortcuts.envelope_view.load_remote_content
+                                )
+                            } else {
+                                String::new()
+                            }
                         )
                                 .into_bytes();
                                 v.extend(html_filter.wait_with_output().unwrap().stdout);
@@ -360,11 +416,37 @@ impl Component for EnvelopeView {
                 return true;
             }
         } else if let Some(ref mut p) = self.pager {
-            if p.process_event(event, context) {
+            /* Keep count prefixes for our own shortcuts; the pager's jumps are handled below. */
+            let is_count = match *event {
+                UIEvent::Input(Key::Char(c)) if c.is_ascii_digit() => true,
+                UIEvent::Input(_) => !self.cmd_buf.is_empty(),
+                _ => false,
+            };
+            if !is_count && p.process_event(event, context) {
                 return true;
             }
         }
         match *event {
+            UIEvent::Input(ref key)
+                if self.pager.is_some()
+                    && !self.cmd_buf.is_empty()
+                    && (*key == context.settings.shortcuts.pager.go_to_line
+                        || *key == context.settings.shortcuts.pager.go_to_percentage) =>
+            {
+                let count = self.cmd_buf.parse::<usize>().unwrap_or(usize::MAX);
+                self.cmd_buf.clear();
+                context
+                    .replies
+                    .push_back(UIEvent::StatusEvent(StatusEvent::BufClear));
+                if let Some(ref mut p) = self.pager {
+                    if *key == context.settings.shortcuts.pager.go_to_line {
+                        p.go_to_line(count);
+                    } else {
+                        p.go_to_percentage(count);
+                    }
+                }
+                return true;
+            }
             UIEvent::Input(Key::Esc) | UIEvent::Input(Key::Alt('')) if !self.cmd_buf.is_empty() => {
                 self.cmd_buf.clear();
                 context
@@ -376,6 +458,15 @@ impl Component for EnvelopeView {
                 self.cmd_buf.push(c);
                 return true;
             }
+            UIEvent::Input(ref key)
+                if self.mode == ViewMode::Normal
+                    && !self.load_remote_content
+                    && *key == context.settings.shortcuts.envelope_view.load_remote_content =>
+            {
+                self.load_remote_content = true;
+                self.dirty = true;
+                return true;
+            }
             UIEvent::Input(Key::Char('r'))
                 if self.mode == ViewMode::Normal || self.mode == ViewMode::Raw =>
             {
@@ -411,7 +502,7 @@ impl Component for EnvelopeView {
                                 self.mode = ViewMode::Subview;
                                 let colors = crate::conf::value(context, "mail.view.body");
                                 self.subview = Some(Box::new(Pager::from_string(
-                                    String::from_utf8_lossy(&decode_rec(u, None)).to_string(),
+                                    String::from_utf8_lossy(&decode_rec_lossy(u, None)).to_string(),
                                     Some(context),
                                     None,
                                     None,
@@ -483,12 +574,12 @@ impl Component for EnvelopeView {
                             }
                         }
                     } else {
-                        context.replies.push_back(UIEvent::StatusEvent(
-                            StatusEvent::DisplayMessage(format!(
+                        context
+                            .replies
+                            .push_back(UIEvent::StatusEvent(StatusEvent::Notify(format!(
                                 "Attachment `{}` not found.",
                                 lidx
-                            )),
-                        ));
+                            ))));
                         return true;
                     }
                 };
@@ -516,17 +607,7 @@ impl Component for EnvelopeView {
                     }
                 };
 
-                match Command::new("xdg-open")
-                    .arg(url)
-                    .stdin(Stdio::piped())
-                    .stdout(Stdio::piped())
-                    .spawn()
-                {
-                    Ok(child) => context.children.push(child),
-                    Err(_err) => context.replies.push_back(UIEvent::StatusEvent(
-                        StatusEvent::DisplayMessage("Failed to start xdg_open".into()),
-                    )),
-                }
+                super::open_url(&url, context);
                 return true;
             }
             UIEvent::Input(Key::Char('u')) => {
@@ -566,3 +647,39 @@ impl Component for EnvelopeView {
         self.id = id;
     }
 }
+
+#[test]
+fn test_envelope_view_read_mail_file() {
+    let dir = melib::TempDir::new("view-file");
+
+    let eml = dir.join("message.eml");
+    std::fs::write(
+        &eml,
+        "From: alice@example.com\nSubject: forwarded\n\nHello\n",
+    )
+    .unwrap();
+    let mails = EnvelopeView::read_mail_file(&eml).unwrap();
+    assert_eq!(mails.len(), 1);
+    assert_eq!(mails[0].subject(), "forwarded");
+
+    let mbox = dir.join("archive.mbox");
+    std::fs::write(
+        &mbox,
+        "From alice@example.com Tue Jul 14 12:00:00 2020\nFrom: alice@example.com\nSubject: first\n\nOne\n\nFrom bob@example.com Tue Jul 14 13:00:00 2020\nFrom: bob@example.com\nSubject: second\n\nTwo\n",
+    )
+    .unwrap();
+    let mails = EnvelopeView::read_mail_file(&mbox).unwrap();
+    assert_eq!(
+        mails
+            .iter()
+            .map(|m| m.subject().to_string())
+            .collect::<Vec<String>>(),
+        vec!["first".to_string(), "second".to_string()]
+    );
+    assert!(String::from_utf8_lossy(&mails[1].bytes).contains("Two"));
+
+    let binary = dir.join("image.png");
+    std::fs::write(&binary, b"\x89PNG\r\n\x1a\n\x00\xff").unwrap();
+    assert!(EnvelopeView::read_mail_file(&binary).is_err());
+    assert!(EnvelopeView::read_mail_file(&dir.join("missing.eml")).is_err());
+}
@@ -23,17 +23,170 @@ use super::*;
 use std::io::Write;
 use std::process::{Command, Stdio};
 
+/// Removes references to remote resources from html: `<img>` tags with a remote `src` or
+/// `srcset`, `<link>` tags, remote `src`, `srcset` and `background` attributes of other tags and
+/// remote `url(...)` values in styles. Returns the filtered html and whether anything was removed.
+pub fn block_remote_content(html: &[u8]) -> (Vec<u8>, bool) {
+    fn is_remote(value: &[u8]) -> bool {
+        let start = value
+            .iter()
+            .position(|&b| !(b.is_ascii_whitespace() || matches!(b, b'"' | b'\'')))
+            .unwrap_or(value.len());
+        let value = &value[start..];
+        value.starts_with(b"http:") || value.starts_with(b"https:") || value.starts_with(b"//")
+    }
+    fn is_tag(input: &[u8], name: &[u8]) -> bool {
+        input.starts_with(name)
+            && matches!(
+                input.get(name.len()),
+                Some(b' ' | b'\t' | b'\r' | b'\n' | b'/' | b'>')
+            )
+    }
+    /* Byte ranges of the `src`, `srcset` and `background` attributes of the lowercased `tag` that
+     * have remote values, including the whitespace before them */
+    fn remote_attributes(tag: &[u8]) -> Vec<std::ops::Range<usize>> {
+        let is_space = |b: u8| b.is_ascii_whitespace();
+        let mut ret = vec![];
+        let mut i = tag
+            .iter()
+            .position(|&b| is_space(b) || matches!(b, b'/' | b'>'))
+            .unwrap_or(tag.len());
+        while i < tag.len() {
+            let attr_start = i;
+            while i < tag.len() && (is_space(tag[i]) || tag[i] == b'/') {
+                i += 1;
+            }
+            let name_start = i;
+            while i < tag.len() && !(is_space(tag[i]) || matches!(tag[i], b'=' | b'/' | b'>')) {
+                i += 1;
+            }
+            let name = &tag[name_start..i];
+            let mut j = i;
+            while j < tag.len() && is_space(tag[j]) {
+                j += 1;
+            }
+            if tag.get(j) != Some(&b'=') {
+                if name.is_empty() {
+                    i += 1;
+                }
+                continue;
+            }
+            j += 1;
+            while j < tag.len() && is_space(tag[j]) {
+                j += 1;
+            }
+            let (value, end) = match tag.get(j) {
+                Some(&quote @ (b'"' | b'\'')) => {
+                    let end = tag[j + 1..]
+                        .iter()
+                        .position(|&b| b == quote)
+                        .map(|p| j + 1 + p)
+                        .unwrap_or(tag.len());
+                    (&tag[j + 1..end], std::cmp::min(end + 1, tag.len()))
+                }
+                _ => {
+                    let end = tag[j..]
+                        .iter()
+                        .position(|&b| is_space(b) || b == b'>')
+                        .map(|p| j + p)
+                        .unwrap_or(tag.len());
+                    (&tag[j..end], end)
+                }
+            };
+            i = end;
+            let remote = match name {
+                b"src" | b"background" => is_remote(value),
+                b"srcset" => value.split(|&b| b == b',').any(is_remote),
+                _ => false,
+            };
+            if remote {
+                ret.push(attr_start..end);
+            }
+        }
+        ret
+    }
+
+    let lowercase = html.to_ascii_lowercase();
+    let mut ret = Vec::with_capacity(html.len());
+    let mut blocked = false;
+    /* Remote attributes left to skip in the current tag, last one first */
+    let mut skip: Vec<std::ops::Range<usize>> = vec![];
+    let mut i = 0;
+    while i < html.len() {
+        if skip.last().map(|r| r.start) == Some(i) {
+            i = skip.pop().unwrap().end;
+            continue;
+        }
+        let rest = &lowercase[i..];
+        if rest.starts_with(b"<") && matches!(rest.get(1), Some(b) if b.is_ascii_alphabetic()) {
+            let end = rest
+                .iter()
+                .position(|&b| b == b'>')
+                .map(|p| i + p + 1)
+                .unwrap_or_else(|| html.len());
+            let remote = remote_attributes(&lowercase[i..end]);
+            if is_tag(rest, b"<link") || (is_tag(rest, b"<img") && !remote.is_empty()) {
+                blocked = true;
+                i = end;
+            } else {
+                blocked |= !remote.is_empty();
+                skip = remote
+                    .into_iter()
+                    .rev()
+                    .map(|r| i + r.start..i + r.end)
+                    .collect();
+                ret.push(html[i]);
+                i += 1;
+            }
+        } else if rest.starts_with(b"url(") && is_remote(&rest[b"url(".len()..]) {
+            blocked = true;
+            ret.extend_from_slice(b"url()");
+            i += rest
+                .iter()
+                .position(|&b| b == b')')
+                .map(|p| p + 1)
+                .unwrap_or_else(|| rest.len());
+        } else {
+            ret.push(html[i]);
+            i += 1;
+        }
+    }
+    (ret, blocked)
+}
+
 #[derive(Debug)]
 pub struct HtmlView {
     pager: Pager,
+    attachment: Attachment,
     bytes: Vec<u8>,
     id: ComponentId,
 }
 
 impl HtmlView {
     pub fn new(body: &Attachment, context: &mut Context) -> Self {
+        Self::new_with_remote_content(body, context.settings.pager.load_remote_content, context)
+    }
+
+    fn new_with_remote_content(
+        body: &Attachment,
+        load_remote_content: bool,
+        context: &mut Context,
+    ) -> Self {
         let id = ComponentId::new_v4();
-        let bytes: Vec<u8> = decode_rec(body, None);
+        let bytes: Vec<u8> = decode_rec_lossy(body, None);
+        let (filtered_bytes, blocked) = if load_remote_content {
+            (bytes.clone(), false)
+        } else {
+            block_remote_content(&bytes)
+        };
+        let banner = if blocked {
+            format!(
+                "Remote content blocked. Press `{}` to load it. ",
+                context.settings.shortcuts.envelope_view.load_remote_content
+            )
+        } else {
+            String::new()
+        };
 
         let settings = &context.settings;
         let mut display_text = if let Some(filter_invocation) = settings.pager.html_filter.as_ref()
@@ -60,11 +213,11 @@ impl HtmlView {
                         .stdin
                         .as_mut()
                         .unwrap()
-                        .write_all(&bytes)
+                        .write_all(&filtered_bytes)
                         .expect("Failed to write to html filter stdin");
                     let mut display_text = format!(
-                        "Text piped through `{}`. Press `v` to open in web browser. \n\n",
-                        filter_invocation
+                        "Text piped through `{}`. Press `v` to open in web browser. {}\n\n",
+                        filter_invocation, banner
                     );
                     display_text.push_str(&String::from_utf8_lossy(
                         &html_filter.wait_with_output().unwrap().stdout,
@@ -82,10 +235,12 @@ impl HtmlView {
                 .stdin
                 .as_mut()
                 .unwrap()
-                .write_all(&bytes)
+                .write_all(&filtered_bytes)
                 .expect("Failed to write to html filter stdin");
-            let mut display_text =
-                String::from("Text piped through `w3m`. Press `v` to open in web browser. \n\n");
+            let mut display_text = format!(
+                "Text piped through `w3m`. Press `v` to open in web browser. {}\n\n",
+                banner
+            );
             display_text.push_str(&String::from_utf8_lossy(
                 &html_filter.wait_with_output().unwrap().stdout,
             ));
@@ -111,7 +266,12 @@ impl HtmlView {
         }
         let colors = crate::conf::value(context, "mail.view.body");
         let pager = Pager::from_string(display_text, None, None, None, colors);
-        HtmlView { pager, bytes, id }
+        HtmlView {
+            pager,
+            attachment: body.clone(),
+            bytes,
+            id,
+        }
     }
 }
 
@@ -130,6 +290,15 @@ impl Component for HtmlView {
             return true;
         }
 
+        if let UIEvent::Input(ref key) = event {
+            if *key == context.settings.shortcuts.envelope_view.load_remote_content {
+                let attachment = self.attachment.clone();
+                *self = Self::new_with_remote_content(&attachment, true, context);
+                self.set_dirty(true);
+                return true;
+            }
+        }
+
         if let UIEvent::Input(Key::Char('v')) = event {
             let binary = query_default_app("text/html");
             if let Ok(binary) = binary {
@@ -182,3 +351,25 @@ impl Component for HtmlView {
         self.id = id;
     }
 }
+
+#[test]
+fn test_block_remote_content() {
+    let html = br#"<html><head><link rel="stylesheet" href="https://example.com/a.css"></head><body style="background:url('http://example.com/bg.png')"><p>Hi</p><IMG SRC="https://tracker.example.com/pixel.gif" width=1><img src="cid:logo@example.com"><img alt="x" src=//cdn.example.com/i.png /></body></html>"#;
+    let (filtered, blocked) = block_remote_content(html);
+    assert!(blocked);
+    assert_eq!(
+        String::from_utf8(filtered).unwrap(),
+        r#"<html><head></head><body style="background:url()"><p>Hi</p><img src="cid:logo@example.com"></body></html>"#
+    );
+
+    let html = br#"<BODY Background = "https://example.com/bg.png" bgcolor=white><img Src = 'http://tracker.example.com/p.gif'><img srcset="a.png 1x, https://example.com/b.png 2x" src="cid:a"><picture><source srcset=//cdn.example.com/a.webp type="image/webp"></picture><td background=http://example.com/td.png>x</td><img data-src="https://example.com/lazy.png" src="cid:b"></BODY>"#;
+    let (filtered, blocked) = block_remote_content(html);
+    assert!(blocked);
+    assert_eq!(
+        String::from_utf8(filtered).unwrap(),
+        r#"<BODY bgcolor=white><picture><source type="image/webp"></picture><td>x</td><img data-src="https://example.com/lazy.png" src="cid:b"></BODY>"#
+    );
+
+    let local = b"<p>No <b>remote</b> content, <img src=\"cid:a\"></p>";
+    assert_eq!(block_remote_content(local), (local.to_vec(), false));
+}
@@ -34,7 +34,9 @@ mod layouts;
 pub use self::layouts::*;
 
 use crate::jobs::JobId;
+use std::borrow::Cow;
 use std::collections::HashSet;
+use std::time::{Duration, Instant};
 
 #[derive(Default, Debug, Clone)]
 pub struct SearchPattern {
@@ -53,6 +55,10 @@ pub struct StatusBar {
     ex_buffer: Field,
     ex_buffer_cmd_history_pos: Option<usize>,
     display_buffer: String,
+    progress_message: Option<String>,
+    /// Messages shown instead of `status_message` until their deadline, oldest first.
+    notifications: VecDeque<(String, Instant)>,
+    notification_timer: crate::jobs::Timer,
     mode: UIMode,
     mouse: bool,
     height: usize,
@@ -79,11 +85,25 @@ impl StatusBar {
             Some(conf::terminal::ProgressSpinnerSequence::Integer(k)) => {
                 progress_spinner.set_kind(*k);
             }
+            Some(conf::terminal::ProgressSpinnerSequence::Named(ref n)) => {
+                if let Err(err) = progress_spinner.set_named_kind(n) {
+                    let _ = context
+                        .sender
+                        .send(ThreadEvent::UIEvent(UIEvent::StatusEvent(
+                            StatusEvent::DisplayMessage(err.to_string()),
+                        )));
+                }
+            }
             Some(conf::terminal::ProgressSpinnerSequence::Custom(ref s)) => {
                 progress_spinner.set_custom_kind(s.clone());
             }
             None => {}
         }
+        let notification_timer = context
+            .job_executor
+            .clone()
+            .create_timer(Self::NOTIFICATION_INTERVAL, Self::NOTIFICATION_INTERVAL);
+        notification_timer.disable();
 
         StatusBar {
             container,
@@ -92,6 +112,9 @@ impl StatusBar {
             ex_buffer: Field::Text(UText::new(String::with_capacity(256)), None),
             ex_buffer_cmd_history_pos: None,
             display_buffer: String::with_capacity(8),
+            progress_message: None,
+            notifications: VecDeque::new(),
+            notification_timer,
             dirty: true,
             mode: UIMode::Normal,
             mouse: context.settings.terminal.use_mouse.is_true(),
@@ -101,8 +124,37 @@ impl StatusBar {
             progress_spinner,
             in_progress_jobs: HashSet::default(),
             done_jobs: HashSet::default(),
-            cmd_history: crate::command::history::old_cmd_history(),
+            cmd_history: crate::command::history::old_cmd_history(
+                context.settings.terminal.command_history_max,
+            ),
+        }
+    }
+
+    const NOTIFICATION_INTERVAL: Duration = Duration::from_secs(1);
+
+    /// Queues `msg` to be shown for `timeout` after the notifications before it expire.
+    fn push_notification(&mut self, msg: String, timeout: Duration) {
+        let start = self
+            .notifications
+            .back()
+            .map(|(_, deadline)| *deadline)
+            .unwrap_or_else(Instant::now)
+            .max(Instant::now());
+        self.notifications.push_back((msg, start + timeout));
+        self.notification_timer.rearm();
+        self.dirty = true;
+    }
+
+    /// Drops the notifications whose deadline has passed. Returns true if any was dropped.
+    fn expire_notifications(&mut self, now: Instant) -> bool {
+        let len = self.notifications.len();
+        while matches!(self.notifications.front(), Some((_, deadline)) if *deadline <= now) {
+            self.notifications.pop_front();
+        }
+        if self.notifications.is_empty() {
+            self.notification_timer.disable();
         }
+        len != self.notifications.len()
     }
 
     fn draw_status_bar(&mut self, grid: &mut CellBuffer, area: Area, context: &mut Context) {
@@ -110,8 +162,16 @@ impl StatusBar {
         if !context.settings.terminal.use_color() {
             attribute.attrs |= Attr::REVERSE;
         }
+        self.expire_notifications(Instant::now());
+        let status = match self.notifications.front() {
+            Some((msg, _)) => {
+                let offset = self.status.find('|').unwrap_or(self.status.len());
+                Cow::Owned(format!("{}| {}", &self.status[..offset], msg))
+            }
+            None => Cow::Borrowed(self.status.as_str()),
+        };
         let (x, y) = write_string_to_grid(
-            &self.status,
+            &status,
             grid,
             attribute.fg,
             attribute.bg,
@@ -126,7 +186,7 @@ impl StatusBar {
                 .set_bg(attribute.bg)
                 .set_attrs(attribute.attrs);
         }
-        let offset = self.status.find('|').unwrap_or_else(|| self.status.len());
+        let offset = status.find('|').unwrap_or_else(|| status.len());
         if y < get_y(bottom_right!(area)) + 1 {
             for x in get_x(upper_left!(area))
                 ..std::cmp::min(
@@ -159,6 +219,17 @@ impl StatusBar {
                 break;
             }
         }
+        if let Some(ref progress_message) = self.progress_message {
+            /* Shown to the left of the display buffer */
+            let x = x.saturating_sub(self.display_buffer.chars().count() + 1);
+            for (idx, c) in progress_message.chars().rev().enumerate() {
+                if let Some(cell) = grid.get_mut(x.saturating_sub(idx).saturating_sub(1), y) {
+                    cell.set_ch(c);
+                } else {
+                    break;
+                }
+            }
+        }
 
         context.dirty_areas.push_back(area);
     }
@@ -269,6 +340,18 @@ impl Component for StatusBar {
                     }
                 }));
                 */
+                if let Some(prefix) = self.ex_buffer.as_str().strip_prefix("theme ") {
+                    let prefix = prefix.trim_start();
+                    suggestions.extend(
+                        context
+                            .settings
+                            .terminal
+                            .themes
+                            .names()
+                            .filter(|name| name.starts_with(prefix) && *name != prefix)
+                            .map(|name| format!("theme {}", name).into()),
+                    );
+                }
                 if let Some(p) = self
                     .ex_buffer
                     .as_str()
@@ -481,12 +564,18 @@ impl Component for StatusBar {
                                 .replies
                                 .push_back(UIEvent::Command(self.ex_buffer.as_str().to_string()));
                         }
+                        let max = context.settings.terminal.command_history_max;
                         if parse_command(&self.ex_buffer.as_str().as_bytes()).is_ok()
-                            && self.cmd_history.last().map(String::as_str)
-                                != Some(self.ex_buffer.as_str())
+                            && crate::command::history::push_history(
+                                &mut self.cmd_history,
+                                self.ex_buffer.as_str().to_string(),
+                                max,
+                            )
                         {
-                            crate::command::history::log_cmd(self.ex_buffer.as_str().to_string());
-                            self.cmd_history.push(self.ex_buffer.as_str().to_string());
+                            crate::command::history::log_cmd(
+                                self.ex_buffer.as_str().to_string(),
+                                max,
+                            );
                         }
                         self.ex_buffer.clear();
                         self.ex_buffer_cmd_history_pos.take();
@@ -657,6 +746,16 @@ impl Component for StatusBar {
                 );
                 self.dirty = true;
             }
+            UIEvent::StatusEvent(StatusEvent::Notify(ref msg)) => {
+                self.push_notification(
+                    msg.clone(),
+                    Duration::from_secs(context.settings.terminal.status_notification_timeout),
+                );
+            }
+            UIEvent::StatusEvent(StatusEvent::Progress(ref msg)) => {
+                self.progress_message = msg.clone();
+                self.dirty = true;
+            }
             UIEvent::StatusEvent(StatusEvent::SetMouse(val)) => {
                 self.mouse = *val;
                 self.status = format!(
@@ -695,6 +794,12 @@ impl Component for StatusBar {
                 self.progress_spinner.set_dirty(true);
                 self.in_progress_jobs.insert(*job_id);
             }
+            UIEvent::Timer(id) if *id == self.notification_timer.id() => {
+                if self.expire_notifications(Instant::now()) {
+                    self.dirty = true;
+                }
+                return true;
+            }
             UIEvent::Timer(_) => {
                 if self.progress_spinner.process_event(event, context) {
                     return true;
@@ -741,6 +846,8 @@ pub struct Tabbed {
     help_content: CellBuffer,
     help_curr_views: ShortcutMaps,
     help_search: Option<SearchPattern>,
+    /// Search of a closed help overlay, restored when it is shown again
+    help_last_search: Option<SearchPattern>,
 
     dirty: bool,
     id: ComponentId,
@@ -757,6 +864,7 @@ impl Tabbed {
             help_content: CellBuffer::default(),
             help_screen_cursor: (0, 0),
             help_search: None,
+            help_last_search: None,
             pinned,
             children,
             cursor_pos: 0,
@@ -847,6 +955,29 @@ impl Tabbed {
     pub fn add_component(&mut self, new: Box<dyn Component>) {
         self.children.push(new);
     }
+
+    /// Swaps the current tab with its left (or right) neighbour. Pinned tabs can't be moved and
+    /// other tabs can't be moved before them. Returns true if the tab was moved.
+    fn move_tab(&mut self, right: bool) -> bool {
+        if self.cursor_pos < self.pinned {
+            return false;
+        }
+        let new_pos = if right {
+            if self.cursor_pos + 1 >= self.children.len() {
+                return false;
+            }
+            self.cursor_pos + 1
+        } else {
+            if self.cursor_pos <= self.pinned {
+                return false;
+            }
+            self.cursor_pos - 1
+        };
+        self.children.swap(self.cursor_pos, new_pos);
+        self.cursor_pos = new_pos;
+        self.set_dirty(true);
+        true
+    }
 }
 
 impl fmt::Display for Tabbed {
@@ -916,7 +1047,11 @@ impl Component for Tabbed {
             }
             if (children_maps == self.help_curr_views) && must_redraw_shortcuts {
                 let (width, height) = self.help_content.size();
-                let (cols, rows) = (width!(area), height!(area));
+                let (cols, mut rows) = (width!(area), height!(area));
+                /* Leave room for the horizontal scrollbar */
+                if cols < width {
+                    rows = rows.saturating_sub(1);
+                }
                 copy_area(
                     grid,
                     &self.help_content,
@@ -938,6 +1073,19 @@ impl Component for Tabbed {
                         ),
                     ),
                 );
+                if cols < width {
+                    ScrollBar::default().set_show_arrows(true).draw_horizontal(
+                        grid,
+                        (
+                            set_y(upper_left!(area), get_y(bottom_right!(area))),
+                            bottom_right!(area),
+                        ),
+                        context,
+                        self.help_screen_cursor.0,
+                        cols,
+                        width,
+                    );
+                }
                 self.dirty = false;
                 return;
             }
@@ -963,7 +1111,11 @@ impl Component for Tabbed {
                 CellBuffer::new_with_context(max_width, max_length + 2, Cell::default(), context);
             self.help_content.set_growable(true);
             let (width, height) = self.help_content.size();
-            let (cols, rows) = (width!(area), height!(area));
+            let (cols, mut rows) = (width!(area), height!(area));
+            /* Leave room for the horizontal scrollbar */
+            if cols < width {
+                rows = rows.saturating_sub(1);
+            }
             if cols == 0 || rows == 0 {
                 return;
             }
@@ -1125,6 +1277,19 @@ impl Component for Tabbed {
                     ),
                 ),
             );
+            if cols < width {
+                ScrollBar::default().set_show_arrows(true).draw_horizontal(
+                    grid,
+                    (
+                        set_y(upper_left!(area), get_y(bottom_right!(area))),
+                        bottom_right!(area),
+                    ),
+                    context,
+                    self.help_screen_cursor.0,
+                    cols,
+                    width,
+                );
+            }
         }
         self.dirty = false;
     }
@@ -1160,10 +1325,24 @@ impl Component for Tabbed {
                 self.set_dirty(true);
                 return true;
             }
+            UIEvent::Input(ref key)
+                if shortcut!(key == shortcuts["general"]["move_tab_left"])
+                    || shortcut!(key == shortcuts["general"]["move_tab_right"]) =>
+            {
+                self.move_tab(shortcut!(key == shortcuts["general"]["move_tab_right"]));
+                return true;
+            }
             UIEvent::Input(ref key) if shortcut!(key == shortcuts["general"]["toggle_help"]) => {
                 if self.show_shortcuts {
                     /* children below the shortcut overlay must be redrawn */
                     self.set_dirty(true);
+                    self.help_last_search = self.help_search.take();
+                } else if let Some(mut search) = self.help_last_search.take() {
+                    /* help_content is rebuilt on the next draw, so the search positions have to
+                     * be recalculated. Keep the cursor so that n/N continue from the last match */
+                    search.positions.clear();
+                    search.movement = Some(PageMovement::Home);
+                    self.help_search = Some(search);
                 }
                 self.show_shortcuts = !self.show_shortcuts;
                 self.dirty = true;
@@ -1190,13 +1369,30 @@ impl Component for Tabbed {
                 self.set_dirty(true);
                 return true;
             }
-            UIEvent::Action(Tab(Kill(id))) => {
-                if self.pinned > self.cursor_pos {
-                    return true;
+            UIEvent::Action(Tab(CloseRight)) => {
+                /* Pinned tabs are never closed. Each tab replies with Tab(Kill) when it agrees to
+                 * close, e.g. a draft with unsaved changes asks first. */
+                let start = std::cmp::max(self.cursor_pos + 1, self.pinned);
+                for c in self.children.iter_mut().skip(start) {
+                    let id = c.id();
+                    c.kill(id, context);
                 }
+                return true;
+            }
+            UIEvent::Action(Tab(Kill(id))) => {
                 if let Some(c_idx) = self.children.iter().position(|x| x.id() == *id) {
+                    if c_idx < self.pinned {
+                        return true;
+                    }
                     self.children.remove(c_idx);
-                    self.cursor_pos = 0;
+                    /* Stay on the current tab unless it was the one closed */
+                    if c_idx == self.cursor_pos {
+                        self.cursor_pos = 0;
+                    } else if c_idx < self.cursor_pos {
+                        self.cursor_pos -= 1;
+                    }
+                    self.cursor_pos =
+                        std::cmp::min(self.cursor_pos, self.children.len().saturating_sub(1));
                     self.set_dirty(true);
                     let mut children_maps = self.children[self.cursor_pos].get_shortcuts(context);
                     children_maps.extend(self.get_shortcuts(context));
@@ -1247,6 +1443,7 @@ impl Component for Tabbed {
             }
             UIEvent::Input(Key::Esc) if self.show_shortcuts && self.help_search.is_some() => {
                 self.help_search = None;
+                self.help_last_search = None;
                 self.dirty = true;
                 return true;
             }
@@ -1367,6 +1564,15 @@ pub struct Selector<T: 'static + PartialEq + Debug + Clone + Sync + Send, F: 'st
     done_fn: F,
     dirty: bool,
     id: ComponentId,
+    /// If set, typing filters the entries. See `Selector::new_filterable`.
+    filter: Option<SelectorFilter<T>>,
+}
+
+/// Query and unfiltered entries of a `Selector` whose entries can be filtered by typing.
+struct SelectorFilter<T> {
+    title: String,
+    query: String,
+    entries: Vec<(T, String)>,
 }
 
 pub type UIConfirmationDialog = Selector<
@@ -1410,269 +1616,7 @@ impl<T: 'static + PartialEq + Debug + Clone + Sync + Send> Component for UIDialo
         context.dirty_areas.push_back(area);
     }
     fn process_event(&mut self, event: &mut UIEvent, context: &mut Context) -> bool {
-        let (width, height) = self.content.size();
-        let shortcuts = self.get_shortcuts(context);
-        let mut highlighted_attrs = crate::conf::value(context, "widgets.options.highlighted");
-        if !context.settings.terminal.use_color() {
-            highlighted_attrs.attrs |= Attr::REVERSE;
-        }
-        match (event, self.cursor) {
-            (UIEvent::Input(Key::Char('\n')), _) if self.single_only => {
-                /* User can only select one entry, so Enter key finalises the selection */
-                self.done = true;
-                if let Some(event) = self.done() {
-                    context.replies.push_back(event);
-                    context.replies.push_back(UIEvent::ComponentKill(self.id));
-                }
-                return true;
-            }
-            (UIEvent::Input(Key::Char('\n')), SelectorCursor::Entry(c)) if !self.single_only => {
-                /* User can select multiple entries, so Enter key toggles the entry under the
-                 * cursor */
-                self.entries[c].1 = !self.entries[c].1;
-                if self.entries[c].1 {
-                    write_string_to_grid(
-                        "x",
-                        &mut self.content,
-                        Color::Default,
-                        Color::Default,
-                        Attr::DEFAULT,
-                        ((3, c + 2), (width - 2, c + 2)),
-                        None,
-                    );
-                } else {
-                    write_string_to_grid(
-                        " ",
-                        &mut self.content,
-                        Color::Default,
-                        Color::Default,
-                        Attr::DEFAULT,
-                        ((3, c + 2), (width - 2, c + 2)),
-                        None,
-                    );
-                }
-                self.dirty = true;
-                return true;
-            }
-            (UIEvent::Input(Key::Char('\n')), SelectorCursor::Ok) if !self.single_only => {
-                self.done = true;
-                if let Some(event) = self.done() {
-                    context.replies.push_back(event);
-                    context.replies.push_back(UIEvent::ComponentKill(self.id));
-                }
-                return true;
-            }
-            (UIEvent::Input(Key::Esc), _) => {
-                for e in self.entries.iter_mut() {
-                    e.1 = false;
-                }
-                self.done = true;
-                if let Some(event) = self.done() {
-                    context.replies.push_back(event);
-                    context.replies.push_back(UIEvent::ComponentKill(self.id));
-                }
-                return true;
-            }
-            (UIEvent::Input(Key::Char('\n')), SelectorCursor::Cancel) if !self.single_only => {
-                for e in self.entries.iter_mut() {
-                    e.1 = false;
-                }
-                self.done = true;
-                if let Some(event) = self.done() {
-                    context.replies.push_back(event);
-                    context.replies.push_back(UIEvent::ComponentKill(self.id));
-                }
-                return true;
-            }
-            (UIEvent::Input(Key::Up), SelectorCursor::Entry(c)) if c > 0 => {
-                if self.single_only {
-                    // Redraw selection
-                    for c in self.content.row_iter(2..(width - 2), c + 2) {
-                        self.content[c]
-                            .set_fg(Color::Default)
-                            .set_bg(Color::Default)
-                            .set_attrs(Attr::DEFAULT);
-                    }
-                    for c in self.content.row_iter(2..(width - 2), c + 1) {
-                        self.content[c]
-                            .set_fg(highlighted_attrs.fg)
-                            .set_bg(highlighted_attrs.bg)
-                            .set_attrs(highlighted_attrs.attrs);
-                    }
-                    self.entries[c].1 = false;
-                    self.entries[c - 1].1 = true;
-                } else {
-                    // Redraw cursor
-                    for c in self.content.row_iter(2..4, c + 2) {
-                        self.content[c]
-                            .set_fg(Color::Default)
-                            .set_bg(Color::Default)
-                            .set_attrs(Attr::DEFAULT);
-                    }
-                    for c in self.content.row_iter(2..4, c + 1) {
-                        self.content[c]
-                            .set_fg(highlighted_attrs.fg)
-                            .set_bg(highlighted_attrs.bg)
-                            .set_attrs(highlighted_attrs.attrs);
-                    }
-                }
-                self.cursor = SelectorCursor::Entry(c - 1);
-                self.dirty = true;
-                return true;
-            }
-            (UIEvent::Input(ref key), SelectorCursor::Ok)
-            | (UIEvent::Input(ref key), SelectorCursor::Cancel)
-                if shortcut!(key == shortcuts["general"]["scroll_up"]) =>
-            {
-                for c in self.content.row_iter(
-                    ((width - "OK    Cancel".len()) / 2)..(width - 1),
-                    height - 3,
-                ) {
-                    self.content[c]
-                        .set_fg(Color::Default)
-                        .set_bg(Color::Default)
-                        .set_attrs(Attr::DEFAULT);
-                }
-                let c = self.entries.len().saturating_sub(1);
-                self.cursor = SelectorCursor::Entry(c);
-                let mut highlighted_attrs =
-                    crate::conf::value(context, "widgets.options.highlighted");
-                if !context.settings.terminal.use_color() {
-                    highlighted_attrs.attrs |= Attr::REVERSE;
-                }
-                for c in self.content.row_iter(2..4, c + 2) {
-                    self.content[c]
-                        .set_fg(highlighted_attrs.fg)
-                        .set_bg(highlighted_attrs.bg)
-                        .set_attrs(highlighted_attrs.attrs);
-                }
-                self.dirty = true;
-                return true;
-            }
-            (UIEvent::Input(ref key), SelectorCursor::Entry(c))
-                if c < self.entries.len().saturating_sub(1)
-                    && shortcut!(key == shortcuts["general"]["scroll_down"]) =>
-            {
-                if self.single_only {
-                    // Redraw selection
-                    for c in self.content.row_iter(2..(width - 2), c + 2) {
-                        self.content[c]
-                            .set_fg(Color::Default)
-                            .set_bg(Color::Default)
-                            .set_attrs(Attr::DEFAULT);
-                    }
-                    for c in self.content.row_iter(2..(width - 2), c + 3) {
-                        self.content[c]
-                            .set_fg(highlighted_attrs.fg)
-                            .set_bg(highlighted_attrs.bg)
-                            .set_attrs(highlighted_attrs.attrs);
-                    }
-                    self.entries[c].1 = false;
-                    self.entries[c + 1].1 = true;
-                } else {
-                    // Redraw cursor
-                    for c in self.content.row_iter(2..4, c + 2) {
-                        self.content[c]
-                            .set_fg(Color::Default)
-                            .set_bg(Color::Default)
-                            .set_attrs(Attr::DEFAULT);
-                    }
-                    for c in self.content.row_iter(2..4, c + 3) {
-                        self.content[c]
-                            .set_fg(highlighted_attrs.fg)
-                            .set_bg(highlighted_attrs.bg)
-                            .set_attrs(highlighted_attrs.attrs);
-                    }
-                }
-                self.cursor = SelectorCursor::Entry(c + 1);
-                self.dirty = true;
-                return true;
-            }
-            (UIEvent::Input(ref key), SelectorCursor::Entry(c))
-                if !self.single_only && shortcut!(key == shortcuts["general"]["scroll_down"]) =>
-            {
-                self.cursor = SelectorCursor::Ok;
-                for c in self.content.row_iter(2..4, c + 2) {
-                    self.content[c]
-                        .set_fg(Color::Default)
-                        .set_bg(Color::Default)
-                        .set_attrs(Attr::DEFAULT);
-                }
-                for c in self.content.row_iter(
-                    ((width - "OK    Cancel".len()) / 2)..((width - "OK    Cancel".len()) / 2 + 1),
-                    height - 3,
-                ) {
-                    self.content[c]
-                        .set_fg(highlighted_attrs.fg)
-                        .set_bg(highlighted_attrs.bg)
-                        .set_attrs(highlighted_attrs.attrs);
-                }
-                self.dirty = true;
-                return true;
-            }
-            (UIEvent::Input(ref key), SelectorCursor::Ok)
-                if shortcut!(key == shortcuts["general"]["scroll_right"]) =>
-            {
-                self.cursor = SelectorCursor::Cancel;
-                for c in self.content.row_iter(
-                    ((width - "OK    Cancel".len()) / 2)..((width - "OK    Cancel".len()) / 2 + 1),
-                    height - 3,
-                ) {
-                    self.content[c]
-                        .set_fg(Color::Default)
-                        .set_bg(Color::Default)
-                        .set_attrs(Attr::DEFAULT);
-                }
-                for c in self.content.row_iter(
-                    ((width - "OK    Cancel".len()) / 2 + 6)
-                        ..((width - "OK    Cancel".len()) / 2 + 11),
-                    height - 3,
-                ) {
-                    self.content[c]
-                        .set_fg(highlighted_attrs.fg)
-                        .set_bg(highlighted_attrs.bg)
-                        .set_attrs(highlighted_attrs.attrs);
-                }
-                self.dirty = true;
-                return true;
-            }
-            (UIEvent::Input(ref key), SelectorCursor::Cancel)
-                if shortcut!(key == shortcuts["general"]["scroll_left"]) =>
-            {
-                self.cursor = SelectorCursor::Ok;
-                for c in self.content.row_iter(
-                    ((width - "OK    Cancel".len()) / 2)..((width - "OK    Cancel".len()) / 2 + 1),
-                    height - 3,
-                ) {
-                    self.content[c]
-                        .set_fg(highlighted_attrs.fg)
-                        .set_bg(highlighted_attrs.bg)
-                        .set_attrs(highlighted_attrs.attrs);
-                }
-                change_colors(
-                    &mut self.content,
-                    (
-                        ((width - "OK    Cancel".len()) / 2 + 6, height - 3),
-                        ((width - "OK    Cancel".len()) / 2 + 11, height - 3),
-                    ),
-                    Color::Default,
-                    Color::Default,
-                );
-                self.dirty = true;
-                return true;
-            }
-            (UIEvent::Input(ref key), _)
-                if shortcut!(key == shortcuts["general"]["scroll_left"])
-                    || shortcut!(key == shortcuts["general"]["scroll_right"])
-                    || shortcut!(key == shortcuts["general"]["scroll_up"])
-                    || shortcut!(key == shortcuts["general"]["scroll_down"]) =>
-            {
-                return true
-            }
-            _ => {}
-        }
-
-        false
+        self.process_selector_event(event, context, Self::done)
     }
     fn get_shortcuts(&self, context: &Context) -> ShortcutMaps {
         let mut map = ShortcutMaps::default();
@@ -1702,8 +1646,227 @@ impl Component for UIConfirmationDialog {
         context.dirty_areas.push_back(area);
     }
     fn process_event(&mut self, event: &mut UIEvent, context: &mut Context) -> bool {
+        self.process_selector_event(event, context, Self::done)
+    }
+    fn get_shortcuts(&self, context: &Context) -> ShortcutMaps {
+        let mut map = ShortcutMaps::default();
+        map.insert("general", context.settings.shortcuts.general.key_values());
+        map
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+    fn set_dirty(&mut self, value: bool) {
+        self.dirty = value;
+    }
+
+    fn id(&self) -> ComponentId {
+        self.id
+    }
+    fn set_id(&mut self, id: ComponentId) {
+        self.id = id;
+    }
+}
+
+impl<T: PartialEq + Debug + Clone + Sync + Send, F: 'static + Sync + Send> Selector<T, F> {
+    pub fn new(
+        title: &str,
+        entries: Vec<(T, String)>,
+        single_only: bool,
+        done_fn: F,
+        context: &Context,
+    ) -> Selector<T, F> {
+        Self::new_with_defaults(
+            title,
+            entries
+                .into_iter()
+                .map(|(id, label)| (id, label, false))
+                .collect(),
+            single_only,
+            done_fn,
+            context,
+        )
+    }
+
+    /// Like `Selector::new`, but the third element of each entry sets its initial checked state.
+    /// If `single_only` is true and no entry is checked, the first entry is selected.
+    pub fn new_with_defaults(
+        title: &str,
+        entries: Vec<(T, String, bool)>,
+        single_only: bool,
+        done_fn: F,
+        context: &Context,
+    ) -> Selector<T, F> {
+        /* In single-only mode the selected entry is highlighted and the cursor starts on it */
+        let selected = if single_only {
+            entries.iter().position(|e| e.2).unwrap_or(0)
+        } else {
+            0
+        };
+        let content = Self::render_content(title, &entries, single_only, selected, context);
+        let identifiers: Vec<(T, bool)> = entries
+            .into_iter()
+            .enumerate()
+            .map(|(i, (id, _, checked))| (id, if single_only { i == selected } else { checked }))
+            .collect();
+
+        Selector {
+            single_only,
+            entries: identifiers,
+            content,
+            cursor: SelectorCursor::Entry(selected),
+            done: false,
+            done_fn,
+            dirty: true,
+            id: ComponentId::new_v4(),
+            filter: None,
+        }
+    }
+
+    /// Like `Selector::new` with `single_only` set, but typing filters the entries with a fuzzy
+    /// match on their labels. Backspace removes the last character of the query; an empty query
+    /// shows all entries.
+    pub fn new_filterable(
+        title: &str,
+        entries: Vec<(T, String)>,
+        done_fn: F,
+        context: &Context,
+    ) -> Selector<T, F> {
+        let mut ret = Self::new(title, entries.clone(), true, done_fn, context);
+        ret.filter = Some(SelectorFilter {
+            title: title.to_string(),
+            query: String::new(),
+            entries,
+        });
+        ret
+    }
+
+    /// Sets the query of a filterable selector and redraws its matching entries, best matches
+    /// first.
+    fn set_filter_query(&mut self, query: String, context: &Context) {
+        let filter = match self.filter.as_mut() {
+            Some(f) => f,
+            None => return,
+        };
+        let mut matches = filter
+            .entries
+            .iter()
+            .filter_map(|(id, label)| {
+                melib::text_processing::search::fuzzy_match(label, &query)
+                    .map(|score| (score, id.clone(), label.clone()))
+            })
+            .collect::<Vec<(usize, T, String)>>();
+        /* Stable sort keeps the original order between equally good matches */
+        matches.sort_by_key(|m| m.0);
+        let title = if query.is_empty() {
+            filter.title.clone()
+        } else if matches.is_empty() {
+            format!("{}: {} (no matches)", filter.title, query)
+        } else {
+            format!("{}: {}", filter.title, query)
+        };
+        filter.query = query;
+        let entries = matches
+            .into_iter()
+            .enumerate()
+            .map(|(i, (_, id, label))| (id, label, i == 0))
+            .collect::<Vec<(T, String, bool)>>();
+        self.content = Self::render_content(&title, &entries, true, 0, context);
+        self.entries = entries
+            .into_iter()
+            .map(|(id, _, selected)| (id, selected))
+            .collect();
+        self.cursor = SelectorCursor::Entry(0);
+        self.dirty = true;
+    }
+
+    /// Returns where Tab (`forward`) or Shift-Tab moves the cursor: entries, then OK, then Cancel
+    /// and back to the first entry. Without buttons (`single_only`) it cycles through the entries.
+    fn tab_cursor(&self, forward: bool) -> SelectorCursor {
+        let last = self.entries.len().saturating_sub(1);
+        match (self.cursor, forward) {
+            (SelectorCursor::Entry(c), true) if self.single_only => {
+                SelectorCursor::Entry(if c < last { c + 1 } else { 0 })
+            }
+            (SelectorCursor::Entry(c), false) if self.single_only => {
+                SelectorCursor::Entry(if c > 0 { c - 1 } else { last })
+            }
+            (SelectorCursor::Entry(_), true) => SelectorCursor::Ok,
+            (SelectorCursor::Ok, true) => SelectorCursor::Cancel,
+            (SelectorCursor::Cancel, true) => SelectorCursor::Entry(0),
+            (SelectorCursor::Entry(_), false) => SelectorCursor::Cancel,
+            (SelectorCursor::Cancel, false) => SelectorCursor::Ok,
+            (SelectorCursor::Ok, false) => SelectorCursor::Entry(last),
+        }
+    }
+
+    /// Moves the cursor to `cursor` and redraws the highlight. In `single_only` mode the entry
+    /// under the cursor is also the selected one.
+    fn move_cursor(&mut self, cursor: SelectorCursor, context: &Context) {
         let (width, height) = self.content.size();
-        let shortcuts = self.get_shortcuts(context);
+        let mut highlighted_attrs = crate::conf::value(context, "widgets.options.highlighted");
+        if !context.settings.terminal.use_color() {
+            highlighted_attrs.attrs |= Attr::REVERSE;
+        }
+        let buttons_start = (width - "OK    Cancel".len()) / 2;
+        for &(cursor, highlight) in &[(self.cursor, false), (cursor, true)] {
+            let (cols, row) = match cursor {
+                SelectorCursor::Entry(c) if c >= self.entries.len() => continue,
+                SelectorCursor::Entry(c) if self.single_only => {
+                    self.entries[c].1 = highlight;
+                    (2..(width - 2), c + 2)
+                }
+                SelectorCursor::Entry(c) => (2..4, c + 2),
+                SelectorCursor::Ok => (buttons_start..(buttons_start + 1), height - 3),
+                SelectorCursor::Cancel => ((buttons_start + 6)..(buttons_start + 11), height - 3),
+            };
+            for c in self.content.row_iter(cols, row) {
+                if highlight {
+                    self.content[c]
+                        .set_fg(highlighted_attrs.fg)
+                        .set_bg(highlighted_attrs.bg)
+                        .set_attrs(highlighted_attrs.attrs);
+                } else {
+                    self.content[c]
+                        .set_fg(Color::Default)
+                        .set_bg(Color::Default)
+                        .set_attrs(Attr::DEFAULT);
+                }
+            }
+        }
+        self.cursor = cursor;
+        self.dirty = true;
+    }
+
+    /// Handles the input shared by all selector dialogs: filtering, moving the cursor, toggling
+    /// entries and the OK/Cancel buttons. `done` is called once the selection is finalised.
+    fn process_selector_event(
+        &mut self,
+        event: &mut UIEvent,
+        context: &mut Context,
+        done: fn(&mut Self) -> Option<UIEvent>,
+    ) -> bool {
+        if let Some(query) = self.filter.as_ref().map(|f| &f.query) {
+            match event {
+                UIEvent::Input(Key::Char(c)) if *c != '\n' && *c != '\t' => {
+                    let mut query = query.clone();
+                    query.push(*c);
+                    self.set_filter_query(query, context);
+                    return true;
+                }
+                UIEvent::Input(Key::Backspace) => {
+                    let mut query = query.clone();
+                    query.pop();
+                    self.set_filter_query(query, context);
+                    return true;
+                }
+                _ => {}
+            }
+        }
+        let (width, height) = self.content.size();
+        let mut shortcuts = ShortcutMaps::default();
+        shortcuts.insert("general", context.settings.shortcuts.general.key_values());
         let mut highlighted_attrs = crate::conf::value(context, "widgets.options.highlighted");
         if !context.settings.terminal.use_color() {
             highlighted_attrs.attrs |= Attr::REVERSE;
@@ -1712,7 +1875,7 @@ impl Component for UIConfirmationDialog {
             (UIEvent::Input(Key::Char('\n')), _) if self.single_only => {
                 /* User can only select one entry, so Enter key finalises the selection */
                 self.done = true;
-                if let Some(event) = self.done() {
+                if let Some(event) = done(self) {
                     context.replies.push_back(event);
                     context.replies.push_back(UIEvent::ComponentKill(self.id));
                 }
@@ -1748,7 +1911,7 @@ impl Component for UIConfirmationDialog {
             }
             (UIEvent::Input(Key::Char('\n')), SelectorCursor::Ok) if !self.single_only => {
                 self.done = true;
-                if let Some(event) = self.done() {
+                if let Some(event) = done(self) {
                     context.replies.push_back(event);
                     context.replies.push_back(UIEvent::ComponentKill(self.id));
                 }
@@ -1759,7 +1922,7 @@ impl Component for UIConfirmationDialog {
                     e.1 = false;
                 }
                 self.done = true;
-                if let Some(event) = self.done() {
+                if let Some(event) = done(self) {
                     context.replies.push_back(event);
                     context.replies.push_back(UIEvent::ComponentKill(self.id));
                 }
@@ -1770,12 +1933,22 @@ impl Component for UIConfirmationDialog {
                     e.1 = false;
                 }
                 self.done = true;
-                if let Some(event) = self.done() {
+                if let Some(event) = done(self) {
                     context.replies.push_back(event);
                     context.replies.push_back(UIEvent::ComponentKill(self.id));
                 }
                 return true;
             }
+            (UIEvent::Input(Key::Char('\t')), _) => {
+                let cursor = self.tab_cursor(true);
+                self.move_cursor(cursor, context);
+                return true;
+            }
+            (UIEvent::Input(Key::BackTab), _) => {
+                let cursor = self.tab_cursor(false);
+                self.move_cursor(cursor, context);
+                return true;
+            }
             (UIEvent::Input(Key::Up), SelectorCursor::Entry(c)) if c > 0 => {
                 if self.single_only {
                     // Redraw selection
@@ -1966,35 +2139,14 @@ impl Component for UIConfirmationDialog {
 
         false
     }
-    fn get_shortcuts(&self, context: &Context) -> ShortcutMaps {
-        let mut map = ShortcutMaps::default();
-        map.insert("general", context.settings.shortcuts.general.key_values());
-        map
-    }
-
-    fn is_dirty(&self) -> bool {
-        self.dirty
-    }
-    fn set_dirty(&mut self, value: bool) {
-        self.dirty = value;
-    }
 
-    fn id(&self) -> ComponentId {
-        self.id
-    }
-    fn set_id(&mut self, id: ComponentId) {
-        self.id = id;
-    }
-}
-
-impl<T: PartialEq + Debug + Clone + Sync + Send, F: 'static + Sync + Send> Selector<T, F> {
-    pub fn new(
+    fn render_content(
         title: &str,
-        entries: Vec<(T, String)>,
+        entries: &[(T, String, bool)],
         single_only: bool,
-        done_fn: F,
+        selected: usize,
         context: &Context,
-    ) -> Selector<T, F> {
+    ) -> CellBuffer {
         let width = std::cmp::max(
             "OK    Cancel".len(),
             std::cmp::max(
@@ -2116,12 +2268,12 @@ impl<T: PartialEq + Debug + Clone + Sync + Send, F: 'static + Sync + Send> Selec
                     &e.1,
                     &mut content,
                     Color::Default,
-                    if i == 0 {
+                    if i == selected {
                         highlighted_attrs.bg
                     } else {
                         Color::Default
                     },
-                    if i == 0 {
+                    if i == selected {
                         highlighted_attrs.attrs
                     } else {
                         Attr::DEFAULT
@@ -2133,7 +2285,7 @@ impl<T: PartialEq + Debug + Clone + Sync + Send, F: 'static + Sync + Send> Selec
         } else {
             for (i, e) in entries.iter().enumerate() {
                 write_string_to_grid(
-                    &format!("[ ] {}", e.1),
+                    &format!("[{}] {}", if e.2 { "x" } else { " " }, e.1),
                     &mut content,
                     Color::Default,
                     Color::Default,
@@ -2166,23 +2318,7 @@ impl<T: PartialEq + Debug + Clone + Sync + Send, F: 'static + Sync + Send> Selec
                 None,
             );
         }
-        let mut identifiers: Vec<(T, bool)> =
-            entries.into_iter().map(|(id, _)| (id, false)).collect();
-        if single_only {
-            /* set default option */
-            identifiers[0].1 = true;
-        }
-
-        Selector {
-            single_only,
-            entries: identifiers,
-            content,
-            cursor: SelectorCursor::Entry(0),
-            done: false,
-            done_fn,
-            dirty: true,
-            id: ComponentId::new_v4(),
-        }
+        content
     }
 
     pub fn is_done(&self) -> bool {
@@ -2286,8 +2422,25 @@ impl Component for RawBuffer {
             self.dirty = false;
         }
     }
-    fn process_event(&mut self, event: &mut UIEvent, _context: &mut Context) -> bool {
+    fn process_event(&mut self, event: &mut UIEvent, context: &mut Context) -> bool {
         match *event {
+            UIEvent::Input(Key::Mouse(MouseEvent::Press(MouseButton::WheelUp, _, _)))
+                if context.runtime_settings.terminal.use_mouse.is_true() =>
+            {
+                self.cursor.1 = self
+                    .cursor
+                    .1
+                    .saturating_sub(context.runtime_settings.terminal.mouse_scroll_lines);
+                self.dirty = true;
+                true
+            }
+            UIEvent::Input(Key::Mouse(MouseEvent::Press(MouseButton::WheelDown, _, _)))
+                if context.runtime_settings.terminal.use_mouse.is_true() =>
+            {
+                self.cursor.1 += context.runtime_settings.terminal.mouse_scroll_lines;
+                self.dirty = true;
+                true
+            }
             UIEvent::Input(Key::Left) => {
                 self.cursor.0 = self.cursor.0.saturating_sub(1);
                 self.dirty = true;
@@ -2341,3 +2494,341 @@ impl RawBuffer {
             .unwrap_or("untitled")
     }
 }
+
+#[test]
+fn test_selector_default_checked_entries() {
+    let context = Context::new_mock();
+    let selector: UIDialog<usize> = Selector::new_with_defaults(
+        "select folders",
+        vec![
+            (0, "INBOX".to_string(), false),
+            (1, "Sent".to_string(), true),
+            (2, "Drafts".to_string(), false),
+            (3, "Trash".to_string(), true),
+        ],
+        false,
+        None,
+        &context,
+    );
+    assert_eq!(selector.collect(), vec![1, 3]);
+}
+
+#[test]
+fn test_selector_filter_query() {
+    let context = Context::new_mock();
+    let entries = vec![
+        (0, "INBOX".to_string()),
+        (1, "INBOX/Lists/luddites".to_string()),
+        (2, "Sent".to_string()),
+        (3, "Archive/2020".to_string()),
+    ];
+    let mut selector: UIDialog<usize> =
+        Selector::new_filterable("go to mailbox", entries, None, &context);
+    selector.set_filter_query("lud".to_string(), &context);
+    assert_eq!(selector.entries, vec![(1, true)]);
+    /* Best matches come first and are preselected */
+    selector.set_filter_query("st".to_string(), &context);
+    assert_eq!(selector.entries, vec![(1, true), (2, false)]);
+    selector.set_filter_query("nothing".to_string(), &context);
+    assert!(selector.entries.is_empty());
+    selector.set_filter_query(String::new(), &context);
+    assert_eq!(selector.entries.len(), 4);
+    assert_eq!(selector.collect(), vec![0]);
+}
+
+#[test]
+fn test_selector_tab_navigation() {
+    fn press(
+        selector: &mut UIDialog<usize>,
+        keys: &[(Key, SelectorCursor)],
+        context: &mut Context,
+    ) {
+        for (key, cursor) in keys {
+            assert!(selector.process_event(&mut UIEvent::Input(key.clone()), context));
+            assert_eq!(selector.cursor, *cursor, "after {}", key);
+        }
+    }
+    let mut context = Context::new_mock();
+    let entries = vec![
+        (0, "INBOX".to_string()),
+        (1, "Sent".to_string()),
+        (2, "Drafts".to_string()),
+    ];
+    let mut selector: UIDialog<usize> =
+        Selector::new("select folders", entries.clone(), false, None, &context);
+    press(
+        &mut selector,
+        &[
+            (Key::Char('\t'), SelectorCursor::Ok),
+            (Key::Char('\t'), SelectorCursor::Cancel),
+            (Key::Char('\t'), SelectorCursor::Entry(0)),
+            (Key::BackTab, SelectorCursor::Cancel),
+            (Key::BackTab, SelectorCursor::Ok),
+            (Key::BackTab, SelectorCursor::Entry(2)),
+            /* Arrow keys still move between entries */
+            (Key::Up, SelectorCursor::Entry(1)),
+        ],
+        &mut context,
+    );
+    assert!(selector.collect().is_empty());
+
+    /* Without buttons, Tab cycles through the entries and selects them */
+    let mut selector: UIDialog<usize> =
+        Selector::new("select folder", entries, true, None, &context);
+    press(
+        &mut selector,
+        &[
+            (Key::Char('\t'), SelectorCursor::Entry(1)),
+            (Key::Char('\t'), SelectorCursor::Entry(2)),
+            (Key::Char('\t'), SelectorCursor::Entry(0)),
+            (Key::BackTab, SelectorCursor::Entry(2)),
+        ],
+        &mut context,
+    );
+    assert_eq!(selector.collect(), vec![2]);
+}
+
+#[test]
+fn test_selector_dialog_results() {
+    /* Feeds `keys` to the dialog and returns the value it reported when it finished, if it did */
+    fn finish<T: 'static + Clone>(
+        dialog: &mut dyn Component,
+        keys: &[Key],
+        context: &mut Context,
+    ) -> Option<T> {
+        context.replies.clear();
+        for key in keys {
+            assert!(dialog.process_event(&mut UIEvent::Input(key.clone()), context));
+        }
+        let ret = match context.replies.pop_front() {
+            Some(UIEvent::FinishedUIDialog(id, msg)) => {
+                assert_eq!(id, dialog.id());
+                msg.downcast_ref::<T>().cloned()
+            }
+            None => return None,
+            other => panic!("unexpected reply {:?}", other),
+        };
+        assert!(
+            matches!(context.replies.pop_front(), Some(UIEvent::ComponentKill(id)) if id == dialog.id())
+        );
+        ret
+    }
+    let mut context = Context::new_mock();
+    let entries = vec![
+        (0, "INBOX".to_string()),
+        (1, "Sent".to_string()),
+        (2, "Drafts".to_string()),
+    ];
+    let dialog = |single_only: bool, context: &Context| -> UIDialog<usize> {
+        Selector::new(
+            "select folders",
+            entries.clone(),
+            single_only,
+            Some(Box::new(|id: ComponentId, results: &[usize]| {
+                Some(UIEvent::FinishedUIDialog(id, Box::new(results.to_vec())))
+            })),
+            context,
+        )
+    };
+    /* Enter toggles entries until OK is pressed */
+    let mut d = dialog(false, &context);
+    assert_eq!(
+        finish::<Vec<usize>>(
+            &mut d,
+            &[Key::Char('\n'), Key::Down, Key::Char('\n')],
+            &mut context
+        ),
+        None
+    );
+    assert_eq!(d.entries, vec![(0, true), (1, true), (2, false)]);
+    assert_eq!(
+        finish::<Vec<usize>>(
+            &mut d,
+            &[Key::Up, Key::Char('\n'), Key::Char('\t'), Key::Char('\n')],
+            &mut context
+        ),
+        Some(vec![1])
+    );
+    /* Cancel and Esc discard the selection */
+    let mut d = dialog(false, &context);
+    assert_eq!(
+        finish::<Vec<usize>>(
+            &mut d,
+            &[Key::Char('\n'), Key::BackTab, Key::Char('\n')],
+            &mut context
+        ),
+        Some(vec![])
+    );
+    let mut d = dialog(false, &context);
+    assert_eq!(
+        finish::<Vec<usize>>(&mut d, &[Key::Char('\n'), Key::Esc], &mut context),
+        Some(vec![])
+    );
+    /* In single-only mode Enter picks the entry under the cursor */
+    let mut d = dialog(true, &context);
+    assert_eq!(
+        finish::<Vec<usize>>(
+            &mut d,
+            &[Key::Down, Key::Down, Key::Char('\n')],
+            &mut context
+        ),
+        Some(vec![2])
+    );
+    let mut d = dialog(true, &context);
+    assert_eq!(
+        finish::<Vec<usize>>(&mut d, &[Key::Down, Key::Esc], &mut context),
+        Some(vec![])
+    );
+
+    let confirmation = |single_only: bool, context: &Context| -> UIConfirmationDialog {
+        Selector::new(
+            "you sure?",
+            vec![(true, "yes".to_string()), (false, "no".to_string())],
+            single_only,
+            Some(Box::new(|id: ComponentId, result: bool| {
+                Some(UIEvent::FinishedUIDialog(id, Box::new(result)))
+            })),
+            context,
+        )
+    };
+    let mut d = confirmation(true, &context);
+    assert_eq!(
+        finish::<bool>(&mut d, &[Key::Char('\n')], &mut context),
+        Some(true)
+    );
+    let mut d = confirmation(true, &context);
+    assert_eq!(
+        finish::<bool>(&mut d, &[Key::Down, Key::Char('\n')], &mut context),
+        Some(false)
+    );
+    let mut d = confirmation(true, &context);
+    assert_eq!(
+        finish::<bool>(&mut d, &[Key::Esc], &mut context),
+        Some(false)
+    );
+    let mut d = confirmation(false, &context);
+    assert_eq!(
+        finish::<bool>(&mut d, &[Key::Char('\n')], &mut context),
+        None
+    );
+    assert_eq!(
+        finish::<bool>(&mut d, &[Key::Char('\t'), Key::Char('\n')], &mut context),
+        Some(true)
+    );
+    let mut d = confirmation(false, &context);
+    assert_eq!(
+        finish::<bool>(
+            &mut d,
+            &[Key::Char('\n'), Key::BackTab, Key::Char('\n')],
+            &mut context
+        ),
+        Some(false)
+    );
+    let mut d = confirmation(false, &context);
+    assert_eq!(
+        finish::<bool>(&mut d, &[Key::Char('\n'), Key::Esc], &mut context),
+        Some(false)
+    );
+}
+
+#[test]
+fn test_status_bar_notification_expiry() {
+    let mut context = Context::new_mock();
+    let container = RawBuffer::new(CellBuffer::new(1, 1, Cell::default()), None);
+    let mut status_bar = StatusBar::new(&context, Box::new(container));
+    status_bar.process_event(
+        &mut UIEvent::StatusEvent(StatusEvent::UpdateStatus("INBOX".to_string())),
+        &mut context,
+    );
+    let mut render = |status_bar: &mut StatusBar| {
+        let mut grid = CellBuffer::new(40, 1, Cell::default());
+        status_bar.draw_status_bar(&mut grid, ((0, 0), (39, 0)), &mut context);
+        grid.row_iter(0..40, 0)
+            .map(|c| grid[c].ch())
+            .collect::<String>()
+            .trim_end()
+            .to_string()
+    };
+    assert!(render(&mut status_bar).ends_with("| INBOX"));
+    status_bar.push_notification(
+        "Attachment `3` not found.".to_string(),
+        Duration::from_secs(60),
+    );
+    assert!(render(&mut status_bar).ends_with("| Attachment `3` not found."));
+    /* An expired notification is dropped and the status is shown again */
+    status_bar.notifications[0].1 = Instant::now();
+    assert!(render(&mut status_bar).ends_with("| INBOX"));
+    assert!(status_bar.notifications.is_empty());
+}
+
+#[test]
+fn test_tabbed_move_tab() {
+    let context = Context::new_mock();
+    let tab = || -> Box<dyn Component> {
+        Box::new(RawBuffer::new(CellBuffer::new(1, 1, Cell::default()), None))
+    };
+    let mut tabbed = Tabbed::new(vec![tab()], &context);
+    tabbed.add_component(tab());
+    tabbed.add_component(tab());
+    tabbed.add_component(tab());
+    let ids = tabbed.children.iter().map(|c| c.id()).collect::<Vec<_>>();
+    /* Pinned tabs stay put */
+    assert!(!tabbed.move_tab(true));
+    /* and other tabs can't be moved past them */
+    tabbed.cursor_pos = 1;
+    assert!(!tabbed.move_tab(false));
+    assert!(tabbed.move_tab(true));
+    assert_eq!(tabbed.cursor_pos, 2);
+    assert!(tabbed.move_tab(true));
+    assert!(!tabbed.move_tab(true));
+    assert_eq!(tabbed.cursor_pos, 3);
+    assert_eq!(
+        tabbed.children.iter().map(|c| c.id()).collect::<Vec<_>>(),
+        vec![ids[0], ids[2], ids[3], ids[1]]
+    );
+    assert!(tabbed.move_tab(false));
+    assert_eq!(tabbed.cursor_pos, 2);
+    assert_eq!(
+        tabbed.children.iter().map(|c| c.id()).collect::<Vec<_>>(),
+        vec![ids[0], ids[2], ids[1], ids[3]]
+    );
+}
+
+#[test]
+fn test_tabbed_close_right() {
+    let mut context = Context::new_mock();
+    let pinned = || -> Box<dyn Component> {
+        Box::new(RawBuffer::new(CellBuffer::new(1, 1, Cell::default()), None))
+    };
+    let mut tabbed = Tabbed::new(vec![pinned(), pinned()], &context);
+    for _ in 0..4 {
+        tabbed.add_component(Box::new(crate::components::ContactList::new(&context)));
+    }
+    let ids = tabbed.children.iter().map(|c| c.id()).collect::<Vec<_>>();
+    tabbed.cursor_pos = 3;
+    assert!(tabbed.process_event(&mut UIEvent::Action(Tab(CloseRight)), &mut context));
+    /* Tabs confirm closing by replying with Tab(Kill) */
+    let mut replies = context.replies.drain(..).collect::<Vec<UIEvent>>();
+    assert_eq!(replies.len(), 2);
+    for reply in replies.iter_mut() {
+        assert!(tabbed.process_event(reply, &mut context));
+    }
+    assert_eq!(
+        tabbed.children.iter().map(|c| c.id()).collect::<Vec<_>>(),
+        ids[..4].to_vec()
+    );
+    assert_eq!(tabbed.cursor_pos, 3);
+
+    /* Pinned tabs are left alone even with the cursor on one of them */
+    tabbed.cursor_pos = 0;
+    tabbed.process_event(&mut UIEvent::Action(Tab(CloseRight)), &mut context);
+    let mut replies = context.replies.drain(..).collect::<Vec<UIEvent>>();
+    for reply in replies.iter_mut() {
+        tabbed.process_event(reply, &mut context);
+    }
+    assert_eq!(
+        tabbed.children.iter().map(|c| c.id()).collect::<Vec<_>>(),
+        ids[..2].to_vec()
+    );
+    assert_eq!(tabbed.cursor_pos, 0);
+}
@@ -36,11 +36,17 @@ pub struct Pager {
     dirty: bool,
 
     colors: ThemeAttribute,
+    quote_colors: Vec<Color>,
+    signature_colors: Option<ThemeAttribute>,
+    show_line_numbers: bool,
+    /// Logical line number of each displayed line, `0` for continuations of split lines.
+    line_numbers: Vec<usize>,
     initialised: bool,
     show_scrollbar: bool,
     content: CellBuffer,
     text_lines: (usize, Vec<String>),
     movement: Option<PageMovement>,
+    cmd_buf: String,
     id: ComponentId,
 }
 
@@ -55,12 +61,11 @@ impl Pager {
     pub fn new(context: &Context) -> Self {
         let mut ret = Pager::default();
         ret.minimum_width = context.settings.pager.minimum_width;
+        ret.quote_colors = context.settings.pager.quote_colors.clone();
+        ret.signature_colors = signature_colors(context);
+        ret.show_line_numbers = context.settings.pager.show_line_numbers;
         ret.set_colors(crate::conf::value(context, "theme_default"))
-            .set_reflow(if context.settings.pager.split_long_lines {
-                Reflow::All
-            } else {
-                Reflow::No
-            });
+            .set_reflow(context.settings.pager.reflow());
         ret
     }
 
@@ -132,11 +137,7 @@ impl Pager {
         };
 
         let reflow: Reflow = if let Some(context) = context {
-            if context.settings.pager.split_long_lines {
-                Reflow::All
-            } else {
-                Reflow::No
-            }
+            context.settings.pager.reflow()
         } else {
             Reflow::All
         };
@@ -196,6 +197,13 @@ impl Pager {
             height: content.size().1,
             width: content.size().0,
             minimum_width: pager_minimum_width,
+            quote_colors: context
+                .map(|context| context.settings.pager.quote_colors.clone())
+                .unwrap_or_default(),
+            signature_colors: context.and_then(signature_colors),
+            show_line_numbers: context
+                .map(|context| context.settings.pager.show_line_numbers)
+                .unwrap_or(false),
             initialised: false,
             dirty: true,
             content,
@@ -243,6 +251,21 @@ impl Pager {
             ..Default::default()
         }
     }
+    /// Returns the `(row, column)` cell coordinates of every occurrence of `pattern` in `lines`.
+    pub fn search_positions(lines: &[String], pattern: &str) -> Vec<(usize, usize)> {
+        use melib::text_processing::search::KMP;
+        let mut ret = vec![];
+        for (y, l) in lines.iter().enumerate() {
+            /* kmp_search returns byte offsets, but wide graphemes take up more than one cell */
+            ret.extend(
+                l.kmp_search(pattern)
+                    .into_iter()
+                    .map(|offset| (y, l[..offset].grapheme_width())),
+            );
+        }
+        ret
+    }
+
     pub fn print_string(content: &mut CellBuffer, lines: &[String], colors: ThemeAttribute) {
         let width = content.size().0;
         debug!(colors);
@@ -263,10 +286,78 @@ impl Pager {
         }
     }
 
+    /// Colors quoted lines according to their quote depth. Continuation lines of split long
+    /// lines keep the depth of the line they continue.
+    pub fn highlight_quotes(content: &mut CellBuffer, lines: &[String], quote_colors: &[Color]) {
+        if quote_colors.is_empty() {
+            return;
+        }
+        let width = content.size().0;
+        let mut depth = 0;
+        for (i, l) in lines.iter().enumerate() {
+            if !l.starts_with('⤷') {
+                depth = quote_depth(l);
+            }
+            if depth == 0 {
+                continue;
+            }
+            let fg = quote_colors[(depth - 1) % quote_colors.len()];
+            for x in 0..width {
+                content[(x, i)].set_fg(fg);
+            }
+        }
+    }
+
+    /// Applies `colors` to the lines after the last `-- ` signature separator line, if any.
+    pub fn highlight_signature(content: &mut CellBuffer, lines: &[String], colors: ThemeAttribute) {
+        let separator = match lines.iter().rposition(|l| l == "-- ") {
+            Some(idx) => idx,
+            None => return,
+        };
+        let width = content.size().0;
+        for i in (separator + 1)..lines.len() {
+            for x in 0..width {
+                content[(x, i)].set_fg(colors.fg).set_attrs(colors.attrs);
+            }
+        }
+    }
+
+    /// Number of cells needed to print line numbers up to `line_count`.
+    pub fn line_number_width(line_count: usize) -> usize {
+        let mut width = 1;
+        let mut n = line_count / 10;
+        while n > 0 {
+            width += 1;
+            n /= 10;
+        }
+        width
+    }
+
+    /// Width of the line number gutter, including its padding.
+    fn gutter_width(&self) -> usize {
+        if self.show_line_numbers && !self.text.is_empty() {
+            Pager::line_number_width(self.text.lines().count()) + 1
+        } else {
+            0
+        }
+    }
+
     pub fn cursor_pos(&self) -> usize {
         self.cursor.1
     }
 
+    /// Scrolls to the 1-based `line`, clamped to the last line of the text.
+    pub fn go_to_line(&mut self, line: usize) {
+        self.cursor.1 = std::cmp::min(line.saturating_sub(1), self.height.saturating_sub(1));
+        self.dirty = true;
+    }
+
+    /// Scrolls to `percent` of the text, clamped to 100%.
+    pub fn go_to_percentage(&mut self, percent: usize) {
+        self.cursor.1 = self.height.saturating_sub(1) * std::cmp::min(percent, 100) / 100;
+        self.dirty = true;
+    }
+
     pub fn size(&self) -> (usize, usize) {
         (self.width, self.height)
     }
@@ -282,7 +373,7 @@ impl Component for Pager {
         }
 
         if !self.initialised && !self.text.is_empty() {
-            let mut width = width!(area);
+            let mut width = width!(area).saturating_sub(self.gutter_width());
             if width < self.minimum_width {
                 width = self.minimum_width;
             }
@@ -290,10 +381,13 @@ impl Component for Pager {
             let lines: &[String] = if self.text_lines.0 == width.saturating_sub(4) {
                 &self.text_lines.1
             } else {
-                let lines = self
-                    .text
-                    .split_lines_reflow(self.reflow, Some(width.saturating_sub(4)));
+                let (lines, line_numbers) = melib::text_processing::split_lines_reflow_numbered(
+                    &self.text,
+                    self.reflow,
+                    Some(width.saturating_sub(4)),
+                );
                 self.text_lines = (width.saturating_sub(4), lines);
+                self.line_numbers = line_numbers;
                 &self.text_lines.1
             };
             let height = lines.len() + 2;
@@ -303,17 +397,13 @@ impl Component for Pager {
             let mut content = CellBuffer::new(width, height, empty_cell);
             content.set_ascii_drawing(self.content.ascii_drawing);
             if let Some(ref mut search) = self.search {
-                use melib::text_processing::search::KMP;
-                search.positions.clear();
-                for (y, l) in lines.iter().enumerate() {
-                    search.positions.extend(
-                        l.kmp_search(&search.pattern)
-                            .into_iter()
-                            .map(|offset| (y, offset)),
-                    );
-                }
+                search.positions = Pager::search_positions(lines, &search.pattern);
             }
             Pager::print_string(&mut content, &lines, self.colors);
+            Pager::highlight_quotes(&mut content, lines, &self.quote_colors);
+            if let Some(signature_colors) = self.signature_colors {
+                Pager::highlight_signature(&mut content, lines, signature_colors);
+            }
             #[cfg(feature = "regexp")]
             {
                 for text_formatter in
@@ -334,7 +424,7 @@ impl Component for Pager {
                 search.cursor =
                     std::cmp::min(search.positions.len().saturating_sub(1), search.cursor);
                 for (i, (y, x)) in search.positions.iter().enumerate() {
-                    for c in content.row_iter(*x..*x + search.pattern.grapheme_len(), *y) {
+                    for c in content.row_iter(*x..*x + search.pattern.grapheme_width(), *y) {
                         if i == search.cursor {
                             content[c]
                                 .set_fg(results_current_attr.fg)
@@ -408,15 +498,15 @@ impl Component for Pager {
             if !search.positions.is_empty() {
                 if let Some(mvm) = search.movement.take() {
                     match mvm {
-                        PageMovement::Up(_) => {
-                            if self.cursor.1 > search.positions[search.cursor].0 {
-                                self.cursor.1 = search.positions[search.cursor].0;
-                            }
+                        PageMovement::Up(_)
+                            if self.cursor.1 > search.positions[search.cursor].0 =>
+                        {
+                            self.cursor.1 = search.positions[search.cursor].0;
                         }
-                        PageMovement::Down(_) => {
-                            if self.cursor.1 + height < search.positions[search.cursor].0 {
-                                self.cursor.1 = search.positions[search.cursor].0;
-                            }
+                        PageMovement::Down(_)
+                            if self.cursor.1 + height <= search.positions[search.cursor].0 =>
+                        {
+                            self.cursor.1 = search.positions[search.cursor].0;
                         }
                         _ => {}
                     }
@@ -425,6 +515,16 @@ impl Component for Pager {
         }
 
         clear_area(grid, area, crate::conf::value(context, "theme_default"));
+        let gutter_width = self.gutter_width();
+        let gutter_area = area;
+        let area = if gutter_width > 0 && gutter_width < width!(area) {
+            (
+                pos_inc(upper_left!(area), (gutter_width, 0)),
+                bottom_right!(area),
+            )
+        } else {
+            area
+        };
         let (width, height) = self.content.size();
         let (mut cols, mut rows) = (width!(area), height!(area));
         if self.show_scrollbar && rows < height {
@@ -452,6 +552,28 @@ impl Component for Pager {
                 ),
             ),
         );
+        if area != gutter_area {
+            let attr = crate::conf::value(context, "pager.line_numbers");
+            for row in 0..=rows {
+                let line_number = match self.line_numbers.get(self.cursor.1 + row) {
+                    Some(n) if *n > 0 => *n,
+                    _ => continue,
+                };
+                let y = get_y(upper_left!(gutter_area)) + row;
+                write_string_to_grid(
+                    &format!("{:>1$}", line_number, gutter_width - 1),
+                    grid,
+                    attr.fg,
+                    attr.bg,
+                    attr.attrs,
+                    (
+                        (get_x(upper_left!(gutter_area)), y),
+                        (get_x(upper_left!(area)).saturating_sub(1), y),
+                    ),
+                    None,
+                );
+            }
+        }
         if self.show_scrollbar && rows + 1 < height {
             ScrollBar::default().set_show_arrows(true).draw(
                 grid,
@@ -497,6 +619,47 @@ impl Component for Pager {
                 self.dirty = true;
                 return true;
             }
+            UIEvent::Input(ref key)
+                if !self.text.is_empty()
+                    && shortcut!(key == shortcuts[Self::DESCRIPTION]["toggle_line_numbers"]) =>
+            {
+                self.show_line_numbers = !self.show_line_numbers;
+                self.initialised = false;
+                self.dirty = true;
+                return true;
+            }
+            UIEvent::Input(ref key)
+                if !self.text.is_empty()
+                    && shortcut!(key == shortcuts[Self::DESCRIPTION]["toggle_reflow"]) =>
+            {
+                self.reflow = match self.reflow {
+                    Reflow::All => Reflow::No,
+                    Reflow::No => Reflow::FormatFlowed,
+                    Reflow::FormatFlowed => Reflow::All,
+                };
+                self.text_lines = (0, vec![]);
+                self.initialised = false;
+                self.dirty = true;
+                return true;
+            }
+            UIEvent::Input(Key::Mouse(MouseEvent::Press(MouseButton::WheelUp, _, _)))
+                if context.runtime_settings.terminal.use_mouse.is_true() =>
+            {
+                self.movement = Some(PageMovement::Up(
+                    context.runtime_settings.terminal.mouse_scroll_lines,
+                ));
+                self.dirty = true;
+                return true;
+            }
+            UIEvent::Input(Key::Mouse(MouseEvent::Press(MouseButton::WheelDown, _, _)))
+                if context.runtime_settings.terminal.use_mouse.is_true() =>
+            {
+                self.movement = Some(PageMovement::Down(
+                    context.runtime_settings.terminal.mouse_scroll_lines,
+                ));
+                self.dirty = true;
+                return true;
+            }
             UIEvent::Input(Key::Home) => {
                 self.movement = Some(PageMovement::Home);
                 self.dirty = true;
@@ -531,6 +694,70 @@ impl Component for Pager {
                 self.dirty = true;
                 return true;
             }
+            UIEvent::Input(Key::Char(c))
+                if c.is_ascii_digit()
+                    && !shortcuts[Self::DESCRIPTION]
+                        .values()
+                        .any(|k| *k == Key::Char(*c)) =>
+            {
+                self.cmd_buf.push(*c);
+                context
+                    .replies
+                    .push_back(UIEvent::StatusEvent(StatusEvent::BufSet(
+                        self.cmd_buf.clone(),
+                    )));
+                return true;
+            }
+            UIEvent::Input(Key::Esc) | UIEvent::Input(Key::Alt(''))
+                if !self.cmd_buf.is_empty() =>
+            {
+                self.cmd_buf.clear();
+                context
+                    .replies
+                    .push_back(UIEvent::StatusEvent(StatusEvent::BufClear));
+                return true;
+            }
+            UIEvent::Input(ref key)
+                if shortcut!(key == shortcuts[Self::DESCRIPTION]["go_to_top"]) =>
+            {
+                if !self.cmd_buf.is_empty() {
+                    self.cmd_buf.clear();
+                    context
+                        .replies
+                        .push_back(UIEvent::StatusEvent(StatusEvent::BufClear));
+                }
+                self.movement = Some(PageMovement::Home);
+                self.dirty = true;
+                return true;
+            }
+            UIEvent::Input(ref key)
+                if shortcut!(key == shortcuts[Self::DESCRIPTION]["go_to_line"]) =>
+            {
+                if self.cmd_buf.is_empty() {
+                    self.movement = Some(PageMovement::End);
+                    self.dirty = true;
+                } else {
+                    let line = self.cmd_buf.parse::<usize>().unwrap_or(usize::MAX);
+                    self.cmd_buf.clear();
+                    context
+                        .replies
+                        .push_back(UIEvent::StatusEvent(StatusEvent::BufClear));
+                    self.go_to_line(line);
+                }
+                return true;
+            }
+            UIEvent::Input(ref key)
+                if !self.cmd_buf.is_empty()
+                    && shortcut!(key == shortcuts[Self::DESCRIPTION]["go_to_percentage"]) =>
+            {
+                let percent = self.cmd_buf.parse::<usize>().unwrap_or(usize::MAX);
+                self.cmd_buf.clear();
+                context
+                    .replies
+                    .push_back(UIEvent::StatusEvent(StatusEvent::BufClear));
+                self.go_to_percentage(percent);
+                return true;
+            }
             UIEvent::ChangeMode(UIMode::Normal) => {
                 self.dirty = true;
             }
@@ -569,6 +796,18 @@ impl Component for Pager {
                     ))));
                 return true;
             }
+            UIEvent::Input(ref key)
+                if !self.text.is_empty()
+                    && shortcut!(key == shortcuts[Self::DESCRIPTION]["search"]) =>
+            {
+                context
+                    .replies
+                    .push_back(UIEvent::CmdInput(Key::Paste("search ".to_string())));
+                context
+                    .replies
+                    .push_back(UIEvent::ChangeMode(UIMode::Command));
+                return true;
+            }
             UIEvent::Action(Action::Listing(ListingAction::Search(pattern))) => {
                 self.search = Some(SearchPattern {
                     pattern: pattern.to_string(),
@@ -642,3 +881,124 @@ impl Component for Pager {
         self.id = id;
     }
 }
+
+fn signature_colors(context: &Context) -> Option<ThemeAttribute> {
+    if context.settings.pager.dim_signature {
+        Some(crate::conf::value(context, "pager.signature"))
+    } else {
+        None
+    }
+}
+
+/// Number of leading `>` quote markers of `line`, ignoring whitespace between them.
+fn quote_depth(line: &str) -> usize {
+    line.chars()
+        .filter(|c| !c.is_whitespace())
+        .take_while(|c| *c == '>')
+        .count()
+}
+
+#[test]
+fn test_pager_highlight_quotes() {
+    let lines: Vec<String> = ["Hello,", "> > nested", ">reply", "text"]
+        .iter()
+        .map(|l| l.to_string())
+        .collect();
+    let quote_colors = [Color::Byte(6), Color::Byte(3)];
+    let mut content = CellBuffer::new(20, lines.len(), Cell::with_char(' '));
+    Pager::print_string(&mut content, &lines, ThemeAttribute::default());
+    Pager::highlight_quotes(&mut content, &lines, &quote_colors);
+    assert_eq!(quote_depth("> > nested"), 2);
+    assert_eq!(content[(4, 1)].fg(), Color::Byte(3));
+    assert_eq!(content[(1, 2)].fg(), Color::Byte(6));
+    assert_eq!(content[(0, 0)].fg(), Color::Default);
+    assert_eq!(content[(0, 3)].fg(), Color::Default);
+}
+
+#[test]
+fn test_pager_highlight_signature() {
+    let lines: Vec<String> = ["Hello,", "see you", "-- ", "John", "john@example.com"]
+        .iter()
+        .map(|l| l.to_string())
+        .collect();
+    let mut colors = ThemeAttribute::default();
+    colors.fg = Color::Byte(243);
+    colors.attrs = Attr::DIM;
+    let mut content = CellBuffer::new(20, lines.len(), Cell::with_char(' '));
+    Pager::print_string(&mut content, &lines, ThemeAttribute::default());
+    Pager::highlight_signature(&mut content, &lines, colors);
+    for i in 0..3 {
+        assert_eq!(content[(0, i)].fg(), Color::Default);
+        assert_eq!(content[(0, i)].attrs(), Attr::DEFAULT);
+    }
+    for i in 3..5 {
+        assert_eq!(content[(0, i)].fg(), Color::Byte(243));
+        assert_eq!(content[(0, i)].attrs(), Attr::DIM);
+    }
+
+    let lines: Vec<String> = vec!["no signature".to_string(), "--".to_string()];
+    let mut content = CellBuffer::new(20, lines.len(), Cell::with_char(' '));
+    Pager::print_string(&mut content, &lines, ThemeAttribute::default());
+    Pager::highlight_signature(&mut content, &lines, colors);
+    assert_eq!(content[(0, 1)].fg(), Color::Default);
+}
+
+#[test]
+fn test_pager_search_positions() {
+    let lines: Vec<String> = [
+        "Hello world",
+        "say hello to the world",
+        "γειά σου world",
+        "日本 world",
+    ]
+    .iter()
+    .map(|l| l.to_string())
+    .collect();
+    let positions = Pager::search_positions(&lines, "world");
+    assert_eq!(positions, vec![(0, 6), (1, 17), (2, 9), (3, 5)]);
+    let mut content = CellBuffer::new(30, lines.len(), Cell::with_char(' '));
+    Pager::print_string(&mut content, &lines, ThemeAttribute::default());
+    for (y, x) in positions {
+        assert_eq!(content[(x, y)].ch(), 'w');
+        assert_eq!(content[(x + 4, y)].ch(), 'd');
+    }
+    assert!(Pager::search_positions(&lines, "moon").is_empty());
+}
+
+#[test]
+fn test_pager_jumps() {
+    let mut context = Context::new_mock();
+    let text = (1..=200)
+        .map(|i| format!("line {}", i))
+        .collect::<Vec<String>>()
+        .join("\n");
+    let mut pager = Pager::default();
+    pager.update_from_str(&text, Some(80));
+    for c in "50%".chars() {
+        assert!(pager.process_event(&mut UIEvent::Input(Key::Char(c)), &mut context));
+    }
+    assert!((99..=101).contains(&pager.cursor_pos()));
+    assert!(pager.cmd_buf.is_empty());
+    for c in "120G".chars() {
+        assert!(pager.process_event(&mut UIEvent::Input(Key::Char(c)), &mut context));
+    }
+    assert_eq!(pager.cursor_pos(), 119);
+    /* out of range targets are clamped */
+    pager.go_to_line(1000);
+    assert_eq!(pager.cursor_pos(), pager.size().1 - 1);
+    pager.go_to_line(1);
+    pager.go_to_percentage(250);
+    assert_eq!(pager.cursor_pos(), pager.size().1 - 1);
+    pager.go_to_line(0);
+    assert_eq!(pager.cursor_pos(), 0);
+}
+
+#[test]
+fn test_pager_line_numbers() {
+    assert_eq!(Pager::line_number_width(0), 1);
+    assert_eq!(Pager::line_number_width(9), 1);
+    assert_eq!(Pager::line_number_width(10), 2);
+    assert_eq!(Pager::line_number_width(200), 3);
+    assert_eq!(Pager::line_number_width(999), 3);
+    assert_eq!(Pager::line_number_width(1000), 4);
+}
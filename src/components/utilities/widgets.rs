@@ -1199,6 +1199,20 @@ impl ProgressSpinner {
         &[".", "o", "O", "°", "O", "o", "."],
     ];
 
+    /// Human readable names for some of the sequences in `KINDS`.
+    pub const NAMED_KINDS: &'static [(&'static str, usize)] = &[
+        ("bar", 0),
+        ("circle", 3),
+        ("line", 16),
+        ("eyes", 18),
+        ("arrows", 20),
+        ("bounce", 21),
+        ("quadrant", 23),
+        ("triangle", 26),
+        ("braille", 27),
+        ("dots", 29),
+    ];
+
     const INTERVAL: std::time::Duration = std::time::Duration::from_millis(50);
 
     pub fn new(kind: usize, context: &Context) -> Self {
@@ -1238,6 +1252,29 @@ impl ProgressSpinner {
         self.dirty = true;
     }
 
+    /// Set the sequence by one of the names in `NAMED_KINDS`. Unknown names fall back to the
+    /// first sequence and return an error.
+    pub fn set_named_kind(&mut self, name: &str) -> Result<()> {
+        if let Some(&(_, kind)) = Self::NAMED_KINDS
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        {
+            self.set_kind(kind);
+            Ok(())
+        } else {
+            self.set_kind(0);
+            Err(MeliError::new(format!(
+                "Unknown progress spinner sequence `{}`. Valid names are: {}",
+                name,
+                Self::NAMED_KINDS
+                    .iter()
+                    .map(|(n, _)| *n)
+                    .collect::<Vec<&str>>()
+                    .join(", ")
+            )))
+        }
+    }
+
     pub fn set_custom_kind(&mut self, custom: Vec<String>) {
         self.stage = 0;
         self.width = custom.iter().map(|f| f.grapheme_len()).max().unwrap_or(0);
@@ -1333,3 +1370,205 @@ impl Component for ProgressSpinner {
         self.id = id;
     }
 }
+
+/// A bordered box with a single-line text field for prompting the user for a string.
+///
+/// The entered string is passed to `done_fn` when the user presses Enter. If the user cancels
+/// with Esc, `done_fn` receives `None`. The box switches to `UIMode::Insert` the first time it
+/// is drawn and supports the same readline shortcuts as `Field`.
+pub struct InputBox {
+    title: String,
+    prompt: String,
+    input: Field,
+    /// If true, user has finished their input
+    done: bool,
+    done_fn: Option<
+        Box<dyn FnOnce(ComponentId, Option<String>) -> Option<UIEvent> + 'static + Sync + Send>,
+    >,
+    insert_mode: bool,
+    dirty: bool,
+    id: ComponentId,
+}
+
+impl fmt::Debug for InputBox {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Display::fmt("InputBox", f)
+    }
+}
+
+impl fmt::Display for InputBox {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Display::fmt("InputBox", f)
+    }
+}
+
+impl InputBox {
+    pub fn new(
+        title: &str,
+        prompt: &str,
+        initial: String,
+        done_fn: Option<
+            Box<dyn FnOnce(ComponentId, Option<String>) -> Option<UIEvent> + 'static + Sync + Send>,
+        >,
+    ) -> Self {
+        let mut text = UText::new(initial);
+        let len = text.as_str().len();
+        text.set_cursor(len);
+        InputBox {
+            title: title.to_string(),
+            prompt: prompt.to_string(),
+            input: Field::Text(text, None),
+            done: false,
+            done_fn,
+            insert_mode: false,
+            dirty: true,
+            id: ComponentId::new_v4(),
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.input.as_str()
+    }
+
+    fn done(&mut self, value: Option<String>, context: &mut Context) {
+        self.done = true;
+        if let Some(event) = self
+            .done_fn
+            .take()
+            .and_then(|done_fn| done_fn(self.id, value))
+        {
+            context.replies.push_back(event);
+        }
+        context
+            .replies
+            .push_back(UIEvent::ChangeMode(UIMode::Normal));
+        context.replies.push_back(UIEvent::ComponentKill(self.id));
+    }
+}
+
+impl Component for InputBox {
+    fn draw(&mut self, grid: &mut CellBuffer, area: Area, context: &mut Context) {
+        if !self.insert_mode {
+            self.insert_mode = true;
+            context
+                .replies
+                .push_back(UIEvent::ChangeMode(UIMode::Insert));
+        }
+        let width = std::cmp::min(
+            width!(area),
+            std::cmp::max(
+                40,
+                std::cmp::max(
+                    self.title.grapheme_len(),
+                    self.prompt.grapheme_len() + self.input.as_str().grapheme_len() + 1,
+                ) + 4,
+            ),
+        );
+        let area = center_area(area, (width, 3));
+        clear_area(grid, area, crate::conf::value(context, "theme_default"));
+        let inner_area = create_box(grid, area);
+        write_string_to_grid(
+            &self.title,
+            grid,
+            Color::Default,
+            Color::Default,
+            Attr::BOLD,
+            (
+                pos_inc(upper_left!(area), (2, 0)),
+                set_y(bottom_right!(area), get_y(upper_left!(area))),
+            ),
+            None,
+        );
+        let label_attr = crate::conf::value(context, "widgets.form.label");
+        let (x, _) = write_string_to_grid(
+            &self.prompt,
+            grid,
+            label_attr.fg,
+            label_attr.bg,
+            label_attr.attrs,
+            inner_area,
+            None,
+        );
+        let field_area = (
+            set_x(upper_left!(inner_area), x + 1),
+            bottom_right!(inner_area),
+        );
+        self.input.draw(grid, field_area, context);
+        self.input
+            .draw_cursor(grid, field_area, ((0, 0), (0, 0)), context);
+        context.dirty_areas.push_back(area);
+        self.dirty = false;
+    }
+
+    fn process_event(&mut self, event: &mut UIEvent, context: &mut Context) -> bool {
+        if self.done {
+            return false;
+        }
+        match event {
+            UIEvent::InsertInput(Key::Char('\n')) => {
+                let value = self.input.as_str().to_string();
+                self.done(Some(value), context);
+                true
+            }
+            UIEvent::ChangeMode(UIMode::Normal) | UIEvent::Input(Key::Esc) if self.insert_mode => {
+                self.done(None, context);
+                true
+            }
+            UIEvent::InsertInput(_) => {
+                let ret = self.input.process_event(event, context);
+                self.dirty |= ret;
+                ret
+            }
+            _ => false,
+        }
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    fn set_dirty(&mut self, value: bool) {
+        self.dirty = value;
+    }
+
+    fn id(&self) -> ComponentId {
+        self.id
+    }
+
+    fn set_id(&mut self, id: ComponentId) {
+        self.id = id;
+    }
+}
+
+#[test]
+fn test_progress_spinner_named_kinds() {
+    let context = Context::new_mock();
+    let mut spinner = ProgressSpinner::new(0, &context);
+    for (name, expected) in &[
+        ("bar", &["▁", "▂", "▃", "▄", "▅", "▆", "▇", "█"][..]),
+        ("circle", &["○", "◔", "◐", "◕", "⬤"][..]),
+        ("line", &["|", "/", "-", "\\"][..]),
+        ("eyes", &["◡◡", "⊙⊙", "◠◠", "⊙⊙"][..]),
+        ("arrows", &["←", "↖", "↑", "↗", "→", "↘", "↓", "↙"][..]),
+        (
+            "bounce",
+            &["▁", "▃", "▄", "▅", "▆", "▇", "█", "▇", "▆", "▅", "▄", "▃"][..],
+        ),
+        ("quadrant", &["▖", "▘", "▝", "▗"][..]),
+        ("triangle", &["◢", "◣", "◤", "◥"][..]),
+        ("braille", &["⠁", "⠂", "⠄", "⡀", "⢀", "⠠", "⠐", "⠈"][..]),
+        ("dots", &[".", "o", "O", "°", "O", "o", "."][..]),
+    ] {
+        assert!(spinner.set_named_kind(name).is_ok());
+        assert_eq!(
+            ProgressSpinner::KINDS[*spinner.kind.as_ref().unwrap()],
+            *expected
+        );
+    }
+    assert!(spinner.set_named_kind("does not exist").is_err());
+    assert_eq!(spinner.kind, Ok(0));
+}
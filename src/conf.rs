@@ -29,6 +29,7 @@ extern crate xdg;
 use crate::conf::deserializers::non_empty_string;
 use crate::terminal::Color;
 use melib::search::Query;
+use melib::thread::{SortField, SortOrder};
 use std::collections::HashSet;
 mod overrides;
 pub use overrides::*;
@@ -53,9 +54,10 @@ pub use self::tags::*;
 
 use self::default_vals::*;
 use self::listing::ListingSettings;
+pub use self::listing::{DateStyle, FlagFilter};
 use self::notifications::NotificationsSettings;
 use self::terminal::TerminalSettings;
-use crate::pager::PagerSettings;
+use crate::pager::{PagerReflow, PagerSettings, UrlDisplay};
 use melib::conf::{AccountSettings, MailboxConf, ToggleFlag};
 use melib::error::*;
 
@@ -130,8 +132,8 @@ pub struct MailUIConf {
     pub pgp: PGPSettingsOverride,
 }
 
-#[serde(default)]
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct FileMailboxConf {
     #[serde(flatten)]
     pub conf_override: MailUIConf,
@@ -149,11 +151,46 @@ impl FileMailboxConf {
     }
 }
 
+/// The `root_mailbox` of an account: either a single root path, or a list of paths that each
+/// become a top level mailbox.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RootMailbox {
+    Path(String),
+    Paths(Vec<String>),
+}
+
+impl Default for RootMailbox {
+    fn default() -> Self {
+        RootMailbox::Path(String::new())
+    }
+}
+
+impl RootMailbox {
+    /// The first (or only) configured path.
+    pub fn first(&self) -> &str {
+        match self {
+            RootMailbox::Path(p) => p.as_str(),
+            RootMailbox::Paths(v) => v.first().map(String::as_str).unwrap_or_default(),
+        }
+    }
+
+    /// Split into the values of `AccountSettings::root_mailbox` and
+    /// `AccountSettings::root_mailboxes`.
+    fn into_settings(self) -> (String, Vec<String>) {
+        match self {
+            RootMailbox::Path(p) => (p, vec![]),
+            RootMailbox::Paths(v) => (v.first().cloned().unwrap_or_default(), v),
+        }
+    }
+}
+
 use crate::conf::deserializers::extra_settings;
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct FileAccount {
-    root_mailbox: String,
+    root_mailbox: RootMailbox,
     format: String,
+    #[serde(default)]
     identity: String,
     #[serde(default = "none")]
     display_name: Option<String>,
@@ -166,10 +203,18 @@ pub struct FileAccount {
     mailboxes: IndexMap<String, FileMailboxConf>,
     #[serde(default)]
     search_backend: SearchBackend,
+    #[serde(default)]
+    pub trash: Trash,
     #[serde(default = "false_val")]
     pub manual_refresh: bool,
     #[serde(default = "none")]
     pub refresh_command: Option<String>,
+    #[serde(default = "none")]
+    pub spam_command: Option<String>,
+    #[serde(default = "none")]
+    pub ham_command: Option<String>,
+    #[serde(default = "none")]
+    pub junk_mailbox: Option<String>,
     #[serde(flatten)]
     pub conf_override: MailUIConf,
     #[serde(flatten)]
@@ -183,7 +228,7 @@ impl FileAccount {
     }
 
     pub fn mailbox(&self) -> &str {
-        &self.root_mailbox
+        self.root_mailbox.first()
     }
 
     pub fn search_backend(&self) -> &SearchBackend {
@@ -240,8 +285,12 @@ impl AccountConf {
 impl From<FileAccount> for AccountConf {
     fn from(x: FileAccount) -> Self {
         let format = x.format.to_lowercase();
-        let root_mailbox = x.root_mailbox.clone();
-        let identity = x.identity.clone();
+        let (root_mailbox, root_mailboxes) = x.root_mailbox.clone().into_settings();
+        let identity = if x.identity.is_empty() {
+            std::env::var("EMAIL").unwrap_or_default()
+        } else {
+            x.identity.clone()
+        };
         let display_name = x.display_name.clone();
         let mailboxes = x
             .mailboxes
@@ -252,6 +301,7 @@ impl From<FileAccount> for AccountConf {
         let acc = AccountSettings {
             name: String::new(),
             root_mailbox,
+            root_mailboxes,
             format,
             identity,
             read_only: x.read_only,
@@ -391,14 +441,20 @@ impl FileSettings {
                 extra,
                 manual_refresh,
                 refresh_command: _,
+                spam_command: _,
+                ham_command: _,
+                junk_mailbox: _,
                 search_backend: _,
+                trash: _,
                 conf_override: _,
             } = acc.clone();
 
             let lowercase_format = format.to_lowercase();
+            let (root_mailbox, root_mailboxes) = root_mailbox.into_settings();
             let s = AccountSettings {
                 name: name.to_string(),
                 root_mailbox,
+                root_mailboxes,
                 format: format.clone(),
                 identity,
                 read_only,
@@ -664,6 +720,47 @@ impl Serialize for SearchBackend {
     }
 }
 
+/// What deleting messages does in an account.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Trash {
+    /// Delete messages in place.
+    Expunge,
+    /// Move messages to the mailbox with this path. Messages deleted from it are expunged.
+    Mailbox(String),
+}
+
+impl Default for Trash {
+    fn default() -> Self {
+        Trash::Expunge
+    }
+}
+
+impl<'de> Deserialize<'de> for Trash {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = <String>::deserialize(deserializer)?;
+        match s.as_str() {
+            expunge if expunge.eq_ignore_ascii_case("expunge") => Ok(Trash::Expunge),
+            "" => Err(de::Error::custom("invalid `trash` value")),
+            _ => Ok(Trash::Mailbox(s)),
+        }
+    }
+}
+
+impl Serialize for Trash {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Trash::Expunge => serializer.serialize_str("expunge"),
+            Trash::Mailbox(path) => serializer.serialize_str(path),
+        }
+    }
+}
+
 pub fn create_config_file(p: &Path) -> Result<()> {
     let mut file = OpenOptions::new()
         .write(true)
@@ -845,9 +942,13 @@ mod dotaddressable {
     impl DotAddressable for Query {}
     impl DotAddressable for melib::LoggingLevel {}
     impl DotAddressable for PathBuf {}
+    impl DotAddressable for RootMailbox {}
     impl DotAddressable for ToggleFlag {}
     impl DotAddressable for SearchBackend {}
+    impl DotAddressable for Trash {}
     impl DotAddressable for melib::SpecialUsageMailbox {}
+    impl DotAddressable for melib::thread::SortField {}
+    impl DotAddressable for melib::thread::SortOrder {}
     impl<T: DotAddressable> DotAddressable for Option<T> {}
     impl<T: DotAddressable> DotAddressable for Vec<T> {}
     impl<K: DotAddressable + std::cmp::Eq + std::hash::Hash, V: DotAddressable> DotAddressable
@@ -989,8 +1090,12 @@ mod dotaddressable {
                         "subscribed_mailboxes" => self.subscribed_mailboxes.lookup(field, tail),
                         "mailboxes" => self.mailboxes.lookup(field, tail),
                         "search_backend" => self.search_backend.lookup(field, tail),
+                        "trash" => self.trash.lookup(field, tail),
                         "manual_refresh" => self.manual_refresh.lookup(field, tail),
                         "refresh_command" => self.refresh_command.lookup(field, tail),
+                        "spam_command" => self.spam_command.lookup(field, tail),
+                        "ham_command" => self.ham_command.lookup(field, tail),
+                        "junk_mailbox" => self.junk_mailbox.lookup(field, tail),
                         "conf_override" => self.conf_override.lookup(field, tail),
                         "extra" => self.extra.lookup(field, tail),
                         other => Err(MeliError::new(format!(
@@ -1012,6 +1117,7 @@ mod dotaddressable {
                     match *field {
                         "name" => self.name.lookup(field, tail),
                         "root_mailbox" => self.root_mailbox.lookup(field, tail),
+                        "root_mailboxes" => self.root_mailboxes.lookup(field, tail),
                         "format" => self.format.lookup(field, tail),
                         "identity" => self.identity.lookup(field, tail),
                         "read_only" => self.read_only.lookup(field, tail),
@@ -1054,3 +1160,71 @@ mod dotaddressable {
         }
     }
 }
+
+#[test]
+fn test_account_identity_display_name() {
+    let acc: FileAccount = toml::from_str(
+        r#"
+root_mailbox = "~/Mail"
+format = "Maildir"
+identity = "user@example.com"
+display_name = "Jane Doe"
+"#,
+    )
+    .unwrap();
+    let settings = AccountConf::from(acc).account;
+    assert_eq!(settings.identity(), "user@example.com");
+    assert_eq!(settings.display_name(), Some(&"Jane Doe".to_string()));
+    assert_eq!(settings.make_display_name(), "Jane Doe <user@example.com>");
+}
+
+#[test]
+fn test_root_mailbox_deserialize() {
+    const TEMPLATE: &str = r#"
+format = "Maildir"
+identity = "user@example.com"
+"#;
+    let acc: FileAccount =
+        toml::from_str(&format!("root_mailbox = \"~/Mail\"\n{}", TEMPLATE)).unwrap();
+    assert_eq!(acc.root_mailbox, RootMailbox::Path("~/Mail".to_string()));
+    let settings = AccountConf::from(acc).account;
+    assert_eq!(settings.root_mailbox, "~/Mail");
+    assert_eq!(settings.root_mailboxes(), vec!["~/Mail"]);
+
+    let acc: FileAccount = toml::from_str(&format!(
+        "root_mailbox = [\"~/mail/personal\", \"/srv/shared/list\"]\n{}",
+        TEMPLATE
+    ))
+    .unwrap();
+    assert_eq!(
+        acc.root_mailbox,
+        RootMailbox::Paths(vec![
+            "~/mail/personal".to_string(),
+            "/srv/shared/list".to_string()
+        ])
+    );
+    assert_eq!(acc.mailbox(), "~/mail/personal");
+    let settings = AccountConf::from(acc).account;
+    assert_eq!(settings.root_mailbox, "~/mail/personal");
+    assert_eq!(
+        settings.root_mailboxes(),
+        vec!["~/mail/personal", "/srv/shared/list"]
+    );
+}
+
+#[test]
+fn test_trash_deserialize() {
+    const TEMPLATE: &str = r#"
+root_mailbox = "~/Mail"
+format = "Maildir"
+identity = "user@example.com"
+"#;
+    let acc: FileAccount = toml::from_str(TEMPLATE).unwrap();
+    assert_eq!(acc.trash, Trash::Expunge);
+    let acc: FileAccount = toml::from_str(&format!("trash = \"expunge\"\n{}", TEMPLATE)).unwrap();
+    assert_eq!(acc.trash, Trash::Expunge);
+    let acc: FileAccount =
+        toml::from_str(&format!("trash = \"INBOX/Trash\"\n{}", TEMPLATE)).unwrap();
+    assert_eq!(acc.trash, Trash::Mailbox("INBOX/Trash".to_string()));
+    assert!(toml::from_str::<FileAccount>(&format!("trash = \"\"\n{}", TEMPLATE)).is_err());
+}
@@ -28,7 +28,7 @@ use crate::jobs::{JobExecutor, JobId, JoinHandle};
 use indexmap::IndexMap;
 use melib::backends::*;
 use melib::email::*;
-use melib::error::{MeliError, Result};
+use melib::error::{MeliError, Result, ResultIntoMeliError};
 use melib::text_processing::GlobMatch;
 use melib::thread::{SortField, SortOrder, Threads};
 use melib::AddressBook;
@@ -156,6 +156,10 @@ pub struct Account {
     sender: Sender<ThreadEvent>,
     event_queue: VecDeque<(MailboxHash, RefreshEvent)>,
     pub backend_capabilities: MailBackendCapabilities,
+    /// Set with the `filter` command and applied to the listings of this account's mailboxes.
+    pub flag_filter: Option<crate::conf::FlagFilter>,
+    /// Recent destructive operations that the `undo` command can reverse, most recent last.
+    pub undo_stack: VecDeque<UndoOperation>,
 }
 
 pub enum JobRequest {
@@ -169,6 +173,10 @@ pub enum JobRequest {
             Pin<Box<dyn Stream<Item = Result<Vec<Envelope>>> + Send + 'static>>,
         )>,
     },
+    FetchRange {
+        mailbox_hash: MailboxHash,
+        handle: JoinHandle<Result<Vec<Envelope>>>,
+    },
     Generic {
         name: Cow<'static, str>,
         logging_level: melib::LoggingLevel,
@@ -201,6 +209,19 @@ pub enum JobRequest {
     },
     DeleteMessages {
         env_hashes: EnvelopeHashBatch,
+        handle: JoinHandle<Result<Option<UndoOperation>>>,
+    },
+    MoveMessages {
+        env_hashes: EnvelopeHashBatch,
+        undo: Option<UndoOperation>,
+        handle: JoinHandle<Result<()>>,
+    },
+    ReportMessages {
+        env_hashes: EnvelopeHashBatch,
+        source: MailboxHash,
+        destination: MailboxHash,
+        spam: bool,
+        command: String,
         handle: JoinHandle<Result<()>>,
     },
     CreateMailbox {
@@ -224,6 +245,7 @@ pub enum JobRequest {
     },
     SetMailboxSubscription {
         mailbox_hash: MailboxHash,
+        new_value: bool,
         handle: JoinHandle<Result<()>>,
     },
     Watch {
@@ -251,6 +273,12 @@ impl Drop for JobRequest {
             JobRequest::DeleteMessages { handle, .. } => {
                 handle.cancel();
             }
+            JobRequest::MoveMessages { handle, .. } => {
+                handle.cancel();
+            }
+            JobRequest::ReportMessages { handle, .. } => {
+                handle.cancel();
+            }
             JobRequest::CreateMailbox { handle, .. } => {
                 handle.cancel();
             }
@@ -260,6 +288,9 @@ impl Drop for JobRequest {
             JobRequest::Fetch { handle, .. } => {
                 handle.cancel();
             }
+            JobRequest::FetchRange { handle, .. } => {
+                handle.cancel();
+            }
             JobRequest::Mailboxes { handle, .. } => {
                 handle.cancel();
             }
@@ -269,6 +300,198 @@ impl Drop for JobRequest {
     }
 }
 
+/// Maximum number of operations kept in an account's undo stack.
+pub const UNDO_STACK_MAX: usize = 10;
+
+/// Maximum total size in bytes of the deleted messages kept in an account's undo stack.
+pub const UNDO_STACK_MAX_BYTES: usize = 16 * 1024 * 1024;
+
+/// A destructive operation on messages that the `undo` command can reverse.
+#[derive(Debug, Clone)]
+pub enum UndoOperation {
+    /// Messages deleted from `mailbox_hash`, kept with their flags so that they can be saved
+    /// back.
+    Delete {
+        mailbox_hash: MailboxHash,
+        messages: Vec<(Vec<u8>, Flag)>,
+    },
+    /// Messages moved from `source` to `destination`. They are identified by `Message-ID` since
+    /// their envelope hashes change with the mailbox.
+    Move {
+        source: MailboxHash,
+        destination: MailboxHash,
+        message_ids: Vec<MessageID>,
+    },
+}
+
+impl UndoOperation {
+    /// Size in bytes of the message contents kept to reverse this operation.
+    pub fn size(&self) -> usize {
+        match self {
+            UndoOperation::Delete { messages, .. } => {
+                messages.iter().map(|(bytes, _)| bytes.len()).sum()
+            }
+            UndoOperation::Move { .. } => 0,
+        }
+    }
+
+    /// Returns the backend job that reverses this operation. Moved messages are looked up by
+    /// `Message-ID` in the destination mailbox of `collection`.
+    pub fn undo_job(
+        &self,
+        backend: &mut dyn MailBackend,
+        collection: &Collection,
+    ) -> ResultFuture<()> {
+        match self {
+            UndoOperation::Delete {
+                mailbox_hash,
+                messages,
+            } => {
+                let jobs = messages
+                    .iter()
+                    .map(|(bytes, flags)| backend.save(bytes.clone(), *mailbox_hash, Some(*flags)))
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(Box::pin(async move {
+                    for job in jobs {
+                        job.await?;
+                    }
+                    Ok(())
+                }))
+            }
+            UndoOperation::Move {
+                source,
+                destination,
+                message_ids,
+            } => {
+                let env_hashes = collection
+                    .get_mailbox(*destination)
+                    .iter()
+                    .filter(|env_hash| {
+                        message_ids.contains(collection.get_env(**env_hash).message_id())
+                    })
+                    .cloned()
+                    .collect::<Vec<EnvelopeHash>>();
+                let env_hashes = EnvelopeHashBatch::try_from(env_hashes.as_slice())
+                    .map_err(|()| MeliError::new("The moved messages were not found."))?;
+                backend.copy_messages(env_hashes, *destination, *source, /* move? */ true)
+            }
+        }
+    }
+}
+
+/// Returns a job that deletes `messages` from `mailbox_hash`, resolving to the
+/// [`UndoOperation`] that restores them. If `trash` is another mailbox, they are moved there.
+/// Otherwise their contents are fetched and then they are expunged; if the contents are larger
+/// than `max_bytes` the deletion can't be undone and fetching stops early.
+fn delete_messages_job(
+    backend: &mut dyn MailBackend,
+    messages: Vec<(EnvelopeHash, Flag, MessageID)>,
+    mailbox_hash: MailboxHash,
+    trash: Option<MailboxHash>,
+    max_bytes: usize,
+) -> ResultFuture<Option<UndoOperation>> {
+    let env_hashes = messages
+        .iter()
+        .map(|(h, _, _)| *h)
+        .collect::<Vec<EnvelopeHash>>();
+    let env_hashes = EnvelopeHashBatch::try_from(env_hashes.as_slice())
+        .map_err(|()| MeliError::new("No messages to delete."))?;
+    if let Some(trash) = trash.filter(|trash| *trash != mailbox_hash) {
+        let message_ids = messages
+            .into_iter()
+            .map(|(_, _, message_id)| message_id)
+            .collect::<Vec<MessageID>>();
+        let move_ =
+            backend.copy_messages(env_hashes, mailbox_hash, trash, /* move? */ true)?;
+        return Ok(Box::pin(async move {
+            move_.await?;
+            Ok(Some(UndoOperation::Move {
+                source: mailbox_hash,
+                destination: trash,
+                message_ids,
+            }))
+        }));
+    }
+    let fetches = messages
+        .into_iter()
+        .map(|(env_hash, flags, _)| Ok((backend.operation(env_hash)?.as_bytes()?, flags)))
+        .collect::<Result<Vec<_>>>()?;
+    let delete = backend.delete_messages(env_hashes, mailbox_hash)?;
+    Ok(Box::pin(async move {
+        let mut messages = Vec::with_capacity(fetches.len());
+        let mut size = 0;
+        for (bytes, flags) in fetches {
+            let bytes = bytes.await?;
+            size += bytes.len();
+            if size > max_bytes {
+                messages.clear();
+                break;
+            }
+            messages.push((bytes, flags));
+        }
+        delete.await?;
+        if size > max_bytes {
+            return Ok(None);
+        }
+        Ok(Some(UndoOperation::Delete {
+            mailbox_hash,
+            messages,
+        }))
+    }))
+}
+
+/// Pushes `undo` onto `undo_stack`, dropping the oldest operations to keep it within
+/// [`UNDO_STACK_MAX`] operations and [`UNDO_STACK_MAX_BYTES`] bytes.
+fn push_undo(undo_stack: &mut VecDeque<UndoOperation>, undo: UndoOperation) {
+    undo_stack.push_back(undo);
+    while undo_stack.len() > UNDO_STACK_MAX
+        || undo_stack.iter().map(UndoOperation::size).sum::<usize>() > UNDO_STACK_MAX_BYTES
+    {
+        undo_stack.pop_front();
+    }
+}
+
+/// Returns the argument vector that runs an account's `spam_command` or `ham_command`.
+fn report_command_argv(command: &str) -> Vec<String> {
+    vec!["sh".to_string(), "-c".to_string(), command.to_string()]
+}
+
+/// Returns a job that pipes the raw source of each of `env_hashes` to the command `argv`, failing
+/// if it does not succeed for all of them.
+fn report_messages_job(
+    backend: &mut dyn MailBackend,
+    env_hashes: &EnvelopeHashBatch,
+    argv: Vec<String>,
+) -> ResultFuture<()> {
+    let fetches = env_hashes
+        .iter()
+        .map(|env_hash| backend.operation(env_hash)?.as_bytes())
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Box::pin(async move {
+        let command = argv.last().cloned().unwrap_or_default();
+        for bytes in fetches {
+            let bytes = bytes.await?;
+            let mut child = std::process::Command::new(&argv[0])
+                .args(&argv[1..])
+                .stdin(std::process::Stdio::piped())
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .spawn()
+                .chain_err_summary(|| format!("Could not execute `{}`", command))?;
+            /* The command may exit without reading all of its input */
+            let _ = io::Write::write_all(&mut child.stdin.take().unwrap(), &bytes);
+            let status = child.wait()?;
+            if !status.success() {
+                return Err(MeliError::new(format!(
+                    "`{}` exited with {}, messages were not moved",
+                    command, status
+                )));
+            }
+        }
+        Ok(())
+    }))
+}
+
 impl core::fmt::Debug for JobRequest {
     fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         match self {
@@ -277,12 +500,17 @@ impl core::fmt::Debug for JobRequest {
             JobRequest::Fetch { mailbox_hash, .. } => {
                 write!(f, "JobRequest::Fetch({})", mailbox_hash)
             }
+            JobRequest::FetchRange { mailbox_hash, .. } => {
+                write!(f, "JobRequest::FetchRange({})", mailbox_hash)
+            }
             JobRequest::IsOnline { .. } => write!(f, "JobRequest::IsOnline"),
             JobRequest::Refresh { .. } => write!(f, "JobRequest::Refresh"),
             JobRequest::SetFlags { .. } => write!(f, "JobRequest::SetFlags"),
             JobRequest::SaveMessage { .. } => write!(f, "JobRequest::SaveMessage"),
             JobRequest::CopyTo { .. } => write!(f, "JobRequest::CopyTo"),
             JobRequest::DeleteMessages { .. } => write!(f, "JobRequest::DeleteMessages"),
+            JobRequest::MoveMessages { .. } => write!(f, "JobRequest::MoveMessages"),
+            JobRequest::ReportMessages { .. } => write!(f, "JobRequest::ReportMessages"),
             JobRequest::CreateMailbox { .. } => write!(f, "JobRequest::CreateMailbox"),
             JobRequest::DeleteMailbox { mailbox_hash, .. } => {
                 write!(f, "JobRequest::DeleteMailbox({})", mailbox_hash)
@@ -311,6 +539,7 @@ impl core::fmt::Display for JobRequest {
             JobRequest::Generic { name, .. } => write!(f, "{}", name),
             JobRequest::Mailboxes { .. } => write!(f, "Get mailbox list"),
             JobRequest::Fetch { .. } => write!(f, "Mailbox fetch"),
+            JobRequest::FetchRange { .. } => write!(f, "Mailbox page fetch"),
             JobRequest::IsOnline { .. } => write!(f, "Online status check"),
             JobRequest::Refresh { .. } => write!(f, "Refresh mailbox"),
             JobRequest::SetFlags { env_hashes, .. } => write!(
@@ -327,6 +556,21 @@ impl core::fmt::Display for JobRequest {
                 env_hashes.len(),
                 if env_hashes.len() == 1 { "" } else { "s" }
             ),
+            JobRequest::MoveMessages { env_hashes, .. } => write!(
+                f,
+                "Move {} message{}",
+                env_hashes.len(),
+                if env_hashes.len() == 1 { "" } else { "s" }
+            ),
+            JobRequest::ReportMessages {
+                env_hashes, spam, ..
+            } => write!(
+                f,
+                "Report {} message{} as {}",
+                env_hashes.len(),
+                if env_hashes.len() == 1 { "" } else { "s" },
+                if *spam { "spam" } else { "ham" }
+            ),
             JobRequest::CreateMailbox { path, .. } => write!(f, "Create mailbox {}", path),
             JobRequest::DeleteMailbox { .. } => write!(f, "Delete mailbox"),
             //JobRequest::RenameMailbox,
@@ -435,7 +679,9 @@ impl Account {
         event_consumer: BackendEventConsumer,
     ) -> Result<Self> {
         let s = settings.clone();
-        let backend = map.get(settings.account().format())(
+        let backend = map
+            .get(settings.account().format())
+            .map_err(|err| err.set_summary(format!("Could not set up account `{}`", name)))?(
             settings.account(),
             Box::new(move |path: &str| {
                 s.account.subscribed_mailboxes.is_empty()
@@ -457,6 +703,9 @@ impl Account {
                 let reader = io::BufReader::new(fs::File::open(data).unwrap());
                 let result: result::Result<AddressBook, _> = serde_json::from_reader(reader);
                 if let Ok(data_t) = result {
+                    for addr in data_t.seen_addresses().values() {
+                        address_book.add_seen_address(addr);
+                    }
                     for (id, c) in data_t.cards {
                         if !address_book.card_exists(id) && !c.external_resource() {
                             address_book.add_card(c);
@@ -522,9 +771,43 @@ impl Account {
             event_queue: VecDeque::with_capacity(8),
             backend_capabilities: backend.capabilities(),
             backend: Arc::new(RwLock::new(backend)),
+            flag_filter: None,
+            undo_stack: VecDeque::with_capacity(UNDO_STACK_MAX),
         })
     }
 
+    /// An account with default settings that uses `backend` as is, without any jobs or files.
+    #[cfg(test)]
+    pub fn new_mock(
+        hash: AccountHash,
+        name: String,
+        backend: Box<dyn MailBackend>,
+        job_executor: Arc<JobExecutor>,
+        sender: Sender<ThreadEvent>,
+    ) -> Self {
+        Account {
+            hash,
+            address_book: AddressBook::new(name.clone()),
+            name,
+            is_online: Ok(()),
+            mailbox_entries: Default::default(),
+            mailboxes_order: Default::default(),
+            tree: Default::default(),
+            sent_mailbox: Default::default(),
+            collection: Default::default(),
+            settings: Default::default(),
+            sender,
+            job_executor,
+            active_jobs: Default::default(),
+            active_job_instants: Default::default(),
+            event_queue: VecDeque::with_capacity(8),
+            backend_capabilities: backend.capabilities(),
+            backend: Arc::new(RwLock::new(backend)),
+            flag_filter: None,
+            undo_stack: VecDeque::with_capacity(UNDO_STACK_MAX),
+        }
+    }
+
     fn init(&mut self, mut ref_mailboxes: HashMap<MailboxHash, Mailbox>) -> Result<()> {
         self.backend_capabilities = self.backend.read().unwrap().capabilities();
         let mut mailbox_entries: IndexMap<MailboxHash, MailboxEntry> =
@@ -686,9 +969,23 @@ impl Account {
         self.mailbox_entries = mailbox_entries;
         self.tree = tree;
         self.sent_mailbox = sent_mailbox;
+        if let Err(err) = self.validate_sent_mailbox() {
+            melib::log(err.to_string(), melib::WARN);
+            self.sender
+                .send(ThreadEvent::UIEvent(UIEvent::StatusEvent(
+                    StatusEvent::DisplayMessage(err.to_string()),
+                )))
+                .unwrap();
+        }
         Ok(())
     }
 
+    /// Checks that the account has a mailbox to save copies of sent messages in. This can only be
+    /// known after the backend has returned its mailboxes.
+    pub fn validate_sent_mailbox(&self) -> Result<MailboxHash> {
+        validate_sent_mailbox(&self.name, self.sent_mailbox, &self.settings.mailbox_confs)
+    }
+
     pub fn reload(&mut self, event: RefreshEvent, mailbox_hash: MailboxHash) -> Option<UIEvent> {
         if !self.mailbox_entries[&mailbox_hash].status.is_available()
             && !self.mailbox_entries[&mailbox_hash].status.is_parsing()
@@ -895,6 +1192,7 @@ impl Account {
                         );
                     }
 
+                    self.address_book.harvest(&envelope);
                     if self.collection.insert(*envelope, mailbox_hash) {
                         /* is a duplicate */
                         return None;
@@ -1032,21 +1330,50 @@ impl Account {
                 .unwrap();
             return Ok(());
         }
-        let refresh_job = self.backend.write().unwrap().refresh(mailbox_hash);
-        if let Ok(refresh_job) = refresh_job {
-            let handle = if self.backend_capabilities.is_async {
-                self.job_executor.spawn_specialized(refresh_job)
-            } else {
-                self.job_executor.spawn_blocking(refresh_job)
-            };
-            self.insert_job(
-                handle.job_id,
-                JobRequest::Refresh {
-                    mailbox_hash,
-                    handle,
-                },
-            );
+        let refresh_job = self.backend.write().unwrap().refresh(mailbox_hash)?;
+        let handle = if self.backend_capabilities.is_async {
+            self.job_executor.spawn_specialized(refresh_job)
+        } else {
+            self.job_executor.spawn_blocking(refresh_job)
+        };
+        self.insert_job(
+            handle.job_id,
+            JobRequest::Refresh {
+                mailbox_hash,
+                handle,
+            },
+        );
+        Ok(())
+    }
+
+    /// Fetches the envelopes at positions `from..=to` of `mailbox_hash`, counted from its newest
+    /// message, ahead of the fetch of the whole mailbox. Does nothing if the backend doesn't
+    /// support it or such a fetch is already running.
+    pub fn fetch_range(&mut self, mailbox_hash: MailboxHash, from: usize, to: usize) -> Result<()> {
+        if !self.backend_capabilities.supports_fetch_range
+            || self.active_jobs.values().any(|j| {
+                matches!(j, JobRequest::FetchRange { mailbox_hash: h, .. } if *h == mailbox_hash)
+            })
+        {
+            return Ok(());
         }
+        let fetch_job = self
+            .backend
+            .write()
+            .unwrap()
+            .fetch_range(mailbox_hash, from, to)?;
+        let handle = if self.backend_capabilities.is_async {
+            self.job_executor.spawn_specialized(fetch_job)
+        } else {
+            self.job_executor.spawn_blocking(fetch_job)
+        };
+        self.insert_job(
+            handle.job_id,
+            JobRequest::FetchRange {
+                mailbox_hash,
+                handle,
+            },
+        );
         Ok(())
     }
 
@@ -1382,6 +1709,198 @@ impl Account {
     pub fn contains_key(&self, h: EnvelopeHash) -> bool {
         self.collection.contains_key(&h)
     }
+
+    /// Deletes messages from `mailbox_hash` according to the account's `trash` setting. The
+    /// deletion can be reversed with [`Account::undo`].
+    pub fn delete_messages(
+        &mut self,
+        env_hashes: EnvelopeHashBatch,
+        mailbox_hash: MailboxHash,
+    ) -> Result<()> {
+        let trash = match self.settings.conf.trash {
+            crate::conf::Trash::Expunge => None,
+            crate::conf::Trash::Mailbox(ref path) => {
+                Some(self.mailbox_by_path(path).chain_err_summary(|| {
+                    format!("Account {}: trash mailbox {} not found", self.name, path)
+                })?)
+            }
+        };
+        let messages = env_hashes
+            .iter()
+            .map(|env_hash| {
+                let envelope = self.collection.get_env(env_hash);
+                (env_hash, envelope.flags(), envelope.message_id().clone())
+            })
+            .collect::<Vec<(EnvelopeHash, Flag, MessageID)>>();
+        let job = delete_messages_job(
+            &mut **self.backend.write().unwrap(),
+            messages,
+            mailbox_hash,
+            trash,
+            UNDO_STACK_MAX_BYTES,
+        )?;
+        let handle = self.job_executor.spawn_specialized(job);
+        self.insert_job(
+            handle.job_id,
+            JobRequest::DeleteMessages { env_hashes, handle },
+        );
+        Ok(())
+    }
+
+    /// Moves messages from `source` to `destination`. Their `Message-ID`s are remembered so that
+    /// the move can be reversed with [`Account::undo`].
+    pub fn move_messages(
+        &mut self,
+        env_hashes: EnvelopeHashBatch,
+        source: MailboxHash,
+        destination: MailboxHash,
+    ) -> Result<()> {
+        let message_ids = env_hashes
+            .iter()
+            .map(|env_hash| self.collection.get_env(env_hash).message_id().clone())
+            .collect::<Vec<MessageID>>();
+        let job = self.backend.write().unwrap().copy_messages(
+            env_hashes.clone(),
+            source,
+            destination,
+            /* move? */ true,
+        )?;
+        let handle = self.job_executor.spawn_specialized(job);
+        self.insert_job(
+            handle.job_id,
+            JobRequest::MoveMessages {
+                env_hashes,
+                undo: Some(UndoOperation::Move {
+                    source,
+                    destination,
+                    message_ids,
+                }),
+                handle,
+            },
+        );
+        Ok(())
+    }
+
+    /// Pipes messages to the account's `spam_command` and moves them to its junk mailbox, or, if
+    /// `spam` is false, pipes them to its `ham_command` and moves them to its inbox. The messages
+    /// are moved with [`Account::move_messages`] once the command has succeeded for all of them.
+    pub fn report_messages(
+        &mut self,
+        env_hashes: EnvelopeHashBatch,
+        mailbox_hash: MailboxHash,
+        spam: bool,
+    ) -> Result<()> {
+        let (setting, command) = if spam {
+            ("spam_command", self.settings.conf.spam_command.clone())
+        } else {
+            ("ham_command", self.settings.conf.ham_command.clone())
+        };
+        let command = command.ok_or_else(|| {
+            MeliError::new(format!("Account {}: {} is not set", self.name, setting))
+        })?;
+        let destination = if spam {
+            match self.settings.conf.junk_mailbox {
+                Some(ref path) => self.mailbox_by_path(path).chain_err_summary(|| {
+                    format!("Account {}: junk mailbox {} not found", self.name, path)
+                })?,
+                None => self
+                    .special_use_mailbox(SpecialUsageMailbox::Junk)
+                    .ok_or_else(|| {
+                        MeliError::new(format!(
+                            "Account {}: no junk mailbox found, set junk_mailbox",
+                            self.name
+                        ))
+                    })?,
+            }
+        } else {
+            self.special_use_mailbox(SpecialUsageMailbox::Inbox)
+                .or_else(|| self.mailbox_by_path("INBOX").ok())
+                .ok_or_else(|| {
+                    MeliError::new(format!("Account {}: no inbox mailbox found", self.name))
+                })?
+        };
+        let job = report_messages_job(
+            &mut **self.backend.write().unwrap(),
+            &env_hashes,
+            report_command_argv(&command),
+        )?;
+        let handle = self.job_executor.spawn_specialized(job);
+        self.insert_job(
+            handle.job_id,
+            JobRequest::ReportMessages {
+                env_hashes,
+                source: mailbox_hash,
+                destination,
+                spam,
+                command,
+                handle,
+            },
+        );
+        Ok(())
+    }
+
+    fn push_undo(&mut self, undo: UndoOperation) {
+        push_undo(&mut self.undo_stack, undo);
+    }
+
+    /// Describes the operation [`Account::undo`] would reverse, if any.
+    pub fn undo_description(&self) -> Option<String> {
+        let mailbox_name = |mailbox_hash: &MailboxHash| {
+            self.mailbox_entries
+                .get(mailbox_hash)
+                .map(|entry| entry.name().to_string())
+                .unwrap_or_else(|| mailbox_hash.to_string())
+        };
+        self.undo_stack.back().map(|undo| match undo {
+            UndoOperation::Delete {
+                mailbox_hash,
+                messages,
+            } => format!(
+                "deletion of {} message{} from {}",
+                messages.len(),
+                if messages.len() == 1 { "" } else { "s" },
+                mailbox_name(mailbox_hash)
+            ),
+            UndoOperation::Move {
+                source,
+                destination,
+                message_ids,
+            } => format!(
+                "move of {} message{} from {} to {}",
+                message_ids.len(),
+                if message_ids.len() == 1 { "" } else { "s" },
+                mailbox_name(source),
+                mailbox_name(destination)
+            ),
+        })
+    }
+
+    /// Reverses the most recent operation in the undo stack.
+    pub fn undo(&mut self) -> Result<()> {
+        let description = self
+            .undo_description()
+            .ok_or_else(|| MeliError::new("Nothing to undo."))?;
+        let undo = self.undo_stack.pop_back().unwrap();
+        let job = match undo.undo_job(&mut **self.backend.write().unwrap(), &self.collection) {
+            Ok(job) => job,
+            Err(err) => {
+                self.undo_stack.push_back(undo);
+                return Err(err);
+            }
+        };
+        let handle = self.job_executor.spawn_specialized(job);
+        self.insert_job(
+            handle.job_id,
+            JobRequest::Generic {
+                name: format!("undo of {}", description).into(),
+                handle,
+                on_finish: None,
+                logging_level: melib::LoggingLevel::INFO,
+            },
+        );
+        Ok(())
+    }
+
     pub fn operation(&self, h: EnvelopeHash) -> Result<Box<dyn BackendOp>> {
         let operation = self.backend.read().unwrap().operation(h)?;
         Ok(if self.settings.account.read_only() {
@@ -1435,51 +1954,36 @@ impl Account {
                 );
                 Ok(())
             }
-            MailboxOperation::Subscribe(path) => {
-                let mailbox_hash = self.mailbox_by_path(&path)?;
-                self.backend
-                    .write()
-                    .unwrap()
-                    .set_mailbox_subscription(mailbox_hash, true)?;
-                self.mailbox_entries.entry(mailbox_hash).and_modify(|m| {
-                    m.conf.mailbox_conf.subscribe = super::ToggleFlag::True;
-                    let _ = m.ref_mailbox.set_is_subscribed(true);
-                });
-
-                self.sender
-                    .send(ThreadEvent::UIEvent(UIEvent::Notification(
-                        None,
-                        format!("'`{}` has been subscribed.", &path),
-                        Some(crate::types::NotificationType::Info),
-                    )))
-                    .expect("Could not send event on main channel");
-                Ok(())
-            }
-            MailboxOperation::Unsubscribe(path) => {
-                let mailbox_hash = self.mailbox_by_path(&path)?;
-                self.backend
-                    .write()
-                    .unwrap()
-                    .set_mailbox_subscription(mailbox_hash, false)?;
-                self.mailbox_entries.entry(mailbox_hash).and_modify(|m| {
-                    m.conf.mailbox_conf.subscribe = super::ToggleFlag::False;
-                    let _ = m.ref_mailbox.set_is_subscribed(false);
-                });
-
-                self.sender
-                    .send(ThreadEvent::UIEvent(UIEvent::Notification(
-                        None,
-                        format!("'`{}` has been unsubscribed.", &path),
-                        Some(crate::types::NotificationType::Info),
-                    )))
-                    .expect("Could not send event on main channel");
-                Ok(())
-            }
+            MailboxOperation::Subscribe(path) => self.set_mailbox_subscription(&path, true),
+            MailboxOperation::Unsubscribe(path) => self.set_mailbox_subscription(&path, false),
             MailboxOperation::Rename(_, _) => Err(MeliError::new("Not implemented.")),
             MailboxOperation::SetPermissions(_) => Err(MeliError::new("Not implemented.")),
         }
     }
 
+    fn set_mailbox_subscription(&mut self, path: &str, new_value: bool) -> Result<()> {
+        let mailbox_hash = self.mailbox_by_path(path)?;
+        let job = self
+            .backend
+            .write()
+            .unwrap()
+            .set_mailbox_subscription(mailbox_hash, new_value)?;
+        let handle = if self.backend_capabilities.is_async {
+            self.job_executor.spawn_specialized(job)
+        } else {
+            self.job_executor.spawn_blocking(job)
+        };
+        self.insert_job(
+            handle.job_id,
+            JobRequest::SetMailboxSubscription {
+                mailbox_hash,
+                new_value,
+                handle,
+            },
+        );
+        Ok(())
+    }
+
     pub fn special_use_mailbox(&self, special_use: SpecialUsageMailbox) -> Option<MailboxHash> {
         let ret = self
             .mailbox_entries
@@ -1668,28 +2172,18 @@ impl Account {
                             .unwrap();
                         return true;
                     }
-                    let envelopes = payload
-                        .unwrap()
-                        .into_iter()
-                        .map(|e| (e.hash(), e))
-                        .collect::<HashMap<EnvelopeHash, Envelope>>();
-                    if let Some(updated_mailboxes) =
-                        self.collection
-                            .merge(envelopes, mailbox_hash, self.sent_mailbox)
-                    {
-                        for f in updated_mailboxes {
-                            self.sender
-                                .send(ThreadEvent::UIEvent(UIEvent::MailboxUpdate((self.hash, f))))
-                                .unwrap();
-                        }
-                    }
-                    self.sender
-                        .send(ThreadEvent::UIEvent(UIEvent::MailboxUpdate((
-                            self.hash,
-                            mailbox_hash,
-                        ))))
-                        .unwrap();
+                    self.merge_envelopes(mailbox_hash, payload.unwrap());
                 }
+                JobRequest::FetchRange {
+                    mailbox_hash,
+                    ref mut handle,
+                } => match handle.chan.try_recv().unwrap() {
+                    Some(Ok(envelopes)) => self.merge_envelopes(mailbox_hash, envelopes),
+                    Some(Err(err)) => {
+                        debug!("could not fetch envelopes of {}: {}", mailbox_hash, err);
+                    }
+                    None => {}
+                },
                 JobRequest::IsOnline { ref mut handle, .. } => {
                     let is_online = handle.chan.try_recv().unwrap();
                     if let Some(is_online) = is_online {
@@ -1852,17 +2346,110 @@ impl Account {
                     }
                 }
                 JobRequest::DeleteMessages { ref mut handle, .. } => {
-                    let r = handle.chan.try_recv().unwrap();
-                    if let Some(Err(err)) = r {
+                    match handle.chan.try_recv().unwrap() {
+                        Some(Err(err)) => {
+                            self.sender
+                                .send(ThreadEvent::UIEvent(UIEvent::Notification(
+                                    Some(format!("{}: could not delete message", &self.name)),
+                                    err.to_string(),
+                                    Some(crate::types::NotificationType::Error(err.kind)),
+                                )))
+                                .expect("Could not send event on main channel");
+                        }
+                        Some(Ok(Some(undo))) => self.push_undo(undo),
+                        Some(Ok(None)) => {
+                            self.sender
+                                .send(ThreadEvent::UIEvent(UIEvent::Notification(
+                                    Some(format!("{}: deleted messages", &self.name)),
+                                    "The messages are too large to keep, so their deletion can't \
+                                     be undone."
+                                        .to_string(),
+                                    Some(crate::types::NotificationType::Info),
+                                )))
+                                .expect("Could not send event on main channel");
+                        }
+                        None => {}
+                    }
+                }
+                JobRequest::MoveMessages {
+                    ref mut handle,
+                    ref mut undo,
+                    ..
+                } => match handle.chan.try_recv().unwrap() {
+                    Some(Err(err)) => {
                         self.sender
                             .send(ThreadEvent::UIEvent(UIEvent::Notification(
-                                Some(format!("{}: could not delete message", &self.name)),
+                                Some(format!("{}: could not move message", &self.name)),
                                 err.to_string(),
                                 Some(crate::types::NotificationType::Error(err.kind)),
                             )))
                             .expect("Could not send event on main channel");
                     }
-                }
+                    Some(Ok(())) => {
+                        if let Some(undo) = undo.take() {
+                            self.push_undo(undo);
+                        }
+                        self.sender
+                            .send(ThreadEvent::UIEvent(UIEvent::Notification(
+                                Some(format!("{}: message moving succeeded", &self.name)),
+                                String::new(),
+                                Some(crate::types::NotificationType::Info),
+                            )))
+                            .expect("Could not send event on main channel");
+                    }
+                    None => {}
+                },
+                JobRequest::ReportMessages {
+                    ref env_hashes,
+                    source,
+                    destination,
+                    spam,
+                    ref command,
+                    ref mut handle,
+                } => match handle.chan.try_recv().unwrap() {
+                    Some(Err(err)) => {
+                        self.sender
+                            .send(ThreadEvent::UIEvent(UIEvent::Notification(
+                                Some(format!(
+                                    "{}: could not report message as {}",
+                                    &self.name,
+                                    if spam { "spam" } else { "ham" }
+                                )),
+                                err.to_string(),
+                                Some(crate::types::NotificationType::Error(err.kind)),
+                            )))
+                            .expect("Could not send event on main channel");
+                    }
+                    Some(Ok(())) => {
+                        self.sender
+                            .send(ThreadEvent::UIEvent(UIEvent::Notification(
+                                Some(format!(
+                                    "{}: reported {} message{} as {}",
+                                    &self.name,
+                                    env_hashes.len(),
+                                    if env_hashes.len() == 1 { "" } else { "s" },
+                                    if spam { "spam" } else { "ham" }
+                                )),
+                                format!("`{}` exited successfully", command),
+                                Some(crate::types::NotificationType::Info),
+                            )))
+                            .expect("Could not send event on main channel");
+                        if source != destination {
+                            if let Err(err) =
+                                self.move_messages(env_hashes.clone(), source, destination)
+                            {
+                                self.sender
+                                    .send(ThreadEvent::UIEvent(UIEvent::Notification(
+                                        Some(format!("{}: could not move message", &self.name)),
+                                        err.to_string(),
+                                        Some(crate::types::NotificationType::Error(err.kind)),
+                                    )))
+                                    .expect("Could not send event on main channel");
+                            }
+                        }
+                    }
+                    None => {}
+                },
                 JobRequest::CreateMailbox {
                     ref path,
                     ref mut handle,
@@ -2053,7 +2640,11 @@ impl Account {
                         None => {}
                     }
                 }
-                JobRequest::SetMailboxSubscription { ref mut handle, .. } => {
+                JobRequest::SetMailboxSubscription {
+                    ref mut handle,
+                    mailbox_hash,
+                    new_value,
+                } => {
                     let r = handle.chan.try_recv().unwrap();
                     match r {
                         Some(Err(err)) => {
@@ -2069,6 +2660,24 @@ impl Account {
                                 .expect("Could not send event on main channel");
                         }
                         Some(Ok(_)) => {
+                            self.mailbox_entries.entry(mailbox_hash).and_modify(|m| {
+                                m.conf.mailbox_conf.subscribe = if new_value {
+                                    super::ToggleFlag::True
+                                } else {
+                                    super::ToggleFlag::False
+                                };
+                                let _ = m.ref_mailbox.set_is_subscribed(new_value);
+                            });
+                            build_mailboxes_order(
+                                &mut self.tree,
+                                &self.mailbox_entries,
+                                &mut self.mailboxes_order,
+                            );
+                            self.sender
+                                .send(ThreadEvent::UIEvent(UIEvent::AccountStatusChange(
+                                    self.hash,
+                                )))
+                                .unwrap();
                             self.sender
                                 .send(ThreadEvent::UIEvent(UIEvent::Notification(
                                     Some(format!(
@@ -2147,6 +2756,33 @@ impl Account {
         }
     }
 
+    /// Adds fetched `envelopes` of `mailbox_hash` to the collection.
+    fn merge_envelopes(&mut self, mailbox_hash: MailboxHash, envelopes: Vec<Envelope>) {
+        let envelopes = envelopes
+            .into_iter()
+            .map(|e| (e.hash(), e))
+            .collect::<HashMap<EnvelopeHash, Envelope>>();
+        for envelope in envelopes.values() {
+            self.address_book.harvest(envelope);
+        }
+        if let Some(updated_mailboxes) =
+            self.collection
+                .merge(envelopes, mailbox_hash, self.sent_mailbox)
+        {
+            for f in updated_mailboxes {
+                self.sender
+                    .send(ThreadEvent::UIEvent(UIEvent::MailboxUpdate((self.hash, f))))
+                    .unwrap();
+            }
+        }
+        self.sender
+            .send(ThreadEvent::UIEvent(UIEvent::MailboxUpdate((
+                self.hash,
+                mailbox_hash,
+            ))))
+            .unwrap();
+    }
+
     pub fn insert_job(&mut self, job_id: JobId, job: JobRequest) {
         self.active_jobs.insert(job_id, job);
         self.active_job_instants
@@ -2192,13 +2828,23 @@ fn build_mailboxes_order(
 ) {
     tree.clear();
     mailboxes_order.clear();
+    /* Guards against malformed parent/children links that would otherwise make the walk loop
+     * forever. */
+    let mut visited: HashSet<MailboxHash> = HashSet::with_capacity(mailbox_entries.len());
     for (h, f) in mailbox_entries.iter() {
-        if f.ref_mailbox.parent().is_none() {
+        /* Mailboxes whose parent is unknown are shown as roots instead of being hidden. */
+        if f.ref_mailbox
+            .parent()
+            .map(|p| !mailbox_entries.contains_key(&p))
+            .unwrap_or(true)
+        {
             fn rec(
                 h: MailboxHash,
                 mailbox_entries: &IndexMap<MailboxHash, MailboxEntry>,
                 depth: usize,
+                visited: &mut HashSet<MailboxHash>,
             ) -> MailboxNode {
+                visited.insert(h);
                 let mut node = MailboxNode {
                     hash: h,
                     children: Vec::new(),
@@ -2207,14 +2853,17 @@ fn build_mailboxes_order(
                     has_sibling: false,
                 };
                 for &c in mailbox_entries[&h].ref_mailbox.children() {
-                    if mailbox_entries.contains_key(&c) {
-                        node.children.push(rec(c, mailbox_entries, depth + 1));
+                    if mailbox_entries.contains_key(&c) && !visited.contains(&c) {
+                        node.children
+                            .push(rec(c, mailbox_entries, depth + 1, visited));
                     }
                 }
                 node
             };
 
-            tree.push(rec(*h, &mailbox_entries, 0));
+            if !visited.contains(h) {
+                tree.push(rec(*h, mailbox_entries, 0, &mut visited));
+            }
         }
     }
 
@@ -2309,3 +2958,297 @@ fn build_mailboxes_order(
         rec(node, &mailbox_entries, 0, 1, false);
     }
 }
+
+fn validate_sent_mailbox(
+    account_name: &str,
+    sent_mailbox: Option<MailboxHash>,
+    mailbox_confs: &IndexMap<String, FileMailboxConf>,
+) -> Result<MailboxHash> {
+    if let Some(mailbox_hash) = sent_mailbox {
+        return Ok(mailbox_hash);
+    }
+    if let Some(path) = mailbox_confs
+        .iter()
+        .find(|(_, conf)| conf.mailbox_conf.usage == Some(SpecialUsageMailbox::Sent))
+        .map(|(path, _)| path)
+    {
+        Err(MeliError::new(format!(
+            "Account `{}`: mailbox `{}` is configured as the Sent mailbox but does not exist. Is it misspelled?",
+            account_name, path
+        )))
+    } else {
+        Err(MeliError::new(format!(
+            "Account `{}` has no Sent mailbox, sent messages will be saved in the inbox. Set `usage = \"Sent\"` in a mailbox's configuration to choose one.",
+            account_name
+        )))
+    }
+}
+
+#[test]
+fn test_validate_sent_mailbox() {
+    let mut mailbox_confs: IndexMap<String, FileMailboxConf> = IndexMap::default();
+    let mut conf = FileMailboxConf::default();
+    conf.mailbox_conf.usage = Some(SpecialUsageMailbox::Sent);
+    mailbox_confs.insert("Snet".to_string(), conf);
+    let err = validate_sent_mailbox("personal", None, &mailbox_confs).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("mailbox `Snet` is configured as the Sent mailbox"));
+
+    let err = validate_sent_mailbox("personal", None, &IndexMap::default()).unwrap_err();
+    assert!(err.to_string().contains("has no Sent mailbox"));
+
+    assert_eq!(
+        validate_sent_mailbox("personal", Some(1), &mailbox_confs).unwrap(),
+        1
+    );
+}
+
+/// A memory backend with `mailboxes` and a message in each `(mailbox, subject)` of `messages`,
+/// whose `Message-ID`s are `<1@example.com>`, `<2@example.com>`, … in order.
+#[cfg(test)]
+fn memory_backend(
+    mailboxes: &[&str],
+    messages: &[(&str, &str)],
+) -> melib::backends::memory::MemoryType {
+    let mut builder = melib::backends::memory::MemoryType::builder();
+    for mailbox in mailboxes {
+        builder = builder.mailbox(mailbox);
+    }
+    for (i, (mailbox, subject)) in messages.iter().enumerate() {
+        builder = builder.message(
+            mailbox,
+            format!(
+                "From: a@example.com\nSubject: {subject}\nMessage-ID: <{i}@example.com>\n\n{subject}\n",
+                subject = subject,
+                i = i + 1
+            )
+            .into_bytes(),
+        );
+    }
+    builder
+        .build(BackendEventConsumer::new(Arc::new(|_, _| {})))
+        .unwrap()
+}
+
+#[cfg(test)]
+fn fetch_envelopes(backend: &mut dyn MailBackend, mailbox_hash: MailboxHash) -> Vec<Envelope> {
+    futures::executor::block_on(backend.fetch(mailbox_hash).unwrap().next())
+        .unwrap()
+        .unwrap()
+}
+
+#[test]
+fn test_undo_delete() {
+    let mut backend = memory_backend(&["INBOX"], &[("INBOX", "hello")]);
+    let inbox_hash = melib::get_path_hash!("INBOX");
+    let envelope = fetch_envelopes(&mut backend, inbox_hash).remove(0);
+
+    let undo = futures::executor::block_on(
+        delete_messages_job(
+            &mut backend,
+            vec![(envelope.hash(), Flag::SEEN, envelope.message_id().clone())],
+            inbox_hash,
+            None,
+            UNDO_STACK_MAX_BYTES,
+        )
+        .unwrap(),
+    )
+    .unwrap()
+    .unwrap();
+    assert!(fetch_envelopes(&mut backend, inbox_hash).is_empty());
+
+    futures::executor::block_on(undo.undo_job(&mut backend, &Collection::new()).unwrap()).unwrap();
+    let envelopes = fetch_envelopes(&mut backend, inbox_hash);
+    assert_eq!(envelopes.len(), 1);
+    assert_eq!(envelopes[0].message_id_display(), "<1@example.com>");
+    assert_eq!(envelopes[0].flags(), Flag::SEEN);
+}
+
+#[test]
+fn test_undo_stack_limits() {
+    let mut backend = memory_backend(&["INBOX"], &[("INBOX", "hello")]);
+    let inbox_hash = melib::get_path_hash!("INBOX");
+    let envelope = fetch_envelopes(&mut backend, inbox_hash).remove(0);
+    /* Messages larger than the limit are deleted without being kept */
+    let undo = futures::executor::block_on(
+        delete_messages_job(
+            &mut backend,
+            vec![(envelope.hash(), Flag::SEEN, envelope.message_id().clone())],
+            inbox_hash,
+            None,
+            1,
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(undo.is_none());
+    assert!(fetch_envelopes(&mut backend, inbox_hash).is_empty());
+
+    let delete = |size: usize| UndoOperation::Delete {
+        mailbox_hash: inbox_hash,
+        messages: vec![(vec![b'a'; size], Flag::SEEN)],
+    };
+    let mut undo_stack = VecDeque::new();
+    for _ in 0..3 {
+        push_undo(&mut undo_stack, delete(UNDO_STACK_MAX_BYTES / 3 + 1));
+    }
+    assert_eq!(undo_stack.len(), 2);
+    for _ in 0..UNDO_STACK_MAX {
+        push_undo(
+            &mut undo_stack,
+            UndoOperation::Move {
+                source: inbox_hash,
+                destination: inbox_hash,
+                message_ids: vec![],
+            },
+        );
+    }
+    assert_eq!(undo_stack.len(), UNDO_STACK_MAX);
+    assert!(undo_stack.iter().all(|undo| undo.size() == 0));
+}
+
+#[test]
+fn test_delete_messages_trash() {
+    let mut backend = memory_backend(&["INBOX", "Trash"], &[("INBOX", "one"), ("INBOX", "two")]);
+    let inbox_hash = melib::get_path_hash!("INBOX");
+    let trash_hash = melib::get_path_hash!("Trash");
+    let delete = |backend: &mut dyn MailBackend,
+                  envelope: &Envelope,
+                  mailbox_hash: MailboxHash,
+                  trash: Option<MailboxHash>| {
+        futures::executor::block_on(
+            delete_messages_job(
+                backend,
+                vec![(
+                    envelope.hash(),
+                    envelope.flags(),
+                    envelope.message_id().clone(),
+                )],
+                mailbox_hash,
+                trash,
+                UNDO_STACK_MAX_BYTES,
+            )
+            .unwrap(),
+        )
+        .unwrap()
+        .unwrap()
+    };
+    let envelopes = fetch_envelopes(&mut backend, inbox_hash);
+
+    /* `trash = "Trash"` moves the message */
+    let undo = delete(&mut backend, &envelopes[0], inbox_hash, Some(trash_hash));
+    assert!(
+        matches!(undo, UndoOperation::Move { source, destination, .. } if source == inbox_hash && destination == trash_hash)
+    );
+    assert_eq!(fetch_envelopes(&mut backend, inbox_hash).len(), 1);
+    let trashed = fetch_envelopes(&mut backend, trash_hash);
+    assert_eq!(trashed.len(), 1);
+    assert_eq!(
+        trashed[0].message_id_display(),
+        envelopes[0].message_id_display()
+    );
+
+    /* Deleting from the trash mailbox itself expunges */
+    let undo = delete(&mut backend, &trashed[0], trash_hash, Some(trash_hash));
+    assert!(
+        matches!(undo, UndoOperation::Delete { mailbox_hash, .. } if mailbox_hash == trash_hash)
+    );
+    assert!(fetch_envelopes(&mut backend, trash_hash).is_empty());
+
+    /* `trash = "expunge"` expunges in place */
+    let undo = delete(&mut backend, &envelopes[1], inbox_hash, None);
+    assert!(
+        matches!(undo, UndoOperation::Delete { mailbox_hash, .. } if mailbox_hash == inbox_hash)
+    );
+    assert!(fetch_envelopes(&mut backend, inbox_hash).is_empty());
+    assert!(fetch_envelopes(&mut backend, trash_hash).is_empty());
+}
+
+#[test]
+fn test_report_messages() {
+    assert_eq!(
+        report_command_argv("sa-learn --spam"),
+        vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            "sa-learn --spam".to_string()
+        ]
+    );
+
+    let mut backend = memory_backend(&["INBOX", "Junk"], &[("INBOX", "spam")]);
+    let mailboxes = futures::executor::block_on(backend.mailboxes().unwrap()).unwrap();
+    let inbox_hash = melib::get_path_hash!("INBOX");
+    let junk_hash = melib::get_path_hash!("Junk");
+    let env_hash = fetch_envelopes(&mut backend, inbox_hash)[0].hash();
+
+    let (sender, receiver) = crossbeam::unbounded();
+    let job_executor = Arc::new(JobExecutor::new(sender.clone()));
+    let mut account = Account::new_mock(
+        1,
+        "test".to_string(),
+        Box::new(backend),
+        job_executor,
+        sender,
+    );
+    account.init(mailboxes).unwrap();
+    /* Runs the account's jobs to completion, and returns the titles of its notifications */
+    let run_jobs = |account: &mut Account| {
+        let mut notifications = vec![];
+        loop {
+            let event = if account.active_jobs.is_empty() {
+                match receiver.try_recv() {
+                    Ok(event) => event,
+                    Err(_) => break,
+                }
+            } else {
+                receiver
+                    .recv_timeout(std::time::Duration::from_secs(10))
+                    .unwrap()
+            };
+            match event {
+                ThreadEvent::JobFinished(job_id) => {
+                    account.process_event(&job_id);
+                }
+                ThreadEvent::UIEvent(Notification(Some(title), _, _)) => notifications.push(title),
+                _ => {}
+            }
+        }
+        notifications
+    };
+    let fetch = |account: &Account, mailbox_hash: MailboxHash| {
+        fetch_envelopes(&mut **account.backend.write().unwrap(), mailbox_hash)
+    };
+    run_jobs(&mut account);
+
+    /* A failing command is reported and the message is not moved; the command reads the raw
+     * message */
+    account.settings.conf.spam_command = Some("grep -q 'Subject: ham'".to_string());
+    account
+        .report_messages(env_hash.into(), inbox_hash, true)
+        .unwrap();
+    assert_eq!(
+        run_jobs(&mut account),
+        vec!["test: could not report message as spam".to_string()]
+    );
+    assert_eq!(fetch(&account, inbox_hash).len(), 1);
+    assert!(fetch(&account, junk_hash).is_empty());
+
+    /* Spam is moved to the junk mailbox */
+    account.settings.conf.spam_command = Some("grep -q 'Subject: spam'".to_string());
+    account
+        .report_messages(env_hash.into(), inbox_hash, true)
+        .unwrap();
+    run_jobs(&mut account);
+    assert!(fetch(&account, inbox_hash).is_empty());
+    assert_eq!(fetch(&account, junk_hash).len(), 1);
+
+    /* Ham is moved back to the inbox */
+    account.settings.conf.ham_command = Some("cat > /dev/null".to_string());
+    account
+        .report_messages(env_hash.into(), junk_hash, false)
+        .unwrap();
+    run_jobs(&mut account);
+    assert_eq!(fetch(&account, inbox_hash).len(), 1);
+    assert!(fetch(&account, junk_hash).is_empty());
+}
@@ -53,6 +53,26 @@ pub struct ComposingSettings {
     /// Default: empty
     #[serde(default, alias = "default-header-values")]
     pub default_header_values: HashMap<String, String>,
+    /// Interval in seconds between autosaves of drafts being composed. Set to 0 to disable.
+    /// Default: 30
+    #[serde(default = "default_autosave_interval", alias = "autosave-interval")]
+    pub autosave_interval: u64,
+    /// Signature appended to new drafts and replies after a `-- ` line.
+    /// Default: None
+    #[serde(default = "none")]
+    pub signature: Option<String>,
+    /// File to read the signature from, eg `~/.signature`. Takes precedence over `signature`.
+    /// Default: None
+    #[serde(default = "none", alias = "signature-file")]
+    pub signature_file: Option<String>,
+    /// Forward messages as `message/rfc822` attachments instead of quoting them in the body.
+    /// Default: true
+    #[serde(default = "true_val", alias = "forward-as-attachment")]
+    pub forward_as_attachment: bool,
+}
+
+fn default_autosave_interval() -> u64 {
+    30
 }
 
 impl Default for ComposingSettings {
@@ -64,6 +84,10 @@ impl Default for ComposingSettings {
             format_flowed: true,
             insert_user_agent: true,
             default_header_values: HashMap::default(),
+            autosave_interval: default_autosave_interval(),
+            signature: None,
+            signature_file: None,
+            forward_as_attachment: true,
         }
     }
 }
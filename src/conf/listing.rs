@@ -21,7 +21,66 @@
 
 use super::{default_vals::*, DotAddressable, IndexStyle};
 use melib::search::Query;
-use melib::{MeliError, Result};
+use melib::thread::{SortField, SortOrder, ThreadHash, Threads};
+use melib::{Envelope, EnvelopeHash, Flag, MeliError, Result};
+use smallvec::SmallVec;
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DateStyle {
+    Relative,
+    Absolute,
+}
+
+impl DotAddressable for DateStyle {}
+
+/// Restricts the listing to envelopes with certain flags. Set at runtime with the `filter`
+/// command.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FlagFilter {
+    /// Envelopes without the `SEEN` flag.
+    Unread,
+    /// Envelopes with the `FLAGGED` flag.
+    Flagged,
+}
+
+impl FlagFilter {
+    pub fn matches(self, flags: Flag) -> bool {
+        match self {
+            FlagFilter::Unread => !flags.contains(Flag::SEEN),
+            FlagFilter::Flagged => flags.contains(Flag::FLAGGED),
+        }
+    }
+
+    /// Returns true if any envelope of `thread` matches.
+    pub fn matches_thread(
+        self,
+        threads: &Threads,
+        envelopes: &HashMap<EnvelopeHash, Envelope>,
+        thread: ThreadHash,
+    ) -> bool {
+        threads.thread_group_iter(thread).any(|(_, h)| {
+            threads.thread_nodes()[&h]
+                .message()
+                .and_then(|env_hash| envelopes.get(&env_hash))
+                .map(|env| self.matches(env.flags()))
+                .unwrap_or(false)
+        })
+    }
+
+    /// Keeps only the thread `roots` that have a matching envelope.
+    pub fn retain_roots(
+        self,
+        roots: &mut SmallVec<[ThreadHash; 1024]>,
+        threads: &Threads,
+        envelopes: &RwLock<HashMap<EnvelopeHash, Envelope>>,
+    ) {
+        let envelopes = envelopes.read().unwrap();
+        roots.retain(|thread| self.matches_thread(threads, &envelopes, *thread));
+    }
+}
 
 /// Settings for mail listings
 ///
@@ -67,6 +126,12 @@ pub struct ListingSettings {
     #[serde(default = "true_val", alias = "recent-dates")]
     pub recent_dates: bool,
 
+    /// How to show dates: `relative` shows recent dates in compact units (`5m`, `3h`, `2d`) up to
+    /// 7 days, `absolute` always uses `datetime_fmt`. If not set, `recent_dates` applies.
+    /// Default: None
+    #[serde(default = "none", alias = "date-style")]
+    pub date_style: Option<DateStyle>,
+
     /// Show only envelopes that match this query
     /// Default: None
     #[serde(default = "none")]
@@ -75,6 +140,16 @@ pub struct ListingSettings {
     #[serde(default, alias = "index-style")]
     pub index_style: IndexStyle,
 
+    /// Field to sort the listing by: `date`, `subject`, `from` or `received`.
+    /// Default: date
+    #[serde(default, alias = "sort-field")]
+    pub sort_field: SortField,
+
+    /// Sort order of the listing: `asc` or `desc`.
+    /// Default: desc
+    #[serde(default, alias = "sort-order")]
+    pub sort_order: SortOrder,
+
     ///Default: " "
     #[serde(default = "none")]
     pub sidebar_mailbox_tree_has_sibling: Option<String>,
@@ -90,6 +165,12 @@ pub struct ListingSettings {
     ///Default: " "
     #[serde(default)]
     pub sidebar_mailbox_tree_no_sibling_leaf: Option<String>,
+
+    /// Show the total message count next to the unread count of each mailbox in the sidebar,
+    /// e.g. `INBOX 3/120`.
+    /// Default: false
+    #[serde(default = "false_val", alias = "sidebar-show-total-count")]
+    pub sidebar_show_total_count: bool,
 }
 
 impl Default for ListingSettings {
@@ -98,12 +179,16 @@ impl Default for ListingSettings {
             context_lines: 0,
             datetime_fmt: None,
             recent_dates: true,
+            date_style: None,
             filter: None,
             index_style: IndexStyle::default(),
+            sort_field: SortField::default(),
+            sort_order: SortOrder::default(),
             sidebar_mailbox_tree_has_sibling: None,
             sidebar_mailbox_tree_no_sibling: None,
             sidebar_mailbox_tree_has_sibling_leaf: None,
             sidebar_mailbox_tree_no_sibling_leaf: None,
+            sidebar_show_total_count: false,
         }
     }
 }
@@ -117,8 +202,11 @@ impl DotAddressable for ListingSettings {
                     "context_lines" => self.context_lines.lookup(field, tail),
                     "datetime_fmt" => self.datetime_fmt.lookup(field, tail),
                     "recent_dates" => self.recent_dates.lookup(field, tail),
+                    "date_style" => self.date_style.lookup(field, tail),
                     "filter" => self.filter.lookup(field, tail),
                     "index_style" => self.index_style.lookup(field, tail),
+                    "sort_field" => self.sort_field.lookup(field, tail),
+                    "sort_order" => self.sort_order.lookup(field, tail),
                     "sidebar_mailbox_tree_has_sibling" => {
                         self.sidebar_mailbox_tree_has_sibling.lookup(field, tail)
                     }
@@ -131,6 +219,7 @@ impl DotAddressable for ListingSettings {
                     "sidebar_mailbox_tree_no_sibling_leaf" => self
                         .sidebar_mailbox_tree_no_sibling_leaf
                         .lookup(field, tail),
+                    "sidebar_show_total_count" => self.sidebar_show_total_count.lookup(field, tail),
                     other => Err(MeliError::new(format!(
                         "{} has no field named {}",
                         parent_field, other
@@ -141,3 +230,58 @@ impl DotAddressable for ListingSettings {
         }
     }
 }
+
+#[test]
+fn test_flag_filter() {
+    use melib::Collection;
+    let collection = Collection::new();
+    let mailbox_hash = 0;
+    collection.new_mailbox(mailbox_hash);
+    let envelope = |msg_id: &str, in_reply_to: Option<&str>, flags: Flag| {
+        let raw = format!(
+            "From: a@example.com\r\nTo: b@example.com\r\nSubject: {}\r\nMessage-ID: <{}@example.com>\r\n{}Date: Mon, 1 Mar 2021 10:00:00 +0000\r\n\r\nbody\r\n",
+            msg_id,
+            msg_id,
+            in_reply_to
+                .map(|r| format!("In-Reply-To: <{}@example.com>\r\n", r))
+                .unwrap_or_default()
+        );
+        Envelope::from_bytes(raw.as_bytes(), Some(flags)).unwrap()
+    };
+    /* "a" is read but has an unread reply, "b" is flagged and "c" is only read */
+    for env in vec![
+        envelope("a", None, Flag::SEEN),
+        envelope("a-reply", Some("a"), Flag::empty()),
+        envelope("b", None, Flag::SEEN | Flag::FLAGGED),
+        envelope("c", None, Flag::SEEN),
+    ] {
+        collection.insert(env, mailbox_hash);
+    }
+    let threads = collection.get_threads(mailbox_hash);
+    let subjects = |filter: FlagFilter| {
+        let mut roots = threads.roots();
+        filter.retain_roots(&mut roots, &threads, &collection.envelopes);
+        let envelopes = collection.envelopes.read().unwrap();
+        let mut ret = roots
+            .into_iter()
+            .map(|t| {
+                let root = threads.thread_ref(t).root();
+                let env_hash = threads.thread_nodes()[&root].message().unwrap();
+                envelopes[&env_hash].subject().to_string()
+            })
+            .collect::<Vec<String>>();
+        ret.sort();
+        ret
+    };
+    assert_eq!(threads.roots().len(), 3);
+    assert_eq!(subjects(FlagFilter::Unread), vec!["a"]);
+    assert_eq!(subjects(FlagFilter::Flagged), vec!["b"]);
+    /* Plain listings match each envelope on its own */
+    let envelopes = collection.envelopes.read().unwrap();
+    let unread = envelopes
+        .values()
+        .filter(|e| FlagFilter::Unread.matches(e.flags()))
+        .map(|e| e.subject().to_string())
+        .collect::<Vec<String>>();
+    assert_eq!(unread, vec!["a-reply"]);
+}
@@ -55,6 +55,13 @@ pub struct PagerSettingsOverride {
     #[serde(deserialize_with = "non_empty_string", alias = "html-filter")]
     #[serde(default)]
     pub html_filter: Option<Option<String>>,
+    #[doc = " Load remote images, stylesheets and backgrounds of html mail when passing it to the html"]
+    #[doc = " filter. When false, such references are removed first so that they can't be used to track"]
+    #[doc = " when a mail was read."]
+    #[doc = " Default: false"]
+    #[serde(alias = "load-remote-content")]
+    #[serde(default)]
+    pub load_remote_content: Option<bool>,
     #[doc = " Respect \"format=flowed\""]
     #[doc = " Default: true"]
     #[serde(alias = "format-flowed")]
@@ -65,6 +72,12 @@ pub struct PagerSettingsOverride {
     #[serde(alias = "split-long-lines")]
     #[serde(default)]
     pub split_long_lines: Option<bool>,
+    #[doc = " How to fit text to the pager width: `all` reflows every paragraph, `no_wrap` truncates long"]
+    #[doc = " lines and `off` only reflows paragraphs sent as format=flowed. If not set,"]
+    #[doc = " `split_long_lines` chooses between `all` and `no_wrap`."]
+    #[doc = " Default: None"]
+    #[serde(default)]
+    pub reflow: Option<Option<PagerReflow>>,
     #[doc = " Minimum text width in columns."]
     #[doc = " Default: 80"]
     #[serde(alias = "minimum-width")]
@@ -76,6 +89,59 @@ pub struct PagerSettingsOverride {
     #[serde(alias = "auto-choose-multipart-alternative")]
     #[serde(default)]
     pub auto_choose_multipart_alternative: Option<ToggleFlag>,
+    #[doc = " Headers to show above the mail body in mail view, in this order. Headers that are missing"]
+    #[doc = " or empty in an e-mail are skipped."]
+    #[doc = " Default: [\"Date\", \"From\", \"To\", \"Cc\", \"Subject\", \"Message-ID\"]"]
+    #[serde(default)]
+    pub headers: Option<Vec<String>>,
+    #[doc = " Wrap header values that don't fit in one line, instead of truncating them."]
+    #[doc = " Default: true"]
+    #[serde(alias = "header-wrap")]
+    #[serde(default)]
+    pub header_wrap: Option<bool>,
+    #[doc = " Colors of quoted text, by quote depth. Lines quoted deeper than the number of colors"]
+    #[doc = " cycle through the list again. An empty list disables quote highlighting."]
+    #[doc = " Default: [\"Teal\", \"Olive\", \"Purple\", \"Green\"]"]
+    #[serde(alias = "quote-colors")]
+    #[serde(default)]
+    pub quote_colors: Option<Vec<Color>>,
+    #[doc = " Dim e-mail signatures, i.e. the lines after a `-- ` separator line, with the"]
+    #[doc = " `pager.signature` theme attribute."]
+    #[doc = " Default: true"]
+    #[serde(alias = "dim-signature")]
+    #[serde(default)]
+    pub dim_signature: Option<bool>,
+    #[doc = " Show a gutter with line numbers, colored with the `pager.line_numbers` theme attribute."]
+    #[doc = " Lines split to fit the pager width share the number of the line they continue."]
+    #[doc = " Default: false"]
+    #[serde(alias = "show-line-numbers")]
+    #[serde(default)]
+    pub show_line_numbers: Option<bool>,
+    #[doc = " Command used to open URLs. Occurrences of `{}` in its arguments are replaced by the URL; if"]
+    #[doc = " there are none, the URL is appended as the last argument."]
+    #[doc = " Default: \"xdg-open\""]
+    #[serde(alias = "url-launcher")]
+    #[serde(default)]
+    pub url_launcher: Option<String>,
+    #[doc = " How links are labeled in the URL view of a mail: `inline` puts `[n]` before each link,"]
+    #[doc = " `references` replaces each link with `[n]` and lists the links after the body."]
+    #[doc = " Default: \"inline\""]
+    #[serde(alias = "url-display")]
+    #[serde(default)]
+    pub url_display: Option<UrlDisplay>,
+    #[doc = " strftime(3) format of the `Date` header in mail view, shown in the local timezone. If"]
+    #[doc = " unset, the header is shown as it is in the e-mail."]
+    #[doc = " Default: None"]
+    #[serde(alias = "date-format")]
+    #[serde(default)]
+    pub date_format: Option<Option<String>>,
+    #[doc = " Messages larger than this many bytes are not fetched when opened, until the"]
+    #[doc = " `fetch_message` shortcut is pressed. Only backends that report message sizes (IMAP) are"]
+    #[doc = " affected."]
+    #[doc = " Default: None"]
+    #[serde(alias = "max-autofetch-size")]
+    #[serde(default)]
+    pub max_autofetch_size: Option<Option<usize>>,
 }
 impl Default for PagerSettingsOverride {
     fn default() -> Self {
@@ -86,10 +152,21 @@ impl Default for PagerSettingsOverride {
             pager_ratio: None,
             filter: None,
             html_filter: None,
+            load_remote_content: None,
             format_flowed: None,
             split_long_lines: None,
+            reflow: None,
             minimum_width: None,
             auto_choose_multipart_alternative: None,
+            headers: None,
+            header_wrap: None,
+            quote_colors: None,
+            dim_signature: None,
+            show_line_numbers: None,
+            url_launcher: None,
+            url_display: None,
+            date_format: None,
+            max_autofetch_size: None,
         }
     }
 }
@@ -112,6 +189,12 @@ pub struct ListingSettingsOverride {
     #[serde(alias = "recent-dates")]
     #[serde(default)]
     pub recent_dates: Option<bool>,
+    #[doc = " How to show dates: `relative` shows recent dates in compact units (`5m`, `3h`, `2d`) up to"]
+    #[doc = " 7 days, `absolute` always uses `datetime_fmt`. If not set, `recent_dates` applies."]
+    #[doc = " Default: None"]
+    #[serde(alias = "date-style")]
+    #[serde(default)]
+    pub date_style: Option<Option<DateStyle>>,
     #[doc = " Show only envelopes that match this query"]
     #[doc = " Default: None"]
     #[serde(default)]
@@ -119,6 +202,16 @@ pub struct ListingSettingsOverride {
     #[serde(alias = "index-style")]
     #[serde(default)]
     pub index_style: Option<IndexStyle>,
+    #[doc = " Field to sort the listing by: `date`, `subject`, `from` or `received`."]
+    #[doc = " Default: date"]
+    #[serde(alias = "sort-field")]
+    #[serde(default)]
+    pub sort_field: Option<SortField>,
+    #[doc = " Sort order of the listing: `asc` or `desc`."]
+    #[doc = " Default: desc"]
+    #[serde(alias = "sort-order")]
+    #[serde(default)]
+    pub sort_order: Option<SortOrder>,
     #[doc = "Default: \" \""]
     #[serde(default)]
     pub sidebar_mailbox_tree_has_sibling: Option<Option<String>>,
@@ -131,6 +224,12 @@ pub struct ListingSettingsOverride {
     #[doc = "Default: \" \""]
     #[serde(default)]
     pub sidebar_mailbox_tree_no_sibling_leaf: Option<Option<String>>,
+    #[doc = " Show the total message count next to the unread count of each mailbox in the sidebar,"]
+    #[doc = " e.g. `INBOX 3/120`."]
+    #[doc = " Default: false"]
+    #[serde(alias = "sidebar-show-total-count")]
+    #[serde(default)]
+    pub sidebar_show_total_count: Option<bool>,
 }
 impl Default for ListingSettingsOverride {
     fn default() -> Self {
@@ -138,12 +237,16 @@ impl Default for ListingSettingsOverride {
             context_lines: None,
             datetime_fmt: None,
             recent_dates: None,
+            date_style: None,
             filter: None,
             index_style: None,
+            sort_field: None,
+            sort_order: None,
             sidebar_mailbox_tree_has_sibling: None,
             sidebar_mailbox_tree_no_sibling: None,
             sidebar_mailbox_tree_has_sibling_leaf: None,
             sidebar_mailbox_tree_no_sibling_leaf: None,
+            sidebar_show_total_count: None,
         }
     }
 }
@@ -252,6 +355,25 @@ pub struct ComposingSettingsOverride {
     #[serde(alias = "default-header-values")]
     #[serde(default)]
     pub default_header_values: Option<HashMap<String, String>>,
+    #[doc = " Interval in seconds between autosaves of drafts being composed. Set to 0 to disable."]
+    #[doc = " Default: 30"]
+    #[serde(alias = "autosave-interval")]
+    #[serde(default)]
+    pub autosave_interval: Option<u64>,
+    #[doc = " Signature appended to new drafts and replies after a `-- ` line."]
+    #[doc = " Default: None"]
+    #[serde(default)]
+    pub signature: Option<Option<String>>,
+    #[doc = " File to read the signature from, eg `~/.signature`. Takes precedence over `signature`."]
+    #[doc = " Default: None"]
+    #[serde(alias = "signature-file")]
+    #[serde(default)]
+    pub signature_file: Option<Option<String>>,
+    #[doc = " Forward messages as `message/rfc822` attachments instead of quoting them in the body."]
+    #[doc = " Default: true"]
+    #[serde(alias = "forward-as-attachment")]
+    #[serde(default)]
+    pub forward_as_attachment: Option<bool>,
 }
 impl Default for ComposingSettingsOverride {
     fn default() -> Self {
@@ -262,6 +384,10 @@ impl Default for ComposingSettingsOverride {
             format_flowed: None,
             insert_user_agent: None,
             default_header_values: None,
+            autosave_interval: None,
+            signature: None,
+            signature_file: None,
+            forward_as_attachment: None,
         }
     }
 }
@@ -331,6 +457,16 @@ pub struct PGPSettingsOverride {
     #[serde(alias = "remote-lookup-mechanisms")]
     #[serde(default)]
     pub remote_lookup_mechanisms: Option<melib::gpgme::LocateKey>,
+    #[doc = " Command to verify OpenPGP signatures with instead of gpgme."]
+    #[doc = " Default: None"]
+    #[serde(alias = "pgp-verify-command")]
+    #[serde(default)]
+    pub pgp_verify_command: Option<Option<String>>,
+    #[doc = " Command to verify S/MIME signatures with."]
+    #[doc = " Default: \"openssl smime -verify -inform DER -in {signature} -content {data} -out /dev/null\""]
+    #[serde(alias = "smime-verify-command")]
+    #[serde(default)]
+    pub smime_verify_command: Option<String>,
 }
 #[cfg(feature = "gpgme")]
 impl Default for PGPSettingsOverride {
@@ -345,6 +481,8 @@ impl Default for PGPSettingsOverride {
             encrypt_key: None,
             allow_remote_lookup: None,
             remote_lookup_mechanisms: None,
+            pgp_verify_command: None,
+            smime_verify_command: None,
         }
     }
 }
@@ -352,10 +490,30 @@ impl Default for PGPSettingsOverride {
 #[cfg(not(feature = "gpgme"))]
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(deny_unknown_fields)]
-pub struct PGPSettingsOverride {}
+pub struct PGPSettingsOverride {
+    #[doc = " auto verify signed e-mail according to RFC3156"]
+    #[doc = " Default: true"]
+    #[serde(alias = "auto-verify-signatures")]
+    #[serde(default)]
+    pub auto_verify_signatures: Option<bool>,
+    #[doc = " Command to verify OpenPGP signatures with."]
+    #[doc = " Default: \"gpg --batch --status-fd 1 --verify {signature} {data}\""]
+    #[serde(alias = "pgp-verify-command")]
+    #[serde(default)]
+    pub pgp_verify_command: Option<String>,
+    #[doc = " Command to verify S/MIME signatures with."]
+    #[doc = " Default: \"openssl smime -verify -inform DER -in {signature} -content {data} -out /dev/null\""]
+    #[serde(alias = "smime-verify-command")]
+    #[serde(default)]
+    pub smime_verify_command: Option<String>,
+}
 #[cfg(not(feature = "gpgme"))]
 impl Default for PGPSettingsOverride {
     fn default() -> Self {
-        PGPSettingsOverride {}
+        PGPSettingsOverride {
+            auto_verify_signatures: None,
+            pgp_verify_command: None,
+            smime_verify_command: None,
+        }
     }
 }
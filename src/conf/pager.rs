@@ -24,6 +24,8 @@
 use super::default_vals::*;
 use super::deserializers::*;
 use super::DotAddressable;
+use crate::terminal::Color;
+use melib::text_processing::Reflow;
 use melib::{MeliError, Result, ToggleFlag};
 
 /// Settings for the pager function.
@@ -64,6 +66,13 @@ pub struct PagerSettings {
     )]
     pub html_filter: Option<String>,
 
+    /// Load remote images, stylesheets and backgrounds of html mail when passing it to the html
+    /// filter. When false, such references are removed first so that they can't be used to track
+    /// when a mail was read.
+    /// Default: false
+    #[serde(default = "false_val", alias = "load-remote-content")]
+    pub load_remote_content: bool,
+
     /// Respect "format=flowed"
     /// Default: true
     #[serde(default = "true_val", alias = "format-flowed")]
@@ -74,6 +83,13 @@ pub struct PagerSettings {
     #[serde(default = "true_val", alias = "split-long-lines")]
     pub split_long_lines: bool,
 
+    /// How to fit text to the pager width: `all` reflows every paragraph, `no_wrap` truncates long
+    /// lines and `off` only reflows paragraphs sent as format=flowed. If not set,
+    /// `split_long_lines` chooses between `all` and `no_wrap`.
+    /// Default: None
+    #[serde(default = "none")]
+    pub reflow: Option<PagerReflow>,
+
     /// Minimum text width in columns.
     /// Default: 80
     #[serde(default = "eighty_val", alias = "minimum-width")]
@@ -87,6 +103,126 @@ pub struct PagerSettings {
         alias = "auto-choose-multipart-alternative"
     )]
     pub auto_choose_multipart_alternative: ToggleFlag,
+
+    /// Headers to show above the mail body in mail view, in this order. Headers that are missing
+    /// or empty in an e-mail are skipped.
+    /// Default: ["Date", "From", "To", "Cc", "Subject", "Message-ID"]
+    #[serde(default = "default_headers")]
+    pub headers: Vec<String>,
+
+    /// Wrap header values that don't fit in one line, instead of truncating them.
+    /// Default: true
+    #[serde(default = "true_val", alias = "header-wrap")]
+    pub header_wrap: bool,
+
+    /// Colors of quoted text, by quote depth. Lines quoted deeper than the number of colors
+    /// cycle through the list again. An empty list disables quote highlighting.
+    /// Default: ["Teal", "Olive", "Purple", "Green"]
+    #[serde(default = "default_quote_colors", alias = "quote-colors")]
+    pub quote_colors: Vec<Color>,
+
+    /// Dim e-mail signatures, i.e. the lines after a `-- ` separator line, with the
+    /// `pager.signature` theme attribute.
+    /// Default: true
+    #[serde(default = "true_val", alias = "dim-signature")]
+    pub dim_signature: bool,
+
+    /// Show a gutter with line numbers, colored with the `pager.line_numbers` theme attribute.
+    /// Lines split to fit the pager width share the number of the line they continue.
+    /// Default: false
+    #[serde(default = "false_val", alias = "show-line-numbers")]
+    pub show_line_numbers: bool,
+
+    /// Command used to open URLs. Occurrences of `{}` in its arguments are replaced by the URL; if
+    /// there are none, the URL is appended as the last argument.
+    /// Default: "xdg-open"
+    #[serde(default = "default_url_launcher", alias = "url-launcher")]
+    pub url_launcher: String,
+
+    /// How links are labeled in the URL view of a mail: `inline` puts `[n]` before each link,
+    /// `references` replaces each link with `[n]` and lists the links after the body.
+    /// Default: "inline"
+    #[serde(default, alias = "url-display")]
+    pub url_display: UrlDisplay,
+
+    /// strftime(3) format of the `Date` header in mail view, shown in the local timezone. If
+    /// unset, the header is shown as it is in the e-mail.
+    /// Default: None
+    #[serde(default = "none", alias = "date-format")]
+    pub date_format: Option<String>,
+
+    /// Messages larger than this many bytes are not fetched when opened, until the
+    /// `fetch_message` shortcut is pressed. Only backends that report message sizes (IMAP) are
+    /// affected.
+    /// Default: None
+    #[serde(default = "none", alias = "max-autofetch-size")]
+    pub max_autofetch_size: Option<usize>,
+}
+
+fn default_headers() -> Vec<String> {
+    ["Date", "From", "To", "Cc", "Subject", "Message-ID"]
+        .iter()
+        .map(|h| h.to_string())
+        .collect()
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PagerReflow {
+    Off,
+    All,
+    NoWrap,
+}
+
+impl From<PagerReflow> for Reflow {
+    fn from(val: PagerReflow) -> Self {
+        match val {
+            PagerReflow::Off => Reflow::FormatFlowed,
+            PagerReflow::All => Reflow::All,
+            PagerReflow::NoWrap => Reflow::No,
+        }
+    }
+}
+
+impl DotAddressable for PagerReflow {}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UrlDisplay {
+    Inline,
+    References,
+}
+
+impl Default for UrlDisplay {
+    fn default() -> Self {
+        UrlDisplay::Inline
+    }
+}
+
+impl DotAddressable for UrlDisplay {}
+
+impl PagerSettings {
+    /// The `Reflow` mode to use, taking `split_long_lines` into account if `reflow` isn't set.
+    pub fn reflow(&self) -> Reflow {
+        match self.reflow {
+            Some(val) => val.into(),
+            None if self.split_long_lines => Reflow::All,
+            None => Reflow::No,
+        }
+    }
+}
+
+fn default_url_launcher() -> String {
+    "xdg-open".to_string()
+}
+
+fn default_quote_colors() -> Vec<Color> {
+    vec![
+        Color::Byte(6),
+        Color::Byte(3),
+        Color::Byte(5),
+        Color::Byte(2),
+    ]
 }
 
 impl Default for PagerSettings {
@@ -98,10 +234,21 @@ impl Default for PagerSettings {
             pager_ratio: 80,
             filter: None,
             html_filter: None,
+            load_remote_content: false,
             format_flowed: true,
             split_long_lines: true,
+            reflow: None,
             minimum_width: 80,
             auto_choose_multipart_alternative: ToggleFlag::InternalVal(true),
+            headers: default_headers(),
+            header_wrap: true,
+            quote_colors: default_quote_colors(),
+            dim_signature: true,
+            show_line_numbers: false,
+            url_launcher: default_url_launcher(),
+            url_display: UrlDisplay::default(),
+            date_format: None,
+            max_autofetch_size: None,
         }
     }
 }
@@ -118,12 +265,23 @@ impl DotAddressable for PagerSettings {
                     "pager_ratio" => self.pager_ratio.lookup(field, tail),
                     "filter" => self.filter.lookup(field, tail),
                     "html_filter" => self.html_filter.lookup(field, tail),
+                    "load_remote_content" => self.load_remote_content.lookup(field, tail),
                     "format_flowed" => self.format_flowed.lookup(field, tail),
                     "split_long_lines" => self.split_long_lines.lookup(field, tail),
+                    "reflow" => self.reflow.lookup(field, tail),
                     "minimum_width" => self.minimum_width.lookup(field, tail),
                     "auto_choose_multipart_alternative" => {
                         self.auto_choose_multipart_alternative.lookup(field, tail)
                     }
+                    "headers" => self.headers.lookup(field, tail),
+                    "header_wrap" => self.header_wrap.lookup(field, tail),
+                    "quote_colors" => self.quote_colors.lookup(field, tail),
+                    "dim_signature" => self.dim_signature.lookup(field, tail),
+                    "show_line_numbers" => self.show_line_numbers.lookup(field, tail),
+                    "url_launcher" => self.url_launcher.lookup(field, tail),
+                    "url_display" => self.url_display.lookup(field, tail),
+                    "date_format" => self.date_format.lookup(field, tail),
+                    "max_autofetch_size" => self.max_autofetch_size.lookup(field, tail),
                     other => Err(MeliError::new(format!(
                         "{} has no field named {}",
                         parent_field, other
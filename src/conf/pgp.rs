@@ -19,11 +19,29 @@
  * along with meli. If not, see <http://www.gnu.org/licenses/>.
  */
 
-#[cfg(feature = "gpgme")]
 use super::default_vals::*;
 #[cfg(feature = "gpgme")]
 use melib::conf::ToggleFlag;
 
+/// Command used to verify detached OpenPGP signatures when meli is built without gpgme.
+/// `{signature}` and `{data}` are replaced with the paths of the signature and the signed data.
+pub const DEFAULT_PGP_VERIFY_COMMAND: &str =
+    "gpg --batch --status-fd 1 --verify {signature} {data}";
+
+/// Command used to verify detached S/MIME signatures.
+/// `{signature}` and `{data}` are replaced with the paths of the signature and the signed data.
+pub const DEFAULT_SMIME_VERIFY_COMMAND: &str =
+    "openssl smime -verify -inform DER -in {signature} -content {data} -out /dev/null";
+
+#[cfg(not(feature = "gpgme"))]
+fn default_pgp_verify_command() -> String {
+    DEFAULT_PGP_VERIFY_COMMAND.to_string()
+}
+
+fn default_smime_verify_command() -> String {
+    DEFAULT_SMIME_VERIFY_COMMAND.to_string()
+}
+
 #[cfg(feature = "gpgme")]
 /// Settings for digital signing and encryption
 #[derive(Debug, Deserialize, Clone, Serialize)]
@@ -74,6 +92,19 @@ pub struct PGPSettings {
         alias = "remote-lookup-mechanisms"
     )]
     pub remote_lookup_mechanisms: melib::gpgme::LocateKey,
+
+    /// Command to verify OpenPGP signatures with instead of gpgme.
+    /// Default: None
+    #[serde(default = "none", alias = "pgp-verify-command")]
+    pub pgp_verify_command: Option<String>,
+
+    /// Command to verify S/MIME signatures with.
+    /// Default: "openssl smime -verify -inform DER -in {signature} -content {data} -out /dev/null"
+    #[serde(
+        default = "default_smime_verify_command",
+        alias = "smime-verify-command"
+    )]
+    pub smime_verify_command: String,
 }
 
 #[cfg(feature = "gpgme")]
@@ -94,11 +125,43 @@ impl Default for PGPSettings {
             encrypt_key: None,
             allow_remote_lookup: internal_value_false::<ToggleFlag>(),
             remote_lookup_mechanisms: default_lookup_mechanism(),
+            pgp_verify_command: None,
+            smime_verify_command: default_smime_verify_command(),
         }
     }
 }
 
 #[cfg(not(feature = "gpgme"))]
-#[derive(Debug, Default, Deserialize, Clone, Serialize)]
+/// Settings for digital signing and encryption
+#[derive(Debug, Deserialize, Clone, Serialize)]
 #[serde(deny_unknown_fields)]
-pub struct PGPSettings;
+pub struct PGPSettings {
+    /// auto verify signed e-mail according to RFC3156
+    /// Default: true
+    #[serde(default = "true_val", alias = "auto-verify-signatures")]
+    pub auto_verify_signatures: bool,
+
+    /// Command to verify OpenPGP signatures with.
+    /// Default: "gpg --batch --status-fd 1 --verify {signature} {data}"
+    #[serde(default = "default_pgp_verify_command", alias = "pgp-verify-command")]
+    pub pgp_verify_command: String,
+
+    /// Command to verify S/MIME signatures with.
+    /// Default: "openssl smime -verify -inform DER -in {signature} -content {data} -out /dev/null"
+    #[serde(
+        default = "default_smime_verify_command",
+        alias = "smime-verify-command"
+    )]
+    pub smime_verify_command: String,
+}
+
+#[cfg(not(feature = "gpgme"))]
+impl Default for PGPSettings {
+    fn default() -> Self {
+        PGPSettings {
+            auto_verify_signatures: true,
+            pgp_verify_command: default_pgp_verify_command(),
+            smime_verify_command: default_smime_verify_command(),
+        }
+    }
+}
@@ -175,15 +175,19 @@ shortcut_key_values! { "listing",
         next_page |> "Go to next page." |> Key::PageDown,
         prev_account |> "Go to previous account." |> Key::Char('l'),
         prev_mailbox |> "Go to previous mailbox." |> Key::Char('K'),
+        go_to_mailbox |> "Fuzzy search mailboxes by name and go to the selected one." |> Key::Char('g'),
         open_mailbox |> "Open selected mailbox" |> Key::Char('\n'),
         prev_page |> "Go to previous page." |> Key::PageUp,
         search |> "Search within list of e-mails." |> Key::Char('/'),
         refresh |> "Manually request a mailbox refresh." |> Key::F(5),
         set_seen |> "Set thread as seen." |> Key::Char('n'),
+        report_spam |> "Report thread as spam and move it to the junk mailbox." |> Key::Char('S'),
+        report_ham |> "Report thread as not spam and move it to the inbox." |> Key::Char('H'),
         union_modifier |> "Union modifier." |> Key::Ctrl('u'),
         diff_modifier |> "Difference modifier." |> Key::Ctrl('d'),
         intersection_modifier |> "Intersection modifier." |> Key::Ctrl('i'),
         select_entry |> "Select thread entry." |> Key::Char('v'),
+        toggle_thread_collapse |> "Collapse or expand thread under cursor in threaded listing." |> Key::Char(' '),
         toggle_menu_visibility |> "Toggle visibility of side menu in mail list." |> Key::Char('`')
     }
 }
@@ -208,7 +212,13 @@ shortcut_key_values! { "pager",
         page_down |> "Go to next pager page" |>  Key::PageDown,
         page_up |> "Go to previous pager page" |>  Key::PageUp,
         scroll_down |> "Scroll down pager." |> Key::Char('j'),
-        scroll_up |> "Scroll up pager." |> Key::Char('k')
+        scroll_up |> "Scroll up pager." |> Key::Char('k'),
+        search |> "Search within the pager text. Use n and N to go to the next and previous match." |> Key::Char('/'),
+        toggle_reflow |> "Cycle between reflowing text, not wrapping long lines and only reflowing format=flowed text." |> Key::Char('w'),
+        go_to_top |> "Go to the start of the pager text." |> Key::Char('g'),
+        go_to_line |> "Go to the line given as a numeric prefix, or to the end of the pager text." |> Key::Char('G'),
+        go_to_percentage |> "Go to the percentage of the pager text given as a numeric prefix." |> Key::Char('%'),
+        toggle_line_numbers |> "Toggle the line number gutter." |> Key::Char('#')
     }
 }
 
@@ -218,6 +228,8 @@ shortcut_key_values! { "general",
         enter_command_mode |> "Enter COMMAND mode." |> Key::Char(':'),
         go_to_tab |> "Go to the nth tab" |> Key::Alt('n'),
         next_tab |> "Next tab." |> Key::Char('T'),
+        move_tab_left |> "Move tab to the left." |> Key::Char('{'),
+        move_tab_right |> "Move tab to the right." |> Key::Char('}'),
         scroll_right |> "Generic scroll right (catch-all setting)" |> Key::Right,
         scroll_left |> "Generic scroll left (catch-all setting)" |> Key::Left,
         scroll_up |> "Generic scroll up (catch-all setting)" |> Key::Up,
@@ -229,6 +241,7 @@ shortcut_key_values! { "composing",
     pub struct ComposingShortcuts {
         edit_mail |> "Edit mail." |> Key::Char('e'),
         send_mail |> "Deliver draft to mailer" |> Key::Char('s'),
+        toggle_signature |> "Remove or add the signature." |> Key::Char('S'),
         scroll_up |> "Change field focus." |> Key::Up,
         scroll_down |> "Change field focus." |> Key::Down
     }
@@ -238,16 +251,22 @@ shortcut_key_values! { "envelope-view",
     pub struct EnvelopeViewShortcuts {
         add_addresses_to_contacts |> "Select addresses from envelope to add to contacts." |> Key::Char('c'),
         edit |> "Open envelope in composer." |> Key::Char('e'),
+        fetch_message |> "Fetch envelope larger than pager.max_autofetch_size." |> Key::Char('F'),
+        forward |> "Forward envelope." |> Key::Char('f'),
         go_to_url |> "Go to url of given index" |> Key::Char('g'),
+        load_remote_content |> "Load remote content of html parts." |> Key::Char('X'),
         open_attachment |> "Opens selected attachment with xdg-open." |> Key::Char('a'),
         open_mailcap |> "Opens selected attachment according to its mailcap entry." |> Key::Char('m'),
+        pipe_message |> "Pipe envelope to a shell command (raw source when viewing it, decoded text otherwise)." |> Key::Char('|'),
         reply |> "Reply to envelope." |> Key::Char('R'),
         reply_to_author |> "Reply to author." |> Key::Ctrl('r'),
         reply_to_all |> "Reply to all/Reply to list/Follow up." |> Key::Ctrl('g'),
         return_to_normal_view |> "Return to envelope if viewing raw source or attachment." |> Key::Char('r'),
         toggle_expand_headers |> "Expand extra headers (References and others)." |> Key::Char('h'),
         toggle_url_mode |> "Toggles url open mode." |> Key::Char('u'),
-        view_raw_source |> "View envelope source in a pager. (toggles between raw and decoded source)" |> Key::Alt('r')
+        view_raw_source |> "View envelope source in a pager. (cycles between decoded, raw and highlighted raw source)" |> Key::Alt('r'),
+        yank_message_id |> "Copy the Message-ID of the envelope to the clipboard." |> Key::Char('Y'),
+        yank_url |> "Copy url of given index to the clipboard." |> Key::Char('y')
     }
 }
 
@@ -35,6 +35,9 @@ pub struct TerminalSettings {
     pub themes: Themes,
     pub ascii_drawing: bool,
     pub use_color: ToggleFlag,
+    /// Use 24-bit colors. If false, `#rrggbb` theme colors are mapped to the nearest 256 color.
+    /// Default: true if `COLORTERM` is `truecolor` or `24bit`
+    pub use_truecolor: ToggleFlag,
     /// Use mouse events. This will disable text selection, but you will be able to resize some
     /// widgets.
     /// Default: False
@@ -43,15 +46,34 @@ pub struct TerminalSettings {
     /// Default: "🖱️ "
     #[serde(deserialize_with = "non_empty_string")]
     pub mouse_flag: Option<String>,
+    /// Number of lines to scroll for each mouse wheel tick.
+    /// Default: 3
+    pub mouse_scroll_lines: usize,
+    /// Seconds a notification is shown in the status bar before the status is restored.
+    /// Default: 5
+    pub status_notification_timeout: u64,
+    /// Maximum number of commands kept in the command history.
+    /// Default: 1000
+    pub command_history_max: usize,
     #[serde(deserialize_with = "non_empty_string")]
     pub window_title: Option<String>,
     #[serde(deserialize_with = "non_empty_string")]
     pub file_picker_command: Option<String>,
-    /// Choose between 30-something built in sequences (integers between 0-30) or define your own
-    /// list of strings for the progress spinner animation.
+    /// Command that reads text to copy to the clipboard from its standard input, eg `xclip
+    /// -selection clipboard`, `wl-copy` or `pbcopy`. If not set, the terminal's clipboard is set
+    /// with an OSC 52 escape sequence.
+    /// Default: None
+    #[serde(deserialize_with = "non_empty_string")]
+    pub clipboard_command: Option<String>,
+    /// Choose between 30-something built in sequences (integers between 0-30 or names such as
+    /// "dots", "braille", "arrows", "bounce") or define your own list of strings for the progress
+    /// spinner animation.
     /// Default: 0
     #[serde(default)]
     pub progress_spinner_sequence: Option<ProgressSpinnerSequence>,
+    /// Ask for confirmation before quitting.
+    /// Default: False
+    pub confirm_quit: bool,
 }
 
 impl Default for TerminalSettings {
@@ -61,11 +83,17 @@ impl Default for TerminalSettings {
             themes: Themes::default(),
             ascii_drawing: false,
             use_color: ToggleFlag::InternalVal(true),
+            use_truecolor: ToggleFlag::InternalVal(true),
             use_mouse: ToggleFlag::InternalVal(false),
             mouse_flag: Some("🖱️ ".to_string()),
+            mouse_scroll_lines: 3,
+            status_notification_timeout: 5,
+            command_history_max: 1000,
             window_title: Some("meli".to_string()),
             file_picker_command: None,
+            clipboard_command: None,
             progress_spinner_sequence: None,
+            confirm_quit: false,
         }
     }
 }
@@ -80,6 +108,16 @@ impl TerminalSettings {
             && (self.use_color.is_false() || self.use_color.is_internal()))
             || (self.use_color.is_false() && !self.use_color.is_internal()))
     }
+
+    pub fn use_truecolor(&self) -> bool {
+        if self.use_truecolor.is_internal() {
+            std::env::var("COLORTERM")
+                .map(|v| v == "truecolor" || v == "24bit")
+                .unwrap_or(false)
+        } else {
+            self.use_truecolor.is_true()
+        }
+    }
 }
 
 impl DotAddressable for TerminalSettings {
@@ -92,13 +130,21 @@ impl DotAddressable for TerminalSettings {
                     "themes" => Err(MeliError::new("unimplemented")),
                     "ascii_drawing" => self.ascii_drawing.lookup(field, tail),
                     "use_color" => self.use_color.lookup(field, tail),
+                    "use_truecolor" => self.use_truecolor.lookup(field, tail),
                     "use_mouse" => self.use_mouse.lookup(field, tail),
                     "mouse_flag" => self.mouse_flag.lookup(field, tail),
+                    "mouse_scroll_lines" => self.mouse_scroll_lines.lookup(field, tail),
+                    "status_notification_timeout" => {
+                        self.status_notification_timeout.lookup(field, tail)
+                    }
+                    "command_history_max" => self.command_history_max.lookup(field, tail),
                     "window_title" => self.window_title.lookup(field, tail),
                     "file_picker_command" => self.file_picker_command.lookup(field, tail),
+                    "clipboard_command" => self.clipboard_command.lookup(field, tail),
                     "progress_spinner_sequence" => {
                         self.progress_spinner_sequence.lookup(field, tail)
                     }
+                    "confirm_quit" => self.confirm_quit.lookup(field, tail),
                     other => Err(MeliError::new(format!(
                         "{} has no field named {}",
                         parent_field, other
@@ -114,6 +160,7 @@ impl DotAddressable for TerminalSettings {
 #[serde(untagged)]
 pub enum ProgressSpinnerSequence {
     Integer(usize),
+    Named(String),
     Custom(Vec<String>),
 }
 
@@ -303,6 +303,8 @@ const DEFAULT_KEYS: &[&str] = &[
     "mail.listing.tag_default",
     "pager.highlight_search",
     "pager.highlight_search_current",
+    "pager.signature",
+    "pager.line_numbers",
 ];
 
 /// `ThemeAttributeInner` but with the links resolved.
@@ -1004,6 +1006,18 @@ impl<'de> Deserialize<'de> for Themes {
 }
 
 impl Themes {
+    /// Names of all available themes, starting with the built-in `light` and `dark`.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        ["light", "dark"]
+            .iter()
+            .copied()
+            .chain(self.other_themes.keys().map(String::as_str))
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        name == "light" || name == "dark" || self.other_themes.contains_key(name)
+    }
+
     fn validate_keys(name: &str, theme: &Theme, hash_set: &HashSet<&'static str>) -> Result<()> {
         let mut keys = theme
             .keys()
@@ -1663,6 +1677,8 @@ impl Default for Themes {
 
         add!("pager.highlight_search", light = { fg: Color::White, bg: Color::Byte(6) /* Teal */, attrs: Attr::BOLD }, dark = { fg: Color::White, bg: Color::Byte(6) /* Teal */, attrs: Attr::BOLD });
         add!("pager.highlight_search_current", light = { fg: Color::White, bg: Color::Byte(17) /* NavyBlue */, attrs: Attr::BOLD }, dark = { fg: Color::White, bg: Color::Byte(17) /* NavyBlue */, attrs: Attr::BOLD });
+        add!("pager.signature", light = { fg: Color::Byte(243) /* Grey46 */ }, dark = { fg: Color::Byte(243) /* Grey46 */ });
+        add!("pager.line_numbers", light = { fg: Color::Byte(243) /* Grey46 */, attrs: Attr::DIM }, dark = { fg: Color::Byte(243) /* Grey46 */, attrs: Attr::DIM });
         Themes {
             light: Theme {
                 keys: light,
@@ -2116,3 +2132,29 @@ color_aliases= { "Jebediah" = "$JebediahJr", "JebediahJr" = "mail.listing.tag_de
     let parsed: Themes = toml::from_str(TEST_INVALID_LINK_KEY_FIELD_STR).unwrap();
     assert!(parsed.validate().is_err());
 }
+
+#[test]
+fn test_theme_switch() {
+    let mut context = Context::new_mock();
+    assert!(context.settings.terminal.themes.contains("light"));
+    assert!(!context.settings.terminal.themes.contains("solarized"));
+    assert_eq!(
+        context
+            .settings
+            .terminal
+            .themes
+            .names()
+            .collect::<Vec<&str>>(),
+        vec!["light", "dark"]
+    );
+    context.settings.terminal.theme = "light".to_string();
+    assert_eq!(
+        value(&context, "mail.listing.plain.even").bg,
+        Color::Byte(252)
+    );
+    context.settings.terminal.theme = "dark".to_string();
+    assert_eq!(
+        value(&context, "mail.listing.plain.even").bg,
+        Color::Byte(236)
+    );
+}
@@ -72,6 +72,7 @@ impl MailBackend for PluginBackend {
             extensions: None,
             supports_tags: false,
             supports_submission: false,
+            supports_fetch_range: false,
         };
         CAPABILITIES
     }
@@ -262,7 +263,7 @@ impl PluginBackend {
         }))
     }
 
-    pub fn register(listener: UnixListener, plugin: Plugin, backends: &mut Backends) {
+    pub fn register(listener: UnixListener, plugin: Plugin, backends: &mut Backends) -> Result<()> {
         backends.register(
             plugin.name.clone(),
             Backend {
@@ -277,7 +278,7 @@ impl PluginBackend {
                 }),
                 validate_conf_fn: Box::new(|_| Ok(())),
             },
-        );
+        )
     }
 }
 
@@ -354,7 +354,8 @@ pub fn search(
 
     let sort_field = match debug!(sort_field) {
         SortField::Subject => "subject",
-        SortField::Date => "timestamp",
+        SortField::Date | SortField::Received => "timestamp",
+        SortField::From => "_from",
     };
 
     let sort_order = match debug!(sort_order) {
@@ -132,6 +132,36 @@ impl Context {
         self.input_thread.restore();
     }
 
+    #[cfg(test)]
+    pub fn new_mock() -> Self {
+        let (sender, receiver) =
+            crossbeam::channel::bounded(32 * ::std::mem::size_of::<ThreadEvent>());
+        let job_executor = Arc::new(JobExecutor::new(sender.clone()));
+        let input_thread = unbounded();
+        let input_thread_pipe = nix::unistd::pipe().unwrap();
+        let settings = Settings::default();
+        Context {
+            accounts: IndexMap::new(),
+            settings: settings.clone(),
+            runtime_settings: settings,
+            dirty_areas: VecDeque::with_capacity(5),
+            replies: VecDeque::with_capacity(5),
+            temp_files: Vec::new(),
+            job_executor,
+            children: vec![],
+
+            input_thread: InputHandler {
+                pipe: input_thread_pipe,
+                rx: input_thread.1,
+                tx: input_thread.0,
+                control: std::sync::Weak::new(),
+                state_tx: sender.clone(),
+            },
+            sender,
+            receiver,
+        }
+    }
+
     pub fn is_online_idx(&mut self, account_pos: usize) -> Result<()> {
         let Context {
             ref mut accounts,
@@ -183,6 +213,10 @@ pub struct State {
     pub mode: UIMode,
     overlay: Vec<Box<dyn Component>>,
     components: Vec<Box<dyn Component>>,
+    /// The user has confirmed they want to quit, see `terminal.confirm_quit`
+    quit_confirmed: bool,
+    /// Quit confirmation dialog currently shown in `overlay`, if any
+    quit_dialog: Option<ComponentId>,
     pub context: Context,
     timer: thread::JoinHandle<()>,
 
@@ -251,7 +285,7 @@ impl State {
                     plugin_manager.listener(),
                     p.clone(),
                     &mut backends,
-                );
+                )?;
             }
             plugin_manager.register(p)?;
         }
@@ -326,10 +360,16 @@ impl State {
             mode: UIMode::Normal,
             components: Vec::with_capacity(8),
             overlay: Vec::new(),
+            quit_confirmed: false,
+            quit_dialog: None,
             timer,
             draw_rate_limit: RateLimit::new(1, 3, job_executor.clone()),
             draw_horizontal_segment_fn: if settings.terminal.use_color() {
-                State::draw_horizontal_segment
+                if settings.terminal.use_truecolor() {
+                    State::draw_horizontal_segment
+                } else {
+                    State::draw_horizontal_segment_256
+                }
             } else {
                 State::draw_horizontal_segment_no_color
             },
@@ -378,6 +418,9 @@ impl State {
                 //)));
             }
         }
+        if let Some(dialog) = crate::components::mail::restore_autosaves_dialog(&s.context) {
+            s.context.replies.push_back(dialog);
+        }
         s.context.restore_input();
         Ok(s)
     }
@@ -788,6 +831,28 @@ impl State {
         x_start: usize,
         x_end: usize,
         y: usize,
+    ) {
+        Self::draw_horizontal_segment_colors(grid, stdout, x_start, x_end, y, true)
+    }
+
+    /// Like `draw_horizontal_segment`, for terminals without 24-bit color support.
+    fn draw_horizontal_segment_256(
+        grid: &mut CellBuffer,
+        stdout: &mut StateStdout,
+        x_start: usize,
+        x_end: usize,
+        y: usize,
+    ) {
+        Self::draw_horizontal_segment_colors(grid, stdout, x_start, x_end, y, false)
+    }
+
+    fn draw_horizontal_segment_colors(
+        grid: &mut CellBuffer,
+        stdout: &mut StateStdout,
+        x_start: usize,
+        x_end: usize,
+        y: usize,
+        truecolor: bool,
     ) {
         write!(
             stdout,
@@ -805,13 +870,18 @@ impl State {
                 c.attrs().write(current_attrs, stdout).unwrap();
                 current_attrs = c.attrs();
             }
-            if c.bg() != current_bg {
-                c.bg().write_bg(stdout).unwrap();
-                current_bg = c.bg();
+            let (fg, bg) = if truecolor {
+                (c.fg(), c.bg())
+            } else {
+                (c.fg().to_256(), c.bg().to_256())
+            };
+            if bg != current_bg {
+                bg.write_bg(stdout).unwrap();
+                current_bg = bg;
             }
-            if c.fg() != current_fg {
-                c.fg().write_fg(stdout).unwrap();
-                current_fg = c.fg();
+            if fg != current_fg {
+                fg.write_fg(stdout).unwrap();
+                current_fg = fg;
             }
             if !c.empty() {
                 write!(stdout, "{}", c.ch()).unwrap();
@@ -875,10 +945,25 @@ impl State {
     pub fn can_quit_cleanly(&mut self) -> bool {
         let State {
             ref mut components,
-            ref context,
+            ref mut context,
+            ref mut overlay,
+            ref mut quit_confirmed,
+            ref mut quit_dialog,
             ..
         } = self;
-        components.iter_mut().all(|c| c.can_quit_cleanly(context))
+        if !components.iter_mut().all(|c| c.can_quit_cleanly(context)) {
+            *quit_confirmed = false;
+            return false;
+        }
+        if overlay.iter().any(|c| Some(c.id()) == *quit_dialog) {
+            return false;
+        }
+        if let Some(dialog) = quit_confirmation_dialog(*quit_confirmed, context) {
+            *quit_dialog = Some(dialog.id());
+            overlay.push(Box::new(dialog));
+            return false;
+        }
+        true
     }
 
     pub fn register_component(&mut self, component: Box<dyn Component>) {
@@ -996,15 +1081,14 @@ impl State {
                     .iter()
                     .position(|(_h, a)| a.name() == account_name)
                 {
-                    self.context.replies.push_back(UIEvent::StatusEvent(
-                        StatusEvent::UpdateStatus(format!(
-                            "{}",
+                    self.context
+                        .replies
+                        .push_back(UIEvent::StatusEvent(StatusEvent::Notify(
                             self.context.accounts[pos]
                                 .settings
                                 .lookup("settings", &path)
-                                .unwrap_or_else(|err| err.to_string())
-                        )),
-                    ));
+                                .unwrap_or_else(|err| err.to_string()),
+                        )));
                 } else {
                     self.context.replies.push_back(UIEvent::Notification(
                         None,
@@ -1018,19 +1102,67 @@ impl State {
                 let path = setting.split(".").collect::<SmallVec<[&str; 16]>>();
                 self.context
                     .replies
-                    .push_back(UIEvent::StatusEvent(StatusEvent::UpdateStatus(format!(
-                        "{}",
+                    .push_back(UIEvent::StatusEvent(StatusEvent::Notify(
                         self.context
                             .settings
                             .lookup("settings", &path)
-                            .unwrap_or_else(|err| err.to_string())
-                    ))));
+                            .unwrap_or_else(|err| err.to_string()),
+                    )));
             }
             ToggleMouse => {
                 self.mouse = !self.mouse;
+                self.context.runtime_settings.terminal.use_mouse = self.mouse.into();
                 self.set_mouse(self.mouse);
                 self.rcv_event(UIEvent::StatusEvent(StatusEvent::SetMouse(self.mouse)));
             }
+            SetTheme(name) => {
+                if !self.context.settings.terminal.themes.contains(&name) {
+                    self.context.replies.push_back(UIEvent::Notification(
+                        None,
+                        format!("Theme {} was not found.", name),
+                        Some(NotificationType::Error(ErrorKind::None)),
+                    ));
+                    return;
+                }
+                self.context.settings.terminal.theme = name.clone();
+                self.context.runtime_settings.terminal.theme = name.clone();
+                self.rcv_event(UIEvent::Action(SetTheme(name)));
+                self.rcv_event(UIEvent::Resize);
+            }
+            ViewFile(ref path) => match EnvelopeView::read_mail_file(path) {
+                Ok(mails) => {
+                    let account_hash = self.context.accounts.keys().next().cloned().unwrap_or(0);
+                    let total = mails.len();
+                    for mail in mails.into_iter().take(EnvelopeView::MAX_FILE_TABS) {
+                        self.rcv_event(UIEvent::Action(Tab(New(Some(Box::new(
+                            EnvelopeView::new(mail, None, None, account_hash),
+                        ))))));
+                    }
+                    if total > EnvelopeView::MAX_FILE_TABS {
+                        self.context.replies.push_back(UIEvent::Notification(
+                            Some(format!(
+                                "Opened {} of {} messages",
+                                EnvelopeView::MAX_FILE_TABS,
+                                total
+                            )),
+                            format!(
+                                "{} has {} more messages. Add it as an mbox account to read them \
+                                 all.",
+                                path.display(),
+                                total - EnvelopeView::MAX_FILE_TABS
+                            ),
+                            Some(NotificationType::Info),
+                        ));
+                    }
+                }
+                Err(err) => {
+                    self.context.replies.push_back(UIEvent::Notification(
+                        Some(format!("Could not open {}", path.display())),
+                        err.to_string(),
+                        Some(NotificationType::Error(err.kind)),
+                    ));
+                }
+            },
             Quit => {
                 self.context
                     .sender
@@ -1123,6 +1255,28 @@ impl State {
                 )));
                 return;
             }
+            UIEvent::BackendEvent(
+                _,
+                BackendEvent::Progress {
+                    ref description,
+                    done,
+                    total,
+                },
+            ) => {
+                self.rcv_event(UIEvent::StatusEvent(StatusEvent::Progress(
+                    if done < total {
+                        Some(format!(
+                            "{} {}/{}",
+                            description,
+                            melib::Bytes(done),
+                            melib::Bytes(total)
+                        ))
+                    } else {
+                        None
+                    },
+                )));
+                return;
+            }
             UIEvent::BackendEvent(_, BackendEvent::Refresh(refresh_event)) => {
                 self.refresh_event(refresh_event);
                 return;
@@ -1177,6 +1331,9 @@ impl State {
                 if self.overlay.iter().any(|c| c.id() == *id) =>
             {
                 if let Some(ref mut action @ Some(_)) = results.downcast_mut::<Option<Action>>() {
+                    if let Some(Quit) = action {
+                        self.quit_confirmed = true;
+                    }
                     self.exec_command(action.take().unwrap());
 
                     return;
@@ -1280,3 +1437,35 @@ impl State {
         self.context.input_thread.check();
     }
 }
+
+/// Returns a dialog asking the user whether to quit if `terminal.confirm_quit` is set and they
+/// haven't confirmed yet. Answering "yes" sends [`Action::Quit`].
+fn quit_confirmation_dialog(
+    quit_confirmed: bool,
+    context: &mut Context,
+) -> Option<UIConfirmationDialog> {
+    if quit_confirmed || !context.settings.terminal.confirm_quit {
+        return None;
+    }
+    Some(UIConfirmationDialog::new(
+        "Are you sure you want to quit?",
+        vec![(true, "yes".to_string()), (false, "no".to_string())],
+        true,
+        Some(Box::new(move |id: ComponentId, result: bool| {
+            Some(UIEvent::FinishedUIDialog(
+                id,
+                Box::new(if result { Some(Quit) } else { None }),
+            ))
+        })),
+        context,
+    ))
+}
+
+#[test]
+fn test_quit_confirmation_dialog() {
+    let mut context = Context::new_mock();
+    assert!(quit_confirmation_dialog(false, &mut context).is_none());
+    context.settings.terminal.confirm_quit = true;
+    assert!(quit_confirmation_dialog(false, &mut context).is_some());
+    assert!(quit_confirmation_dialog(true, &mut context).is_none());
+}
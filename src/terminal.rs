@@ -32,6 +32,7 @@ mod position;
 mod cells;
 #[macro_use]
 mod keys;
+pub mod clipboard;
 pub mod embed;
 mod text_editing;
 pub use self::cells::*;
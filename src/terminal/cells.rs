@@ -804,7 +804,49 @@ impl Color {
             | b @ Color::White
             | b @ Color::Default => AnsiValue(b.as_byte()),
             Color::Byte(b) => AnsiValue(b),
-            Color::Rgb(_, _, _) => AnsiValue(0),
+            Color::Rgb(_, _, _) => AnsiValue(self.to_256().as_byte()),
+        }
+    }
+
+    /// Maps a 24-bit `Color::Rgb` to the nearest color of the xterm 256 color palette, for
+    /// terminals without truecolor support. Other colors are returned as they are.
+    pub fn to_256(self) -> Self {
+        let (r, g, b) = match self {
+            Color::Rgb(r, g, b) => (r as i32, g as i32, b as i32),
+            other => return other,
+        };
+        const CUBE_LEVELS: [i32; 6] = [0, 95, 135, 175, 215, 255];
+        let cube_index = |v: i32| -> i32 {
+            if v < 48 {
+                0
+            } else if v < 115 {
+                1
+            } else {
+                (v - 35) / 40
+            }
+        };
+        let distance = |(r_, g_, b_): (i32, i32, i32)| -> i32 {
+            (r - r_) * (r - r_) + (g - g_) * (g - g_) + (b - b_) * (b - b_)
+        };
+        /* 6x6x6 color cube, bytes 16-231 */
+        let (ri, gi, bi) = (cube_index(r), cube_index(g), cube_index(b));
+        let cube = (
+            CUBE_LEVELS[ri as usize],
+            CUBE_LEVELS[gi as usize],
+            CUBE_LEVELS[bi as usize],
+        );
+        /* grayscale ramp, bytes 232-255 */
+        let average = (r + g + b) / 3;
+        let gray_index = if average > 238 {
+            23
+        } else {
+            std::cmp::max(average - 3, 0) / 10
+        };
+        let gray = 8 + 10 * gray_index;
+        if distance((gray, gray, gray)) < distance(cube) {
+            Color::Byte((232 + gray_index) as u8)
+        } else {
+            Color::Byte((16 + 36 * ri + 6 * gi + bi) as u8)
         }
     }
 
@@ -1156,6 +1198,17 @@ fn test_color_de() {
     test_color!("Oafahifdave", err "invalid `color` value for key `k` at line 1 column 1");
 }
 
+#[test]
+fn test_color_to_256() {
+    assert_eq!(Color::Rgb(255, 102, 0).to_256(), Color::Byte(202));
+    assert_eq!(Color::Rgb(0, 0, 0).to_256(), Color::Byte(16));
+    assert_eq!(Color::Rgb(255, 255, 255).to_256(), Color::Byte(231));
+    assert_eq!(Color::Rgb(128, 128, 128).to_256(), Color::Byte(244));
+    assert_eq!(Color::Rgb(180, 218, 85).to_256(), Color::Byte(149));
+    assert_eq!(Color::Byte(26).to_256(), Color::Byte(26));
+    assert_eq!(Color::Default.to_256(), Color::Default);
+}
+
 impl Serialize for Color {
     fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
     where
@@ -0,0 +1,73 @@
+/*
+ * meli
+ *
+ * Copyright 2020 Manos Pitsidianakis
+ *
+ * This file is part of meli.
+ *
+ * meli is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * meli is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with meli. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/*! Copying text to the system clipboard.
+
+By default the terminal itself is asked to set its clipboard with an OSC 52 escape sequence. This
+works over ssh, but some terminals disable it; for those, `terminal.clipboard_command` can name a
+command such as `xclip -selection clipboard`, `wl-copy` or `pbcopy` that reads the text from its
+standard input.
+*/
+
+use melib::data_encoding::BASE64;
+use melib::{MeliError, Result};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Returns the OSC 52 escape sequence that sets the terminal's clipboard to `text`.
+pub fn osc52_sequence(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", BASE64.encode(text.as_bytes()))
+}
+
+/// Copies `text` to the clipboard, by piping it to `clipboard_command` if it is set or with an
+/// OSC 52 escape sequence otherwise.
+pub fn copy_to_clipboard(text: &str, clipboard_command: Option<&str>) -> Result<()> {
+    if let Some(cmd) = clipboard_command {
+        let mut child = Command::new("sh")
+            .args(&["-c", cmd])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        /* Dropping stdin closes it, so that the command sees EOF */
+        child.stdin.take().unwrap().write_all(text.as_bytes())?;
+        let status = child.wait()?;
+        if !status.success() {
+            return Err(MeliError::new(format!("`{}` failed: {}", cmd, status)));
+        }
+    } else {
+        let stdout = std::io::stdout();
+        let mut stdout = stdout.lock();
+        stdout.write_all(osc52_sequence(text).as_bytes())?;
+        stdout.flush()?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_osc52_sequence() {
+    assert_eq!(osc52_sequence("hello"), "\x1b]52;c;aGVsbG8=\x07");
+    assert_eq!(
+        osc52_sequence("https://meli.delivery/?q=ö"),
+        "\x1b]52;c;aHR0cHM6Ly9tZWxpLmRlbGl2ZXJ5Lz9xPcO2\x07"
+    );
+    assert_eq!(osc52_sequence(""), "\x1b]52;c;\x07");
+}
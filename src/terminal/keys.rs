@@ -49,6 +49,8 @@ pub enum Key {
     Delete,
     /// Insert key.
     Insert,
+    /// Shift-Tab.
+    BackTab,
     /// Function keys.
     ///
     /// Only function keys 1 through 12 are supported.
@@ -97,6 +99,7 @@ impl fmt::Display for Key {
             PageDown => write!(f, "PageDown"),
             Delete => write!(f, "Delete"),
             Insert => write!(f, "Insert"),
+            BackTab => write!(f, "BackTab"),
             Mouse(_) => write!(f, "Mouse"),
         }
     }
@@ -122,6 +125,7 @@ impl From<TermionKey> for Key {
             TermionKey::PageDown => Key::PageDown,
             TermionKey::Delete => Key::Delete,
             TermionKey::Insert => Key::Insert,
+            TermionKey::BackTab => Key::BackTab,
             TermionKey::F(u) => Key::F(u),
             TermionKey::Char(c) => Key::Char(c),
             TermionKey::Alt(c) => Key::Alt(c),
@@ -274,6 +278,7 @@ impl<'de> Deserialize<'de> for Key {
                     "PageDown" | "pagedown" => Ok(Key::PageDown),
                     "Delete" | "delete" => Ok(Key::Delete),
                     "Insert" | "insert" => Ok(Key::Insert),
+                    "BackTab" | "backtab" => Ok(Key::BackTab),
                     "Enter" | "enter" => Ok(Key::Char('\n')),
                     "Tab" | "tab" => Ok(Key::Char('\t')),
                     "Esc" | "esc" => Ok(Key::Esc),
@@ -343,6 +348,7 @@ impl Serialize for Key {
             Key::PageDown => serializer.serialize_str("PageDown"),
             Key::Delete => serializer.serialize_str("Delete"),
             Key::Insert => serializer.serialize_str("Insert"),
+            Key::BackTab => serializer.serialize_str("BackTab"),
             Key::Esc => serializer.serialize_str("Esc"),
             Key::Char('\n') => serializer.serialize_str("Enter"),
             Key::Char('\t') => serializer.serialize_str("Tab"),
@@ -393,6 +399,7 @@ fn test_key_serde() {
     test_key!("Insert", ok  Key::Insert );
     test_key!("Enter", ok  Key::Char('\n') );
     test_key!("Tab", ok  Key::Char('\t') );
+    test_key!("BackTab", ok  Key::BackTab );
     test_key!("k", ok  Key::Char('k') );
     test_key!("1", ok  Key::Char('1') );
     test_key!("Esc", ok  Key::Esc );
@@ -53,10 +53,15 @@ pub enum StatusEvent {
     BufClear,
     BufSet(String),
     UpdateStatus(String),
+    /// Show a message in the status bar with priority over the status set with `UpdateStatus`,
+    /// until `terminal.status_notification_timeout` seconds pass.
+    Notify(String),
     NewJob(JobId),
     JobFinished(JobId),
     JobCanceled(JobId),
     SetMouse(bool),
+    /// Show the progress of a long running operation in the status bar; `None` clears it.
+    Progress(Option<String>),
 }
 
 /// `ThreadEvent` encapsulates all of the possible values we need to transfer between our threads